@@ -0,0 +1,70 @@
+#![crate_id(name="arch", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::print;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/utsname.rs"]
+mod utsname;
+
+static NAME: &'static str = "arch";
+static VERSION: &'static str = "1.0.0";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]...", args.get(0));
+        println!("");
+        print(getopts::usage("Print machine architecture.", opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+    if !matches.free.is_empty() {
+        crash!(1, "extra operand '{}'", matches.free.get(0));
+    }
+
+    println!("{}", utsname::get().machine);
+}
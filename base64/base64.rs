@@ -22,7 +22,6 @@ use std::os;
 use std::str;
 
 use getopts::{
-    getopts,
     optflag,
     optopt,
     usage
@@ -33,10 +32,17 @@ use serialize::base64::{FromBase64, ToBase64};
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "base64";
 
-pub fn uumain(args: Vec<String>) {
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         optflag("d", "decode", "decode data"),
         optflag("i", "ignore-garbage", "when decoding, ignore non-alphabetic characters"),
         optopt("w", "wrap",
@@ -44,8 +50,12 @@ pub fn uumain(args: Vec<String>) {
         ),
         optflag("h", "help", "display this help text and exit"),
         optflag("V", "version", "output version information and exit")
-    ];
-    let matches = match getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(e) => {
             error!("error: {:s}", e.to_err_msg());
@@ -54,7 +64,7 @@ pub fn uumain(args: Vec<String>) {
     };
 
     let progname = args.get(0).clone();
-    let usage = usage("Base64 encode or decode FILE, or standard input, to standard output.", opts);
+    let usage = usage("Base64 encode or decode FILE, or standard input, to standard output.", opts.as_slice());
     let mode = if matches.opt_present("help") {
         Help
     } else if matches.opt_present("version") {
@@ -91,7 +101,7 @@ pub fn uumain(args: Vec<String>) {
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
 fn decode(input: &mut Reader, ignore_garbage: bool) {
     let mut to_decode = match input.read_to_str() {
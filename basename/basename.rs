@@ -13,42 +13,56 @@
 extern crate getopts;
 extern crate libc;
 
-use std::io::{print, println};
+use std::io::print;
 use std::os;
 use std::str::StrSlice;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "basename";
 static VERSION: &'static str = "1.0.0";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("a", "multiple", "support multiple arguments and treat each as a NAME"),
+        getopts::optopt("s", "suffix", "remove a trailing SUFFIX; implies -a", "SUFFIX"),
+        getopts::optflag("z", "zero", "end each output line with NUL, not newline"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
 
-pub fn uumain(args: Vec<String>) {
+fn run(args: Vec<String>) {
     let program = strip_dir(args.get(0).as_slice());
 
     //
     // Argument parsing
     //
-    let opts = [
-        getopts::optflag("h", "help", "display this help and exit"),
-        getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    let opts = opts();
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m)  => m,
         Err(f) => crash!(1, "Invalid options\n{}", f.to_err_msg())
     };
 
     if matches.opt_present("help") {
         println!("Usage: {0:s} NAME [SUFFIX]", program);
-        println!("  or: {0:s} OPTION", program);
+        println!("  or: {0:s} OPTION... NAME...", program);
         println!("Print NAME with any leading directory components removed.");
         println!("If specified, also remove a trailing SUFFIX.");
 
-        print(getopts::usage("", opts).as_slice());
+        print(getopts::usage("", opts.as_slice()).as_slice());
 
         return;
     }
@@ -58,48 +72,57 @@ pub fn uumain(args: Vec<String>) {
         return;
     }
 
+    let suffix = matches.opt_str("suffix");
+    let multiple = matches.opt_present("multiple") || suffix.is_some();
+
     // too few arguments
-    if args.len() < 2 {
-        println!("{}: {}", program, "missing operand");
-        println!("Try '{} --help' for more information.", program);
-        return;
+    if matches.free.is_empty() {
+        crash!(1, "missing operand\nTry '{} --help' for more information.", program);
     }
     // too many arguments
-    else if args.len() > 3 {
-        println!("{}: extra operand '{}'", program, args.get(3));
-        println!("Try '{} --help' for more information.", program);
-        return;
+    else if !multiple && matches.free.len() > 2 {
+        crash!(1, "extra operand '{}'\nTry '{} --help' for more information.",
+               matches.free.get(2), program);
     }
 
     //
     // Main Program Processing
     //
 
-    let fullname = args.get(1);
+    // without -a, a second operand is the suffix
+    let (names, suffix) = if multiple {
+        (matches.free.as_slice(), suffix)
+    } else {
+        (matches.free.slice_to(1), matches.free.as_slice().get(1).map(|s| s.clone()))
+    };
+    let terminator = if matches.opt_present("zero") { "\0" } else { "\n" };
 
-    let mut name = strip_dir(fullname.as_slice());
+    for fullname in names.iter() {
+        let mut name = strip_dir(fullname.as_slice());
 
-    if args.len() > 2 {
-        let suffix = args.get(2).clone();
-        name = strip_suffix(name.as_slice(), suffix.as_slice());
-    }
+        match suffix {
+            Some(ref suffix) => name = strip_suffix(name.as_slice(), suffix.as_slice()),
+            None => {}
+        }
 
-    println(name.as_slice());
+        print(name.as_slice());
+        print(terminator);
+    }
 }
 
 fn strip_dir(fullname: &str) -> String {
-    let mut name = String::new();
+    let trimmed = fullname.trim_right_chars('/');
 
-    for c in fullname.chars().rev() {
-        if c == '/' || c == '\\' {
-            break;
-        }
-        name.push_char(c);
+    // nothing but slashes
+    if trimmed.is_empty() && !fullname.is_empty() {
+        return "/".to_string();
     }
 
-    return name.as_slice().chars().rev().collect();
+    match trimmed.rfind('/') {
+        Some(i) => trimmed.slice_from(i + 1).to_string(),
+        None => trimmed.to_string()
+    }
 }
-
 fn strip_suffix(name: &str, suffix: &str) -> String {
     if name == suffix {
         return name.into_string();
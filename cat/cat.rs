@@ -9,22 +9,33 @@
  * file that was distributed with this source code.
  */
 
+#![feature(macro_rules)]
+
 /* last synced with: cat (GNU coreutils) 8.13 */
 
 extern crate getopts;
 
 use std::os;
 use std::io::{print, File};
-use std::io::stdio::{stdout_raw, stdin_raw, stderr};
+use std::io::stdio::{stdout_raw, stdin_raw};
 use std::io::{IoResult};
 use std::ptr::{copy_nonoverlapping_memory};
 
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).as_slice();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("A", "show-all", "equivalent to -vET"),
         getopts::optflag("b", "number-nonblank",
                          "number nonempty output lines, overrides -n"),
@@ -38,8 +49,13 @@ pub fn uumain(args: Vec<String>) {
                          "use ^ and M- notation, except for LF (\\n) and TAB (\\t)"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).as_slice();
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => fail!("Invalid options\n{}", f.to_err_msg())
     };
@@ -50,7 +66,7 @@ pub fn uumain(args: Vec<String>) {
         println!("  {0:s} [OPTION]... [FILE]...", program);
         println!("");
         print(getopts::usage("Concatenate FILE(s), or standard input, to \
-                             standard output.", opts).as_slice());
+                             standard output.", opts.as_slice()).as_slice());
         println!("");
         println!("With no FILE, or when FILE is -, read standard input.");
         return;
@@ -285,7 +301,7 @@ fn open(path: &str) -> Option<(Box<Reader>, bool)> {
     match File::open(&std::path::Path::new(path)) {
         Ok(f) => return Some((box f as Box<Reader>, false)),
         Err(e) => {
-            (writeln!(stderr(), "cat: {0:s}: {1:s}", path, e.to_str())).unwrap();
+            show_error!(1, "{}: {}", path, util::strerror(&e));
             return None;
         },
     };
@@ -0,0 +1,99 @@
+#![crate_id(name="chgrp", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::print;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/walk.rs"]
+mod walk;
+
+#[path = "../common/c_types.rs"]
+mod c_types;
+
+#[path = "../common/owner.rs"]
+mod owner;
+
+static NAME: &'static str = "chgrp";
+static VERSION: &'static str = "1.0.0";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    owner::opts()
+}
+
+fn run(args: Vec<String>) {
+    let opts = owner::opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... GROUP FILE...", args.get(0));
+        println!("  {0} [OPTION]... --reference=RFILE FILE...", args.get(0));
+        println!("");
+        print(getopts::usage("Change the group of each FILE to GROUP.",
+                             opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let mut free = matches.free.clone();
+    let gid = match matches.opt_str("reference") {
+        Some(reference) => match fsext::stat_path(&Path::new(reference.as_slice())) {
+            Ok(stat) => stat.st_gid,
+            Err(e) => crash!(1, "failed to get attributes of '{}': {}", reference,
+                             util::strerror(&e))
+        },
+        None => {
+            if free.is_empty() {
+                crash!(1, "missing operand");
+            }
+            let group = free.remove(0).unwrap();
+            match owner::parse_group(group.as_slice()) {
+                Ok(gid) => gid,
+                Err(msg) => crash!(1, "{}", msg)
+            }
+        }
+    };
+    if free.is_empty() {
+        crash!(1, "missing operand");
+    }
+
+    let mut options = owner::options(&matches, true);
+    options.gid = Some(gid);
+    owner::change_all(free.as_slice(), &options);
+}
@@ -0,0 +1,268 @@
+#![crate_id(name="chmod", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::{print, IoError};
+use walk::{FollowRoot, Visitor, WalkEntry, Walker};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/walk.rs"]
+mod walk;
+
+#[path = "../common/mode.rs"]
+mod mode;
+
+static NAME: &'static str = "chmod";
+static VERSION: &'static str = "1.0.0";
+
+#[deriving(PartialEq)]
+enum Verbosity {
+    Silent,
+    Normal,
+    Changes,
+    Verbose,
+}
+
+// where the new mode comes from
+enum Source {
+    Spec(String),
+    Reference(libc::mode_t),
+}
+
+struct Options {
+    source: Source,
+    umask: libc::mode_t,
+    recursive: bool,
+    preserve_root: bool,
+    verbosity: Verbosity,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("c", "changes", "like verbose but report only when a change is made"),
+        getopts::optflag("f", "silent", "suppress most error messages"),
+        getopts::optflag("", "quiet", "same as -f"),
+        getopts::optflag("v", "verbose", "output a diagnostic for every file processed"),
+        getopts::optflag("", "no-preserve-root", "do not treat '/' specially (the default)"),
+        getopts::optflag("", "preserve-root", "fail to operate recursively on '/'"),
+        getopts::optopt("", "reference", "use RFILE's mode instead of MODE values", "RFILE"),
+        getopts::optflag("R", "recursive", "change files and directories recursively"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    // modes such as -w look like options, so pull the first one out before
+    // getopts sees it
+    let mut rest = vec!();
+    let mut dash_mode = None;
+    for arg in args.tail().iter() {
+        if dash_mode.is_none() && is_dash_mode(arg.as_slice()) {
+            dash_mode = Some(arg.clone());
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+
+    let matches = match posix::getopts(rest.as_slice(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... MODE[,MODE]... FILE...", args.get(0));
+        println!("  {0} [OPTION]... OCTAL-MODE FILE...", args.get(0));
+        println!("  {0} [OPTION]... --reference=RFILE FILE...", args.get(0));
+        println!("");
+        print(getopts::usage("Change the mode of each FILE to MODE.", opts.as_slice()).as_slice());
+        println!("");
+        println!("Each MODE is of the form '[ugoa]*([-+=]([rwxXst]*|[ugo]))+|[-+=][0-7]+'.");
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let mut free = matches.free.clone();
+    let source = match matches.opt_str("reference") {
+        Some(reference) => {
+            // a -w style argument is a file after all
+            match dash_mode {
+                Some(file) => free.insert(0, file),
+                None => {}
+            }
+            if free.is_empty() {
+                crash!(1, "missing operand");
+            }
+            match fsext::stat_path(&Path::new(reference.as_slice())) {
+                Ok(stat) => Reference(stat.st_mode as libc::mode_t & 0o7777),
+                Err(e) => crash!(1, "failed to get attributes of '{}': {}", reference,
+                                 util::strerror(&e))
+            }
+        }
+        None => {
+            let mode = match dash_mode {
+                Some(mode) => mode,
+                None if !free.is_empty() => free.remove(0).unwrap(),
+                None => crash!(1, "missing operand")
+            };
+            if free.is_empty() {
+                crash!(1, "missing operand after '{}'", mode);
+            }
+            // checked once up front, so a bad mode is not reported for every
+            // file
+            match mode::parse(mode.as_slice(), 0, 0, false) {
+                Ok(_) => {}
+                Err(msg) => crash!(1, "{}", msg)
+            }
+            Spec(mode)
+        }
+    };
+
+    let verbosity = if matches.opt_present("verbose") {
+        Verbose
+    } else if matches.opt_present("changes") {
+        Changes
+    } else if matches.opt_present("silent") || matches.opt_present("quiet") {
+        Silent
+    } else {
+        Normal
+    };
+    let options = Options {
+        source: source,
+        umask: fsext::umask(),
+        recursive: matches.opt_present("recursive"),
+        preserve_root: matches.opt_present("preserve-root")
+                       && !matches.opt_present("no-preserve-root"),
+        verbosity: verbosity,
+    };
+
+    let root = fsext::stat_path(&Path::new("/")).ok();
+    for file in free.iter() {
+        let path = Path::new(file.as_slice());
+        if options.recursive && options.preserve_root {
+            match (fsext::stat_path(&path), root) {
+                (Ok(stat), Some(root)) if stat.st_dev == root.st_dev
+                                          && stat.st_ino == root.st_ino => {
+                    show_error!(1, "it is dangerous to operate recursively on '{}'", file);
+                    show_error!(1, "use --no-preserve-root to override this failsafe");
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        let mut visitor = ChmodVisitor { options: &options };
+        Walker::new().follow(FollowRoot).walk(&path, &mut visitor);
+    }
+}
+
+fn is_dash_mode(arg: &str) -> bool {
+    arg.len() > 1 && arg.starts_with("-") && !arg.starts_with("--")
+        && arg.slice_from(1).chars().all(|c| "rwxXstugoa,+-=01234567".contains_char(c))
+}
+
+struct ChmodVisitor<'a> {
+    options: &'a Options,
+}
+
+impl<'a> Visitor for ChmodVisitor<'a> {
+    // directories are changed on the way in, so that a mode that makes them
+    // readable takes effect before they are read
+    fn enter_dir(&mut self, dir: &WalkEntry) -> bool {
+        change(&dir.path, &dir.stat, self.options);
+        self.options.recursive
+    }
+
+    fn visit_file(&mut self, file: &WalkEntry) {
+        // links met while recursing have no mode of their own to change
+        if fsext::is_lnk(file.stat.st_mode) {
+            if self.options.verbosity == Verbose {
+                println!("neither symbolic link '{}' nor referent has been changed",
+                         file.path.display());
+            }
+            return;
+        }
+        change(&file.path, &file.stat, self.options);
+    }
+
+    fn error(&mut self, path: &Path, err: IoError) {
+        if self.options.verbosity != Silent {
+            show_error!(1, "cannot access '{}': {}", path.display(), util::strerror(&err));
+        } else {
+            util::set_status(1);
+        }
+    }
+}
+
+fn change(path: &Path, stat: &fsext::c_stat, options: &Options) {
+    let old = stat.st_mode as libc::mode_t & 0o7777;
+    let is_dir = fsext::is_dir(stat.st_mode);
+    let new = match options.source {
+        Spec(ref spec) => match mode::parse(spec.as_slice(), old, options.umask, is_dir) {
+            Ok(new) => new,
+            Err(msg) => crash!(1, "{}", msg)
+        },
+        Reference(mode) => mode
+    };
+    if new == old {
+        if options.verbosity == Verbose {
+            println!("mode of '{}' retained as {:04o} ({})", path.display(), old,
+                     describe(old));
+        }
+        return;
+    }
+    match fsext::set_mode(path, new) {
+        Ok(()) => {
+            if options.verbosity == Verbose || options.verbosity == Changes {
+                println!("mode of '{}' changed from {:04o} ({}) to {:04o} ({})",
+                         path.display(), old, describe(old), new, describe(new));
+            }
+        }
+        Err(e) => {
+            if options.verbosity != Silent {
+                show_error!(1, "changing permissions of '{}': {}", path.display(),
+                            util::strerror(&e));
+            } else {
+                util::set_status(1);
+            }
+        }
+    }
+}
+
+// rwxr-xr-x, without the file type ls puts in front
+fn describe(mode: libc::mode_t) -> String {
+    fsext::mode_string(mode).as_slice().slice_from(1).to_string()
+}
@@ -0,0 +1,217 @@
+#![crate_id(name="chroot", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate libc;
+
+use std::os;
+use libc::{c_char, c_int, gid_t, uid_t};
+use libc::funcs::posix88::unistd::{setgid, setuid};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/exec.rs"]
+mod exec;
+
+#[path = "../common/c_types.rs"]
+mod c_types;
+
+static NAME: &'static str = "chroot";
+static VERSION: &'static str = "1.0.0";
+
+#[cfg(target_os = "linux")]
+type GroupCount = libc::size_t;
+#[cfg(not(target_os = "linux"))]
+type GroupCount = c_int;
+
+extern {
+    fn chroot(path: *c_char) -> c_int;
+    fn setgroups(size: GroupCount, list: *gid_t) -> c_int;
+}
+
+struct Options {
+    user: Option<String>,
+    group: Option<String>,
+    groups: Option<String>,
+    skip_chdir: bool,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    exec::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let prog = args.get(0).as_slice();
+    let mut options = Options { user: None, group: None, groups: None, skip_chdir: false };
+
+    let mut i = 1;
+    while i < args.len() {
+        let opt = args.get(i).as_slice();
+        if opt == "--" {
+            i += 1;
+            break;
+        } else if !opt.starts_with("-") || opt == "-" {
+            break;
+        }
+        i += 1;
+
+        let (name, value) = match opt.find('=') {
+            Some(eq) => (opt.slice_to(eq), Some(opt.slice_from(eq + 1).to_string())),
+            None => (opt, None)
+        };
+        let value = if name == "--userspec" || name == "--groups" {
+            match value {
+                Some(value) => Some(value),
+                None if i < args.len() => {
+                    i += 1;
+                    Some(args.get(i - 1).clone())
+                }
+                None => bad_usage(prog, format!("option '{}' requires an argument", name))
+            }
+        } else {
+            value
+        };
+        match (name, value) {
+            ("--help", None) => { help(prog); return }
+            ("--version", None) => { println!("{} {}", NAME, VERSION); return }
+            ("--skip-chdir", None) => options.skip_chdir = true,
+            ("--userspec", Some(spec)) => {
+                let (user, group) = match spec.as_slice().find(':') {
+                    Some(colon) => (spec.as_slice().slice_to(colon).to_string(),
+                                    spec.as_slice().slice_from(colon + 1).to_string()),
+                    None => (spec.clone(), String::new())
+                };
+                options.user = if user.is_empty() { None } else { Some(user) };
+                options.group = if group.is_empty() { None } else { Some(group) };
+            }
+            ("--groups", Some(groups)) => options.groups = Some(groups),
+            _ => bad_usage(prog, format!("unrecognized option '{}'", opt))
+        }
+    }
+
+    if i == args.len() {
+        bad_usage(prog, "missing operand".to_string());
+    }
+    let root = args.get(i).as_slice();
+    if options.skip_chdir && !is_root(root) {
+        bad_usage(prog, "option --skip-chdir only permitted if NEWROOT is old '/'".to_string());
+    }
+
+    // the names are looked up here, as the new root may have no way to
+    let ids = identity(&options);
+
+    if root.with_c_str(|r| unsafe { chroot(r) }) != 0 {
+        crash!(exec::EXIT_CANCELED, "cannot change root directory to '{}': {}", root,
+               os::last_os_error());
+    }
+    if !options.skip_chdir && !os::change_dir(&Path::new("/")) {
+        crash!(exec::EXIT_CANCELED, "cannot chdir to root directory: {}", os::last_os_error());
+    }
+    drop_privileges(ids);
+
+    let mut command = Vec::from_slice(args.slice_from(i + 1));
+    if command.is_empty() {
+        let shell = os::getenv("SHELL").unwrap_or("/bin/sh".to_string());
+        command = vec!(shell, "-i".to_string());
+    }
+    exec::exec_or_exit(command.as_slice());
+}
+
+fn help(prog: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0} [OPTION] NEWROOT [COMMAND [ARG]...]", prog);
+    println!("  {0} OPTION", prog);
+    println!("");
+    println!("Run COMMAND with root directory set to NEWROOT.");
+    println!("");
+    println!("      --groups=G_LIST        specify supplementary groups as g1,g2,..,gN");
+    println!("      --userspec=USER:GROUP  specify user and group (ID or name) to use");
+    println!("      --skip-chdir           do not change working directory to '/'");
+    println!("      --help                 display this help and exit");
+    println!("      --version              output version information and exit");
+    println!("");
+    println!("If no command is given, run '\"$SHELL\" -i' (default: '/bin/sh -i').");
+}
+
+fn bad_usage(prog: &str, msg: String) -> ! {
+    show_error!(exec::EXIT_CANCELED, "{}", msg);
+    crash!(exec::EXIT_CANCELED, "Try '{} --help' for more information.", prog)
+}
+
+fn is_root(dir: &str) -> bool {
+    let root = os::make_absolute(&Path::new(dir));
+    root == Path::new("/")
+}
+
+// The user, group and supplementary groups to become, with None for what is
+// to be left as it is.
+fn identity(options: &Options) -> (Option<uid_t>, Option<gid_t>, Option<Vec<gid_t>>) {
+    let pw = options.user.as_ref().and_then(|user| c_types::getpw(user.as_slice()));
+    let uid = options.user.as_ref().map(|user| match pw {
+        Some(ref pw) => pw.pw_uid as uid_t,
+        None => match from_str(user.as_slice()) {
+            Some(uid) => uid,
+            None => crash!(exec::EXIT_CANCELED, "invalid user '{}'", user)
+        }
+    });
+    let gid = match options.group {
+        Some(ref group) => Some(parse_group(group.as_slice())),
+        // a user on their own brings their login group with them
+        None => pw.as_ref().map(|pw| pw.pw_gid as gid_t)
+    };
+    let groups = match options.groups {
+        Some(ref list) => {
+            Some(list.as_slice().split(',').filter(|g| !g.is_empty())
+                                .map(|g| parse_group(g)).collect())
+        }
+        None => pw.as_ref().map(|pw| c_types::user_groups(pw))
+    };
+    (uid, gid, groups)
+}
+
+fn parse_group(name: &str) -> gid_t {
+    match c_types::grp2gid(name).or_else(|| from_str(name)) {
+        Some(gid) => gid,
+        None => crash!(exec::EXIT_CANCELED, "invalid group '{}'", name)
+    }
+}
+
+// The groups go first, and the user last, as after that there may be no
+// permission left to change the others.
+fn drop_privileges((uid, gid, groups): (Option<uid_t>, Option<gid_t>, Option<Vec<gid_t>>)) {
+    match groups {
+        Some(groups) => {
+            if unsafe { setgroups(groups.len() as GroupCount, groups.as_ptr()) } != 0 {
+                crash!(exec::EXIT_CANCELED, "failed to set supplemental groups: {}",
+                       os::last_os_error());
+            }
+        }
+        None => {}
+    }
+    match gid {
+        Some(gid) => if unsafe { setgid(gid) } != 0 {
+            crash!(exec::EXIT_CANCELED, "failed to set group-ID: {}", os::last_os_error());
+        },
+        None => {}
+    }
+    match uid {
+        Some(uid) => if unsafe { setuid(uid) } != 0 {
+            crash!(exec::EXIT_CANCELED, "failed to set user-ID: {}", os::last_os_error());
+        },
+        None => {}
+    }
+}
@@ -19,6 +19,9 @@ use std::os;
 #[path="../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME : &'static str = "cksum";
 static VERSION : &'static str = "1.0.0";
 
@@ -78,15 +81,23 @@ fn open_file(name: &str) -> IoResult<Box<Reader>> {
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); } 
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-pub fn uumain(args: Vec<String>) {
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(err) => fail!("{}", err.to_err_msg()),
     };
@@ -111,7 +122,7 @@ pub fn uumain(args: Vec<String>) {
     if files.is_empty() {
         match cksum("-") {
             Ok((crc, size)) => println!("{} {}", crc, size),
-            Err(err) => show_error!(2, "{}", err),
+            Err(err) => show_error!(2, "-: {}", util::strerror(&err)),
         }
         return
     }
@@ -119,7 +130,7 @@ pub fn uumain(args: Vec<String>) {
     for fname in files.iter() {
         match cksum(fname.as_slice()) {
             Ok((crc, size)) => println!("{} {} {}", crc, size, fname),
-            Err(err) => show_error!(2, "'{}' {}", fname, err),
+            Err(err) => show_error!(2, "{}: {}", fname, util::strerror(&err)),
         }
     }
 }
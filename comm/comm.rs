@@ -9,6 +9,8 @@
  * file that was distributed with this source code.
  */
 
+#![feature(macro_rules)]
+
 extern crate getopts;
 
 use std::cmp::Ord;
@@ -18,6 +20,12 @@ use std::io::stdio::stdin;
 use std::os;
 use std::path::Path;
 
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME : &'static str = "comm";
 static VERSION : &'static str = "1.0.0";
 
@@ -88,19 +96,27 @@ fn open_file(name: &str) -> IoResult<Box<Buffer>> {
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("1", "", "suppress column 1 (lines uniq to FILE1)"),
         getopts::optflag("2", "", "suppress column 2 (lines uniq to FILE2)"),
         getopts::optflag("3", "", "suppress column 3 (lines that appear in both files)"),
         getopts::optopt("", "output-delimiter", "separate columns with STR", "STR"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(err) => fail!("{}", err.to_err_msg()),
     };
@@ -118,7 +134,7 @@ pub fn uumain(args: Vec<String>) {
         println!("");
         print(getopts::usage("Compare sorted files line by line.", opts.as_slice()).as_slice());
         if matches.free.len() != 2 {
-            os::set_exit_status(1);
+            util::set_status(1);
         }
         return;
     }
@@ -5,3 +5,11 @@ RM          := rm
 # Flags
 RUSTCFLAGS  := --opt-level=3
 RMFLAGS     :=
+
+# What stdbuf preloads into the command, from beside the stdbuf binary or
+# the multicall one
+ifeq ($(shell uname),Darwin)
+LIBSTDBUF   := libstdbuf.dylib
+else
+LIBSTDBUF   := libstdbuf.so
+endif
@@ -0,0 +1,142 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Backups of files that are about to be overwritten, as chosen with
+ * --backup[=CONTROL], -b and -S, and the VERSION_CONTROL and
+ * SIMPLE_BACKUP_SUFFIX environment variables.
+ */
+
+#![allow(dead_code)]
+
+use std::io::{fs, IoResult};
+use std::os;
+
+#[deriving(PartialEq)]
+pub enum BackupMode {
+    NoBackup,
+    // FILE~
+    SimpleBackup,
+    // FILE.~N~, one past the highest N there is
+    NumberedBackup,
+    // numbered if FILE already has numbered backups, simple otherwise
+    ExistingBackup,
+}
+
+// Works out the backup mode from the argument to --backup, which is None
+// when no CONTROL was given.  -b is the same as --backup with no CONTROL.
+pub fn mode(backup: bool, control: Option<String>) -> Result<BackupMode, String> {
+    if !backup {
+        return Ok(NoBackup);
+    }
+    match control {
+        Some(control) => parse_control(control.as_slice()),
+        None => match os::getenv("VERSION_CONTROL") {
+            Some(ref control) if !control.is_empty() => match parse_control(control.as_slice()) {
+                Ok(mode) => Ok(mode),
+                Err(msg) => Err(format!("{} (from VERSION_CONTROL)", msg))
+            },
+            _ => Ok(ExistingBackup)
+        }
+    }
+}
+
+// Any unambiguous prefix of a control name will do, as in GNU.
+fn parse_control(control: &str) -> Result<BackupMode, String> {
+    static CONTROLS: &'static [(&'static str, BackupMode)] = &[
+        ("none", NoBackup),
+        ("off", NoBackup),
+        ("simple", SimpleBackup),
+        ("never", SimpleBackup),
+        ("existing", ExistingBackup),
+        ("nil", ExistingBackup),
+        ("numbered", NumberedBackup),
+        ("t", NumberedBackup),
+    ];
+    let mut found = None;
+    for &(name, mode) in CONTROLS.iter() {
+        if name == control {
+            return Ok(mode);
+        }
+        if name.starts_with(control) && !control.is_empty() {
+            match found {
+                Some(other) if other != mode => {
+                    return Err(format!("ambiguous argument '{}' for backup type", control));
+                }
+                _ => found = Some(mode)
+            }
+        }
+    }
+    match found {
+        Some(mode) => Ok(mode),
+        None => Err(format!("invalid argument '{}' for backup type", control))
+    }
+}
+
+// The suffix for simple backups: the argument to -S, or SIMPLE_BACKUP_SUFFIX,
+// or ~.
+pub fn suffix(arg: Option<String>) -> String {
+    match arg {
+        Some(suffix) => suffix,
+        None => match os::getenv("SIMPLE_BACKUP_SUFFIX") {
+            Some(ref suffix) if !suffix.is_empty() && !suffix.as_slice().contains_char('/') => {
+                suffix.clone()
+            }
+            _ => "~".to_string()
+        }
+    }
+}
+
+// Where the backup of path would go, or None for NoBackup.
+pub fn backup_path(path: &Path, mode: BackupMode, suffix: &str) -> Option<Path> {
+    let name = match path.filename_str() {
+        Some(name) => name.to_string(),
+        None => return None
+    };
+    let simple = || path.with_filename(format!("{}{}", name, suffix));
+    let numbered = |n: uint| path.with_filename(format!("{}.~{}~", name, n));
+    match mode {
+        NoBackup => None,
+        SimpleBackup => Some(simple()),
+        NumberedBackup => Some(numbered(highest_backup(path, name.as_slice()) + 1)),
+        ExistingBackup => match highest_backup(path, name.as_slice()) {
+            0 => Some(simple()),
+            n => Some(numbered(n + 1))
+        }
+    }
+}
+
+// The highest N of the NAME.~N~ files beside path, or 0 if there are none.
+fn highest_backup(path: &Path, name: &str) -> uint {
+    let dir = path.dir_path();
+    let entries = match fs::readdir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0
+    };
+    let prefix = format!("{}.~", name);
+    entries.iter().filter_map(|entry| entry.filename_str()).filter_map(|entry| {
+        if entry.starts_with(prefix.as_slice()) && entry.ends_with("~")
+                && entry.len() > prefix.len() + 1 {
+            from_str::<uint>(entry.slice(prefix.len(), entry.len() - 1))
+        } else {
+            None
+        }
+    }).max().unwrap_or(0)
+}
+
+// Moves path out of the way to its backup, returning where it went.
+pub fn make_backup(path: &Path, mode: BackupMode, suffix: &str) -> IoResult<Option<Path>> {
+    match backup_path(path, mode, suffix) {
+        Some(backup) => {
+            try!(fs::rename(path, &backup));
+            Ok(Some(backup))
+        }
+        None => Ok(None)
+    }
+}
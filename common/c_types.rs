@@ -5,19 +5,34 @@ extern crate libc;
 use self::libc::{
     c_char,
     c_int,
+    gid_t,
     uid_t,
     time_t
 };
-use self::libc::funcs::posix88::unistd::getgroups;
+use self::libc::funcs::posix88::unistd::{getegid, getgroups};
 
 use std::vec::Vec;
 
+use std::ptr;
 use std::ptr::read;
 use std::str::raw::from_c_str;
 
+// glibc has none of the BSD login class and expiry fields.
+#[cfg(target_os = "linux")]
+pub struct c_passwd {
+    pub pw_name:    *c_char,    /* user name */
+    pub pw_passwd:  *c_char,    /* user password */
+    pub pw_uid:     c_int,      /* user uid */
+    pub pw_gid:     c_int,      /* user gid */
+    pub pw_gecos:   *c_char,
+    pub pw_dir:     *c_char,
+    pub pw_shell:   *c_char
+}
+
+#[cfg(not(target_os = "linux"))]
 pub struct c_passwd {
     pub pw_name:    *c_char,    /* user name */
-    pub pw_passwd:  *c_char,    /* user name */
+    pub pw_passwd:  *c_char,    /* user password */
     pub pw_uid:     c_int,      /* user uid */
     pub pw_gid:     c_int,      /* user gid */
     pub pw_change:  time_t,
@@ -28,27 +43,11 @@ pub struct c_passwd {
     pub pw_expire:  time_t
 }
 
-#[cfg(target_os = "macos")]
-pub struct utsname {
-    pub sysname: [c_char, ..256],
-    pub nodename: [c_char, ..256],
-    pub release: [c_char, ..256],
-    pub version: [c_char, ..256],
-    pub machine: [c_char, ..256]
-}
-
-#[cfg(target_os = "linux")]
-pub struct utsname {
-    pub sysname: [c_char, ..65],
-    pub nodename: [c_char, ..65],
-    pub release: [c_char, ..65],
-    pub version: [c_char, ..65],
-    pub machine: [c_char, ..65],
-    pub domainame: [c_char, ..65]
-}
-
 pub struct c_group {
-    pub gr_name: *c_char /* group name */
+    pub gr_name: *c_char,   /* group name */
+    pub gr_passwd: *c_char, /* group password */
+    pub gr_gid: gid_t,      /* group id */
+    pub gr_mem: **c_char    /* group members */
 }
 
 pub struct c_tm {
@@ -68,79 +67,147 @@ extern {
     pub fn getpwnam(login: *c_char) -> *c_passwd;
     pub fn getgrouplist(name:   *c_char,
                         basegid: c_int,
-                        groups: *c_int,
+                        groups: *mut c_int,
                         ngroups: *mut c_int) -> c_int;
     pub fn getgrgid(gid: uid_t) -> *c_group;
+    pub fn getgrnam(name: *c_char) -> *c_group;
 }
 
 pub fn get_pw_from_args(free: &Vec<String>) -> Option<c_passwd> {
     if free.len() == 1 {
         let username = free.get(0).as_slice();
+        match getpw(username) {
+            Some(pw) => Some(pw),
+            None => crash!(1, "{:s}: no such user", username)
+        }
+    } else {
+        None
+    }
+}
 
-        // Passed user as id
-        if username.chars().all(|c| c.is_digit()) {
-            let id = from_str::<i32>(username).unwrap();
-            let pw_pointer = unsafe { getpwuid(id) };
+// The user with the given name, or with the given uid if it is all digits.
+pub fn getpw(username: &str) -> Option<c_passwd> {
+    let pw_pointer = if username.len() > 0 && username.chars().all(|c| c.is_digit()) {
+        match from_str::<i32>(username) {
+            Some(id) => unsafe { getpwuid(id) },
+            None => return None
+        }
+    } else {
+        username.with_c_str(|n| unsafe { getpwnam(n) })
+    };
+    if pw_pointer.is_not_null() {
+        Some(unsafe { read(pw_pointer) })
+    } else {
+        None
+    }
+}
 
-            if pw_pointer.is_not_null() {
-                Some(unsafe { read(pw_pointer) })
-            } else {
-                crash!(1, "{:s}: no such user", username);
-            }
+pub fn uid2usr(uid: uid_t) -> Option<String> {
+    let pw_pointer = unsafe { getpwuid(uid as c_int) };
+    if pw_pointer.is_not_null() {
+        Some(unsafe { from_c_str(read(pw_pointer).pw_name) })
+    } else {
+        None
+    }
+}
 
-        // Passed the username as a string
-        } else {
-            let pw_pointer = unsafe {
-                getpwnam(username.as_slice().as_ptr() as *i8)
-            };
-            if pw_pointer.is_not_null() {
-                Some(unsafe { read(pw_pointer) })
-            } else {
-                crash!(1, "{:s}: no such user", username);
-            }
-        }
+pub fn gid2grp(gid: gid_t) -> Option<String> {
+    let group = unsafe { getgrgid(gid) };
+    if group.is_not_null() {
+        Some(unsafe { from_c_str(read(group).gr_name) })
     } else {
         None
     }
 }
 
-static NGROUPS: i32 = 20;
+pub fn usr2uid(name: &str) -> Option<uid_t> {
+    let pw_pointer = name.with_c_str(|n| unsafe { getpwnam(n) });
+    if pw_pointer.is_not_null() {
+        Some(unsafe { (*pw_pointer).pw_uid as uid_t })
+    } else {
+        None
+    }
+}
 
-pub fn group(possible_pw: Option<c_passwd>, nflag: bool) {
-    let mut groups = Vec::with_capacity(NGROUPS as uint);
-    let mut ngroups;
-
-    if possible_pw.is_some() {
-        ngroups = NGROUPS;
-        unsafe {
-            getgrouplist(
-                possible_pw.unwrap().pw_name,
-                possible_pw.unwrap().pw_gid,
-                groups.as_ptr(),
-                &mut ngroups);
-        }
+pub fn grp2gid(name: &str) -> Option<gid_t> {
+    let group = name.with_c_str(|n| unsafe { getgrnam(n) });
+    if group.is_not_null() {
+        Some(unsafe { (*group).gr_gid })
     } else {
-        ngroups = unsafe {
-            getgroups(NGROUPS, groups.as_mut_ptr() as *mut u32)
-        };
+        None
+    }
+}
+
+// The login group of the user with the given uid.
+pub fn uid2gid(uid: uid_t) -> Option<gid_t> {
+    let pw_pointer = unsafe { getpwuid(uid as c_int) };
+    if pw_pointer.is_not_null() {
+        Some(unsafe { (*pw_pointer).pw_gid as gid_t })
+    } else {
+        None
     }
+}
+
+static NGROUPS: i32 = 20;
 
+// Every group the user is in, their login group first, as in /etc/group.
+pub fn user_groups(pw: &c_passwd) -> Vec<gid_t> {
+    let mut size = NGROUPS;
+    loop {
+        let mut groups: Vec<c_int> = Vec::from_elem(size as uint, 0);
+        let mut ngroups = size;
+        let ret = unsafe {
+            getgrouplist(pw.pw_name, pw.pw_gid, groups.as_mut_ptr(), &mut ngroups)
+        };
+        if ret >= 0 {
+            groups.truncate(ngroups as uint);
+            return groups.move_iter().map(|g| g as gid_t).collect();
+        }
+        // glibc says how many there are, other systems leave it to guessing
+        size = if ngroups > size { ngroups } else { size * 2 };
+    }
+}
 
-    unsafe { groups.set_len(ngroups as uint) };
+// The groups of the running process, with the effective group first.
+pub fn process_groups() -> Vec<gid_t> {
+    let count = unsafe { getgroups(0, ptr::mut_null()) };
+    let mut groups: Vec<gid_t> = Vec::from_elem(if count > 0 { count as uint } else { 0 }, 0);
+    let count = unsafe { getgroups(groups.len() as c_int, groups.as_mut_ptr()) };
+    groups.truncate(if count > 0 { count as uint } else { 0 });
 
+    let mut list = vec!(unsafe { getegid() });
     for &g in groups.iter() {
+        if !list.contains(&g) {
+            list.push(g);
+        }
+    }
+    list
+}
+
+// The groups of the user, or of the process without one, separated by
+// spaces; by name with nflag, falling back on the number for a group that
+// has none.
+pub fn group_line(possible_pw: Option<c_passwd>, nflag: bool) -> String {
+    let groups = match possible_pw {
+        Some(ref pw) => user_groups(pw),
+        None => process_groups()
+    };
+    let names: Vec<String> = groups.iter().map(|&g| {
         if nflag {
-            let group = unsafe { getgrgid(g as u32) };
-            if group.is_not_null() {
-                let name = unsafe {
-                    from_c_str(read(group).gr_name)
-                };
-                print!("{:s} ", name);
+            match gid2grp(g) {
+                Some(name) => name,
+                None => {
+                    show_warning!("cannot find name for group ID {}", g);
+                    g.to_str()
+                }
             }
         } else {
-            print!("{:d} ", g);
+            g.to_str()
         }
-    }
+    }).collect();
+    names.connect(" ")
+}
 
-    println!("");
+pub fn group(possible_pw: Option<c_passwd>, nflag: bool) {
+    println!("{}", group_line(possible_pw, nflag));
 }
@@ -0,0 +1,105 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Canonical names: absolute, with every symbolic link resolved and no ".",
+ * ".." or repeated slashes.  Crates using this module need
+ * common/fsext.rs declared as fsext.
+ */
+
+#![allow(dead_code)]
+
+extern crate libc;
+
+use std::io::{IoError, IoResult};
+use std::os;
+
+use fsext;
+
+#[deriving(PartialEq)]
+pub enum CanonMode {
+    // every component must exist
+    CanonExisting,
+    // all but the last component must exist
+    CanonAllButLast,
+    // nothing needs to exist
+    CanonMissing,
+}
+
+// As many links as Linux follows before giving up with ELOOP.
+static MAX_LINKS: uint = 40;
+
+// Works on the name as given rather than a Path, as a Path would take
+// "link/.." to mean "." before it could be resolved.
+pub fn canonicalize(name: &[u8], mode: CanonMode) -> IoResult<Path> {
+    if name.is_empty() {
+        return Err(IoError::from_errno(libc::ENOENT as uint, false));
+    }
+
+    let mut result = if is_absolute(name) { Path::new("/") } else { os::getcwd() };
+    // what is left to resolve, the next component last
+    let mut pending = vec!();
+    push_components(&mut pending, name);
+    let mut links = 0u;
+
+    loop {
+        let component = match pending.pop() {
+            Some(component) => component,
+            None => break
+        };
+        if component.as_slice() == ".".as_bytes() {
+            continue;
+        } else if component.as_slice() == "..".as_bytes() {
+            result.pop();
+            continue;
+        }
+
+        let next = result.join(component.as_slice());
+        let last = pending.is_empty();
+        match fsext::lstat_path(&next) {
+            Ok(stat) if fsext::is_lnk(stat.st_mode) => {
+                links += 1;
+                if links > MAX_LINKS {
+                    return Err(IoError::from_errno(libc::ELOOP as uint, false));
+                }
+                let target = try!(fsext::read_link(&next));
+                if is_absolute(target.as_slice()) {
+                    result = Path::new("/");
+                }
+                push_components(&mut pending, target.as_slice());
+            }
+            Ok(stat) => {
+                if !last && !fsext::is_dir(stat.st_mode) && mode != CanonMissing {
+                    return Err(IoError::from_errno(libc::ENOTDIR as uint, false));
+                }
+                result = next;
+            }
+            Err(e) => {
+                if mode == CanonMissing || (mode == CanonAllButLast && last) {
+                    result = next;
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn is_absolute(name: &[u8]) -> bool {
+    !name.is_empty() && name[0] == '/' as u8
+}
+
+fn push_components(pending: &mut Vec<Vec<u8>>, name: &[u8]) {
+    for component in name.split(|&b| b == '/' as u8).rev() {
+        if !component.is_empty() {
+            pending.push(component.to_vec());
+        }
+    }
+}
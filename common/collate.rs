@@ -0,0 +1,72 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Ordering text as the locale's LC_COLLATE says, through strcoll(3), for the
+ * utilities that sort.  The C and POSIX locales, and any locale the system
+ * does not have, order byte by byte.  Crates using this module need `extern
+ * crate libc`.
+ */
+
+#![allow(dead_code)]
+
+use std::cmp::{Equal, Ordering};
+use libc::{c_char, c_int};
+
+#[cfg(target_os = "linux")]
+static LC_COLLATE: c_int = 3;
+#[cfg(not(target_os = "linux"))]
+static LC_COLLATE: c_int = 1;
+
+extern {
+    fn setlocale(category: c_int, locale: *c_char) -> *c_char;
+    fn strcoll(a: *c_char, b: *c_char) -> c_int;
+}
+
+pub struct Collator {
+    bytewise: bool,
+}
+
+impl Collator {
+    // Byte order, whatever the locale.
+    pub fn bytewise() -> Collator {
+        Collator { bytewise: true }
+    }
+
+    // The collation LC_ALL, LC_COLLATE or LANG chooses, in that order.
+    pub fn from_env() -> Collator {
+        let name = "".with_c_str(|empty| unsafe {
+            let name = setlocale(LC_COLLATE, empty);
+            if name.is_null() {
+                None
+            } else {
+                ::std::c_str::CString::new(name, false).as_str().map(|s| s.to_string())
+            }
+        });
+        match name {
+            Some(ref name) if name.as_slice() != "C" && name.as_slice() != "POSIX" => {
+                Collator { bytewise: false }
+            }
+            _ => Collator::bytewise()
+        }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        // strcoll cannot see past a NUL, and the locale may rank different
+        // strings the same; bytes decide both, so the order stays total
+        if self.bytewise || a.contains_char('\0') || b.contains_char('\0') {
+            return a.cmp(&b);
+        }
+        let order = a.with_c_str(|a| b.with_c_str(|b| unsafe { strcoll(a, b) }));
+        match order.cmp(&0) {
+            Equal => a.cmp(&b),
+            order => order
+        }
+    }
+}
@@ -0,0 +1,72 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * The LS_COLORS database: a colon separated list of KEY=SGR pairs, where KEY
+ * is either a two letter file class (di, ln, ex, ...) or *SUFFIX.
+ */
+
+#![allow(dead_code)]
+
+use std::os;
+
+// What ls uses when LS_COLORS is not set at all.
+pub static BUILTIN: &'static str = "di=01;34:ln=01;36:pi=33:so=01;35:do=01;35:\
+bd=01;33:cd=01;33:ex=01;32:su=37;41:sg=30;43:st=37;44:ow=34;42:tw=30;42";
+
+pub struct LsColors {
+    classes: Vec<(String, String)>,
+    suffixes: Vec<(String, String)>,
+}
+
+impl LsColors {
+    pub fn parse(spec: &str) -> LsColors {
+        let mut colors = LsColors { classes: vec!(), suffixes: vec!() };
+        for item in spec.split(':') {
+            let mut parts = item.splitn('=', 1);
+            let key = parts.next().unwrap_or("");
+            let value = match parts.next() {
+                Some(value) => value,
+                None => continue
+            };
+            if key.starts_with("*") {
+                colors.suffixes.push((key.slice_from(1).to_string(), value.to_string()));
+            } else if !key.is_empty() {
+                colors.classes.push((key.to_string(), value.to_string()));
+            }
+        }
+        colors
+    }
+
+    pub fn from_env() -> LsColors {
+        match os::getenv("LS_COLORS") {
+            Some(spec) => LsColors::parse(spec.as_slice()),
+            None => LsColors::parse(BUILTIN)
+        }
+    }
+
+    // The SGR sequence for a file class, if there is a non-empty one.  Later
+    // entries override earlier ones, as they do for ls.
+    pub fn class(&self, key: &str) -> Option<&str> {
+        lookup(self.classes.as_slice(), |k| k == key)
+    }
+
+    pub fn suffix(&self, name: &str) -> Option<&str> {
+        lookup(self.suffixes.as_slice(), |suffix| name.ends_with(suffix))
+    }
+}
+
+fn lookup<'a>(pairs: &'a [(String, String)], matches: |&str| -> bool) -> Option<&'a str> {
+    for &(ref key, ref value) in pairs.iter().rev() {
+        if matches(key.as_slice()) {
+            return if value.is_empty() { None } else { Some(value.as_slice()) };
+        }
+    }
+    None
+}
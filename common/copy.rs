@@ -0,0 +1,539 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) Jordy Dickinson <jordy.dickinson@gmail.com>
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * The copying engine behind cp, and behind mv when a rename has to cross
+ * file systems.  Crates using this module need `extern crate collections`,
+ * `extern crate time` and common/{util,fsext,fdio,walk,xattr,size,progress,
+ * reflink}.rs declared under their own names.
+ */
+
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::os;
+use std::io;
+use std::io::{fs, EndOfFile, IoError, IoResult};
+
+use collections::hashmap::HashMap;
+use libc;
+
+use fsext;
+use fdio::Fd;
+use progress::Progress;
+use reflink;
+use walk::{Follow, Visitor, WalkEntry, Walker};
+use xattr;
+
+static BUF_SIZE: uint = 64 * 1024;
+// the size of the runs of zeros that --sparse turns into holes
+static HOLE_SIZE: uint = 4096;
+
+pub struct Options {
+    pub force: bool,
+    pub recursive: bool,
+    pub follow: Follow,
+    pub umask: libc::mode_t,
+    pub preserve: Preserve,
+    pub reflink: Reflink,
+    pub sparse: Sparse,
+    pub overwrite: Overwrite,
+    pub update: bool,
+    pub progress: Option<RefCell<Progress>>,
+}
+
+#[deriving(PartialEq)]
+pub enum Overwrite {
+    OverwriteAlways,
+    OverwriteNever,
+    OverwriteInteractive,
+}
+
+#[deriving(PartialEq)]
+pub enum Sparse {
+    SparseNever,
+    SparseAuto,
+    SparseAlways,
+}
+
+#[deriving(PartialEq)]
+pub enum Reflink {
+    ReflinkNever,
+    ReflinkAuto,
+    ReflinkAlways,
+}
+
+pub struct Preserve {
+    pub mode: bool,
+    pub ownership: bool,
+    pub timestamps: bool,
+    pub links: bool,
+    pub xattr: bool,
+    pub context: bool,
+    // xattrs and contexts are only worth complaining about when they were
+    // asked for by name, not as part of "all"
+    pub quiet: bool,
+}
+
+impl Preserve {
+    pub fn none() -> Preserve {
+        Preserve {
+            mode: false,
+            ownership: false,
+            timestamps: false,
+            links: false,
+            xattr: false,
+            context: false,
+            quiet: false,
+        }
+    }
+
+    pub fn all() -> Preserve {
+        let mut preserve = Preserve::none();
+        let _ = preserve.set("all", true);
+        preserve
+    }
+
+    pub fn any(&self) -> bool {
+        self.mode || self.ownership || self.timestamps || self.xattr || self.context
+    }
+
+    // Turns the attributes in a --preserve style list on or off, returning
+    // the first one that is not recognised.
+    pub fn set(&mut self, list: &str, value: bool) -> Result<(), String> {
+        for attr in list.split(',') {
+            match attr {
+                "mode" => self.mode = value,
+                "ownership" => self.ownership = value,
+                "timestamps" => self.timestamps = value,
+                "links" => self.links = value,
+                "xattr" => { self.xattr = value; self.quiet = false; }
+                "context" => { self.context = value; self.quiet = false; }
+                "all" => {
+                    self.mode = value;
+                    self.ownership = value;
+                    self.timestamps = value;
+                    self.links = value;
+                    self.xattr = value;
+                    self.context = value;
+                    self.quiet = value;
+                }
+                _ => return Err(attr.to_string())
+            }
+        }
+        Ok(())
+    }
+}
+
+pub static DEFAULT_PRESERVE: &'static str = "mode,ownership,timestamps";
+
+// Everything that goes wrong is reported as it happens; the result only says
+// whether anything did.
+macro_rules! copy_error(
+    ($visitor:expr, $($args:expr),+) => ({
+        $visitor.ok = false;
+        show_error!(1, $($args),+);
+    })
+)
+
+// Copies source, and with options.recursive everything below it, to dest.
+// links maps the files already copied, by source device and inode, to their
+// copies so that hard links can be recreated with --preserve=links.
+pub fn copy_path(source: &Path, dest: &Path, options: &Options,
+                 links: &mut HashMap<(u64, u64), Path>) -> bool {
+    let is_dir = match fsext::stat_path(source) {
+        Ok(stat) => fsext::is_dir(stat.st_mode),
+        Err(_) => false
+    };
+    if options.recursive && is_dir && is_inside(dest, source) {
+        show_error!(1, "cannot copy a directory, '{}', into itself, '{}'",
+                    source.display(), dest.display());
+        return false;
+    }
+    let mut visitor = CopyVisitor {
+        options: options,
+        source: source.clone(),
+        dest: dest.clone(),
+        created: vec!(),
+        links: links,
+        ok: true,
+    };
+    Walker::new().follow(options.follow).walk(source, &mut visitor);
+    visitor.ok
+}
+
+// Whether path is dir or somewhere below it, once both are made absolute.
+pub fn is_inside(path: &Path, dir: &Path) -> bool {
+    let path = os::make_absolute(path);
+    let dir = os::make_absolute(dir);
+    path == dir || dir.is_ancestor_of(&path)
+}
+
+struct CopyVisitor<'a> {
+    options: &'a Options,
+    source: Path,
+    dest: Path,
+    // for each directory being copied, whether we created its destination
+    created: Vec<bool>,
+    links: &'a mut HashMap<(u64, u64), Path>,
+    ok: bool,
+}
+
+impl<'a> CopyVisitor<'a> {
+    fn dest_for(&self, path: &Path) -> Path {
+        match path.path_relative_from(&self.source) {
+            Some(relative) => self.dest.join(relative),
+            None => self.dest.clone()
+        }
+    }
+}
+
+impl<'a> Visitor for CopyVisitor<'a> {
+    fn enter_dir(&mut self, dir: &WalkEntry) -> bool {
+        if !self.options.recursive {
+            copy_error!(self, "-r not specified; omitting directory '{}'", dir.path.display());
+            self.created.push(false);
+            return false;
+        }
+        let dest = self.dest_for(&dir.path);
+        match fsext::stat_path(&dest) {
+            Ok(stat) if fsext::is_dir(stat.st_mode) => {
+                self.created.push(false);
+                true
+            }
+            Ok(_) => {
+                copy_error!(self, "cannot overwrite non-directory '{}' with directory '{}'",
+                            dest.display(), dir.path.display());
+                self.created.push(false);
+                false
+            }
+            Err(_) => {
+                // keep the directory writable until its contents are in
+                let mode = dir.stat.st_mode as libc::mode_t & 0o777 | 0o700;
+                match fs::mkdir(&dest, io::FilePermission::from_bits_truncate(mode as u32)) {
+                    Ok(()) => {
+                        self.created.push(true);
+                        true
+                    }
+                    Err(e) => {
+                        copy_error!(self, "cannot create directory '{}': {}",
+                                    dest.display(), ::util::strerror(&e));
+                        self.created.push(false);
+                        false
+                    }
+                }
+            }
+        }
+    }
+
+    fn leave_dir(&mut self, dir: &WalkEntry) {
+        let created = self.created.pop() == Some(true);
+        let dest = self.dest_for(&dir.path);
+        if created && !self.options.preserve.mode {
+            let mode = dir.stat.st_mode as libc::mode_t & 0o777 & !self.options.umask;
+            match fs::chmod(&dest, io::FilePermission::from_bits_truncate(mode as u32)) {
+                Ok(()) => {}
+                Err(e) => copy_error!(self, "cannot set permissions of '{}': {}",
+                                      dest.display(), ::util::strerror(&e))
+            }
+        }
+        if self.options.recursive {
+            preserve_attrs(&dir.path, &dir.stat, &dest, self.options);
+        }
+    }
+
+    fn visit_file(&mut self, file: &WalkEntry) {
+        let dest = self.dest_for(&file.path);
+        match fsext::stat_path(&dest) {
+            Ok(dest_stat) => {
+                if fsext::is_dir(dest_stat.st_mode) {
+                    copy_error!(self, "cannot overwrite directory '{}' with non-directory",
+                                dest.display());
+                    return;
+                }
+                if same_file(&file.stat, &dest_stat) {
+                    copy_error!(self, "'{}' and '{}' are the same file",
+                                file.path.display(), dest.display());
+                    return;
+                }
+                if !may_overwrite(&file.stat, &dest, &dest_stat, self.options) {
+                    return;
+                }
+            }
+            Err(_) => {}
+        }
+
+        let id = (file.stat.st_dev as u64, file.stat.st_ino as u64);
+        if self.options.preserve.links && file.stat.st_nlink > 1 {
+            let linked = match self.links.find(&id) {
+                Some(first) => Some((first.clone(), make_link(first, &dest, self.options))),
+                None => None
+            };
+            match linked {
+                Some((_, Ok(()))) => return,
+                Some((first, Err(e))) => {
+                    copy_error!(self, "cannot create hard link '{}' to '{}': {}",
+                                dest.display(), first.display(), ::util::strerror(&e));
+                    return;
+                }
+                None => {}
+            }
+        }
+
+        let mode = file.stat.st_mode as libc::mode_t;
+        let result = if fsext::is_lnk(mode) {
+            copy_link(&file.path, &dest, self.options)
+        } else if self.options.recursive && !fsext::is_reg(mode) {
+            copy_special(&dest, &file.stat, self.options)
+        } else {
+            copy_file(&file.path, &dest, &file.stat, self.options)
+        };
+        match result {
+            Ok(()) => {
+                preserve_attrs(&file.path, &file.stat, &dest, self.options);
+                if self.options.preserve.links && file.stat.st_nlink > 1 {
+                    self.links.insert(id, dest);
+                }
+            }
+            Err(e) => copy_error!(self, "cannot copy '{}' to '{}': {}",
+                                  file.path.display(), dest.display(), ::util::strerror(&e))
+        }
+    }
+
+    fn cycle(&mut self, dir: &WalkEntry) {
+        copy_error!(self, "'{}': not copying a directory that contains itself",
+                    dir.path.display());
+    }
+
+    fn error(&mut self, path: &Path, err: IoError) {
+        copy_error!(self, "cannot access '{}': {}", path.display(), ::util::strerror(&err));
+    }
+}
+
+fn make_link(first: &Path, dest: &Path, options: &Options) -> IoResult<()> {
+    match fs::link(first, dest) {
+        Err(ref e) if e.kind == io::PathAlreadyExists && options.force => {
+            try!(fs::unlink(dest));
+            fs::link(first, dest)
+        }
+        result => result
+    }
+}
+
+// Copies whatever --preserve asked for from source to the freshly copied
+// dest.  Ownership goes first since changing it can clear the set-ID bits,
+// and timestamps last since everything else counts as a change.
+pub fn preserve_attrs(source: &Path, src_stat: &fsext::c_stat, dest: &Path, options: &Options) {
+    let preserve = &options.preserve;
+    if !preserve.any() {
+        return;
+    }
+    let mode = src_stat.st_mode as libc::mode_t;
+    // a link's own mode cannot be changed, the rest applies to the link itself
+    let follow = !fsext::is_lnk(mode);
+
+    if preserve.ownership {
+        match fsext::set_owner(dest, src_stat.st_uid, src_stat.st_gid, follow) {
+            Ok(()) => {}
+            Err(ref e) if e.kind == io::PermissionDenied => {
+                // only root can give files away; keep at least the group
+                let _ = fsext::set_owner(dest, fsext::UNCHANGED, src_stat.st_gid, follow);
+            }
+            Err(e) => show_error!(1, "failed to preserve ownership for '{}': {}", dest.display(),
+                                  ::util::strerror(&e))
+        }
+    }
+    if preserve.mode && follow {
+        match fsext::set_mode(dest, mode & 0o7777) {
+            Ok(()) => {}
+            Err(e) => show_error!(1, "failed to preserve permissions for '{}': {}", dest.display(),
+                                  ::util::strerror(&e))
+        }
+    }
+    let attrs = if preserve.xattr {
+        xattr::copy_all(source, dest)
+    } else if preserve.context {
+        xattr::copy(source, dest, xattr::SELINUX_CONTEXT)
+    } else {
+        Ok(())
+    };
+    match attrs {
+        Err(e) if !preserve.quiet => {
+            show_error!(1, "failed to preserve extended attributes for '{}': {}", dest.display(),
+                        ::util::strerror(&e))
+        }
+        _ => {}
+    }
+    if preserve.timestamps {
+        match fsext::set_times(dest, fsext::atime(src_stat), fsext::mtime(src_stat), follow) {
+            Ok(()) => {}
+            Err(e) => show_error!(1, "failed to preserve times for '{}': {}", dest.display(),
+                                  ::util::strerror(&e))
+        }
+    }
+}
+
+fn copy_link(source: &Path, dest: &Path, options: &Options) -> IoResult<()> {
+    let target = try!(fs::readlink(source));
+    match fs::symlink(&target, dest) {
+        Err(ref e) if e.kind == io::PathAlreadyExists && options.force => {
+            try!(fs::unlink(dest));
+            fs::symlink(&target, dest)
+        }
+        result => result
+    }
+}
+
+// Recursive copies recreate fifos and device nodes rather than reading from
+// them.
+fn copy_special(dest: &Path, src_stat: &fsext::c_stat, options: &Options) -> IoResult<()> {
+    let mode = src_stat.st_mode as libc::mode_t;
+    let perm = mode & 0o777 & !options.umask;
+    if options.force && fsext::lstat_path(dest).is_ok() {
+        try!(fs::unlink(dest));
+    }
+    if fsext::is_fifo(mode) {
+        fsext::make_fifo(dest, perm)
+    } else if fsext::is_chr(mode) || fsext::is_blk(mode) {
+        fsext::make_node(dest, fsext::file_type(mode) | perm, src_stat.st_rdev as u64)
+    } else {
+        Err(IoError {
+            kind: io::OtherIoError,
+            desc: "cannot copy a socket",
+            detail: None
+        })
+    }
+}
+
+// -u skips destinations at least as new as their source without asking, -i
+// asks about the rest.
+fn may_overwrite(src_stat: &fsext::c_stat, dest: &Path, dest_stat: &fsext::c_stat,
+                 options: &Options) -> bool {
+    if options.overwrite == OverwriteNever {
+        return false;
+    }
+    if options.update && fsext::mtime(dest_stat) >= fsext::mtime(src_stat) {
+        return false;
+    }
+    if options.overwrite == OverwriteInteractive {
+        return prompt_yes!("overwrite '{}'?", dest.display());
+    }
+    true
+}
+
+pub fn same_file(a: &fsext::c_stat, b: &fsext::c_stat) -> bool {
+    a.st_dev == b.st_dev && a.st_ino == b.st_ino
+}
+
+fn copy_file(source: &Path, dest: &Path, src_stat: &fsext::c_stat,
+             options: &Options) -> IoResult<()> {
+    match options.progress {
+        Some(ref progress) => {
+            let name = source.display().to_str();
+            progress.borrow_mut().start_file(name.as_slice(), src_stat.st_size as u64);
+        }
+        None => {}
+    }
+    let result = copy_data(source, dest, src_stat, options);
+    match options.progress {
+        Some(ref progress) => progress.borrow_mut().finish_file(),
+        None => {}
+    }
+    result
+}
+
+fn copy_data(source: &Path, dest: &Path, src_stat: &fsext::c_stat,
+             options: &Options) -> IoResult<()> {
+    if options.reflink != ReflinkNever && fsext::lstat_path(dest).is_err() {
+        match reflink::clone_path(source, dest) {
+            Ok(()) => return Ok(()),
+            Err(e) => if options.reflink == ReflinkAlways && cfg!(target_os = "macos") {
+                return Err(e);
+            }
+        }
+    }
+
+    let mut src = try!(Fd::open(source, libc::O_RDONLY, 0));
+    let mut dst = try!(open_dest(dest, src_stat, options));
+
+    // With --sparse=auto, a file that takes up less space than its size has
+    // holes in it, which are likely where its runs of zeros are.
+    let make_holes = fsext::is_reg(src_stat.st_mode) && match options.sparse {
+        SparseAlways => true,
+        SparseAuto => (src_stat.st_blocks as u64) * 512 < src_stat.st_size as u64,
+        SparseNever => false
+    };
+
+    if options.reflink != ReflinkNever {
+        match reflink::clone_fd(&src, &dst) {
+            Ok(()) => return Ok(()),
+            Err(e) => if options.reflink == ReflinkAlways {
+                return Err(e);
+            }
+        }
+        // not a clone, but the kernel may still share extents or at least
+        // avoid copying through userspace.  Where it cannot share them it
+        // writes the holes out as zeros, so it is no use when holes are to be
+        // kept, and --sparse=never asks for every block to be written.
+        if !make_holes && options.sparse != SparseNever && fsext::is_reg(src_stat.st_mode)
+                && try!(reflink::copy_range(&src, &dst)) {
+            return Ok(());
+        }
+    }
+
+    let mut buf = Vec::from_elem(BUF_SIZE, 0u8);
+    let mut size = 0u64;
+    let mut ends_in_hole = false;
+    loop {
+        let n = match src.read(buf.as_mut_slice()) {
+            Ok(n) => n,
+            Err(ref e) if e.kind == EndOfFile => break,
+            Err(e) => return Err(e)
+        };
+        size += n as u64;
+        match options.progress {
+            Some(ref progress) => progress.borrow_mut().advance(n as u64),
+            None => {}
+        }
+        if !make_holes {
+            try!(dst.write(buf.slice_to(n)));
+            continue;
+        }
+        for chunk in buf.slice_to(n).chunks(HOLE_SIZE) {
+            if chunk.iter().all(|&b| b == 0) {
+                try!(dst.skip(chunk.len() as i64));
+                ends_in_hole = true;
+            } else {
+                try!(dst.write(chunk));
+                ends_in_hole = false;
+            }
+        }
+    }
+    // seeking past the end does not make the file any longer
+    if ends_in_hole {
+        try!(dst.truncate(size));
+    }
+    Ok(())
+}
+
+// A new destination gets the source's permissions, less the umask, which
+// open(2) applies for us.  An existing one keeps its own mode.
+fn open_dest(dest: &Path, src_stat: &fsext::c_stat, options: &Options) -> IoResult<Fd> {
+    let flags = libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC;
+    let mode = src_stat.st_mode as libc::mode_t & 0o777;
+    match Fd::open(dest, flags, mode) {
+        Ok(fd) => Ok(fd),
+        Err(ref e) if options.force && e.kind != io::FileNotFound => {
+            try!(fs::unlink(dest));
+            Fd::open(dest, flags, mode)
+        }
+        Err(e) => Err(e)
+    }
+}
@@ -0,0 +1,436 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Dates as people write them on the command line, for touch -d and date -d.
+ * This understands a useful subset of what GNU's parse_datetime does:
+ *
+ *   @SECONDS                       seconds since the epoch
+ *   2014-06-21, 06/21/2014         calendar dates
+ *   Jun 21 2014, 21 June 2014      dates with month names
+ *   13:45, 13:45:10.5              times of day, optionally followed by Z,
+ *                                  UTC or an offset such as +0100
+ *   2014-06-21T13:45:10Z           ISO 8601
+ *   now, today, yesterday, tomorrow, noon, midnight
+ *   3 days, -2 weeks, 1 hour ago, next month, last year
+ *
+ * Crates using this module need `extern crate time`.
+ */
+
+#![allow(dead_code)]
+
+use std::ascii::StrAsciiExt;
+use std::num::Integer;
+use time;
+use time::Timespec;
+
+macro_rules! try_opt(
+    ($e:expr) => (match $e { Some(x) => x, None => return None })
+)
+
+// A broken down date and time, with no time zone attached.
+pub struct Civil {
+    pub year: i64,
+    pub month: i64,
+    pub day: i64,
+    pub hour: i64,
+    pub minute: i64,
+    pub second: i64,
+    pub nsec: i64,
+}
+
+impl Civil {
+    pub fn local(t: Timespec) -> Civil {
+        Civil::from_tm(time::at(t))
+    }
+
+    pub fn utc(t: Timespec) -> Civil {
+        Civil::from_tm(time::at_utc(t))
+    }
+
+    fn from_tm(tm: time::Tm) -> Civil {
+        Civil {
+            year: tm.tm_year as i64 + 1900,
+            month: tm.tm_mon as i64 + 1,
+            day: tm.tm_mday as i64,
+            hour: tm.tm_hour as i64,
+            minute: tm.tm_min as i64,
+            second: tm.tm_sec as i64,
+            nsec: tm.tm_nsec as i64,
+        }
+    }
+
+    // The instant this names in UTC.  Out of range days and months carry
+    // over, so January 32nd is February 1st.
+    pub fn to_utc(&self) -> Timespec {
+        let year = self.year + (self.month - 1).div_floor(&12);
+        let month = (self.month - 1).mod_floor(&12) + 1;
+        let days = days_from_civil(year, month, 1) + self.day - 1;
+        Timespec::new(days * 86400 + self.hour * 3600 + self.minute * 60 + self.second,
+                      self.nsec as i32)
+    }
+
+    // The instant this names as a local time.  The offset is looked up twice
+    // in case the first guess lands on the other side of a DST change.
+    pub fn to_local(&self) -> Timespec {
+        let utc = self.to_utc();
+        let guess = utc.sec - utc_offset(utc.sec);
+        Timespec::new(utc.sec - utc_offset(guess), utc.nsec)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.month >= 1 && self.month <= 12 && self.day >= 1
+            && self.day <= days_in_month(self.year, self.month)
+            && self.hour >= 0 && self.hour < 24 && self.minute >= 0 && self.minute < 60
+            && self.second >= 0 && self.second <= 60
+    }
+}
+
+// Days since 1970-01-01 of a day in the proleptic Gregorian calendar.
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_floor(&400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+pub fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+// Seconds east of UTC of the local time zone at t.
+fn utc_offset(t: i64) -> i64 {
+    time::at(Timespec::new(t, 0)).tm_gmtoff as i64
+}
+
+// A two digit year, as POSIX reads them: 69 to 99 are the 1900s.
+pub fn full_year(yy: i64) -> i64 {
+    if yy < 69 { 2000 + yy } else { 1900 + yy }
+}
+
+// Parses the [[CC]YY]MMDDhhmm[.ss] stamps of touch -t, as a local time.
+pub fn parse_stamp(s: &str, now: Timespec) -> Option<Timespec> {
+    let (digits, seconds) = match s.find('.') {
+        Some(dot) => (s.slice_to(dot), Some(s.slice_from(dot + 1))),
+        None => (s, None)
+    };
+    if number(digits).is_none() {
+        return None;
+    }
+    let field = |i: uint| number(digits.slice(i, i + 2));
+    let (year, rest) = match digits.len() {
+        8 => (Civil::local(now).year, 0),
+        10 => (full_year(try_opt!(field(0))), 2),
+        12 => (try_opt!(number(digits.slice_to(4))), 4),
+        _ => return None
+    };
+    let second = match seconds {
+        Some(ss) if ss.len() == 2 => try_opt!(number(ss)),
+        Some(_) => return None,
+        None => 0
+    };
+    let civil = Civil {
+        year: year,
+        month: try_opt!(field(rest)),
+        day: try_opt!(field(rest + 2)),
+        hour: try_opt!(field(rest + 4)),
+        minute: try_opt!(field(rest + 6)),
+        second: second,
+        nsec: 0,
+    };
+    if civil.is_valid() { Some(civil.to_local()) } else { None }
+}
+
+// A run of decimal digits, without the sign or spaces from_str lets through.
+pub fn number(s: &str) -> Option<i64> {
+    if !s.is_empty() && s.chars().all(|c| c.is_digit()) { from_str(s) } else { None }
+}
+
+enum Unit {
+    Seconds(i64),
+    Days(i64),
+    Months(i64),
+}
+
+fn unit(word: &str) -> Option<Unit> {
+    let word = if word.len() > 1 && word.ends_with("s") {
+        word.slice_to(word.len() - 1)
+    } else {
+        word
+    };
+    match word {
+        "sec" | "second" => Some(Seconds(1)),
+        "min" | "minute" => Some(Seconds(60)),
+        "hour" => Some(Seconds(3600)),
+        "day" => Some(Days(1)),
+        "week" => Some(Days(7)),
+        "fortnight" => Some(Days(14)),
+        "month" => Some(Months(1)),
+        "year" => Some(Months(12)),
+        _ => None
+    }
+}
+
+static MONTHS: [&'static str, ..12] = ["january", "february", "march", "april", "may", "june",
+                                       "july", "august", "september", "october", "november",
+                                       "december"];
+
+fn month(word: &str) -> Option<i64> {
+    let word = word.trim_right_chars('.');
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS.iter().position(|name| name.starts_with(word)).map(|i| i as i64 + 1)
+}
+
+// h:m[:s[.frac]], returning the fields and whatever follows them.
+fn parse_time<'a>(word: &'a str) -> Option<((i64, i64, i64, i64), &'a str)> {
+    let end = word.find(|c: char| !(c.is_digit() || c == ':' || c == '.')).unwrap_or(word.len());
+    let (clock, rest) = (word.slice_to(end), word.slice_from(end));
+    let parts: Vec<&str> = clock.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+    let hour = try_opt!(from_str::<i64>(*parts.get(0)));
+    let minute = try_opt!(from_str::<i64>(*parts.get(1)));
+    let (second, nsec) = if parts.len() == 3 {
+        try_opt!(parse_seconds(*parts.get(2)))
+    } else {
+        (0, 0)
+    };
+    Some(((hour, minute, second, nsec), rest))
+}
+
+// s[.frac], as seconds and nanoseconds.
+fn parse_seconds(s: &str) -> Option<(i64, i64)> {
+    let (whole, frac) = match s.find('.') {
+        Some(dot) => (s.slice_to(dot), s.slice_from(dot + 1)),
+        None => (s, "")
+    };
+    let whole = try_opt!(from_str::<i64>(whole));
+    if !frac.chars().all(|c| c.is_digit()) {
+        return None;
+    }
+    let mut digits = frac.slice_to(if frac.len() > 9 { 9 } else { frac.len() }).to_string();
+    while digits.len() < 9 {
+        digits.push_char('0');
+    }
+    Some((whole, try_opt!(from_str::<i64>(digits.as_slice()))))
+}
+
+// Z, UTC, GMT or [+-]hh[:]mm, as seconds east of UTC.
+fn parse_zone(s: &str) -> Option<i64> {
+    match s {
+        "z" | "utc" | "gmt" => return Some(0),
+        _ => {}
+    }
+    let sign = match s.char_at(0) {
+        '+' => 1,
+        '-' => -1,
+        _ => return None
+    };
+    let digits: String = s.slice_from(1).chars().filter(|&c| c != ':').collect();
+    if digits.len() != 4 || !digits.as_slice().chars().all(|c| c.is_digit()) {
+        return None;
+    }
+    let hours = try_opt!(from_str::<i64>(digits.as_slice().slice_to(2)));
+    let minutes = try_opt!(from_str::<i64>(digits.as_slice().slice_from(2)));
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+fn parse_date(word: &str) -> Option<(i64, i64, i64)> {
+    let (sep, ymd) = if word.contains_char('-') { ('-', true) } else { ('/', false) };
+    let parts: Vec<i64> = word.split(sep).filter_map(|part| from_str(part)).collect();
+    if parts.len() != 3 || word.split(sep).count() != 3 {
+        return None;
+    }
+    if ymd {
+        Some((*parts.get(0), *parts.get(1), *parts.get(2)))
+    } else {
+        // the American month/day/year
+        let year = *parts.get(2);
+        Some((if year < 100 { full_year(year) } else { year }, *parts.get(0), *parts.get(1)))
+    }
+}
+
+// Parses a free-form date, with relative items counted from now.  A date or
+// time without a zone of its own is taken to be in UTC when utc is set, as
+// for date -u, and in the local time zone otherwise.
+pub fn parse_datetime(s: &str, now: Timespec, utc: bool) -> Option<Timespec> {
+    let lower = s.trim().to_ascii_lower();
+    if lower.as_slice().starts_with("@") {
+        let (sec, nsec) = try_opt!(parse_seconds(lower.as_slice().slice_from(1)));
+        return Some(Timespec::new(sec, nsec as i32));
+    }
+
+    // ISO 8601 puts a T between the date and the time
+    let mut words = vec!();
+    for word in lower.as_slice().words() {
+        match word.find('t') {
+            Some(t) if t > 0 && word.char_at(0).is_digit() && word.contains_char(':') => {
+                words.push(word.slice_to(t));
+                words.push(word.slice_from(t + 1));
+            }
+            _ => words.push(word)
+        }
+    }
+
+    let base = if utc { Civil::utc(now) } else { Civil::local(now) };
+    let mut date = None;
+    let mut clock = None;
+    let mut zone = None;
+    let mut relative: Vec<Unit> = vec!();
+    let mut i = 0;
+    while i < words.len() {
+        let word = *words.get(i);
+        i += 1;
+        let next = if i < words.len() { Some(*words.get(i)) } else { None };
+        match word {
+            "now" | "today" | "this" => continue,
+            "yesterday" => { relative.push(Days(-1)); continue; }
+            "tomorrow" => { relative.push(Days(1)); continue; }
+            "midnight" => { clock = Some((0, 0, 0, 0)); continue; }
+            "noon" => { clock = Some((12, 0, 0, 0)); continue; }
+            "z" | "utc" | "gmt" => { zone = Some(0); continue; }
+            "ago" => {
+                match relative.pop() {
+                    Some(Seconds(n)) => relative.push(Seconds(-n)),
+                    Some(Days(n)) => relative.push(Days(-n)),
+                    Some(Months(n)) => relative.push(Months(-n)),
+                    None => return None
+                }
+                continue;
+            }
+            "next" | "last" => {
+                let sign = if word == "next" { 1 } else { -1 };
+                match next.and_then(|w| unit(w)) {
+                    Some(u) => { relative.push(scale(u, sign)); i += 1; continue; }
+                    None => return None
+                }
+            }
+            _ => {}
+        }
+
+        match unit(word) {
+            Some(u) => { relative.push(u); continue; }
+            None => {}
+        }
+        if zone.is_none() && clock.is_some() {
+            match parse_zone(word) {
+                Some(z) => { zone = Some(z); continue; }
+                None => {}
+            }
+        }
+        if word.contains_char(':') {
+            let (fields, rest) = try_opt!(parse_time(word));
+            clock = Some(fields);
+            if !rest.is_empty() {
+                zone = Some(try_opt!(parse_zone(rest)));
+            }
+            continue;
+        }
+        if word.contains_char('-') && word.char_at(0).is_digit() || word.contains_char('/') {
+            date = Some(try_opt!(parse_date(word)));
+            continue;
+        }
+        match month(word) {
+            // Jun 21 [2014]
+            Some(m) => {
+                let day = try_opt!(next.and_then(|n| from_str::<i64>(n)));
+                i += 1;
+                let year = take_year(&words, &mut i).unwrap_or(base.year);
+                date = Some((year, m, day));
+                continue;
+            }
+            None => {}
+        }
+        // what is left is a number: a count of some unit, or 21 Jun [2014]
+        let n = try_opt!(from_str::<i64>(word.trim_left_chars('+')));
+        match next.and_then(|w| unit(w)) {
+            Some(u) => { relative.push(scale(u, n)); i += 1; continue; }
+            None => {}
+        }
+        match next.and_then(|w| month(w)) {
+            Some(m) => {
+                i += 1;
+                let year = take_year(&words, &mut i).unwrap_or(base.year);
+                date = Some((year, m, n));
+                continue;
+            }
+            None => return None
+        }
+    }
+
+    let mut civil = base;
+    if date.is_some() || clock.is_some() {
+        match date {
+            Some((year, month, day)) => {
+                civil.year = year;
+                civil.month = month;
+                civil.day = day;
+            }
+            None => {}
+        }
+        let (hour, minute, second, nsec) = clock.unwrap_or((0, 0, 0, 0));
+        civil.hour = hour;
+        civil.minute = minute;
+        civil.second = second;
+        civil.nsec = nsec;
+        if !civil.is_valid() {
+            return None;
+        }
+    }
+
+    // calendar units move the date and keep the time of day, the rest are
+    // just seconds
+    let mut seconds = 0;
+    for u in relative.iter() {
+        match *u {
+            Seconds(n) => seconds += n,
+            Days(n) => civil.day += n,
+            Months(n) => civil.month += n,
+        }
+    }
+    let t = match zone {
+        Some(offset) => {
+            let utc = civil.to_utc();
+            Timespec::new(utc.sec - offset, utc.nsec)
+        }
+        None if utc => civil.to_utc(),
+        None => civil.to_local()
+    };
+    Some(Timespec::new(t.sec + seconds, t.nsec))
+}
+
+fn scale(u: Unit, n: i64) -> Unit {
+    match u {
+        Seconds(s) => Seconds(s * n),
+        Days(d) => Days(d * n),
+        Months(m) => Months(m * n),
+    }
+}
+
+fn take_year(words: &Vec<&str>, i: &mut uint) -> Option<i64> {
+    if *i < words.len() && words.get(*i).len() == 4 {
+        let year = from_str::<i64>(*words.get(*i));
+        if year.is_some() {
+            *i += 1;
+        }
+        year
+    } else {
+        None
+    }
+}
@@ -0,0 +1,46 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Durations as sleep and timeout take them: a number of seconds, which may
+ * have a fraction, with an optional s, m, h or d after it for seconds,
+ * minutes, hours or days.
+ */
+
+#![allow(dead_code)]
+
+use std::f64;
+
+// The duration in seconds.
+pub fn parse(s: &str) -> Result<f64, String> {
+    let (number, multiplier) = match s.chars().last() {
+        Some('s') => (s.slice_to(s.len() - 1), 1.0),
+        Some('m') => (s.slice_to(s.len() - 1), 60.0),
+        Some('h') => (s.slice_to(s.len() - 1), 60.0 * 60.0),
+        Some('d') => (s.slice_to(s.len() - 1), 60.0 * 60.0 * 24.0),
+        _ => (s, 1.0)
+    };
+    let number = if number.starts_with("+") { number.slice_from(1) } else { number };
+    let value = match number {
+        "inf" | "infinity" => Some(f64::INFINITY),
+        _ => from_str::<f64>(number)
+    };
+    // which also keeps out NaN
+    match value {
+        Some(n) if n >= 0.0 => Ok(n * multiplier),
+        _ => Err(format!("invalid time interval '{}'", s))
+    }
+}
+
+// The duration in whole milliseconds, rounded up so as never to be short,
+// and with None for one too long to ever be over.
+pub fn to_millis(secs: f64) -> Option<u64> {
+    let millis = (secs * 1000.0).ceil();
+    if millis >= 1.8e19 { None } else { Some(millis as u64) }
+}
@@ -0,0 +1,127 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/* Backslash escape interpretation shared by echo and printf. */
+
+#![allow(dead_code)]
+
+#[deriving(Eq, PartialEq)]
+pub enum OctalStyle {
+    // \0NNN, as accepted by echo -e and printf %b
+    OctalZeroPrefixed,
+    // \NNN, as accepted in printf format strings
+    OctalBare,
+}
+
+pub enum Escape {
+    // the sequence stands for this byte
+    Byte(u8),
+    // \c: produce no further output
+    Stop,
+    // not a recognized escape; the backslash is output as is
+    NotEscape,
+}
+
+fn octal_value(c: u8) -> Option<u8> {
+    if c >= '0' as u8 && c <= '7' as u8 { Some(c - '0' as u8) } else { None }
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c as char {
+        '0' .. '9' => Some(c - '0' as u8),
+        'a' .. 'f' => Some(c - 'a' as u8 + 10),
+        'A' .. 'F' => Some(c - 'A' as u8 + 10),
+        _ => None
+    }
+}
+
+// Reads up to `max` digits of `base` from the start of `input`.
+fn read_number(input: &[u8], max: uint, digit: |u8| -> Option<u8>, base: uint) -> (uint, uint) {
+    let mut value = 0u;
+    let mut used = 0u;
+    while used < max && used < input.len() {
+        match digit(input[used]) {
+            Some(d) => value = value * base + d as uint,
+            None => break
+        }
+        used += 1;
+    }
+    (value, used)
+}
+
+/*
+ * Parses the escape sequence at the start of `input`, which is everything
+ * following a backslash.  Returns the escape and the number of bytes of
+ * `input` it consumed.
+ */
+pub fn parse(input: &[u8], style: OctalStyle) -> (Escape, uint) {
+    if input.is_empty() {
+        return (NotEscape, 0);
+    }
+    let simple = match input[0] as char {
+        '\\' => Some('\\' as u8),
+        'a' => Some(0x07),
+        'b' => Some(0x08),
+        'e' => Some(0x1B),
+        'f' => Some(0x0C),
+        'n' => Some('\n' as u8),
+        'r' => Some('\r' as u8),
+        't' => Some('\t' as u8),
+        'v' => Some(0x0B),
+        '"' if style == OctalBare => Some('"' as u8),
+        _ => None
+    };
+    match simple {
+        Some(b) => return (Byte(b), 1),
+        None => {}
+    }
+    match input[0] as char {
+        'c' => (Stop, 1),
+        'x' => {
+            let (value, used) = read_number(input.slice_from(1), 2, hex_value, 16);
+            if used == 0 {
+                (NotEscape, 0)
+            } else {
+                (Byte(value as u8), used + 1)
+            }
+        }
+        '0' if style == OctalZeroPrefixed => {
+            let (value, used) = read_number(input.slice_from(1), 3, octal_value, 8);
+            (Byte(value as u8), used + 1)
+        }
+        '0' .. '7' if style == OctalBare => {
+            let (value, used) = read_number(input, 3, octal_value, 8);
+            (Byte(value as u8), used)
+        }
+        _ => (NotEscape, 0)
+    }
+}
+
+/*
+ * Appends the unescaped form of `input` to `out`.  Returns false if a \c
+ * was encountered, in which case the caller should stop producing output.
+ */
+pub fn unescape(input: &[u8], style: OctalStyle, out: &mut Vec<u8>) -> bool {
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] != '\\' as u8 {
+            out.push(input[i]);
+            i += 1;
+            continue;
+        }
+        let (escape, used) = parse(input.slice_from(i + 1), style);
+        match escape {
+            Byte(b) => out.push(b),
+            Stop => return false,
+            NotEscape => out.push('\\' as u8)
+        }
+        i += used + 1;
+    }
+    true
+}
@@ -0,0 +1,86 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Running another program in place of this one, for the utilities that set
+ * something up and then get out of the way, and the exit statuses GNU uses
+ * to tell their own failures from the command's.  Windows cannot replace a
+ * process, so there the command is run as a child with the same standard
+ * streams and its status becomes ours.
+ */
+
+#![allow(dead_code)]
+
+extern crate libc;
+
+use std::io::{FileNotFound, IoError};
+use std::task;
+
+// The utility itself failed, before there was a command to run.
+pub static EXIT_CANCELED: int = 125;
+// The command was found but could not be run.
+pub static EXIT_CANNOT_INVOKE: int = 126;
+// There was no such command.
+pub static EXIT_ENOENT: int = 127;
+
+// Replaces the process with the command, searching $PATH for it, and only
+// returns when that cannot be done.
+#[cfg(unix)]
+pub fn execute(args: &[String]) -> IoError {
+    use std::ptr;
+    use self::libc::c_char;
+    use self::libc::funcs::posix88::unistd::execvp;
+
+    let c_args: Vec<::std::c_str::CString> = args.iter().map(|a| a.to_c_str()).collect();
+    let mut argv: Vec<*c_char> = c_args.iter().map(|a| a.with_ref(|p| p)).collect();
+    argv.push(ptr::null());
+    unsafe { execvp(*argv.get(0), argv.as_ptr()); }
+    IoError::last_error()
+}
+
+#[cfg(windows)]
+pub fn execute(args: &[String]) -> IoError {
+    use std::io::process::{Command, ExitSignal, ExitStatus, InheritFd};
+
+    let child = Command::new(args[0].as_slice())
+        .args(args.slice_from(1))
+        .stdin(InheritFd(0))
+        .stdout(InheritFd(1))
+        .stderr(InheritFd(2))
+        .spawn();
+    let status = match child.and_then(|mut child| child.wait()) {
+        Ok(ExitStatus(status)) => status,
+        Ok(ExitSignal(signal)) => 128 + signal,
+        Err(err) => return err
+    };
+    unsafe { libc::exit(status as libc::c_int) }
+}
+
+// util::catch_exit for the utilities that run a command.  Their own failures
+// must not be taken for the command's, so one that did not choose a status,
+// such as a bug, ends with EXIT_CANCELED rather than the runtime's 101.
+pub fn catch_exit(body: proc(): Send) -> ::util::Outcome {
+    match task::try(proc() ::util::catch_exit(body)) {
+        Ok(outcome) => outcome,
+        Err(_) => Err(::util::Failure { status: EXIT_CANCELED, message: None })
+    }
+}
+
+// The status for a command that could not be run: 127 if it was not there,
+// 126 for anything else.
+pub fn exec_status(err: &IoError) -> int {
+    if err.kind == FileNotFound { EXIT_ENOENT } else { EXIT_CANNOT_INVOKE }
+}
+
+// Runs the command in place of the process, or exits with the status that
+// says why it could not.
+pub fn exec_or_exit(args: &[String]) -> ! {
+    let err = execute(args);
+    crash!(exec_status(&err), "failed to run command '{}': {}", args[0], ::util::strerror(&err))
+}
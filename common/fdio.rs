@@ -0,0 +1,231 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * A raw file descriptor as a Reader and Writer.  std::io::File picks the
+ * open flags and creation mode itself, which is not good enough for the
+ * utilities that need to control either, or to hand the descriptor to
+ * ioctl(2) and friends.
+ */
+
+#![allow(dead_code)]
+
+extern crate libc;
+
+use std::io::{standard_error, EndOfFile, IoError, IoResult, OtherIoError};
+use std::mem;
+use std::ptr;
+use std::raw::Slice;
+use self::libc::{c_int, c_void, mode_t, off_t, size_t};
+use self::libc::funcs::posix88::fcntl::open;
+use self::libc::funcs::posix88::unistd::{close, read, write};
+
+pub struct Fd {
+    fd: c_int,
+}
+
+impl Fd {
+    pub fn open(path: &Path, flags: c_int, mode: mode_t) -> IoResult<Fd> {
+        let fd = path.with_c_str(|p| unsafe { open(p, flags, mode) });
+        if fd < 0 { Err(IoError::last_error()) } else { Ok(Fd { fd: fd }) }
+    }
+
+    // Takes over fd, which is closed along with the Fd.
+    pub fn from_raw(fd: c_int) -> Fd {
+        Fd { fd: fd }
+    }
+
+    pub fn raw(&self) -> c_int {
+        self.fd
+    }
+
+    // Moves the file offset by offset bytes, returning the new offset.
+    pub fn skip(&mut self, offset: i64) -> IoResult<u64> {
+        let ret = unsafe { lseek(self.fd, offset as off_t, libc::SEEK_CUR) };
+        if ret < 0 { Err(IoError::last_error()) } else { Ok(ret as u64) }
+    }
+
+    pub fn truncate(&mut self, size: u64) -> IoResult<()> {
+        if unsafe { ftruncate(self.fd, size as off_t) } == 0 {
+            Ok(())
+        } else {
+            Err(IoError::last_error())
+        }
+    }
+
+    // Waits for what has been written to reach the disk; with data_only,
+    // metadata that is not needed to read it back may lag behind.
+    pub fn sync(&mut self, data_only: bool) -> IoResult<()> {
+        let ret = unsafe { if data_only { fdatasync(self.fd) } else { fsync(self.fd) } };
+        if ret == 0 { Ok(()) } else { Err(IoError::last_error()) }
+    }
+
+    // A single read(2), which gives None when a signal interrupts it before
+    // anything arrives, so that the caller can see to the signal and then
+    // try again.  read() itself just tries again.
+    pub fn read_interruptible(&mut self, buf: &mut [u8]) -> IoResult<Option<uint>> {
+        let n = unsafe { read(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len() as size_t) };
+        if n > 0 {
+            Ok(Some(n as uint))
+        } else if n == 0 {
+            Err(standard_error(EndOfFile))
+        } else if os_errno() == libc::EINTR {
+            Ok(None)
+        } else {
+            Err(IoError::last_error())
+        }
+    }
+
+    // Turns off status flags such as O_DIRECT or O_APPEND on the open file.
+    pub fn clear_flags(&mut self, flags: c_int) -> IoResult<()> {
+        unsafe {
+            let old = fcntl(self.fd, F_GETFL);
+            if old < 0 || fcntl(self.fd, F_SETFL, old & !flags) < 0 {
+                return Err(IoError::last_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+// A buffer whose start is aligned to a given boundary, as O_DIRECT needs
+// the memory it reads into and writes from to be.  It is allocated once and
+// refilled, with len() of its bytes in use.
+pub struct AlignedBuf {
+    ptr: *mut u8,
+    cap: uint,
+    len: uint,
+}
+
+impl AlignedBuf {
+    pub fn new(cap: uint, align: uint) -> IoResult<AlignedBuf> {
+        let mut ptr: *mut c_void = ptr::mut_null();
+        let ret = unsafe { posix_memalign(&mut ptr, align as size_t, cap as size_t) };
+        if ret != 0 {
+            return Err(IoError::from_errno(ret as uint, false));
+        }
+        if ptr.is_null() {
+            return Err(IoError {
+                kind: OtherIoError,
+                desc: "out of memory",
+                detail: None
+            });
+        }
+        unsafe { ptr::set_memory(ptr as *mut u8, 0, cap); }
+        Ok(AlignedBuf { ptr: ptr as *mut u8, cap: cap, len: 0 })
+    }
+
+    pub fn capacity(&self) -> uint {
+        self.cap
+    }
+
+    pub fn len(&self) -> uint {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn set_len(&mut self, len: uint) {
+        assert!(len <= self.cap);
+        self.len = len;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    // Appends as much of data as fits, returning how much that was.
+    pub fn push_all(&mut self, data: &[u8]) -> uint {
+        let n = if data.len() < self.cap - self.len { data.len() } else { self.cap - self.len };
+        let len = self.len;
+        self.whole_mut().mut_slice(len, len + n).copy_from(data.slice_to(n));
+        self.len += n;
+        n
+    }
+
+    // The bytes in use.
+    pub fn as_slice<'a>(&'a self) -> &'a [u8] {
+        unsafe { mem::transmute(Slice { data: self.ptr as *u8, len: self.len }) }
+    }
+
+    pub fn as_mut_slice<'a>(&'a mut self) -> &'a mut [u8] {
+        unsafe { mem::transmute(Slice { data: self.ptr as *u8, len: self.len }) }
+    }
+
+    // All of the buffer, used or not.
+    pub fn whole_mut<'a>(&'a mut self) -> &'a mut [u8] {
+        unsafe { mem::transmute(Slice { data: self.ptr as *u8, len: self.cap }) }
+    }
+}
+
+impl Drop for AlignedBuf {
+    fn drop(&mut self) {
+        unsafe { libc::free(self.ptr as *mut c_void); }
+    }
+}
+
+static F_GETFL: c_int = 3;
+static F_SETFL: c_int = 4;
+
+extern {
+    fn lseek(fd: c_int, offset: off_t, whence: c_int) -> off_t;
+    fn ftruncate(fd: c_int, length: off_t) -> c_int;
+    fn fsync(fd: c_int) -> c_int;
+    fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+    fn posix_memalign(memptr: *mut *mut c_void, alignment: size_t, size: size_t) -> c_int;
+    #[cfg(target_os = "linux")]
+    fn fdatasync(fd: c_int) -> c_int;
+}
+
+// only Linux has a separate call for the data alone
+#[cfg(not(target_os = "linux"))]
+unsafe fn fdatasync(fd: c_int) -> c_int {
+    fsync(fd)
+}
+
+impl Reader for Fd {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        loop {
+            match try!(self.read_interruptible(buf)) {
+                Some(n) => return Ok(n),
+                None => {}
+            }
+        }
+    }
+}
+
+impl Writer for Fd {
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        let mut written = 0;
+        while written < buf.len() {
+            let rest = buf.slice_from(written);
+            let n = unsafe {
+                write(self.fd, rest.as_ptr() as *c_void, rest.len() as size_t)
+            };
+            if n >= 0 {
+                written += n as uint;
+            } else if os_errno() != libc::EINTR {
+                return Err(IoError::last_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Fd {
+    fn drop(&mut self) {
+        unsafe { close(self.fd); }
+    }
+}
+
+fn os_errno() -> c_int {
+    ::std::os::errno() as c_int
+}
@@ -0,0 +1,233 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Thin wrappers around stat(2) and friends.  std::io::FileStat masks the
+ * mode down to the permission bits and folds several file types into
+ * TypeUnknown, which is not enough for utilities that need the whole mode.
+ */
+
+#![allow(dead_code, non_camel_case_types)]
+
+extern crate libc;
+
+use std::io::{IoError, IoResult};
+use std::mem;
+use self::libc::{c_char, c_int, mode_t};
+
+pub use self::libc::stat as c_stat;
+
+pub static S_IFMT:   mode_t = 0o170000;
+pub static S_IFSOCK: mode_t = 0o140000;
+pub static S_IFLNK:  mode_t = 0o120000;
+pub static S_IFREG:  mode_t = 0o100000;
+pub static S_IFBLK:  mode_t = 0o060000;
+pub static S_IFDIR:  mode_t = 0o040000;
+pub static S_IFCHR:  mode_t = 0o020000;
+pub static S_IFIFO:  mode_t = 0o010000;
+
+pub static S_ISUID: mode_t = 0o4000;
+pub static S_ISGID: mode_t = 0o2000;
+pub static S_ISVTX: mode_t = 0o1000;
+
+extern {
+    fn stat(path: *c_char, buf: *mut c_stat) -> c_int;
+    fn lstat(path: *c_char, buf: *mut c_stat) -> c_int;
+    fn fstat(fd: c_int, buf: *mut c_stat) -> c_int;
+    fn mknod(path: *c_char, mode: mode_t, dev: libc::dev_t) -> c_int;
+    fn mkfifo(path: *c_char, mode: mode_t) -> c_int;
+    fn chown(path: *c_char, uid: libc::uid_t, gid: libc::gid_t) -> c_int;
+    fn lchown(path: *c_char, uid: libc::uid_t, gid: libc::gid_t) -> c_int;
+    fn utimensat(dirfd: c_int, path: *c_char, times: *libc::timespec, flags: c_int) -> c_int;
+    fn readlink(path: *c_char, buf: *mut c_char, size: libc::size_t) -> libc::ssize_t;
+}
+
+#[cfg(target_os = "linux")]
+static AT_FDCWD: c_int = -100;
+#[cfg(target_os = "linux")]
+static AT_SYMLINK_NOFOLLOW: c_int = 0x100;
+#[cfg(not(target_os = "linux"))]
+static AT_FDCWD: c_int = -2;
+#[cfg(not(target_os = "linux"))]
+static AT_SYMLINK_NOFOLLOW: c_int = 0x20;
+
+// Leaves the owner or group unchanged when passed for uid or gid.
+pub static UNCHANGED: u32 = -1 as u32;
+
+fn do_stat(path: &Path, follow: bool) -> IoResult<c_stat> {
+    let mut buf: c_stat = unsafe { mem::zeroed() };
+    let ret = path.with_c_str(|p| unsafe {
+        if follow { stat(p, &mut buf) } else { lstat(p, &mut buf) }
+    });
+    if ret == 0 { Ok(buf) } else { Err(IoError::last_error()) }
+}
+
+pub fn stat_path(path: &Path) -> IoResult<c_stat> {
+    do_stat(path, true)
+}
+
+pub fn lstat_path(path: &Path) -> IoResult<c_stat> {
+    do_stat(path, false)
+}
+
+pub fn stat_fd(fd: c_int) -> IoResult<c_stat> {
+    let mut buf: c_stat = unsafe { mem::zeroed() };
+    if unsafe { fstat(fd, &mut buf) } == 0 { Ok(buf) } else { Err(IoError::last_error()) }
+}
+
+#[inline]
+pub fn file_type(mode: mode_t) -> mode_t {
+    mode & S_IFMT
+}
+
+pub fn is_dir(mode: mode_t) -> bool { file_type(mode) == S_IFDIR }
+pub fn is_reg(mode: mode_t) -> bool { file_type(mode) == S_IFREG }
+pub fn is_lnk(mode: mode_t) -> bool { file_type(mode) == S_IFLNK }
+pub fn is_chr(mode: mode_t) -> bool { file_type(mode) == S_IFCHR }
+pub fn is_blk(mode: mode_t) -> bool { file_type(mode) == S_IFBLK }
+pub fn is_fifo(mode: mode_t) -> bool { file_type(mode) == S_IFIFO }
+pub fn is_sock(mode: mode_t) -> bool { file_type(mode) == S_IFSOCK }
+
+// The mode as ls -l and stat print it, e.g. "drwxr-xr-x".
+pub fn mode_string(mode: mode_t) -> String {
+    let mut s = String::with_capacity(10);
+    s.push_char(match file_type(mode) {
+        S_IFDIR => 'd',
+        S_IFLNK => 'l',
+        S_IFCHR => 'c',
+        S_IFBLK => 'b',
+        S_IFIFO => 'p',
+        S_IFSOCK => 's',
+        _ => '-'
+    });
+    let special = [(S_ISUID, 's', 'S'), (S_ISGID, 's', 'S'), (S_ISVTX, 't', 'T')];
+    for i in range(0u, 3) {
+        let bits = (mode >> (6 - 3 * i)) & 0o7;
+        s.push_char(if bits & 0o4 != 0 { 'r' } else { '-' });
+        s.push_char(if bits & 0o2 != 0 { 'w' } else { '-' });
+        let (flag, set, unset) = special[i];
+        s.push_char(match (mode & flag != 0, bits & 0o1 != 0) {
+            (true, true) => set,
+            (true, false) => unset,
+            (false, true) => 'x',
+            (false, false) => '-'
+        });
+    }
+    s
+}
+
+#[cfg(target_os = "linux")]
+pub fn major(dev: u64) -> u64 { ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff) }
+#[cfg(target_os = "linux")]
+pub fn minor(dev: u64) -> u64 { (dev & 0xff) | ((dev >> 12) & !0xff) }
+
+#[cfg(not(target_os = "linux"))]
+pub fn major(dev: u64) -> u64 { (dev >> 24) & 0xff }
+#[cfg(not(target_os = "linux"))]
+pub fn minor(dev: u64) -> u64 { dev & 0xffffff }
+
+#[cfg(target_os = "linux")]
+pub fn make_dev(major: u64, minor: u64) -> u64 {
+    (minor & 0xff) | ((major & 0xfff) << 8) | ((minor & !0xff) << 12) | ((major & !0xfff) << 32)
+}
+#[cfg(not(target_os = "linux"))]
+pub fn make_dev(major: u64, minor: u64) -> u64 { (major << 24) | minor }
+
+pub fn make_node(path: &Path, mode: mode_t, dev: u64) -> IoResult<()> {
+    let ret = path.with_c_str(|p| unsafe { mknod(p, mode, dev as libc::dev_t) });
+    if ret == 0 { Ok(()) } else { Err(IoError::last_error()) }
+}
+
+pub fn make_fifo(path: &Path, mode: mode_t) -> IoResult<()> {
+    let ret = path.with_c_str(|p| unsafe { mkfifo(p, mode) });
+    if ret == 0 { Ok(()) } else { Err(IoError::last_error()) }
+}
+
+// The contents of a symbolic link, as they are.  std::io::fs::readlink
+// hands back a Path, which has already folded away any "." and "..".
+pub fn read_link(path: &Path) -> IoResult<Vec<u8>> {
+    let mut buf: Vec<u8> = Vec::with_capacity(256);
+    loop {
+        let cap = buf.capacity();
+        let len = path.with_c_str(|p| unsafe {
+            readlink(p, buf.as_mut_ptr() as *mut c_char, cap as libc::size_t)
+        });
+        if len < 0 {
+            return Err(IoError::last_error());
+        } else if (len as uint) < cap {
+            unsafe { buf.set_len(len as uint); }
+            return Ok(buf);
+        }
+        // it may have been cut short
+        buf.reserve(cap * 2);
+    }
+}
+
+// The process umask, which can only be read by setting it.
+pub fn umask() -> mode_t {
+    unsafe {
+        let mask = libc::funcs::posix88::stat_::umask(0);
+        libc::funcs::posix88::stat_::umask(mask);
+        mask
+    }
+}
+
+// Sets all twelve permission bits, which std::io::FilePermission cannot
+// hold.
+pub fn set_mode(path: &Path, mode: mode_t) -> IoResult<()> {
+    let ret = path.with_c_str(|p| unsafe { libc::funcs::posix88::stat_::chmod(p, mode) });
+    if ret == 0 { Ok(()) } else { Err(IoError::last_error()) }
+}
+
+// Changes the owner and group of path, or of the link itself when follow is
+// false.
+pub fn set_owner(path: &Path, uid: libc::uid_t, gid: libc::gid_t, follow: bool) -> IoResult<()> {
+    let ret = path.with_c_str(|p| unsafe {
+        if follow { chown(p, uid, gid) } else { lchown(p, uid, gid) }
+    });
+    if ret == 0 { Ok(()) } else { Err(IoError::last_error()) }
+}
+
+// Sets the access and modification times from (seconds, nanoseconds) pairs,
+// on the link itself when follow is false.
+pub fn set_times(path: &Path, atime: (i64, i64), mtime: (i64, i64), follow: bool) -> IoResult<()> {
+    let times = [to_timespec(atime), to_timespec(mtime)];
+    let flags = if follow { 0 } else { AT_SYMLINK_NOFOLLOW };
+    let ret = path.with_c_str(|p| unsafe {
+        utimensat(AT_FDCWD, p, times.as_ptr(), flags)
+    });
+    if ret == 0 { Ok(()) } else { Err(IoError::last_error()) }
+}
+
+// Nanosecond values set_times understands as "the current time" and "leave
+// this one alone".
+#[cfg(target_os = "linux")]
+pub static UTIME_NOW: i64 = (1 << 30) - 1;
+#[cfg(target_os = "linux")]
+pub static UTIME_OMIT: i64 = (1 << 30) - 2;
+#[cfg(not(target_os = "linux"))]
+pub static UTIME_NOW: i64 = -1;
+#[cfg(not(target_os = "linux"))]
+pub static UTIME_OMIT: i64 = -2;
+
+fn to_timespec((sec, nsec): (i64, i64)) -> libc::timespec {
+    libc::timespec { tv_sec: sec as libc::time_t, tv_nsec: nsec as libc::c_long }
+}
+
+pub fn atime(stat: &c_stat) -> (i64, i64) {
+    (stat.st_atime as i64, stat.st_atime_nsec as i64)
+}
+
+pub fn mtime(stat: &c_stat) -> (i64, i64) {
+    (stat.st_mtime as i64, stat.st_mtime_nsec as i64)
+}
+
+pub fn ctime(stat: &c_stat) -> (i64, i64) {
+    (stat.st_ctime as i64, stat.st_ctime_nsec as i64)
+}
@@ -0,0 +1,113 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Writing JSON for the utilities' machine-readable output.  Unlike
+ * serialize::json, numbers are written exactly as given rather than through
+ * an f64, so sizes and inode numbers survive, and members keep the order they
+ * were added in.
+ */
+
+#![allow(dead_code)]
+
+use std::fmt::Show;
+use std::str;
+
+pub struct Object {
+    members: Vec<String>,
+}
+
+impl Object {
+    pub fn new() -> Object {
+        Object { members: vec!() }
+    }
+
+    pub fn string(&mut self, key: &str, value: &str) {
+        self.raw(key, quote(value).as_slice());
+    }
+
+    // For bytes that need not be UTF-8, such as file names.  When they are
+    // not, the string has U+FFFD for what is not, and a KEY_bytes member
+    // beside it holds every byte, so the name can still be got back exactly.
+    pub fn bytes(&mut self, key: &str, value: &[u8]) {
+        self.raw(key, quote_bytes(value).as_slice());
+        if !str::is_utf8(value) {
+            let bytes: Vec<String> = value.iter().map(|b| b.to_str()).collect();
+            self.raw(format!("{}_bytes", key).as_slice(),
+                     format!("[{}]", bytes.connect(", ")).as_slice());
+        }
+    }
+
+    // For integers; anything Show writes as a valid JSON number will do.
+    pub fn number<T: Show>(&mut self, key: &str, value: T) {
+        self.raw(key, value.to_str().as_slice());
+    }
+
+    pub fn boolean(&mut self, key: &str, value: bool) {
+        self.raw(key, if value { "true" } else { "false" });
+    }
+
+    pub fn null(&mut self, key: &str) {
+        self.raw(key, "null");
+    }
+
+    // A member whose value is already JSON, such as a nested object.
+    pub fn raw(&mut self, key: &str, json: &str) {
+        self.members.push(format!("{}: {}", quote(key), json));
+    }
+
+    pub fn encode(&self) -> String {
+        let mut out = "{".to_string();
+        out.push_str(self.members.connect(", ").as_slice());
+        out.push_char('}');
+        out
+    }
+}
+
+// The values, already JSON, as an array with one to a line.
+pub fn array(items: &[String]) -> String {
+    if items.is_empty() {
+        return "[]".to_string();
+    }
+    let mut out = "[\n".to_string();
+    for (i, item) in items.iter().enumerate() {
+        out.push_str("  ");
+        out.push_str(item.as_slice());
+        out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+    }
+    out.push_char(']');
+    out
+}
+
+// The string as a JSON string literal.  Only what has to be is escaped.
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push_char('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\x08' => out.push_str("\\b"),
+            '\x0c' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(format!("\\u{:04x}", c as u32).as_slice()),
+            c => out.push_char(c)
+        }
+    }
+    out.push_char('"');
+    out
+}
+
+// Bytes that need not be UTF-8, such as file names, as a JSON string; what
+// is not valid UTF-8 becomes U+FFFD, so Object::bytes is the lossless way.
+pub fn quote_bytes(bytes: &[u8]) -> String {
+    quote(str::from_utf8_lossy(bytes).as_slice())
+}
@@ -0,0 +1,766 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * The listing engine shared by ls, dir and vdir, which differ only in the
+ * format they fall back on.  Crates using this module need `extern crate
+ * getopts`, `extern crate libc`, `extern crate time` and common/{util,posix,
+ * fsext,c_types,walk,colors,size,platform,collate}.rs declared under their
+ * own names.
+ */
+
+#![allow(dead_code)]
+
+use std::cmp;
+use std::cmp::{Equal, Ordering};
+use std::io::{print, fs, IoError};
+use std::str;
+use getopts;
+use posix;
+use libc;
+use platform;
+use time;
+use time::Timespec;
+use c_types;
+use collate::Collator;
+use colors::LsColors;
+use fsext;
+use size;
+use size::{BlockSize, Blocks, Human};
+use walk::{FollowRoot, Visitor, WalkEntry, Walker};
+
+#[deriving(PartialEq)]
+enum Hidden {
+    HideDotfiles,
+    ShowAlmostAll,
+    ShowAll,
+}
+
+#[deriving(PartialEq)]
+pub enum Format {
+    Columns,
+    OnePerLine,
+    Long,
+}
+
+#[deriving(PartialEq)]
+enum Sort {
+    SortName,
+    SortTime,
+    SortSize,
+    SortExtension,
+    SortVersion,
+    SortNone,
+}
+
+#[deriving(PartialEq)]
+enum TimeField {
+    Modification,
+    Access,
+    Change,
+}
+
+struct Options {
+    hidden: Hidden,
+    directory: bool,
+    recursive: bool,
+    format: Format,
+    width: uint,
+    numeric_ids: bool,
+    show_owner: bool,
+    show_group: bool,
+    colors: Option<LsColors>,
+    sort: Sort,
+    // how names compare under LC_COLLATE
+    collator: Collator,
+    reverse: bool,
+    time: TimeField,
+    // -s: show each file's allocated size
+    blocks: bool,
+    // the unit for allocated sizes and the total line
+    block_size: BlockSize,
+    // the unit for the long format's size column, which is otherwise bytes
+    size_format: Option<BlockSize>,
+    // what ends each line, a NUL with --zero
+    line_end: &'static str,
+}
+
+struct Entry {
+    // the name as it should be printed: the operand as given on the command
+    // line, or the file name for directory contents
+    name: String,
+    path: Path,
+    stat: fsext::c_stat,
+}
+
+// The options ls, dir and vdir all take.
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("a", "all", "do not ignore entries starting with ."),
+        getopts::optflag("A", "almost-all", "do not list implied . and .."),
+        getopts::optopt("", "block-size", "scale sizes by SIZE before printing them, e.g. '--block-size=M'", "SIZE"),
+        getopts::optflag("c", "", "with -lt: sort by, and show, ctime; with -l: show ctime and sort by name;
+                        otherwise: sort by ctime"),
+        getopts::optflag("C", "", "list entries by columns"),
+        getopts::optflagopt("", "color", "colorize the output; WHEN can be 'always', 'auto' (the default) or 'never'", "WHEN"),
+        getopts::optflag("d", "directory", "list directories themselves, not their contents"),
+        getopts::optflag("f", "", "do not sort, enable -aU"),
+        getopts::optflag("g", "", "like -l, but do not list owner"),
+        getopts::optflag("h", "human-readable", "with -l and/or -s, print human readable sizes (e.g., 1K 234M 2G)"),
+        getopts::optflag("", "si", "likewise, but use powers of 1000 not 1024"),
+        getopts::optflag("k", "kibibytes", "default to 1024-byte blocks for disk usage"),
+        getopts::optflag("l", "", "use a long listing format"),
+        getopts::optflag("n", "numeric-uid-gid", "like -l, but list numeric user and group IDs"),
+        getopts::optflag("o", "", "like -l, but do not list group information"),
+        getopts::optflag("r", "reverse", "reverse order while sorting"),
+        getopts::optflag("R", "recursive", "list subdirectories recursively"),
+        getopts::optflag("s", "size", "print the allocated size of each file, in blocks"),
+        getopts::optflag("S", "", "sort by file size, largest first"),
+        getopts::optflag("t", "", "sort by modification time, newest first"),
+        getopts::optflag("u", "", "with -lt: sort by, and show, access time; with -l: show access time
+                        and sort by name; otherwise: sort by access time"),
+        getopts::optflag("U", "", "do not sort; list entries in directory order"),
+        getopts::optflag("v", "", "natural sort of (version) numbers within text"),
+        getopts::optflag("X", "", "sort alphabetically by entry extension"),
+        getopts::optflag("", "zero", "end each output line with NUL, not newline; implies -1 \
+                                      without -l"),
+        getopts::optflag("1", "", "list one file per line"),
+        getopts::optflag("", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+// Runs ls over the arguments.  The format is the one to use when none of
+// -l, -1 and -C is given; ls leaves it to whether stdout is a terminal.
+pub fn run(args: Vec<String>, default_format: Option<Format>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(2, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", ::NAME, ::VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0:s} [OPTION]... [FILE]...", program);
+        println!("");
+        print(getopts::usage("List information about the FILEs (the current directory by default).
+Entries are sorted alphabetically, in the order LC_COLLATE gives.", opts.as_slice()).as_slice());
+        return;
+    }
+    if matches.opt_present("version") {
+        println!("{} {}", ::NAME, ::VERSION);
+        return;
+    }
+
+    let tty = platform::is_terminal(platform::STDOUT);
+    let color = match matches.opt_str("color") {
+        None => tty,
+        Some(when) => match when.as_slice() {
+            "always" | "yes" | "force" => true,
+            "auto" | "tty" | "if-tty" => tty,
+            "never" | "no" | "none" => false,
+            _ => crash!(2, "invalid argument '{}' for '--color'", when)
+        }
+    };
+    let format = if ["l", "g", "n", "o"].iter().any(|&o| matches.opt_present(o)) {
+        Long
+    } else if matches.opt_present("1") || matches.opt_present("zero") {
+        OnePerLine
+    } else if matches.opt_present("C") {
+        Columns
+    } else {
+        match default_format {
+            Some(format) => format,
+            None if tty => Columns,
+            None => OnePerLine
+        }
+    };
+    let long = format == Long;
+    let time = if matches.opt_present("c") {
+        Change
+    } else if matches.opt_present("u") {
+        Access
+    } else {
+        Modification
+    };
+    let sort = if matches.opt_present("U") || matches.opt_present("f") {
+        SortNone
+    } else if matches.opt_present("S") {
+        SortSize
+    } else if matches.opt_present("t") {
+        SortTime
+    } else if matches.opt_present("X") {
+        SortExtension
+    } else if matches.opt_present("v") {
+        SortVersion
+    } else if time != Modification && !long {
+        // -u and -c only pick the column when the time is on display
+        SortTime
+    } else {
+        SortName
+    };
+    let size_format = if matches.opt_present("human-readable") {
+        Some(Human(1024))
+    } else if matches.opt_present("si") {
+        Some(Human(1000))
+    } else {
+        match size::block_size(matches.opt_str("block-size"), "LS_BLOCK_SIZE") {
+            Ok(size) => size,
+            Err(e) => crash!(2, "{}", e)
+        }
+    };
+    let block_size = match size_format {
+        _ if matches.opt_present("k") => Blocks(1024, None),
+        Some(ref size) => size.clone(),
+        None => size::default_block_size()
+    };
+
+    let options = Options {
+        hidden: if matches.opt_present("all") || matches.opt_present("f") {
+            ShowAll
+        } else if matches.opt_present("almost-all") {
+            ShowAlmostAll
+        } else {
+            HideDotfiles
+        },
+        directory: matches.opt_present("directory"),
+        recursive: matches.opt_present("recursive"),
+        format: format,
+        width: platform::terminal_width().unwrap_or(80),
+        numeric_ids: matches.opt_present("n"),
+        show_owner: !matches.opt_present("g"),
+        show_group: !matches.opt_present("o"),
+        colors: if color { Some(LsColors::from_env()) } else { None },
+        sort: sort,
+        collator: Collator::from_env(),
+        reverse: matches.opt_present("reverse"),
+        time: time,
+        blocks: matches.opt_present("size"),
+        block_size: block_size,
+        size_format: size_format,
+        line_end: if matches.opt_present("zero") { "\0" } else { "\n" },
+    };
+
+    let operands = if matches.free.is_empty() {
+        vec!(".".to_string())
+    } else {
+        matches.free
+    };
+    list(operands, &options);
+}
+
+fn list(operands: Vec<String>, options: &Options) {
+    let show_headers = operands.len() > 1 || options.recursive;
+    let mut files = vec!();
+    let mut dirs = vec!();
+
+    for operand in operands.move_iter() {
+        let path = Path::new(operand.as_slice());
+        // operands that are symlinks are followed unless the link itself is
+        // what gets shown; a dangling one is still listed as itself
+        let follow = !options.directory && options.format != Long;
+        let stat = if follow {
+            fsext::stat_path(&path).or_else(|_| fsext::lstat_path(&path))
+        } else {
+            fsext::lstat_path(&path)
+        };
+        let stat = match stat {
+            Ok(stat) => stat,
+            Err(e) => {
+                show_error!(2, "cannot access {}: {}", operand, ::util::strerror(&e));
+                continue;
+            }
+        };
+        let entry = Entry { name: operand, path: path, stat: stat };
+        if !options.directory && fsext::is_dir(entry.stat.st_mode) {
+            dirs.push(entry);
+        } else {
+            files.push(entry);
+        }
+    }
+
+    sort_entries(&mut files, options);
+    sort_entries(&mut dirs, options);
+
+    display_entries(files.as_slice(), options, false);
+    for (i, dir) in dirs.iter().enumerate() {
+        if i > 0 || !files.is_empty() {
+            put_line("", options);
+        }
+        if show_headers {
+            put_line(format!("{}:", dir.name).as_slice(), options);
+        }
+        list_dir(dir, options);
+    }
+}
+
+fn list_dir(dir: &Entry, options: &Options) {
+    let mut lister = DirLister {
+        options: options,
+        root: dir.path.clone(),
+        names: vec!(dir.name.clone()),
+    };
+    Walker::new().follow(FollowRoot).sorted(options.sort != SortNone).walk(&dir.path, &mut lister);
+}
+
+struct DirLister<'a> {
+    options: &'a Options,
+    root: Path,
+    // display names of the directory being listed and its ancestors,
+    // starting from the operand as it was given
+    names: Vec<String>,
+}
+
+impl<'a> Visitor for DirLister<'a> {
+    fn enter_dir(&mut self, dir: &WalkEntry) -> bool {
+        if dir.depth > 0 {
+            self.names.truncate(dir.depth);
+            let name = format!("{}/{}", self.names.get(dir.depth - 1), file_name(&dir.path));
+            put_line("", self.options);
+            put_line(format!("{}:", name).as_slice(), self.options);
+            self.names.push(name);
+        }
+        true
+    }
+
+    fn children(&mut self, dir: &WalkEntry, children: &mut Vec<WalkEntry>) {
+        let mut entries = vec!();
+        if self.options.hidden == ShowAll {
+            for name in [".", ".."].iter() {
+                let path = dir.path.join(*name);
+                match fsext::lstat_path(&path) {
+                    Ok(stat) => entries.push(Entry { name: name.to_string(), path: path, stat: stat }),
+                    Err(_) => {}
+                }
+            }
+        }
+        for child in children.iter() {
+            let name = file_name(&child.path);
+            if self.options.hidden == HideDotfiles && name.as_slice().starts_with(".") {
+                continue;
+            }
+            entries.push(Entry { name: name, path: child.path.clone(), stat: child.stat });
+        }
+
+        sort_entries(&mut entries, self.options);
+        display_entries(entries.as_slice(), self.options, true);
+
+        // descend into the subdirectories in the order they were listed
+        let depth = dir.depth + 1;
+        *children = if self.options.recursive {
+            entries.move_iter().filter(|e| {
+                fsext::is_dir(e.stat.st_mode) && e.name.as_slice() != "." && e.name.as_slice() != ".."
+            }).map(|e| WalkEntry { path: e.path, stat: e.stat, depth: depth }).collect()
+        } else {
+            vec!()
+        };
+    }
+
+    fn cycle(&mut self, dir: &WalkEntry) {
+        show_error!(2, "{}: not listing already-listed directory", dir.path.display());
+    }
+
+    fn error(&mut self, path: &Path, err: IoError) {
+        // trouble with a command line argument is more serious than trouble
+        // somewhere below it
+        let code = if *path == self.root { 2 } else { 1 };
+        show_error!(code, "cannot access {}: {}", path.display(), ::util::strerror(&err));
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    match path.filename() {
+        Some(name) => str::from_utf8_lossy(name).into_string(),
+        None => path.display().to_str()
+    }
+}
+
+fn sort_entries(entries: &mut Vec<Entry>, options: &Options) {
+    let collator = &options.collator;
+    match options.sort {
+        // directory order has nothing to reverse
+        SortNone => return,
+        SortName => entries.sort_by(|a, b| collator.compare(a.name.as_slice(), b.name.as_slice())),
+        SortTime => entries.sort_by(|a, b| {
+            file_time(&b.stat, options.time).cmp(&file_time(&a.stat, options.time))
+                                            .cmp_then(a, b, collator)
+        }),
+        SortSize => entries.sort_by(|a, b| {
+            b.stat.st_size.cmp(&a.stat.st_size).cmp_then(a, b, collator)
+        }),
+        SortExtension => entries.sort_by(|a, b| {
+            collator.compare(extension(a.name.as_slice()), extension(b.name.as_slice()))
+                    .cmp_then(a, b, collator)
+        }),
+        SortVersion => entries.sort_by(|a, b| version_cmp(a.name.as_slice(), b.name.as_slice())),
+    }
+    if options.reverse {
+        entries.reverse();
+    }
+}
+
+trait ThenByName {
+    fn cmp_then(self, a: &Entry, b: &Entry, collator: &Collator) -> Ordering;
+}
+
+// Ties in any sort key are broken by name.
+impl ThenByName for Ordering {
+    fn cmp_then(self, a: &Entry, b: &Entry, collator: &Collator) -> Ordering {
+        match self {
+            Equal => collator.compare(a.name.as_slice(), b.name.as_slice()),
+            order => order
+        }
+    }
+}
+
+// (seconds, nanoseconds) of the chosen timestamp
+fn file_time(stat: &fsext::c_stat, field: TimeField) -> (i64, i64) {
+    match field {
+        Modification => fsext::mtime(stat),
+        Access => fsext::atime(stat),
+        Change => fsext::ctime(stat),
+    }
+}
+
+// Names without an extension sort before all others.
+fn extension<'a>(name: &'a str) -> &'a str {
+    match name.rfind('.') {
+        Some(0) | None => "",
+        Some(i) => name.slice_from(i + 1)
+    }
+}
+
+// Compares runs of digits by their numeric value and everything else
+// bytewise, so that "file9" sorts before "file10".
+fn version_cmp(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if is_digit(a[i]) && is_digit(b[j]) {
+            let (si, sj) = (i, j);
+            while i < a.len() && is_digit(a[i]) { i += 1; }
+            while j < b.len() && is_digit(b[j]) { j += 1; }
+            let x = trim_zeros(a.slice(si, i));
+            let y = trim_zeros(b.slice(sj, j));
+            // with the leading zeros gone, the longer number is the larger
+            match x.len().cmp(&y.len()) {
+                Equal => match x.cmp(&y) {
+                    Equal => {}
+                    order => return order
+                },
+                order => return order
+            }
+        } else {
+            match a[i].cmp(&b[j]) {
+                Equal => { i += 1; j += 1; }
+                order => return order
+            }
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j))
+}
+
+fn is_digit(b: u8) -> bool {
+    b >= '0' as u8 && b <= '9' as u8
+}
+
+fn trim_zeros<'a>(digits: &'a [u8]) -> &'a [u8] {
+    let mut start = 0;
+    while start + 1 < digits.len() && digits[start] == '0' as u8 {
+        start += 1;
+    }
+    digits.slice_from(start)
+}
+
+fn display_entries(entries: &[Entry], options: &Options, dir_contents: bool) {
+    if (options.format == Long || options.blocks) && dir_contents {
+        display_total(entries, options);
+    }
+    if entries.is_empty() {
+        return;
+    }
+    if options.format == Long {
+        display_long(entries, options);
+        return;
+    }
+
+    let blocks: Vec<String> = if options.blocks {
+        entries.iter().map(|e| display_blocks(&e.stat, options)).collect()
+    } else {
+        vec!()
+    };
+    let blocks_width = blocks.iter().map(|b| b.len()).max().unwrap_or(0);
+    // each item is the text to print and how many columns it takes up
+    let items: Vec<(String, uint)> = entries.iter().enumerate().map(|(i, entry)| {
+        let mut text = String::new();
+        let mut width = entry.name.as_slice().char_len();
+        if options.blocks {
+            let b = blocks.get(i);
+            text.grow(blocks_width - b.len(), ' ');
+            text.push_str(b.as_slice());
+            text.push_char(' ');
+            width += blocks_width + 1;
+        }
+        text.push_str(display_name(entry, options).as_slice());
+        (text, width)
+    }).collect();
+
+    match options.format {
+        OnePerLine => {
+            for &(ref text, _) in items.iter() {
+                put_line(text.as_slice(), options);
+            }
+        }
+        _ => display_columns(items.as_slice(), options.width)
+    }
+}
+
+fn put_line(line: &str, options: &Options) {
+    print!("{}{}", line, options.line_end);
+}
+
+// st_blocks is always in 512-byte units, whatever the block size is.
+fn display_blocks(stat: &fsext::c_stat, options: &Options) -> String {
+    size::format_size(stat.st_blocks as u64 * 512, &options.block_size)
+}
+
+fn display_total(entries: &[Entry], options: &Options) {
+    let blocks = entries.iter().fold(0u64, |a, e| a + e.stat.st_blocks as u64);
+    let total = format!("total {}", size::format_size(blocks * 512, &options.block_size));
+    put_line(total.as_slice(), options);
+}
+
+fn display_long(entries: &[Entry], options: &Options) {
+    let now = time::get_time().sec;
+    let rows: Vec<Vec<String>> = entries.iter().map(|entry| {
+        let stat = &entry.stat;
+        let mut row = vec!();
+        if options.blocks {
+            row.push(display_blocks(stat, options));
+        }
+        row.push(fsext::mode_string(stat.st_mode as libc::mode_t));
+        row.push(stat.st_nlink.to_str());
+        if options.show_owner {
+            row.push(display_uid(stat.st_uid, options.numeric_ids));
+        }
+        if options.show_group {
+            row.push(display_gid(stat.st_gid, options.numeric_ids));
+        }
+        let mode = stat.st_mode as libc::mode_t;
+        row.push(if fsext::is_chr(mode) || fsext::is_blk(mode) {
+            format!("{}, {}", fsext::major(stat.st_rdev as u64), fsext::minor(stat.st_rdev as u64))
+        } else {
+            match options.size_format {
+                Some(ref size) => size::format_size(stat.st_size as u64, size),
+                None => stat.st_size.to_str()
+            }
+        });
+        let (secs, _) = file_time(stat, options.time);
+        row.push(display_time(secs, now));
+        row
+    }).collect();
+
+    let columns = rows.get(0).len();
+    let widths: Vec<uint> = range(0, columns).map(|c| {
+        rows.iter().map(|r| r.get(c).as_slice().char_len()).max().unwrap()
+    }).collect();
+    // owner and group are left aligned, the numeric columns right aligned
+    let first_name = if options.blocks { 3 } else { 2 };
+    let left_aligned = |c: uint| c >= first_name && c < columns - 2;
+
+    for (entry, row) in entries.iter().zip(rows.iter()) {
+        let mut line = String::new();
+        for (c, field) in row.iter().enumerate() {
+            let pad = *widths.get(c) - field.as_slice().char_len();
+            if c > 0 {
+                line.push_char(' ');
+            }
+            if left_aligned(c) {
+                line.push_str(field.as_slice());
+                line.grow(pad, ' ');
+            } else {
+                line.grow(pad, ' ');
+                line.push_str(field.as_slice());
+            }
+        }
+        line.push_char(' ');
+        line.push_str(display_name(entry, options).as_slice());
+        if fsext::is_lnk(entry.stat.st_mode as libc::mode_t) {
+            match fs::readlink(&entry.path) {
+                Ok(target) => {
+                    line.push_str(" -> ");
+                    line.push_str(display_target(entry, &target, options).as_slice());
+                }
+                Err(e) => show_error!(1, "cannot read symbolic link {}: {}", entry.name,
+                                      ::util::strerror(&e))
+            }
+        }
+        put_line(line.as_slice(), options);
+    }
+}
+
+fn display_uid(uid: libc::uid_t, numeric: bool) -> String {
+    match if numeric { None } else { c_types::uid2usr(uid) } {
+        Some(name) => name,
+        None => uid.to_str()
+    }
+}
+
+fn display_gid(gid: libc::gid_t, numeric: bool) -> String {
+    match if numeric { None } else { c_types::gid2grp(gid) } {
+        Some(name) => name,
+        None => gid.to_str()
+    }
+}
+
+// Files modified within the last six months (and not in the future) show the
+// time of day; anything else shows the year instead.
+fn display_time(secs: i64, now: i64) -> String {
+    static SIX_MONTHS: i64 = 31556952 / 2;
+    let tm = time::at(Timespec::new(secs, 0));
+    if secs <= now && now - secs < SIX_MONTHS {
+        tm.strftime("%b %e %H:%M")
+    } else {
+        tm.strftime("%b %e  %Y")
+    }
+}
+
+fn display_name(entry: &Entry, options: &Options) -> String {
+    let color = match options.colors {
+        Some(ref colors) => {
+            let dangling = fsext::is_lnk(entry.stat.st_mode as libc::mode_t)
+                           && fsext::stat_path(&entry.path).is_err();
+            file_color(&entry.stat, &entry.path, entry.name.as_slice(), dangling, colors)
+        }
+        None => None
+    };
+    colorize(entry.name.as_slice(), color)
+}
+
+fn display_target(link: &Entry, target: &Path, options: &Options) -> String {
+    let name = target.display().to_str();
+    let color = match options.colors {
+        Some(ref colors) => match fsext::stat_path(&link.path) {
+            Ok(stat) => file_color(&stat, &link.path, name.as_slice(), false, colors),
+            Err(_) => colors.class("mi").or_else(|| colors.class("or"))
+        },
+        None => None
+    };
+    colorize(name.as_slice(), color)
+}
+
+fn colorize(name: &str, color: Option<&str>) -> String {
+    match color {
+        Some(color) => format!("\x1b[{}m{}\x1b[0m", color, name),
+        None => name.to_string()
+    }
+}
+
+// Picks the LS_COLORS class for a file the way GNU ls does: the most
+// specific class that has a color wins, then the suffix, then the default.
+fn file_color<'a>(stat: &fsext::c_stat, path: &Path, name: &str, dangling: bool,
+                  colors: &'a LsColors) -> Option<&'a str> {
+    let mode = stat.st_mode as libc::mode_t;
+    let other_writable = mode & 0o002 != 0;
+    let sticky = mode & fsext::S_ISVTX != 0;
+    match fsext::file_type(mode) {
+        fsext::S_IFLNK => {
+            if dangling {
+                colors.class("or").or_else(|| colors.class("ln"))
+            } else if colors.class("ln") == Some("target") {
+                match fsext::stat_path(path) {
+                    Ok(ref target) => file_color(target, path, name, false, colors),
+                    Err(_) => colors.class("ln")
+                }
+            } else {
+                colors.class("ln")
+            }
+        }
+        fsext::S_IFDIR => {
+            let special = if sticky && other_writable {
+                colors.class("tw")
+            } else {
+                None
+            };
+            special.or_else(|| if other_writable { colors.class("ow") } else { None })
+                   .or_else(|| if sticky { colors.class("st") } else { None })
+                   .or_else(|| colors.class("di"))
+        }
+        fsext::S_IFREG => {
+            let special = if mode & fsext::S_ISUID != 0 { colors.class("su") } else { None };
+            special.or_else(|| if mode & fsext::S_ISGID != 0 { colors.class("sg") } else { None })
+                   .or_else(|| if mode & 0o111 != 0 { colors.class("ex") } else { None })
+                   .or_else(|| if stat.st_nlink > 1 { multi_hard_link(colors) } else { None })
+                   .or_else(|| colors.suffix(name))
+                   .or_else(|| colors.class("fi"))
+        }
+        fsext::S_IFIFO => colors.class("pi"),
+        fsext::S_IFSOCK => colors.class("so"),
+        fsext::S_IFBLK => colors.class("bd"),
+        fsext::S_IFCHR => colors.class("cd"),
+        _ => colors.class("fi")
+    }
+}
+
+// mh=00, as dircolors has it, only means that files with several links are
+// not singled out, and should not stop the suffix colors applying to them.
+fn multi_hard_link<'a>(colors: &'a LsColors) -> Option<&'a str> {
+    match colors.class("mh") {
+        Some("0") | Some("00") => None,
+        color => color
+    }
+}
+
+// Lays the names out top to bottom, then left to right, using as many
+// columns as fit in the given width.
+fn display_columns(items: &[(String, uint)], width: uint) {
+    let lengths: Vec<uint> = items.iter().map(|&(_, len)| len).collect();
+    let count = items.len();
+
+    let mut layout = (1, vec!(*lengths.iter().max().unwrap()));
+    for cols in range(2, count + 1).rev() {
+        let rows = (count + cols - 1) / cols;
+        // skip column counts that would leave the last column empty
+        if (cols - 1) * rows >= count {
+            continue;
+        }
+        let widths: Vec<uint> = range(0, cols).map(|c| {
+            lengths.slice(c * rows, cmp::min((c + 1) * rows, count))
+                   .iter().map(|&l| l).max().unwrap_or(0)
+        }).collect();
+        let total = widths.iter().fold(0, |a, &w| a + w) + 2 * (cols - 1);
+        if total <= width {
+            layout = (cols, widths);
+            break;
+        }
+    }
+
+    let (cols, widths) = layout;
+    let rows = (count + cols - 1) / cols;
+    for row in range(0, rows) {
+        let mut line = String::new();
+        for col in range(0, cols) {
+            let idx = col * rows + row;
+            if idx >= count {
+                break;
+            }
+            let (ref text, _) = items[idx];
+            line.push_str(text.as_slice());
+            if col + 1 < cols && idx + rows < count {
+                for _ in range(lengths[idx], widths[col] + 2) {
+                    line.push_char(' ');
+                }
+            }
+        }
+        println!("{}", line);
+    }
+}
@@ -0,0 +1,116 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * The characters in text that need not be valid UTF-8, and the columns they
+ * take on a terminal, for the options that count characters or columns
+ * rather than bytes.  A byte that does not begin a valid UTF-8 sequence is
+ * passed on by itself, as GNU does in a UTF-8 locale, so nothing is lost.
+ */
+
+#![allow(dead_code)]
+
+use std::str;
+
+pub struct Char<'a> {
+    // None for a byte that is not part of a valid sequence
+    pub ch: Option<char>,
+    pub bytes: &'a [u8],
+}
+
+pub struct Chars<'a> {
+    rest: &'a [u8],
+}
+
+pub fn chars<'a>(bytes: &'a [u8]) -> Chars<'a> {
+    Chars { rest: bytes }
+}
+
+impl<'a> Iterator<Char<'a>> for Chars<'a> {
+    fn next(&mut self) -> Option<Char<'a>> {
+        let rest = self.rest;
+        if rest.is_empty() {
+            return None;
+        }
+        let len = utf8_len(rest);
+        let (bytes, ch) = if len == 0 {
+            (rest.slice_to(1), None)
+        } else {
+            let bytes = rest.slice_to(len);
+            (bytes, str::from_utf8(bytes).and_then(|s| s.chars().next()))
+        };
+        self.rest = rest.slice_from(bytes.len());
+        Some(Char { ch: ch, bytes: bytes })
+    }
+}
+
+// The length of the UTF-8 sequence the bytes start with, or 0 if they do not
+// start with a valid one.
+pub fn utf8_len(bytes: &[u8]) -> uint {
+    let len = match bytes[0] {
+        0x00 .. 0x7f => 1,
+        0xc2 .. 0xdf => 2,
+        0xe0 .. 0xef => 3,
+        0xf0 .. 0xf4 => 4,
+        _ => return 0
+    };
+    if bytes.len() < len {
+        return 0;
+    }
+    match str::from_utf8(bytes.slice_to(len)) {
+        Some(_) => len,
+        None => 0
+    }
+}
+
+// How many columns the character takes, as wcwidth(3) has it: none for
+// control characters and combining marks, two for East Asian wide ones.
+// Tabs and the like are left to the caller.
+pub fn width(c: char) -> uint {
+    let c = c as u32;
+    if c < 0x20 || (c >= 0x7f && c < 0xa0) || in_table(c, ZERO_WIDTH) {
+        0
+    } else if in_table(c, WIDE) {
+        2
+    } else {
+        1
+    }
+}
+
+// The columns the bytes take, each invalid byte counting as one, which is
+// how a terminal usually shows it.
+pub fn str_width(bytes: &[u8]) -> uint {
+    chars(bytes).fold(0, |total, c| total + match c.ch {
+        Some(ch) => width(ch),
+        None => 1
+    })
+}
+
+fn in_table(c: u32, table: &[(u32, u32)]) -> bool {
+    table.iter().any(|&(first, last)| first <= c && c <= last)
+}
+
+static ZERO_WIDTH: &'static [(u32, u32)] = &[
+    (0x0300, 0x036f), (0x0483, 0x0489), (0x0591, 0x05bd), (0x05bf, 0x05bf),
+    (0x05c1, 0x05c2), (0x05c4, 0x05c5), (0x05c7, 0x05c7), (0x0610, 0x061a),
+    (0x064b, 0x065f), (0x0670, 0x0670), (0x06d6, 0x06dc), (0x06df, 0x06e4),
+    (0x06e7, 0x06e8), (0x06ea, 0x06ed), (0x0900, 0x0902), (0x093c, 0x093c),
+    (0x0941, 0x0948), (0x094d, 0x094d), (0x0e31, 0x0e31), (0x0e34, 0x0e3a),
+    (0x0e47, 0x0e4e), (0x1160, 0x11ff), (0x1ab0, 0x1aff), (0x1dc0, 0x1dff),
+    (0x200b, 0x200f), (0x202a, 0x202e), (0x2060, 0x2064), (0x20d0, 0x20ff),
+    (0x302a, 0x302d), (0x3099, 0x309a), (0xfe00, 0xfe0f), (0xfe20, 0xfe2f),
+    (0xfeff, 0xfeff), (0xe0100, 0xe01ef),
+];
+
+static WIDE: &'static [(u32, u32)] = &[
+    (0x1100, 0x115f), (0x2329, 0x232a), (0x2e80, 0x303e), (0x3041, 0x33ff),
+    (0x3400, 0x4dbf), (0x4e00, 0x9fff), (0xa000, 0xa4cf), (0xac00, 0xd7a3),
+    (0xf900, 0xfaff), (0xfe30, 0xfe4f), (0xff00, 0xff60), (0xffe0, 0xffe6),
+    (0x1f300, 0x1f64f), (0x1f900, 0x1f9ff), (0x20000, 0x2fffd), (0x30000, 0x3fffd),
+];
@@ -0,0 +1,135 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * File mode arguments as chmod(1) takes them: an octal number, or a comma
+ * separated list of symbolic clauses such as u+x,go-w or a=r.
+ */
+
+#![allow(dead_code)]
+
+extern crate libc;
+
+use std::num;
+use self::libc::mode_t;
+
+static USER:  mode_t = 0o4700;
+static GROUP: mode_t = 0o2070;
+static OTHER: mode_t = 0o1007;
+static ALL:   mode_t = 0o7777;
+
+static SET_ID: mode_t = 0o6000;
+static STICKY: mode_t = 0o1000;
+
+// Applies spec to current, the mode the file has now, returning the new
+// permission bits.  Clauses that name no users are limited by umask, as is
+// the X permission by whether the file is a directory.
+pub fn parse(spec: &str, current: mode_t, umask: mode_t, is_dir: bool) -> Result<mode_t, String> {
+    let current = current & ALL;
+    if !spec.is_empty() && spec.chars().all(|c| c >= '0' && c <= '7') {
+        return parse_octal(spec, current, is_dir);
+    }
+    let mut mode = current;
+    for clause in spec.split(',') {
+        mode = match parse_clause(clause, mode, umask, is_dir) {
+            Some(mode) => mode,
+            None => return Err(format!("invalid mode: '{}'", spec))
+        };
+    }
+    Ok(mode)
+}
+
+// A directory keeps its set-ID bits unless the number has five digits or
+// more, as in GNU, since they mean something else for directories.
+fn parse_octal(spec: &str, current: mode_t, is_dir: bool) -> Result<mode_t, String> {
+    match num::from_str_radix::<u32>(spec, 8) {
+        Some(mode) if mode <= ALL as u32 => {
+            let mode = mode as mode_t;
+            if is_dir && spec.len() < 5 {
+                Ok(mode | (current & SET_ID))
+            } else {
+                Ok(mode)
+            }
+        }
+        _ => Err(format!("invalid mode: '{}'", spec))
+    }
+}
+
+fn parse_clause(clause: &str, current: mode_t, umask: mode_t, is_dir: bool) -> Option<mode_t> {
+    let mut chars = clause.chars().peekable();
+    let mut who = 0;
+    loop {
+        match chars.peek() {
+            Some(&'u') => who |= USER,
+            Some(&'g') => who |= GROUP,
+            Some(&'o') => who |= OTHER,
+            Some(&'a') => who |= ALL,
+            _ => break
+        }
+        chars.next();
+    }
+    // with nobody named, the umask decides who is affected
+    let (who, mask) = if who == 0 { (ALL, ALL & !umask) } else { (who, who) };
+
+    let mut mode = current;
+    let mut ops = 0u;
+    loop {
+        let op = match chars.next() {
+            Some(c) if c == '+' || c == '-' || c == '=' => c,
+            Some(_) => return None,
+            None => break
+        };
+        ops += 1;
+
+        let mut perms = 0;
+        let mut copied = false;
+        loop {
+            let bits = match chars.peek() {
+                Some(&'r') => 0o444,
+                Some(&'w') => 0o222,
+                Some(&'x') => 0o111,
+                // execute only for directories and files someone can
+                // already execute
+                Some(&'X') if is_dir || current & 0o111 != 0 => 0o111,
+                Some(&'X') => 0,
+                Some(&'s') => SET_ID,
+                Some(&'t') => STICKY,
+                Some(&c) if !copied && perms == 0 && (c == 'u' || c == 'g' || c == 'o') => {
+                    copied = true;
+                    copy_class(current, c)
+                }
+                _ => break
+            };
+            perms |= bits;
+            chars.next();
+        }
+
+        let bits = perms & mask;
+        mode = match op {
+            '+' => mode | bits,
+            '-' => mode & !bits,
+            _ => {
+                // a directory's set-ID bits only go when asked to
+                let keep = if is_dir && perms & SET_ID == 0 { SET_ID } else { 0 };
+                (mode & !(who & !keep)) | bits
+            }
+        };
+    }
+    if ops == 0 { None } else { Some(mode) }
+}
+
+// The rwx bits of one class of users, copied out to all three.
+fn copy_class(mode: mode_t, class: char) -> mode_t {
+    let bits = match class {
+        'u' => (mode >> 6) & 0o7,
+        'g' => (mode >> 3) & 0o7,
+        _ => mode & 0o7
+    };
+    bits * 0o111
+}
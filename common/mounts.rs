@@ -0,0 +1,233 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * The mounted file systems and how full they are: /proc/self/mounts (or
+ * /etc/mtab) on Linux, getmntinfo(3) elsewhere, and statvfs(3) for the
+ * numbers.
+ */
+
+#![allow(dead_code, non_camel_case_types)]
+
+extern crate libc;
+
+use std::io::{File, IoError, IoResult};
+use std::mem;
+use std::str;
+use self::libc::{c_char, c_int, c_ulong};
+
+pub struct Mount {
+    // what is mounted, such as /dev/sda1 or tmpfs
+    pub device: String,
+    pub dir: String,
+    pub fs_type: String,
+    pub options: String,
+    // device and dir as the system has them, for when they are not UTF-8
+    pub device_bytes: Vec<u8>,
+    pub dir_bytes: Vec<u8>,
+}
+
+impl Mount {
+    // Pseudo file systems that hold no data of their own, which df leaves
+    // out unless asked for everything.
+    pub fn is_dummy(&self) -> bool {
+        DUMMY_TYPES.contains(&self.fs_type.as_slice())
+    }
+
+    // File systems that live on another machine.
+    pub fn is_remote(&self) -> bool {
+        self.device.as_slice().contains_char(':') || self.device.as_slice().starts_with("//")
+        || REMOTE_TYPES.contains(&self.fs_type.as_slice())
+    }
+}
+
+static DUMMY_TYPES: &'static [&'static str] = &[
+    "autofs", "binfmt_misc", "bpf", "cgroup", "cgroup2", "configfs", "debugfs", "devpts",
+    "efivarfs", "fusectl", "hugetlbfs", "ignore", "kernfs", "mqueue", "none", "nsfs", "proc",
+    "pstore", "rootfs", "rpc_pipefs", "securityfs", "selinuxfs", "subfs", "sysfs", "tracefs",
+];
+
+static REMOTE_TYPES: &'static [&'static str] = &[
+    "afs", "cifs", "coda", "fuse.sshfs", "ncpfs", "nfs", "nfs4", "smbfs", "sshfs",
+];
+
+pub struct Usage {
+    // the size of the blocks the counts below are in
+    pub block_size: u64,
+    pub blocks: u64,
+    pub blocks_free: u64,
+    // free to users other than root
+    pub blocks_avail: u64,
+    pub files: u64,
+    pub files_free: u64,
+}
+
+#[cfg(target_os = "linux")]
+struct c_statvfs {
+    f_bsize: c_ulong,
+    f_frsize: c_ulong,
+    f_blocks: c_ulong,
+    f_bfree: c_ulong,
+    f_bavail: c_ulong,
+    f_files: c_ulong,
+    f_ffree: c_ulong,
+    f_favail: c_ulong,
+    f_fsid: c_ulong,
+    f_flag: c_ulong,
+    f_namemax: c_ulong,
+    __f_spare: [c_int, ..6],
+}
+
+#[cfg(not(target_os = "linux"))]
+struct c_statvfs {
+    f_bsize: c_ulong,
+    f_frsize: c_ulong,
+    f_blocks: libc::c_uint,
+    f_bfree: libc::c_uint,
+    f_bavail: libc::c_uint,
+    f_files: libc::c_uint,
+    f_ffree: libc::c_uint,
+    f_favail: libc::c_uint,
+    f_fsid: c_ulong,
+    f_flag: c_ulong,
+    f_namemax: c_ulong,
+}
+
+extern {
+    fn statvfs(path: *c_char, buf: *mut c_statvfs) -> c_int;
+}
+
+pub fn usage(path: &Path) -> IoResult<Usage> {
+    let mut buf: c_statvfs = unsafe { mem::zeroed() };
+    if path.with_c_str(|p| unsafe { statvfs(p, &mut buf) }) != 0 {
+        return Err(IoError::last_error());
+    }
+    // the counts are in fragments, which is what f_frsize is the size of
+    let block_size = if buf.f_frsize > 0 { buf.f_frsize } else { buf.f_bsize };
+    Ok(Usage {
+        block_size: block_size as u64,
+        blocks: buf.f_blocks as u64,
+        blocks_free: buf.f_bfree as u64,
+        blocks_avail: buf.f_bavail as u64,
+        files: buf.f_files as u64,
+        files_free: buf.f_ffree as u64,
+    })
+}
+
+// Everything that is mounted, in the order it was mounted in.
+#[cfg(target_os = "linux")]
+pub fn mounts() -> IoResult<Vec<Mount>> {
+    // names need not be UTF-8, so the table is taken a byte at a time
+    let table = match File::open(&Path::new("/proc/self/mounts")).read_to_end() {
+        Ok(table) => table,
+        Err(_) => try!(File::open(&Path::new("/etc/mtab")).read_to_end())
+    };
+    let mut mounts = vec!();
+    for line in table.as_slice().split(|&b| b == '\n' as u8) {
+        let fields: Vec<&[u8]> = line.split(|&b| b == ' ' as u8 || b == '\t' as u8)
+                                     .filter(|field| !field.is_empty())
+                                     .collect();
+        if fields.len() < 4 {
+            continue;
+        }
+        let device = unescape(*fields.get(0));
+        let dir = unescape(*fields.get(1));
+        mounts.push(Mount {
+            device: lossy(device.as_slice()),
+            dir: lossy(dir.as_slice()),
+            fs_type: lossy(unescape(*fields.get(2)).as_slice()),
+            options: lossy(unescape(*fields.get(3)).as_slice()),
+            device_bytes: device,
+            dir_bytes: dir,
+        });
+    }
+    Ok(mounts)
+}
+
+// Spaces, tabs, newlines and backslashes in the table are written as
+// three-digit octal escapes.
+#[cfg(target_os = "linux")]
+fn unescape(bytes: &[u8]) -> Vec<u8> {
+    let mut out = vec!();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == '\\' as u8 && i + 3 < bytes.len()
+           && bytes.slice(i + 1, i + 4).iter().all(|&b| b >= '0' as u8 && b <= '7' as u8) {
+            let value = bytes.slice(i + 1, i + 4).iter()
+                             .fold(0u, |n, &b| n * 8 + (b - '0' as u8) as uint);
+            out.push(value as u8);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    str::from_utf8_lossy(bytes).into_string()
+}
+
+#[cfg(not(target_os = "linux"))]
+struct c_statfs {
+    f_bsize: u32,
+    f_iosize: i32,
+    f_blocks: u64,
+    f_bfree: u64,
+    f_bavail: u64,
+    f_files: u64,
+    f_ffree: u64,
+    f_fsid: [i32, ..2],
+    f_owner: libc::uid_t,
+    f_type: u32,
+    f_flags: u32,
+    f_fssubtype: u32,
+    f_fstypename: [c_char, ..16],
+    f_mntonname: [c_char, ..1024],
+    f_mntfromname: [c_char, ..1024],
+    f_reserved: [u32, ..8],
+}
+
+#[cfg(not(target_os = "linux"))]
+extern {
+    #[link_name = "getmntinfo$INODE64"]
+    fn getmntinfo(mntbufp: *mut *c_statfs, flags: c_int) -> c_int;
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn mounts() -> IoResult<Vec<Mount>> {
+    use std::c_str::CString;
+
+    let bytes = |p: *c_char| unsafe { Vec::from_slice(CString::new(p, false).as_bytes_no_nul()) };
+
+    static MNT_NOWAIT: c_int = 2;
+    static MNT_RDONLY: u32 = 1;
+
+    let mut list: *c_statfs = ::std::ptr::null();
+    let count = unsafe { getmntinfo(&mut list, MNT_NOWAIT) };
+    if count <= 0 {
+        return Err(IoError::last_error());
+    }
+    let mut mounts = vec!();
+    for i in range(0, count as int) {
+        let fs = unsafe { &*list.offset(i) };
+        let device = bytes(fs.f_mntfromname.as_ptr());
+        let dir = bytes(fs.f_mntonname.as_ptr());
+        mounts.push(Mount {
+            device: lossy(device.as_slice()),
+            dir: lossy(dir.as_slice()),
+            fs_type: lossy(bytes(fs.f_fstypename.as_ptr()).as_slice()),
+            options: if fs.f_flags & MNT_RDONLY != 0 { "ro" } else { "rw" }.to_string(),
+            device_bytes: device,
+            dir_bytes: dir,
+        });
+    }
+    Ok(mounts)
+}
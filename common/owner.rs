@@ -0,0 +1,286 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * What chown and chgrp have in common: working out the new owner from
+ * USER[:GROUP] specs, their shared options, and changing ownership over a
+ * tree.  Crates using this module need `extern crate getopts` and
+ * common/{util,fsext,walk,c_types}.rs declared under their own names.
+ */
+
+#![allow(dead_code)]
+
+use std::io::IoError;
+use std::os;
+use getopts;
+use libc::{gid_t, uid_t};
+
+use c_types;
+use fsext;
+use walk::{Follow, FollowNone, FollowAll, Visitor, WalkEntry, Walker};
+
+#[deriving(PartialEq)]
+pub enum Verbosity {
+    Silent,
+    Normal,
+    Changes,
+    Verbose,
+}
+
+pub struct Options {
+    pub uid: Option<uid_t>,
+    pub gid: Option<gid_t>,
+    // --from: only files owned by these are changed
+    pub from_uid: Option<uid_t>,
+    pub from_gid: Option<gid_t>,
+    pub recursive: bool,
+    pub follow: Follow,
+    // whether to change what symbolic links point to rather than the links
+    pub dereference: bool,
+    pub preserve_root: bool,
+    pub verbosity: Verbosity,
+    // chgrp only talks about groups
+    pub group_only: bool,
+}
+
+// The options chown and chgrp both take.
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("c", "changes", "like verbose but report only when a change is made"),
+        getopts::optflag("f", "silent", "suppress most error messages"),
+        getopts::optflag("", "quiet", "same as -f"),
+        getopts::optflag("v", "verbose", "output a diagnostic for every file processed"),
+        getopts::optflag("", "dereference", "affect the referent of each symbolic link (this is \
+                                             the default), rather than the symbolic link itself"),
+        getopts::optflag("h", "no-dereference", "affect symbolic links instead of any referenced \
+                                                 file"),
+        getopts::optopt("", "from", "change the owner and/or group of each file only if its \
+                                     current owner and/or group match those specified here",
+                        "CURRENT_OWNER:CURRENT_GROUP"),
+        getopts::optflag("", "no-preserve-root", "do not treat '/' specially (the default)"),
+        getopts::optflag("", "preserve-root", "fail to operate recursively on '/'"),
+        getopts::optopt("", "reference", "use RFILE's owner and group rather than specifying \
+                                          values", "RFILE"),
+        getopts::optflag("R", "recursive", "operate on files and directories recursively"),
+        getopts::optflag("H", "", "with -R, traverse symbolic links to directories given on \
+                                   the command line"),
+        getopts::optflag("L", "", "with -R, traverse every symbolic link to a directory"),
+        getopts::optflag("P", "", "with -R, do not traverse any symbolic links (default)"),
+        getopts::optflag("", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+// Everything but the new owner itself out of matches.
+pub fn options(matches: &getopts::Matches, group_only: bool) -> Options {
+    let (from_uid, from_gid) = match matches.opt_str("from") {
+        Some(spec) => match parse_spec(spec.as_slice()) {
+            Ok(ids) => ids,
+            Err(msg) => crash!(1, "{}", msg)
+        },
+        None => (None, None)
+    };
+    let recursive = matches.opt_present("recursive");
+    let follow = Follow::from_flags(matches.opt_present("L"), matches.opt_present("H"),
+                                    matches.opt_present("P"), FollowNone);
+    // walking without following links changes the links themselves, as
+    // there is nothing else to change
+    let dereference = !matches.opt_present("no-dereference")
+                      && !(recursive && follow == FollowNone);
+    if recursive && follow == FollowNone && matches.opt_present("dereference") {
+        crash!(1, "-R --dereference requires either -H or -L");
+    }
+    let verbosity = if matches.opt_present("verbose") {
+        Verbose
+    } else if matches.opt_present("changes") {
+        Changes
+    } else if matches.opt_present("silent") || matches.opt_present("quiet") {
+        Silent
+    } else {
+        Normal
+    };
+    Options {
+        uid: None,
+        gid: None,
+        from_uid: from_uid,
+        from_gid: from_gid,
+        recursive: recursive,
+        follow: follow,
+        dereference: dereference,
+        preserve_root: matches.opt_present("preserve-root")
+                       && !matches.opt_present("no-preserve-root"),
+        verbosity: verbosity,
+        group_only: group_only,
+    }
+}
+
+pub fn parse_user(name: &str) -> Result<uid_t, String> {
+    match c_types::usr2uid(name) {
+        Some(uid) => Ok(uid),
+        None => match from_str(name) {
+            Some(uid) => Ok(uid),
+            None => Err(format!("invalid user: '{}'", name))
+        }
+    }
+}
+
+pub fn parse_group(name: &str) -> Result<gid_t, String> {
+    match c_types::grp2gid(name) {
+        Some(gid) => Ok(gid),
+        None => match from_str(name) {
+            Some(gid) => Ok(gid),
+            None => Err(format!("invalid group: '{}'", name))
+        }
+    }
+}
+
+// Parses USER, USER:GROUP, USER:, :GROUP or the old USER.GROUP.  Names are
+// looked up before being taken as numbers.  USER: means the user's login
+// group.
+pub fn parse_spec(spec: &str) -> Result<(Option<uid_t>, Option<gid_t>), String> {
+    let sep = match spec.find(':') {
+        Some(i) => Some(i),
+        // a dot only separates when the whole thing is not a user name
+        None if c_types::usr2uid(spec).is_none() => spec.find('.'),
+        None => None
+    };
+    let (user, group) = match sep {
+        Some(i) => (spec.slice_to(i), Some(spec.slice_from(i + 1))),
+        None => (spec, None)
+    };
+    let uid = if user.is_empty() { None } else { Some(try!(parse_user(user))) };
+    let gid = match group {
+        Some("") => match uid {
+            Some(uid) => match c_types::uid2gid(uid) {
+                Some(gid) => Some(gid),
+                None => return Err(format!("invalid spec: '{}'", spec))
+            },
+            None => None
+        },
+        Some(group) => Some(try!(parse_group(group))),
+        None => None
+    };
+    Ok((uid, gid))
+}
+
+// Changes the ownership of each file, and everything below it with
+// options.recursive.
+pub fn change_all(files: &[String], options: &Options) {
+    let root = fsext::stat_path(&Path::new("/")).ok();
+    for file in files.iter() {
+        let path = Path::new(file.as_slice());
+        if options.recursive && options.preserve_root {
+            match (fsext::stat_path(&path), root) {
+                (Ok(stat), Some(root)) if stat.st_dev == root.st_dev
+                                          && stat.st_ino == root.st_ino => {
+                    show_error!(1, "it is dangerous to operate recursively on '{}'", file);
+                    show_error!(1, "use --no-preserve-root to override this failsafe");
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        // only the links on the command line matter without -R
+        let follow = if options.recursive {
+            options.follow
+        } else if options.dereference {
+            FollowAll
+        } else {
+            FollowNone
+        };
+        let mut visitor = OwnerVisitor { options: options };
+        Walker::new().follow(follow).walk(&path, &mut visitor);
+    }
+}
+
+struct OwnerVisitor<'a> {
+    options: &'a Options,
+}
+
+impl<'a> Visitor for OwnerVisitor<'a> {
+    fn enter_dir(&mut self, dir: &WalkEntry) -> bool {
+        change(&dir.path, &dir.stat, self.options);
+        self.options.recursive
+    }
+
+    fn visit_file(&mut self, file: &WalkEntry) {
+        change(&file.path, &file.stat, self.options);
+    }
+
+    fn error(&mut self, path: &Path, err: IoError) {
+        if self.options.verbosity != Silent {
+            show_error!(1, "cannot access '{}': {}", path.display(), ::util::strerror(&err));
+        } else {
+            ::util::set_status(1);
+        }
+    }
+}
+
+fn change(path: &Path, stat: &fsext::c_stat, options: &Options) {
+    // a link reached by the walker is the link itself, which -h decides
+    // between changing and seeing through
+    let stat = if options.dereference && fsext::is_lnk(stat.st_mode) {
+        match fsext::stat_path(path) {
+            Ok(stat) => stat,
+            Err(e) => {
+                if options.verbosity != Silent {
+                    show_error!(1, "cannot dereference '{}': {}", path.display(),
+                                ::util::strerror(&e));
+                } else {
+                    ::util::set_status(1);
+                }
+                return;
+            }
+        }
+    } else {
+        *stat
+    };
+
+    let old = describe(stat.st_uid, stat.st_gid, options.group_only);
+    let matches = options.from_uid.map_or(true, |uid| uid == stat.st_uid)
+                  && options.from_gid.map_or(true, |gid| gid == stat.st_gid);
+    let uid = options.uid.unwrap_or(stat.st_uid);
+    let gid = options.gid.unwrap_or(stat.st_gid);
+    let what = if options.group_only { "group" } else { "ownership" };
+    if !matches || (uid == stat.st_uid && gid == stat.st_gid) {
+        if options.verbosity == Verbose {
+            println!("{} of '{}' retained as {}", what, path.display(), old);
+        }
+        return;
+    }
+
+    let set_uid = if options.uid.is_some() { uid } else { fsext::UNCHANGED };
+    let set_gid = if options.gid.is_some() { gid } else { fsext::UNCHANGED };
+    match fsext::set_owner(path, set_uid, set_gid, options.dereference) {
+        Ok(()) => {
+            if options.verbosity == Verbose || options.verbosity == Changes {
+                println!("changed {} of '{}' from {} to {}", what, path.display(), old,
+                         describe(uid, gid, options.group_only));
+            }
+        }
+        Err(e) => {
+            if options.verbosity != Silent {
+                show_error!(1, "changing {} of '{}': {}", what, path.display(),
+                            ::util::strerror(&e));
+            } else {
+                ::util::set_status(1);
+            }
+        }
+    }
+}
+
+// user:group, by name where there is one
+fn describe(uid: uid_t, gid: gid_t, group_only: bool) -> String {
+    let group = c_types::gid2grp(gid).unwrap_or(gid.to_str());
+    if group_only {
+        group
+    } else {
+        format!("{}:{}", c_types::uid2usr(uid).unwrap_or(uid.to_str()), group)
+    }
+}
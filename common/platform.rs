@@ -0,0 +1,122 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * What the utilities need from the console that the standard library does
+ * not give them, for Unix and for Windows.  Crates using this module need
+ * `extern crate libc`.
+ */
+
+#![allow(dead_code)]
+
+use std::os;
+use libc;
+
+pub static STDIN: libc::c_int = 0;
+pub static STDOUT: libc::c_int = 1;
+pub static STDERR: libc::c_int = 2;
+
+// Whether the descriptor is a terminal, or on Windows a console.
+pub fn is_terminal(fd: libc::c_int) -> bool {
+    unsafe { imp::isatty(fd) != 0 }
+}
+
+// How wide the terminal is, from $COLUMNS if that is set and otherwise from
+// the terminal on standard output, if there is one.
+pub fn terminal_width() -> Option<uint> {
+    match os::getenv("COLUMNS").and_then(|c| from_str::<uint>(c.as_slice())) {
+        Some(width) if width > 0 => Some(width),
+        _ => imp::console_width()
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::mem;
+    use libc;
+    use libc::{c_int, c_ushort};
+
+    pub use libc::funcs::posix88::unistd::isatty;
+
+    #[cfg(target_os = "linux")]
+    static TIOCGWINSZ: libc::c_ulong = 0x5413;
+    #[cfg(target_os = "macos")]
+    #[cfg(target_os = "freebsd")]
+    static TIOCGWINSZ: libc::c_ulong = 0x40087468;
+
+    #[allow(dead_code)]
+    struct Winsize {
+        ws_row: c_ushort,
+        ws_col: c_ushort,
+        ws_xpixel: c_ushort,
+        ws_ypixel: c_ushort,
+    }
+
+    extern {
+        fn ioctl(fd: c_int, request: libc::c_ulong, ...) -> c_int;
+    }
+
+    pub fn console_width() -> Option<uint> {
+        let mut ws: Winsize = unsafe { mem::zeroed() };
+        if unsafe { ioctl(libc::STDOUT_FILENO, TIOCGWINSZ, &mut ws) } == 0 && ws.ws_col > 0 {
+            Some(ws.ws_col as uint)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::mem;
+    use libc::{c_int, c_short, HANDLE, BOOL, DWORD, WORD};
+
+    static STD_OUTPUT_HANDLE: DWORD = -11i32 as DWORD;
+
+    #[allow(dead_code)]
+    struct Coord {
+        x: c_short,
+        y: c_short,
+    }
+
+    #[allow(dead_code)]
+    struct SmallRect {
+        left: c_short,
+        top: c_short,
+        right: c_short,
+        bottom: c_short,
+    }
+
+    #[allow(dead_code)]
+    struct ConsoleScreenBufferInfo {
+        size: Coord,
+        cursor_position: Coord,
+        attributes: WORD,
+        window: SmallRect,
+        maximum_window_size: Coord,
+    }
+
+    extern {
+        #[link_name = "_isatty"]
+        pub fn isatty(fd: c_int) -> c_int;
+    }
+
+    extern "system" {
+        fn GetStdHandle(which: DWORD) -> HANDLE;
+        fn GetConsoleScreenBufferInfo(console: HANDLE,
+                                      info: *mut ConsoleScreenBufferInfo) -> BOOL;
+    }
+
+    pub fn console_width() -> Option<uint> {
+        let mut info: ConsoleScreenBufferInfo = unsafe { mem::zeroed() };
+        let ok = unsafe { GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &mut info) };
+        let width = info.window.right - info.window.left + 1;
+        if ok != 0 && width > 0 { Some(width as uint) } else { None }
+    }
+}
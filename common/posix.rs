@@ -0,0 +1,87 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Option parsing that follows POSIX when POSIXLY_CORRECT is set.  getopts
+ * picks options out from anywhere in the arguments, as GNU getopt does, but
+ * POSIX has them end at the first operand, so that in `rm -f a -r` the -r is
+ * a file.  Crates using this module need `extern crate getopts` and
+ * common/util.rs declared under their own names.
+ */
+
+#![allow(dead_code)]
+
+use getopts;
+use getopts::{Fail_, Matches, Maybe, No, OptGroup, Yes};
+
+// getopts::getopts, except that with POSIXLY_CORRECT set everything from the
+// first operand on is an operand.
+pub fn getopts(args: &[String], opts: &[OptGroup]) -> Result<Matches, Fail_> {
+    if !::util::posixly_correct() {
+        return getopts::getopts(args, opts);
+    }
+    match first_operand(args, opts) {
+        Some(i) => {
+            let mut args = Vec::from_slice(args);
+            args.insert(i, "--".to_string());
+            getopts::getopts(args.as_slice(), opts)
+        }
+        None => getopts::getopts(args, opts)
+    }
+}
+
+// Where the operands start, if anywhere before a "--", skipping over the
+// arguments that options take in the same way getopts does.
+fn first_operand(args: &[String], opts: &[OptGroup]) -> Option<uint> {
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_slice();
+        if arg == "--" {
+            return None;
+        }
+        if arg == "-" || !arg.starts_with("-") {
+            return Some(i);
+        }
+        let next_is_value = i + 1 < args.len() && !args[i + 1].as_slice().starts_with("-");
+        if arg.starts_with("--") {
+            // --name=value carries its value with it
+            if !arg.contains_char('=') {
+                match long_option(arg.slice_from(2), opts) {
+                    Some(&OptGroup { hasarg: Yes, .. }) => i += 1,
+                    Some(&OptGroup { hasarg: Maybe, .. }) if next_is_value => i += 1,
+                    _ => {}
+                }
+            }
+        } else {
+            // in a run of short options, the first with a value takes the
+            // rest of the run, or the next argument if it is the last
+            let letters: Vec<char> = arg.slice_from(1).chars().collect();
+            for (j, &c) in letters.iter().enumerate() {
+                let last = j + 1 == letters.len();
+                match short_option(c, opts) {
+                    Some(&OptGroup { hasarg: No, .. }) | None => continue,
+                    Some(&OptGroup { hasarg: Yes, .. }) if last => i += 1,
+                    Some(&OptGroup { hasarg: Maybe, .. }) if last && next_is_value => i += 1,
+                    _ => {}
+                }
+                break;
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+fn long_option<'a>(name: &str, opts: &'a [OptGroup]) -> Option<&'a OptGroup> {
+    opts.iter().find(|opt| opt.long_name.as_slice() == name)
+}
+
+fn short_option<'a>(c: char, opts: &'a [OptGroup]) -> Option<&'a OptGroup> {
+    opts.iter().find(|opt| opt.short_name.as_slice().chars().next() == Some(c))
+}
@@ -0,0 +1,101 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * A one-line progress report on stderr for long running copies.  Crates
+ * using this module need `extern crate time` and common/size.rs declared as
+ * `mod size`.
+ */
+
+#![allow(dead_code)]
+
+use std::io::stderr;
+use size;
+
+// redraw at most this often, in nanoseconds
+static REDRAW_INTERVAL: u64 = 100 * 1000 * 1000;
+
+pub struct Progress {
+    total: u64,
+    done: u64,
+    file_name: String,
+    file_size: u64,
+    file_done: u64,
+    started: u64,
+    last_draw: u64,
+}
+
+impl Progress {
+    // total is the number of bytes expected across all files.
+    pub fn new(total: u64) -> Progress {
+        let now = ::time::precise_time_ns();
+        Progress {
+            total: total,
+            done: 0,
+            file_name: String::new(),
+            file_size: 0,
+            file_done: 0,
+            started: now,
+            last_draw: 0,
+        }
+    }
+
+    pub fn start_file(&mut self, name: &str, size: u64) {
+        self.file_name = name.to_string();
+        self.file_size = size;
+        self.file_done = 0;
+        self.draw(true);
+    }
+
+    pub fn advance(&mut self, bytes: u64) {
+        self.file_done += bytes;
+        self.done += bytes;
+        self.draw(false);
+    }
+
+    // Counts whatever is left of the current file as done, for copies that
+    // happen without passing through advance().
+    pub fn finish_file(&mut self) {
+        if self.file_done < self.file_size {
+            let rest = self.file_size - self.file_done;
+            self.advance(rest);
+        }
+        self.draw(true);
+    }
+
+    // Draws the final state and moves off the progress line.
+    pub fn finish(&mut self) {
+        self.draw(true);
+        safe_write!(&mut stderr(), "\n");
+    }
+
+    fn draw(&mut self, force: bool) {
+        let now = ::time::precise_time_ns();
+        if !force && now - self.last_draw < REDRAW_INTERVAL {
+            return;
+        }
+        self.last_draw = now;
+
+        let elapsed = (now - self.started) as f64 / 1e9;
+        let rate = if elapsed > 0.0 { (self.done as f64 / elapsed) as u64 } else { 0 };
+        let mut err = stderr();
+        safe_write!(&mut err, "\r\x1b[K{}: {}%  total {}/{} ({}%)  {}/s",
+                    self.file_name, percent(self.file_done, self.file_size),
+                    size::human_readable(self.done, 1024),
+                    size::human_readable(self.total, 1024),
+                    percent(self.done, self.total),
+                    size::human_readable(rate, 1024));
+        // the line is never finished, so it has to be pushed out by hand
+        let _ = err.flush();
+    }
+}
+
+fn percent(done: u64, total: u64) -> u64 {
+    if total == 0 { 100 } else { done * 100 / total }
+}
@@ -0,0 +1,108 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE file
+ * that was distributed with this source code.
+ */
+
+/*
+ * Copy-on-write clones.  Linux clones into an already open destination with
+ * the FICLONE ioctl, and copy_file_range(2) lets the kernel share or copy
+ * extents without bouncing the data through userspace.  macOS can only clone
+ * to a path that does not exist yet.
+ */
+
+#![allow(dead_code)]
+
+use std::io::{IoError, IoResult, OtherIoError};
+use libc::c_int;
+use fdio::Fd;
+
+fn unsupported() -> IoError {
+    IoError {
+        kind: OtherIoError,
+        desc: "copy-on-write clones are not supported here",
+        detail: None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use libc::{c_int, c_ulong, c_uint, size_t, ssize_t, off_t};
+
+    pub static FICLONE: c_ulong = 0x40049409;
+
+    extern {
+        pub fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+        pub fn copy_file_range(fd_in: c_int, off_in: *mut off_t, fd_out: c_int,
+                               off_out: *mut off_t, len: size_t, flags: c_uint) -> ssize_t;
+    }
+}
+
+// Makes dest share all of source's data.
+#[cfg(target_os = "linux")]
+pub fn clone_fd(source: &Fd, dest: &Fd) -> IoResult<()> {
+    if unsafe { sys::ioctl(dest.raw(), sys::FICLONE, source.raw()) } == 0 {
+        Ok(())
+    } else {
+        Err(IoError::last_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn clone_fd(_source: &Fd, _dest: &Fd) -> IoResult<()> {
+    Err(unsupported())
+}
+
+// Clones source to the path dest, which must not exist.
+#[cfg(target_os = "macos")]
+pub fn clone_path(source: &Path, dest: &Path) -> IoResult<()> {
+    extern {
+        fn clonefile(src: *::libc::c_char, dst: *::libc::c_char, flags: ::libc::c_uint) -> c_int;
+    }
+    let ret = source.with_c_str(|s| dest.with_c_str(|d| unsafe { clonefile(s, d, 0) }));
+    if ret == 0 { Ok(()) } else { Err(IoError::last_error()) }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn clone_path(_source: &Path, _dest: &Path) -> IoResult<()> {
+    Err(unsupported())
+}
+
+// Copies the rest of source into dest inside the kernel.  Returns false,
+// having copied nothing, if the kernel or file system cannot do it, in which
+// case the caller should copy the data itself.
+#[cfg(target_os = "linux")]
+pub fn copy_range(source: &Fd, dest: &Fd) -> IoResult<bool> {
+    use std::os;
+    use std::ptr;
+    use libc;
+
+    static CHUNK: uint = 1 << 30;
+    let mut copied = false;
+    loop {
+        let n = unsafe {
+            sys::copy_file_range(source.raw(), ptr::mut_null(), dest.raw(), ptr::mut_null(),
+                                 CHUNK as libc::size_t, 0)
+        };
+        if n > 0 {
+            copied = true;
+        } else if n == 0 {
+            return Ok(true);
+        } else {
+            let errno = os::errno() as c_int;
+            let fallback = [libc::EXDEV, libc::EINVAL, libc::ENOSYS, libc::EOPNOTSUPP, libc::EBADF];
+            if !copied && fallback.contains(&errno) {
+                return Ok(false);
+            }
+            return Err(IoError::last_error());
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn copy_range(_source: &Fd, _dest: &Fd) -> IoResult<bool> {
+    Ok(false)
+}
@@ -0,0 +1,218 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ * (c) Maciej Dziardziel <fiedzia@gmail.com>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Catching, ignoring and raising signals.  Handlers run at any point, so all
+ * they should do is set a flag that the main loop looks at.  There is also
+ * the table of signal names, for the utilities that take one.
+ */
+
+#![allow(dead_code)]
+
+extern crate libc;
+
+use std::mem;
+use std::ptr;
+use self::libc::{c_int, c_void, pid_t, size_t};
+
+pub static SIGHUP: c_int = 1;
+pub static SIGINT: c_int = 2;
+pub static SIGQUIT: c_int = 3;
+pub static SIGKILL: c_int = 9;
+pub static SIGPIPE: c_int = 13;
+pub static SIGALRM: c_int = 14;
+pub static SIGTERM: c_int = 15;
+
+#[cfg(target_os = "linux")]
+pub static SIGUSR1: c_int = 10;
+#[cfg(target_os = "linux")]
+pub static SIGCHLD: c_int = 17;
+#[cfg(target_os = "linux")]
+pub static SIGCONT: c_int = 18;
+#[cfg(target_os = "linux")]
+pub static SIGTTIN: c_int = 21;
+#[cfg(target_os = "linux")]
+pub static SIGTTOU: c_int = 22;
+
+#[cfg(not(target_os = "linux"))]
+pub static SIGUSR1: c_int = 30;
+#[cfg(not(target_os = "linux"))]
+pub static SIGCHLD: c_int = 20;
+#[cfg(not(target_os = "linux"))]
+pub static SIGCONT: c_int = 19;
+#[cfg(not(target_os = "linux"))]
+pub static SIGTTIN: c_int = 21;
+#[cfg(not(target_os = "linux"))]
+pub static SIGTTOU: c_int = 22;
+
+pub struct Signal {
+    pub name: &'static str,
+    pub value: c_int,
+}
+
+#[cfg(target_os = "linux")]
+pub static ALL_SIGNALS: [Signal, ..31] = [
+    Signal { name: "HUP",    value: 1 },
+    Signal { name: "INT",    value: 2 },
+    Signal { name: "QUIT",   value: 3 },
+    Signal { name: "ILL",    value: 4 },
+    Signal { name: "TRAP",   value: 5 },
+    Signal { name: "ABRT",   value: 6 },
+    Signal { name: "BUS",    value: 7 },
+    Signal { name: "FPE",    value: 8 },
+    Signal { name: "KILL",   value: 9 },
+    Signal { name: "USR1",   value: 10 },
+    Signal { name: "SEGV",   value: 11 },
+    Signal { name: "USR2",   value: 12 },
+    Signal { name: "PIPE",   value: 13 },
+    Signal { name: "ALRM",   value: 14 },
+    Signal { name: "TERM",   value: 15 },
+    Signal { name: "STKFLT", value: 16 },
+    Signal { name: "CHLD",   value: 17 },
+    Signal { name: "CONT",   value: 18 },
+    Signal { name: "STOP",   value: 19 },
+    Signal { name: "TSTP",   value: 20 },
+    Signal { name: "TTIN",   value: 21 },
+    Signal { name: "TTOU",   value: 22 },
+    Signal { name: "URG",    value: 23 },
+    Signal { name: "XCPU",   value: 24 },
+    Signal { name: "XFSZ",   value: 25 },
+    Signal { name: "VTALRM", value: 26 },
+    Signal { name: "PROF",   value: 27 },
+    Signal { name: "WINCH",  value: 28 },
+    Signal { name: "POLL",   value: 29 },
+    Signal { name: "PWR",    value: 30 },
+    Signal { name: "SYS",    value: 31 },
+];
+
+#[cfg(not(target_os = "linux"))]
+pub static ALL_SIGNALS: [Signal, ..31] = [
+    Signal { name: "HUP",    value: 1 },
+    Signal { name: "INT",    value: 2 },
+    Signal { name: "QUIT",   value: 3 },
+    Signal { name: "ILL",    value: 4 },
+    Signal { name: "TRAP",   value: 5 },
+    Signal { name: "ABRT",   value: 6 },
+    Signal { name: "EMT",    value: 7 },
+    Signal { name: "FPE",    value: 8 },
+    Signal { name: "KILL",   value: 9 },
+    Signal { name: "BUS",    value: 10 },
+    Signal { name: "SEGV",   value: 11 },
+    Signal { name: "SYS",    value: 12 },
+    Signal { name: "PIPE",   value: 13 },
+    Signal { name: "ALRM",   value: 14 },
+    Signal { name: "TERM",   value: 15 },
+    Signal { name: "URG",    value: 16 },
+    Signal { name: "STOP",   value: 17 },
+    Signal { name: "TSTP",   value: 18 },
+    Signal { name: "CONT",   value: 19 },
+    Signal { name: "CHLD",   value: 20 },
+    Signal { name: "TTIN",   value: 21 },
+    Signal { name: "TTOU",   value: 22 },
+    Signal { name: "IO",     value: 23 },
+    Signal { name: "XCPU",   value: 24 },
+    Signal { name: "XFSZ",   value: 25 },
+    Signal { name: "VTALRM", value: 26 },
+    Signal { name: "PROF",   value: 27 },
+    Signal { name: "WINCH",  value: 28 },
+    Signal { name: "INFO",   value: 29 },
+    Signal { name: "USR1",   value: 30 },
+    Signal { name: "USR2",   value: 31 },
+];
+
+// The number for a signal given by name, with or without the SIG, or as the
+// number itself.
+pub fn parse(s: &str) -> Option<c_int> {
+    let name = if s.starts_with("SIG") { s.slice_from(3) } else { s };
+    match ALL_SIGNALS.iter().find(|sig| sig.name == name) {
+        Some(sig) => Some(sig.value),
+        None => ::std::from_str::from_str::<c_int>(s).and_then(|n| {
+            if n >= 0 && n <= ALL_SIGNALS.len() as c_int { Some(n) } else { None }
+        })
+    }
+}
+
+pub fn name(sig: c_int) -> Option<&'static str> {
+    ALL_SIGNALS.iter().find(|s| s.value == sig).map(|s| s.name)
+}
+
+static SIG_DFL: size_t = 0;
+static SIG_IGN: size_t = 1;
+
+// Laid out as the C library has it.  Left zeroed, sa_flags has no
+// SA_RESTART and sa_mask blocks nothing more while the handler runs.
+#[cfg(target_os = "linux")]
+struct SigAction {
+    sa_handler: size_t,
+    sa_mask: [u8, ..128],
+    sa_flags: c_int,
+    sa_restorer: *mut c_void,
+}
+
+#[cfg(target_os = "macos")]
+struct SigAction {
+    sa_handler: size_t,
+    sa_mask: u32,
+    sa_flags: c_int,
+}
+
+#[cfg(target_os = "freebsd")]
+struct SigAction {
+    sa_handler: size_t,
+    sa_flags: c_int,
+    sa_mask: [u32, ..4],
+}
+
+extern {
+    fn signal(signum: c_int, handler: size_t) -> size_t;
+    fn sigaction(signum: c_int, act: *SigAction, oldact: *mut SigAction) -> c_int;
+    fn kill(pid: pid_t, sig: c_int) -> c_int;
+    fn getpid() -> pid_t;
+}
+
+// Runs handler whenever sig arrives.  A system call the signal interrupts
+// fails with EINTR rather than being restarted, as signal(3) would have it
+// on some systems, so that a read waiting on a terminal or a pipe does not
+// keep the flag the handler sets from being looked at.
+pub fn catch(sig: c_int, handler: extern "C" fn(c_int)) {
+    unsafe {
+        let mut action: SigAction = mem::zeroed();
+        action.sa_handler = handler as size_t;
+        sigaction(sig, &action, ptr::mut_null());
+    }
+}
+
+pub fn ignore(sig: c_int) {
+    unsafe { signal(sig, SIG_IGN); }
+}
+
+pub fn reset(sig: c_int) {
+    unsafe { signal(sig, SIG_DFL); }
+}
+
+// Whether sig is being ignored, as it may have been by whoever started us.
+pub fn is_ignored(sig: c_int) -> bool {
+    unsafe {
+        let old = signal(sig, SIG_IGN);
+        signal(sig, old);
+        old == SIG_IGN
+    }
+}
+
+pub fn send(pid: pid_t, sig: c_int) -> bool {
+    unsafe { kill(pid, sig) == 0 }
+}
+
+// Dies of sig, the way a handler that caught it would have the process go
+// had it not been caught.
+pub fn raise(sig: c_int) {
+    reset(sig);
+    unsafe { kill(getpid(), sig); }
+}
@@ -0,0 +1,180 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Sizes with unit suffixes, and the block size rules shared by ls, du and
+ * df: --block-size wins, then the utility's own environment variable, then
+ * BLOCK_SIZE and BLOCKSIZE, then POSIXLY_CORRECT (512-byte blocks), and
+ * finally the utility's default.
+ */
+
+#![allow(dead_code)]
+
+use std::os;
+
+#[deriving(PartialEq, Clone)]
+pub enum BlockSize {
+    // -h / --si: scale each size to fit, in powers of 1024 or 1000
+    Human(u64),
+    // fixed units of this many bytes, with the suffix that named them (if
+    // any) printed after each number
+    Blocks(u64, Option<String>),
+}
+
+static UNITS: &'static str = "KMGTPEZY";
+
+// Parses "10", "10K", "4KiB", "1kB", "2M", ... into a number of bytes along
+// with the unit part of the string.  A bare number has no unit.
+pub fn parse_size(s: &str) -> Option<(u64, Option<String>)> {
+    let digits = s.chars().take_while(|c| c.is_digit()).count();
+    let (number, unit) = (s.slice_to(digits), s.slice_from(digits));
+    let count = if number.is_empty() {
+        if unit.is_empty() { return None } else { 1 }
+    } else {
+        match from_str::<u64>(number) {
+            Some(n) => n,
+            None => return None
+        }
+    };
+    if unit.is_empty() {
+        return Some((count, None));
+    }
+
+    let multiplier = match multiplier(unit) {
+        Some(m) => m,
+        None => return None
+    };
+    match count.checked_mul(&multiplier) {
+        Some(bytes) => Some((bytes, Some(unit.to_string()))),
+        None => None
+    }
+}
+
+fn multiplier(unit: &str) -> Option<u64> {
+    if unit == "b" {
+        return Some(512);
+    }
+    let mut chars = unit.chars();
+    let letter = match chars.next() {
+        Some('k') => 'K',
+        Some(c) => c,
+        None => return None
+    };
+    let power = match UNITS.find(letter) {
+        Some(p) => p + 1,
+        None => return None
+    };
+    let base = match chars.collect::<String>().as_slice() {
+        "" | "iB" => 1024u64,
+        "B" => 1000,
+        _ => return None
+    };
+    let mut result = 1u64;
+    for _ in range(0, power) {
+        result = match result.checked_mul(&base) {
+            Some(r) => r,
+            None => return None
+        };
+    }
+    Some(result)
+}
+
+fn parse_block_size(spec: &str) -> Result<BlockSize, String> {
+    // a leading ' asks for digit grouping, which is left to the locale
+    let spec = if spec.starts_with("'") { spec.slice_from(1) } else { spec };
+    match spec {
+        "human-readable" => return Ok(Human(1024)),
+        "si" => return Ok(Human(1000)),
+        _ => {}
+    }
+    match parse_size(spec) {
+        Some((0, _)) | None => Err(format!("invalid block size: '{}'", spec)),
+        Some((bytes, unit)) => Ok(Blocks(bytes, unit))
+    }
+}
+
+// Works out the block size from an explicit --block-size argument and the
+// environment, checking env_var (e.g. "LS_BLOCK_SIZE") before the generic
+// variables.  Returns None when nothing asked for a particular size, in which
+// case the caller falls back on default_block_size().
+pub fn block_size(arg: Option<String>, env_var: &str) -> Result<Option<BlockSize>, String> {
+    match arg {
+        Some(spec) => return parse_block_size(spec.as_slice()).map(|b| Some(b)),
+        None => {}
+    }
+    for var in [env_var, "BLOCK_SIZE", "BLOCKSIZE"].iter() {
+        match os::getenv(*var) {
+            // bad values in the environment are ignored rather than fatal
+            Some(spec) => match parse_block_size(spec.as_slice()) {
+                Ok(size) => return Ok(Some(size)),
+                Err(_) => {}
+            },
+            None => {}
+        }
+    }
+    Ok(None)
+}
+
+// The unit block counts are shown in when nothing chose one: 1024 bytes, or
+// the 512 POSIX asks for when POSIXLY_CORRECT is set.  It applies to counts
+// of blocks only, never to sizes given in bytes such as ls -l's.
+pub fn default_block_size() -> BlockSize {
+    if ::util::posixly_correct() { Blocks(512, None) } else { Blocks(1024, None) }
+}
+
+// Formats a byte count in the given block size, rounding up like the GNU
+// utilities do.
+pub fn format_size(bytes: u64, size: &BlockSize) -> String {
+    match *size {
+        Human(base) => human_readable(bytes, base),
+        Blocks(block, ref unit) => {
+            let count = (bytes + block - 1) / block;
+            match *unit {
+                Some(ref unit) => format!("{}{}", count, unit),
+                None => count.to_str()
+            }
+        }
+    }
+}
+
+// e.g. 4.0K, 23M, 1.1G: one decimal below ten, none above.
+pub fn human_readable(bytes: u64, base: u64) -> String {
+    let base_f = base as f64;
+    let mut value = bytes as f64;
+    let mut power = 0;
+    while value >= base_f && power < UNITS.len() {
+        value /= base_f;
+        power += 1;
+    }
+    if power == 0 {
+        return bytes.to_str();
+    }
+
+    if value < 10.0 {
+        let rounded = (value * 10.0).ceil() / 10.0;
+        if rounded < 10.0 {
+            return format!("{:.1f}{}", rounded, unit_name(power, base));
+        }
+        value = rounded;
+    }
+    let rounded = value.ceil();
+    if rounded >= base_f && power < UNITS.len() {
+        format!("1.0{}", unit_name(power + 1, base))
+    } else {
+        format!("{}{}", rounded as u64, unit_name(power, base))
+    }
+}
+
+fn unit_name(power: uint, base: u64) -> char {
+    if power == 1 && base == 1000 {
+        'k'
+    } else {
+        UNITS.char_at(power - 1)
+    }
+}
@@ -0,0 +1,240 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * strftime(3) formats as GNU date understands them, in the C locale: the
+ * POSIX conversions plus %N for nanoseconds, %:z, %::z and %:::z for the
+ * offset with colons, %P, %k, %l, %s, and the -, _, 0, ^ and # flags and
+ * field widths that can go between the % and the conversion.  Crates using
+ * this module need `extern crate time`.
+ */
+
+#![allow(dead_code)]
+
+use std::ascii::StrAsciiExt;
+use std::num::Integer;
+use time::Tm;
+
+static DAYS: [&'static str, ..7] =
+    ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+static MONTHS: [&'static str, ..12] =
+    ["January", "February", "March", "April", "May", "June", "July", "August", "September",
+     "October", "November", "December"];
+
+// How a number is padded out to its width.
+#[deriving(PartialEq)]
+enum Pad {
+    Zeros,
+    Spaces,
+    NoPad,
+}
+
+pub fn format(fmt: &str, tm: &Tm) -> String {
+    let mut out = String::new();
+    let chars: Vec<char> = fmt.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = *chars.get(i);
+        i += 1;
+        if c != '%' || i == chars.len() {
+            out.push_char(c);
+            continue;
+        }
+        let start = i - 1;
+
+        let mut pad = None;
+        let mut upper = false;
+        let mut swap_case = false;
+        loop {
+            match *chars.get(i) {
+                '-' => pad = Some(NoPad),
+                '_' => pad = Some(Spaces),
+                '0' => pad = Some(Zeros),
+                '^' => upper = true,
+                '#' => swap_case = true,
+                _ => break
+            }
+            i += 1;
+            if i == chars.len() { break }
+        }
+        let mut width = None;
+        while i < chars.len() && chars.get(i).is_digit() {
+            width = Some(width.unwrap_or(0u) * 10 + chars.get(i).to_digit(10).unwrap());
+            i += 1;
+        }
+        let mut colons = 0;
+        while i < chars.len() && *chars.get(i) == ':' {
+            colons += 1;
+            i += 1;
+        }
+        if i == chars.len() {
+            // an unfinished conversion is copied out as it is
+            out.push_str(chars.slice_from(start).iter().map(|&c| c).collect::<String>()
+                              .as_slice());
+            break;
+        }
+        let conv = *chars.get(i);
+        i += 1;
+        if colons > 0 && conv != 'z' {
+            out.push_str(chars.slice(start, i).iter().map(|&c| c).collect::<String>()
+                              .as_slice());
+            continue;
+        }
+
+        let field = match convert(conv, tm, colons, width) {
+            Some(Number(n, default_width, default_pad)) => {
+                number(n, width.unwrap_or(default_width), pad.unwrap_or(default_pad))
+            }
+            Some(Text(s)) => {
+                let s = if upper {
+                    s.as_slice().to_ascii_upper()
+                } else if swap_case {
+                    // # makes names all upper case, and %Z and %p lower
+                    if conv == 'Z' || conv == 'p' {
+                        s.as_slice().to_ascii_lower()
+                    } else {
+                        s.as_slice().to_ascii_upper()
+                    }
+                } else {
+                    s
+                };
+                pad_text(s, width, pad == Some(Zeros))
+            }
+            None => chars.slice(start, i).iter().map(|&c| c).collect()
+        };
+        out.push_str(field.as_slice());
+    }
+    out
+}
+
+enum Field {
+    // a number with the width and padding it gets by default
+    Number(i64, uint, Pad),
+    Text(String),
+}
+
+fn convert(conv: char, tm: &Tm, colons: uint, width: Option<uint>) -> Option<Field> {
+    let year = tm.tm_year as i64 + 1900;
+    let hour12 = match tm.tm_hour % 12 { 0 => 12, h => h } as i64;
+    let field = match conv {
+        'a' => Text(DAYS[tm.tm_wday as uint].slice_to(3).to_string()),
+        'A' => Text(DAYS[tm.tm_wday as uint].to_string()),
+        'b' | 'h' => Text(MONTHS[tm.tm_mon as uint].slice_to(3).to_string()),
+        'B' => Text(MONTHS[tm.tm_mon as uint].to_string()),
+        'c' => Text(format("%a %b %e %H:%M:%S %Y", tm)),
+        'C' => Number(year.div_floor(&100), 2, Zeros),
+        'd' => Number(tm.tm_mday as i64, 2, Zeros),
+        'D' => Text(format("%m/%d/%y", tm)),
+        'e' => Number(tm.tm_mday as i64, 2, Spaces),
+        'F' => Text(format("%Y-%m-%d", tm)),
+        'g' => Number(iso_week(tm).val0().mod_floor(&100), 2, Zeros),
+        'G' => Number(iso_week(tm).val0(), 4, Zeros),
+        'H' => Number(tm.tm_hour as i64, 2, Zeros),
+        'I' => Number(hour12, 2, Zeros),
+        'j' => Number(tm.tm_yday as i64 + 1, 3, Zeros),
+        'k' => Number(tm.tm_hour as i64, 2, Spaces),
+        'l' => Number(hour12, 2, Spaces),
+        'm' => Number(tm.tm_mon as i64 + 1, 2, Zeros),
+        'M' => Number(tm.tm_min as i64, 2, Zeros),
+        'n' => Text("\n".to_string()),
+        'N' => Text(nanoseconds(tm.tm_nsec as i64, width)),
+        'p' => Text((if tm.tm_hour < 12 { "AM" } else { "PM" }).to_string()),
+        'P' => Text((if tm.tm_hour < 12 { "am" } else { "pm" }).to_string()),
+        'r' => Text(format("%I:%M:%S %p", tm)),
+        'R' => Text(format("%H:%M", tm)),
+        's' => Number(tm.to_timespec().sec, 1, Zeros),
+        'S' => Number(tm.tm_sec as i64, 2, Zeros),
+        't' => Text("\t".to_string()),
+        'T' => Text(format("%H:%M:%S", tm)),
+        'u' => Number(match tm.tm_wday { 0 => 7, d => d } as i64, 1, Zeros),
+        'U' => Number((tm.tm_yday as i64 + 7 - tm.tm_wday as i64) / 7, 2, Zeros),
+        'V' => Number(iso_week(tm).val1(), 2, Zeros),
+        'w' => Number(tm.tm_wday as i64, 1, Zeros),
+        'W' => Number((tm.tm_yday as i64 + 7 - (tm.tm_wday as i64 + 6) % 7) / 7, 2, Zeros),
+        'x' => Text(format("%m/%d/%y", tm)),
+        'X' => Text(format("%H:%M:%S", tm)),
+        'y' => Number(year.mod_floor(&100), 2, Zeros),
+        'Y' => Number(year, 1, Zeros),
+        'z' => Text(offset(tm.tm_gmtoff as i64, colons)),
+        'Z' => Text(tm.strftime("%Z")),
+        '%' => Text("%".to_string()),
+        _ => return None
+    };
+    Some(field)
+}
+
+fn number(n: i64, width: uint, pad: Pad) -> String {
+    let digits = n.abs().to_str();
+    let sign = if n < 0 { "-" } else { "" };
+    let fill = width.saturating_sub(digits.len() + sign.len());
+    match pad {
+        Zeros => format!("{}{}{}", sign, "0".repeat(fill), digits),
+        Spaces => format!("{}{}{}", " ".repeat(fill), sign, digits),
+        NoPad => format!("{}{}", sign, digits)
+    }
+}
+
+fn pad_text(s: String, width: Option<uint>, zeros: bool) -> String {
+    match width {
+        Some(width) if width > s.len() => {
+            let fill = if zeros { "0" } else { " " };
+            format!("{}{}", fill.repeat(width - s.len()), s)
+        }
+        _ => s
+    }
+}
+
+// Nine digits by default, and a width picks how many of them are kept.
+fn nanoseconds(nsec: i64, width: Option<uint>) -> String {
+    let digits = format!("{:09}", nsec);
+    match width {
+        Some(width) if width < 9 => digits.as_slice().slice_to(width).to_string(),
+        Some(width) => format!("{}{}", digits, "0".repeat(width - 9)),
+        None => digits
+    }
+}
+
+// +hhmm, then +hh:mm, +hh:mm:ss and, with three colons, only as much as
+// is needed.
+fn offset(secs: i64, colons: uint) -> String {
+    let sign = if secs < 0 { '-' } else { '+' };
+    let secs = secs.abs();
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    match colons {
+        0 => format!("{}{:02}{:02}", sign, h, m),
+        1 => format!("{}{:02}:{:02}", sign, h, m),
+        2 => format!("{}{:02}:{:02}:{:02}", sign, h, m, s),
+        _ if s != 0 => format!("{}{:02}:{:02}:{:02}", sign, h, m, s),
+        _ if m != 0 => format!("{}{:02}:{:02}", sign, h, m),
+        _ => format!("{}{:02}", sign, h)
+    }
+}
+
+// The ISO 8601 week-numbering year and week: weeks start on Monday, and
+// the first week of a year is the one with its first Thursday.
+fn iso_week(tm: &Tm) -> (i64, i64) {
+    let year = tm.tm_year as i64 + 1900;
+    let weekday = (tm.tm_wday as i64 + 6) % 7 + 1;
+    let week = (tm.tm_yday as i64 + 1 - weekday + 10) / 7;
+    if week < 1 {
+        (year - 1, weeks_in_year(year - 1))
+    } else if week > weeks_in_year(year) {
+        (year + 1, 1)
+    } else {
+        (year, week)
+    }
+}
+
+// A year has 53 weeks when it starts or ends on a Thursday.
+fn weeks_in_year(year: i64) -> i64 {
+    let dec31 = |y: i64| {
+        (y + y.div_floor(&4) - y.div_floor(&100) + y.div_floor(&400)).mod_floor(&7)
+    };
+    if dec31(year) == 4 || dec31(year - 1) == 3 { 53 } else { 52 }
+}
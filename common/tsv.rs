@@ -0,0 +1,49 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Fields for tab-separated output meant for other programs.  Backslash,
+ * tab, newline and carriage return are escaped as \\, \t, \n and \r, and
+ * bytes that are not UTF-8 as \xHH, so any file name can be got back
+ * exactly.  Crates using this module need common/mbchar.rs declared as
+ * mbchar.
+ */
+
+#![allow(dead_code)]
+
+use std::str;
+use mbchar;
+
+pub fn field(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let len = mbchar::utf8_len(bytes.slice_from(i));
+        if len == 0 {
+            out.push_str(format!("\\x{:02x}", bytes[i]).as_slice());
+            i += 1;
+            continue;
+        }
+        match bytes[i] as char {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push_str(str::from_utf8(bytes.slice(i, i + len)).unwrap())
+        }
+        i += len;
+    }
+    out
+}
+
+pub fn line(fields: &[String]) -> String {
+    let mut out = fields.connect("\t");
+    out.push_char('\n');
+    out
+}
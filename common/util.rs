@@ -8,14 +8,125 @@
  */
 
 #![macro_escape]
+#![allow(dead_code)]
 
 extern crate libc;
 
+use std::io;
+use std::io::IoError;
+use std::io::util::NullWriter;
+use std::task::TaskBuilder;
+
+// Why a utility did not succeed: the status it exits with and, when it
+// stopped on an error, what the error was.
+pub struct Failure {
+    pub status: int,
+    pub message: Option<String>,
+}
+
+pub type Outcome = Result<(), Failure>;
+
+// The status of the utility running in this task, which show_error! sets.
+local_data_key!(STATUS: int)
+
+pub fn status() -> int {
+    STATUS.get().map(|status| *status).unwrap_or(0)
+}
+
+pub fn set_status(status: int) {
+    STATUS.replace(Some(status));
+}
+
+pub fn outcome(status: int) -> Outcome {
+    if status == 0 { Ok(()) } else { Err(Failure { status: status, message: None }) }
+}
+
+// Ends the utility, though not the process: crash! and exit! unwind to
+// catch_exit with the failure.
+pub fn stop(status: int, message: Option<String>) -> ! {
+    fail!(Failure { status: status, message: message })
+}
+
+// Runs a utility in a task of its own, so that stopping it cannot take the
+// program embedding it down as well.
+pub fn catch_exit(body: proc(): Send) -> Outcome {
+    // the runtime's report of the unwinding would only repeat the error
+    let task = TaskBuilder::new().stderr(box NullWriter as Box<Writer + Send>);
+    match task.try(proc() { body(); status() }) {
+        Ok(status) => outcome(status),
+        Err(cause) => match cause.move::<Failure>() {
+            Ok(failure) => Err(*failure),
+            // anything else is a bug, which is passed on with its message
+            Err(cause) => {
+                let msg = match cause.as_ref::<&'static str>() {
+                    Some(msg) => msg.to_string(),
+                    None => match cause.as_ref::<String>() {
+                        Some(msg) => msg.clone(),
+                        None => "Box<Any>".to_string()
+                    }
+                };
+                fail!("{}", msg)
+            }
+        }
+    }
+}
+
+// Whether to behave as POSIX requires where that differs from what the GNU
+// utilities do by default.
+pub fn posixly_correct() -> bool {
+    ::std::os::getenv("POSIXLY_CORRECT").is_some()
+}
+
+// The error as strerror(3) would put it, which is what goes after the file
+// name or other context in a diagnostic: "No such file or directory", where
+// IoError itself shows "file not found (...; path=...)".
+pub fn strerror(err: &IoError) -> String {
+    let text = match err.kind {
+        io::FileNotFound | io::PathDoesntExist => "No such file or directory",
+        io::PermissionDenied => "Permission denied",
+        io::PathAlreadyExists => "File exists",
+        io::BrokenPipe => "Broken pipe",
+        io::InvalidInput => "Invalid argument",
+        io::ResourceUnavailable => "Resource temporarily unavailable",
+        io::ConnectionRefused => "Connection refused",
+        io::ConnectionReset => "Connection reset by peer",
+        io::NotConnected => "Transport endpoint is not connected",
+        io::TimedOut => "Connection timed out",
+        _ => {
+            // errors from the system carry its own text as the detail, ahead
+            // of anything such as "path=..." that std adds after a "; "
+            match err.detail {
+                Some(ref detail) => {
+                    let first = detail.as_slice().split_str("; ").next().unwrap_or("");
+                    if !first.is_empty() && !first.contains_char('=') {
+                        return first.to_string();
+                    }
+                }
+                None => {}
+            }
+            let mut chars = err.desc.chars();
+            return match chars.next() {
+                Some(c) => format!("{}{}", c.to_uppercase(), chars.collect::<String>()),
+                None => err.desc.to_string()
+            };
+        }
+    };
+    text.to_string()
+}
+
+// What main makes of a utility's outcome.
+pub fn exit_status(outcome: Outcome) -> int {
+    match outcome {
+        Ok(()) => 0,
+        Err(failure) => failure.status
+    }
+}
+
 #[macro_export]
 macro_rules! show_error(
     ($exitcode:expr, $($args:expr),+) => ({
-        ::std::os::set_exit_status($exitcode as int);
-        safe_write!(&mut ::std::io::stderr(), "{}: error: ", ::NAME);
+        ::util::set_status($exitcode as int);
+        safe_write!(&mut ::std::io::stderr(), "{}: ", ::NAME);
         safe_writeln!(&mut ::std::io::stderr(), $($args),+);
     })
 )
@@ -28,11 +139,39 @@ macro_rules! show_warning(
     })
 )
 
+// show_error for the utilities that report to a writer they were handed, and
+// return their outcome rather than setting a status.  There is nowhere left
+// to report a failure to write the diagnostic itself, so it is ignored.
+#[macro_export]
+macro_rules! write_error(
+    ($w:expr, $($args:expr),+) => ({
+        let _ = write!($w, "{}: ", ::NAME);
+        let _ = writeln!($w, $($args),+);
+    })
+)
+
+// try! for the output of those utilities: a failed write, as to a closed
+// pipe, is reported on the diagnostics and ends the utility with status 1.
+#[macro_export]
+macro_rules! try_write(
+    ($err:expr, $e:expr) => (
+        match $e {
+            Ok(v) => v,
+            Err(e) => {
+                let message = format!("write error: {}", ::util::strerror(&e));
+                write_error!($err, "{}", message);
+                return Err(::util::Failure { status: 1, message: Some(message) })
+            }
+        }
+    )
+)
+
 #[macro_export]
 macro_rules! crash(
     ($exitcode:expr, $($args:expr),+) => ({
-        show_error!($exitcode, $($args),+);
-        unsafe { self::libc::exit($exitcode as self::libc::c_int); }
+        let message = format!($($args),+);
+        show_error!($exitcode, "{}", message);
+        ::util::stop($exitcode as int, Some(message))
     })
 )
 
@@ -40,7 +179,7 @@ macro_rules! crash(
 #[macro_export]
 macro_rules! exit(
     ($exitcode:expr) => ({
-        unsafe { self::libc::exit($exitcode); }
+        ::util::stop($exitcode as int, None)
     })
 )
 
@@ -83,3 +222,26 @@ macro_rules! safe_unwrap(
         }
     )
 )
+
+// Asks a yes/no question on stderr and reads the answer from stdin a byte at
+// a time, so nothing past the answer is consumed.  Anything starting with y
+// or Y counts as yes.
+#[macro_export]
+macro_rules! prompt_yes(
+    ($($args:expr),+) => ({
+        let mut stderr = ::std::io::stderr();
+        safe_write!(&mut stderr, "{}: ", ::NAME);
+        safe_write!(&mut stderr, $($args),+);
+        safe_write!(&mut stderr, " ");
+        let _ = stderr.flush();
+        let mut stdin = ::std::io::stdio::stdin_raw();
+        let mut answer = vec!();
+        loop {
+            match stdin.read_byte() {
+                Ok(b) if b != '\n' as u8 => answer.push(b),
+                _ => break
+            }
+        }
+        answer.len() > 0 && (answer[0] == 'y' as u8 || answer[0] == 'Y' as u8)
+    })
+)
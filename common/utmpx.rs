@@ -1,8 +1,19 @@
+/*
+ * The login records in utmp(5), as who, users and the like want them: read
+ * with getutxent(3) from whichever file is asked for, with the fixed-size
+ * fields turned into strings.
+ */
+
 #![allow(non_camel_case_types)]
 #![allow(dead_code)]
 
 extern crate libc;
 
+use std::io::{File, IoResult};
+use std::str;
+use std::str::raw::from_c_str;
+use self::libc::{c_char, c_int, c_short, pid_t};
+
 pub use self::utmpx::{DEFAULT_FILE,USER_PROCESS,BOOT_TIME,c_utmp};
 #[cfg(target_os = "linux")]
 mod utmpx {
@@ -31,6 +42,11 @@ mod utmpx {
         pub e_exit: libc::c_short,
     }
 
+    pub struct c_timeval {
+        pub tv_sec: libc::int32_t,
+        pub tv_usec: libc::int32_t,
+    }
+
     pub struct c_utmp {
         pub ut_type: libc::c_short,
         pub ut_pid: libc::pid_t,
@@ -40,8 +56,10 @@ mod utmpx {
         pub ut_user: [libc::c_char, ..UT_NAMESIZE],
         pub ut_host: [libc::c_char, ..UT_HOSTSIZE],
         pub ut_exit: c_exit_status,
-        pub ut_session: libc::c_long,
-        pub ut_tv: libc::timeval,
+        // glibc keeps these 32 bits wide on 64-bit systems as well, so that
+        // the file reads the same either way
+        pub ut_session: libc::int32_t,
+        pub ut_tv: c_timeval,
 
         pub ut_addr_v6: [libc::int32_t, ..4],
         pub __unused: [libc::c_char, ..20],
@@ -87,3 +105,82 @@ mod utmpx {
     }
 }
 
+
+extern {
+    fn getutxent() -> *c_utmp;
+    fn setutxent();
+    fn endutxent();
+
+    fn utmpxname(file: *c_char) -> c_int;
+    fn ttyname(fd: c_int) -> *c_char;
+}
+
+pub struct Utmpx {
+    pub ut_type: c_short,
+    pub pid: pid_t,
+    pub user: String,
+    // the terminal, such as pts/0, without /dev/
+    pub line: String,
+    pub id: String,
+    pub host: String,
+    // seconds since the epoch
+    pub time: i64,
+}
+
+impl Utmpx {
+    // Someone logged in, as opposed to a getty waiting for them to.
+    pub fn is_user_process(&self) -> bool {
+        self.ut_type == USER_PROCESS && !self.user.is_empty()
+    }
+}
+
+// Every record in the file, in the order they are kept.
+pub fn read_utmpx(file: &str) -> IoResult<Vec<Utmpx>> {
+    // getutxent(3) only says there is nothing more, not why
+    try!(File::open(&Path::new(file)));
+    file.with_c_str(|file| unsafe { utmpxname(file) });
+
+    let mut records = vec!();
+    unsafe {
+        setutxent();
+        loop {
+            let entry = getutxent();
+            if entry.is_null() {
+                break;
+            }
+            let entry = &*entry;
+            records.push(Utmpx {
+                ut_type: entry.ut_type,
+                pid: entry.ut_pid,
+                user: field(entry.ut_user.as_slice()),
+                line: field(entry.ut_line.as_slice()),
+                id: field(entry.ut_id.as_slice()),
+                host: field(entry.ut_host.as_slice()),
+                time: entry.ut_tv.tv_sec as i64,
+            });
+        }
+        endutxent();
+    }
+    Ok(records)
+}
+
+// The fields are padded with NULs, but one that fills the whole array has
+// none to end it.
+fn field(chars: &[c_char]) -> String {
+    let bytes: Vec<u8> = chars.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+    str::from_utf8_lossy(bytes.as_slice()).into_string()
+}
+
+// The terminal on standard input as utmp names it, e.g. pts/0.
+pub fn stdin_line() -> Option<String> {
+    let name = unsafe { ttyname(0) };
+    if name.is_null() {
+        return None;
+    }
+    let name = unsafe { from_c_str(name) };
+    Some(if name.as_slice().starts_with("/dev/") {
+        name.as_slice().slice_from(5).to_string()
+    } else {
+        name
+    })
+}
@@ -0,0 +1,113 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * What the system says about itself: uname(2), and the host name and id.
+ */
+
+#![allow(dead_code, non_camel_case_types)]
+
+extern crate libc;
+
+use std::io::{IoError, IoResult};
+use std::mem;
+use std::str;
+use self::libc::{c_char, c_int, c_long, size_t};
+
+#[cfg(target_os = "macos")]
+pub struct utsname {
+    pub sysname: [c_char, ..256],
+    pub nodename: [c_char, ..256],
+    pub release: [c_char, ..256],
+    pub version: [c_char, ..256],
+    pub machine: [c_char, ..256]
+}
+
+#[cfg(target_os = "linux")]
+pub struct utsname {
+    pub sysname: [c_char, ..65],
+    pub nodename: [c_char, ..65],
+    pub release: [c_char, ..65],
+    pub version: [c_char, ..65],
+    pub machine: [c_char, ..65],
+    pub domainname: [c_char, ..65]
+}
+
+extern {
+    fn uname(uts: *mut utsname) -> c_int;
+
+    fn gethostname(name: *mut c_char, namelen: size_t) -> c_int;
+    fn sethostname(name: *c_char, namelen: c_int) -> c_int;
+    fn gethostid() -> c_long;
+}
+
+pub struct Uname {
+    pub sysname: String,
+    pub nodename: String,
+    pub release: String,
+    pub version: String,
+    pub machine: String,
+}
+
+pub fn get() -> Uname {
+    let mut uts: utsname = unsafe { mem::zeroed() };
+    unsafe { uname(&mut uts); }
+    Uname {
+        sysname: field(uts.sysname.as_slice()),
+        nodename: field(uts.nodename.as_slice()),
+        release: field(uts.release.as_slice()),
+        version: field(uts.version.as_slice()),
+        machine: field(uts.machine.as_slice()),
+    }
+}
+
+fn field(chars: &[c_char]) -> String {
+    let bytes: Vec<u8> = chars.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+    str::from_utf8_lossy(bytes.as_slice()).into_string()
+}
+
+// The name of the operating system as a whole, which uname(2) does not give.
+#[cfg(target_os = "linux")]
+pub static OPERATING_SYSTEM: &'static str = "GNU/Linux";
+#[cfg(target_os = "macos")]
+pub static OPERATING_SYSTEM: &'static str = "Darwin";
+
+pub fn hostname() -> IoResult<String> {
+    // a name that does not fit may be cut short without an error, so there
+    // has to be room for a NUL after it to know it is all there
+    let mut size = 256u;
+    loop {
+        let mut name: Vec<c_char> = Vec::from_elem(size, 0);
+        if unsafe { gethostname(name.as_mut_ptr(), size as size_t) } != 0 {
+            if size < 1 << 16 {
+                size *= 2;
+                continue;
+            }
+            return Err(IoError::last_error());
+        }
+        if name.iter().any(|&c| c == 0) {
+            return Ok(field(name.as_slice()));
+        }
+        size *= 2;
+    }
+}
+
+pub fn set_hostname(name: &str) -> IoResult<()> {
+    let bytes = name.as_bytes();
+    if unsafe { sethostname(bytes.as_ptr() as *c_char, bytes.len() as c_int) } != 0 {
+        return Err(IoError::last_error());
+    }
+    Ok(())
+}
+
+// POSIX calls it a 32-bit identifier and leaves open whether it comes back
+// sign-extended, so anything above those bits is dropped.
+pub fn hostid() -> u32 {
+    (unsafe { gethostid() } as u64 & 0xffffffff) as u32
+}
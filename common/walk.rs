@@ -0,0 +1,177 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * A depth-first directory walker shared by the recursive utilities.  The
+ * walker only decides what to visit; everything a utility actually does with
+ * the files happens in its Visitor.  Crates using this module must also
+ * declare common/fsext.rs as `mod fsext`.
+ */
+
+#![allow(dead_code)]
+
+extern crate libc;
+
+use std::io::{fs, IoError, IoResult};
+use fsext;
+
+#[deriving(PartialEq)]
+pub enum Follow {
+    // never follow symbolic links (-P)
+    FollowNone,
+    // follow a symbolic link given as the root, but none found below it (-H)
+    FollowRoot,
+    // follow every symbolic link (-L)
+    FollowAll,
+}
+
+impl Follow {
+    // The policy picked by the usual -L, -H and -P flags, in that order of
+    // precedence, or the utility's default when none of them was given.
+    pub fn from_flags(dereference: bool, command_line: bool, no_dereference: bool,
+                      default: Follow) -> Follow {
+        if dereference {
+            FollowAll
+        } else if command_line {
+            FollowRoot
+        } else if no_dereference {
+            FollowNone
+        } else {
+            default
+        }
+    }
+}
+
+pub struct WalkEntry {
+    pub path: Path,
+    pub stat: fsext::c_stat,
+    // 0 for the root, 1 for its children, and so on
+    pub depth: uint,
+}
+
+impl WalkEntry {
+    pub fn is_dir(&self) -> bool {
+        fsext::is_dir(self.stat.st_mode as libc::mode_t)
+    }
+}
+
+pub trait Visitor {
+    // Called on reaching a directory, before reading it.  Returning false
+    // skips the directory's contents; leave_dir is still called.
+    fn enter_dir(&mut self, _dir: &WalkEntry) -> bool { true }
+
+    // Called with the contents of a directory, sorted by name unless the
+    // walker was told otherwise, before any of them are visited.  Entries
+    // may be reordered or removed to change what is walked next.
+    fn children(&mut self, _dir: &WalkEntry, _children: &mut Vec<WalkEntry>) {}
+
+    // Called once all of a directory's contents have been visited.
+    fn leave_dir(&mut self, _dir: &WalkEntry) {}
+
+    // Called for everything that is not a directory.
+    fn visit_file(&mut self, _file: &WalkEntry) {}
+
+    // Called for a directory that is its own ancestor, which can only be
+    // reached by following symbolic links.  It is not descended into.
+    fn cycle(&mut self, _dir: &WalkEntry) {}
+
+    fn error(&mut self, _path: &Path, _err: IoError) {}
+}
+
+pub struct Walker {
+    follow: Follow,
+    sorted: bool,
+}
+
+impl Walker {
+    pub fn new() -> Walker {
+        Walker { follow: FollowNone, sorted: true }
+    }
+
+    pub fn follow(mut self, follow: Follow) -> Walker {
+        self.follow = follow;
+        self
+    }
+
+    // Whether to sort directory contents by name rather than leave them in
+    // the order the directory returns them.
+    pub fn sorted(mut self, sorted: bool) -> Walker {
+        self.sorted = sorted;
+        self
+    }
+
+    pub fn walk<V: Visitor>(&self, root: &Path, visitor: &mut V) {
+        let stat = if self.follow == FollowNone {
+            fsext::lstat_path(root)
+        } else {
+            // a dangling link can still be visited as itself
+            fsext::stat_path(root).or_else(|_| fsext::lstat_path(root))
+        };
+        match stat {
+            Ok(stat) => {
+                let entry = WalkEntry { path: root.clone(), stat: stat, depth: 0 };
+                self.visit(entry, &mut vec!(), visitor);
+            }
+            Err(e) => visitor.error(root, e)
+        }
+    }
+
+    fn visit<V: Visitor>(&self, entry: WalkEntry, ancestors: &mut Vec<(u64, u64)>,
+                         visitor: &mut V) {
+        if !entry.is_dir() {
+            visitor.visit_file(&entry);
+            return;
+        }
+        let id = (entry.stat.st_dev as u64, entry.stat.st_ino as u64);
+        if ancestors.contains(&id) {
+            visitor.cycle(&entry);
+            return;
+        }
+
+        if visitor.enter_dir(&entry) {
+            match self.read_children(&entry, visitor) {
+                Ok(mut children) => {
+                    visitor.children(&entry, &mut children);
+                    ancestors.push(id);
+                    for child in children.move_iter() {
+                        self.visit(child, ancestors, visitor);
+                    }
+                    ancestors.pop();
+                }
+                Err(e) => visitor.error(&entry.path, e)
+            }
+        }
+        visitor.leave_dir(&entry);
+    }
+
+    fn read_children<V: Visitor>(&self, dir: &WalkEntry, visitor: &mut V)
+                                 -> IoResult<Vec<WalkEntry>> {
+        let paths = try!(fs::readdir(&dir.path));
+        let mut children = Vec::with_capacity(paths.len());
+        for path in paths.move_iter() {
+            let stat = if self.follow == FollowAll {
+                fsext::stat_path(&path).or_else(|_| fsext::lstat_path(&path))
+            } else {
+                fsext::lstat_path(&path)
+            };
+            match stat {
+                Ok(stat) => children.push(WalkEntry {
+                    path: path,
+                    stat: stat,
+                    depth: dir.depth + 1
+                }),
+                Err(e) => visitor.error(&path, e)
+            }
+        }
+        if self.sorted {
+            children.sort_by(|a, b| a.path.filename().cmp(&b.path.filename()));
+        }
+        Ok(children)
+    }
+}
@@ -0,0 +1,106 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Extended attributes, never following symbolic links.  Only Linux is
+ * supported for now; elsewhere every file simply has no attributes.
+ */
+
+#![allow(dead_code)]
+
+extern crate libc;
+
+use std::io::{IoError, IoResult};
+use std::str;
+use self::libc::{c_char, c_int, c_void, size_t, ssize_t};
+
+// The attribute SELinux keeps a file's security context in.
+pub static SELINUX_CONTEXT: &'static str = "security.selinux";
+
+#[cfg(target_os = "linux")]
+extern {
+    fn llistxattr(path: *c_char, list: *mut c_char, size: size_t) -> ssize_t;
+    fn lgetxattr(path: *c_char, name: *c_char, value: *mut c_void, size: size_t) -> ssize_t;
+    fn lsetxattr(path: *c_char, name: *c_char, value: *c_void, size: size_t,
+                 flags: c_int) -> c_int;
+}
+
+// The names of all of path's attributes.
+#[cfg(target_os = "linux")]
+pub fn list(path: &Path) -> IoResult<Vec<String>> {
+    let size = path.with_c_str(|p| unsafe { llistxattr(p, 0 as *mut c_char, 0) });
+    if size < 0 {
+        return Err(IoError::last_error());
+    }
+    let mut buf: Vec<u8> = Vec::from_elem(size as uint, 0);
+    let size = path.with_c_str(|p| unsafe {
+        llistxattr(p, buf.as_mut_ptr() as *mut c_char, buf.len() as size_t)
+    });
+    if size < 0 {
+        return Err(IoError::last_error());
+    }
+    buf.truncate(size as uint);
+    // the names come back NUL terminated, one after another
+    Ok(buf.as_slice().split(|&b| b == 0).filter(|name| !name.is_empty())
+          .map(|name| str::from_utf8_lossy(name).into_string()).collect())
+}
+
+#[cfg(target_os = "linux")]
+pub fn get(path: &Path, name: &str) -> IoResult<Vec<u8>> {
+    let getxattr = |buf: &mut Vec<u8>| path.with_c_str(|p| name.with_c_str(|n| unsafe {
+        lgetxattr(p, n, buf.as_mut_ptr() as *mut c_void, buf.len() as size_t)
+    }));
+    let mut buf = vec!();
+    let size = getxattr(&mut buf);
+    if size < 0 {
+        return Err(IoError::last_error());
+    }
+    buf.grow(size as uint, &0);
+    let size = getxattr(&mut buf);
+    if size < 0 {
+        return Err(IoError::last_error());
+    }
+    buf.truncate(size as uint);
+    Ok(buf)
+}
+
+#[cfg(target_os = "linux")]
+pub fn set(path: &Path, name: &str, value: &[u8]) -> IoResult<()> {
+    let ret = path.with_c_str(|p| name.with_c_str(|n| unsafe {
+        lsetxattr(p, n, value.as_ptr() as *c_void, value.len() as size_t, 0)
+    }));
+    if ret == 0 { Ok(()) } else { Err(IoError::last_error()) }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn list(_path: &Path) -> IoResult<Vec<String>> {
+    Ok(vec!())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get(_path: &Path, _name: &str) -> IoResult<Vec<u8>> {
+    Ok(vec!())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set(_path: &Path, _name: &str, _value: &[u8]) -> IoResult<()> {
+    Ok(())
+}
+
+pub fn copy(source: &Path, dest: &Path, name: &str) -> IoResult<()> {
+    let value = try!(get(source, name));
+    set(dest, name, value.as_slice())
+}
+
+pub fn copy_all(source: &Path, dest: &Path) -> IoResult<()> {
+    for name in try!(list(source)).iter() {
+        try!(copy(source, dest, name.as_slice()));
+    }
+    Ok(())
+}
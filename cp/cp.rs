@@ -1,6 +1,5 @@
 #![crate_id(name="cp", vers="1.0.0", author="Jordy Dickinson")]
 #![feature(macro_rules)]
-#![feature(phase)]
 
 /*
  * This file is part of the uutils coreutils package.
@@ -11,19 +10,63 @@
  * that was distributed with this source code.
  */
 
+extern crate collections;
 extern crate getopts;
-#[phase(syntax, link)] extern crate log;
+extern crate libc;
+extern crate time;
 
+use std::cell::RefCell;
 use std::os;
 use std::io;
-use std::io::fs;
+use std::io::{fs, IoError, IoResult};
 
+use collections::hashmap::HashMap;
 use getopts::{
-    getopts,
     optflag,
+    optflagopt,
+    optopt,
     usage,
 };
 
+use copy::{Options, Preserve, DEFAULT_PRESERVE, OverwriteAlways, OverwriteInteractive,
+           OverwriteNever, ReflinkAlways, ReflinkAuto, ReflinkNever, SparseAlways, SparseAuto,
+           SparseNever};
+use progress::Progress;
+use walk::{Follow, FollowAll, FollowNone, Visitor, WalkEntry, Walker};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/fdio.rs"]
+mod fdio;
+
+#[path = "../common/walk.rs"]
+mod walk;
+
+#[path = "../common/xattr.rs"]
+mod xattr;
+
+#[path = "../common/size.rs"]
+mod size;
+
+#[path = "../common/progress.rs"]
+mod progress;
+
+#[path = "../common/reflink.rs"]
+mod reflink;
+
+#[path = "../common/copy.rs"]
+mod copy;
+
+static NAME: &'static str = "cp";
+static VERSION: &'static str = "1.0.0";
+
 #[deriving(Eq, PartialEq)]
 pub enum Mode {
     Copy,
@@ -32,23 +75,56 @@ pub enum Mode {
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        optflag("a", "archive", "same as -dR --preserve=all"),
+        optflag("d", "", "same as --no-dereference --preserve=links"),
+        optflag("f", "force", "if an existing destination file cannot be opened, remove it and \
+                               try again"),
+        optflag("g", "progress", "show the progress of the copy on stderr"),
+        optflag("H", "", "follow command-line symbolic links in SOURCE"),
+        optflag("i", "interactive", "prompt before overwrite (overrides a previous -n option)"),
+        optflag("L", "dereference", "always follow symbolic links in SOURCE"),
+        optflag("n", "no-clobber", "do not overwrite an existing file (overrides a previous -i \
+                                    option)"),
+        optflag("P", "no-dereference", "never follow symbolic links in SOURCE"),
+        optflag("", "parents", "use full source file name under DIRECTORY"),
+        optflag("p", "", "same as --preserve=mode,ownership,timestamps"),
+        optflagopt("", "preserve", "preserve the specified attributes (default: \
+                                    mode,ownership,timestamps), if possible additional \
+                                    attributes: context, links, xattr, all", "ATTR_LIST"),
+        optopt("", "no-preserve", "don't preserve the specified attributes", "ATTR_LIST"),
+        optflag("r", "recursive", "copy directories recursively"),
+        optflag("R", "", "same as -r"),
+        optopt("", "sparse", "control creation of sparse files: WHEN is 'auto' (the default), \
+                              'always' or 'never'", "WHEN"),
+        optflagopt("", "reflink", "control clone/CoW copies: WHEN is 'always' (the default \
+                                   when no WHEN is given), 'auto' (the default) or 'never'",
+                   "WHEN"),
+        optopt("t", "target-directory", "copy all SOURCE arguments into DIRECTORY", "DIRECTORY"),
+        optflag("T", "no-target-directory", "treat DEST as a normal file"),
+        optflag("u", "update", "copy only when the SOURCE file is newer than the destination file \
+                                or when the destination file is missing"),
         optflag("h", "help", "display this help and exit"),
         optflag("", "version", "output version information and exit"),
-    ];
-    let matches = match getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
-        Err(e) => {
-            error!("error: {:s}", e.to_err_msg());
-            fail!()
-        },
+        Err(e) => crash!(1, "{}", e.to_err_msg()),
     };
 
     let progname = args.get(0);
-    let usage = usage("Copy SOURCE to DEST, or multiple SOURCE(s) to DIRECTORY.", opts);
+    let usage = usage("Copy SOURCE to DEST, or multiple SOURCE(s) to DIRECTORY.", opts.as_slice());
     let mode = if matches.opt_present("version") {
         Version
     } else if matches.opt_present("help") {
@@ -65,116 +141,225 @@ pub fn uumain(args: Vec<String>) {
 }
 
 fn version() {
-    println!("cp 1.0.0");
+    println!("{} {}", NAME, VERSION);
 }
 
 fn help(progname: &str, usage: &str) {
     let msg = format!("Usage: {0} SOURCE DEST\n  \
                          or:  {0} SOURCE... DIRECTORY\n  \
-                         or:  {0} -t DIRECTORY SOURCE\n\
+                         or:  {0} -t DIRECTORY SOURCE...\n\
                        \n\
                        {1}", progname, usage);
     println!("{}", msg);
 }
 
 fn copy(matches: getopts::Matches) {
-    let sources : Vec<Path> = if matches.free.len() < 1 {
-        error!("error: Missing SOURCE argument. Try --help.");
-        fail!()
+    let archive = matches.opt_present("archive");
+    let recursive = archive || matches.opt_present("recursive") || matches.opt_present("R");
+    let no_dereference = archive || matches.opt_present("d")
+                         || matches.opt_present("no-dereference");
+
+    let mut preserve = Preserve::none();
+    let mut lists = vec!();
+    if archive {
+        lists.push(("all".to_string(), true));
+    }
+    if matches.opt_present("d") {
+        lists.push(("links".to_string(), true));
+    }
+    if matches.opt_present("p") {
+        lists.push((DEFAULT_PRESERVE.to_string(), true));
+    }
+    if matches.opt_present("preserve") {
+        let list = matches.opt_str("preserve").unwrap_or(DEFAULT_PRESERVE.to_string());
+        lists.push((list, true));
+    }
+    match matches.opt_str("no-preserve") {
+        Some(list) => lists.push((list, false)),
+        None => {}
+    }
+    for &(ref list, value) in lists.iter() {
+        match preserve.set(list.as_slice(), value) {
+            Ok(()) => {}
+            Err(attr) => crash!(1, "invalid attribute '{}' for '--{}preserve'", attr,
+                                if value { "" } else { "no-" })
+        }
+    }
+
+    let reflink = if !matches.opt_present("reflink") {
+        ReflinkAuto
     } else {
-        // All but the last argument:
-        matches.free.slice(0, matches.free.len() - 2).iter()
-            .map(|arg| Path::new(arg.clone())).collect()
+        match matches.opt_str("reflink") {
+            None => ReflinkAlways,
+            Some(when) => match when.as_slice() {
+                "always" => ReflinkAlways,
+                "auto" => ReflinkAuto,
+                "never" => ReflinkNever,
+                _ => crash!(1, "invalid argument '{}' for '--reflink'", when)
+            }
+        }
     };
-    let dest = if matches.free.len() < 2 {
-        error!("error: Missing DEST argument. Try --help.");
-        fail!()
-    } else {
-        // Only the last argument:
-        Path::new(matches.free.get(matches.free.len() - 1).as_slice())
+
+    let sparse = match matches.opt_str("sparse") {
+        None => SparseAuto,
+        Some(when) => match when.as_slice() {
+            "always" => SparseAlways,
+            "auto" => SparseAuto,
+            "never" => SparseNever,
+            _ => crash!(1, "invalid argument '{}' for '--sparse'", when)
+        }
     };
 
-    assert!(sources.len() >= 1);
-
-    if sources.len() == 1 {
-        let source = sources.get(0);
-        let same_file = match paths_refer_to_same_file(source, &dest) {
-            Ok(b)  => b,
-            Err(e) => if e.kind == io::FileNotFound {
-                false
-            } else {
-                error!("error: {:s}", e.to_str());
-                fail!()
-            }
-        };
+    // whichever of -n and -i comes last wins
+    let overwrite = match (matches.opt_positions("no-clobber").last(),
+                           matches.opt_positions("interactive").last()) {
+        (Some(n), Some(i)) => if n > i { OverwriteNever } else { OverwriteInteractive },
+        (Some(_), None) => OverwriteNever,
+        (None, Some(_)) => OverwriteInteractive,
+        (None, None) => OverwriteAlways
+    };
 
-        if same_file {
-            error!("error: \"{:s}\" and \"{:s}\" are the same file",
-                source.display().to_str(),
-                dest.display().to_str());
-            fail!();
-        }
+    let mut options = Options {
+        force: matches.opt_present("force"),
+        recursive: recursive,
+        // symbolic links are copied as links when recursing and followed
+        // otherwise
+        follow: Follow::from_flags(matches.opt_present("dereference"), matches.opt_present("H"),
+                                   no_dereference,
+                                   if recursive { FollowNone } else { FollowAll }),
+        umask: fsext::umask(),
+        preserve: preserve,
+        reflink: reflink,
+        sparse: sparse,
+        overwrite: overwrite,
+        update: matches.opt_present("update"),
+        progress: None,
+    };
+    let parents = matches.opt_present("parents");
 
-        let io_result = fs::copy(source, &dest);
+    let target_dir = matches.opt_str("target-directory");
+    let no_target_dir = matches.opt_present("no-target-directory");
+    if target_dir.is_some() && no_target_dir {
+        crash!(1, "cannot combine --target-directory (-t) and --no-target-directory (-T)");
+    }
 
-        if io_result.is_err() {
-            let err = io_result.unwrap_err();
-            error!("error: {:s}", err.to_str());
-            fail!();
+    let (sources, target): (Vec<Path>, Path) = match target_dir {
+        Some(dir) => {
+            if matches.free.is_empty() {
+                crash!(1, "missing file operand");
+            }
+            (matches.free.iter().map(|arg| Path::new(arg.as_slice())).collect(),
+             Path::new(dir.as_slice()))
         }
-    } else {
-        if fs::stat(&dest).unwrap().kind != io::TypeDirectory {
-            error!("error: TARGET must be a directory");
-            fail!();
+        None => {
+            if matches.free.len() < 1 {
+                crash!(1, "missing file operand");
+            } else if matches.free.len() < 2 {
+                crash!(1, "missing destination file operand after '{}'", matches.free.get(0));
+            } else if no_target_dir && matches.free.len() > 2 {
+                crash!(1, "extra operand '{}'", matches.free.get(2));
+            }
+            // All but the last argument, and only the last argument:
+            (matches.free.init().iter().map(|arg| Path::new(arg.as_slice())).collect(),
+             Path::new(matches.free.last().unwrap().as_slice()))
         }
+    };
 
+    let target_is_dir = !no_target_dir && match fsext::stat_path(&target) {
+        Ok(stat) => fsext::is_dir(stat.st_mode),
+        Err(_) => false
+    };
+    if (sources.len() > 1 || parents || matches.opt_present("target-directory"))
+            && !target_is_dir {
+        crash!(1, "target '{}' is not a directory", target.display());
+    }
+
+    if matches.opt_present("progress") {
+        let mut counter = SizeCounter { total: 0, recursive: options.recursive };
         for source in sources.iter() {
-            if fs::stat(source).unwrap().kind != io::TypeFile {
-                error!("error: \"{:s}\" is not a file", source.display().to_str());
-                continue;
-            }
+            Walker::new().follow(options.follow).walk(source, &mut counter);
+        }
+        options.progress = Some(RefCell::new(Progress::new(counter.total)));
+    }
 
-            let mut full_dest = dest.clone();
+    // files already copied, by source device and inode, so that hard links
+    // can be recreated with --preserve=links
+    let mut links = HashMap::new();
+    for source in sources.iter() {
+        let dest = if parents {
+            match make_parents(source, &target, &options) {
+                Ok(dest) => dest,
+                Err(e) => {
+                    show_error!(1, "cannot make parent directories for '{}': {}",
+                                source.display(), util::strerror(&e));
+                    continue;
+                }
+            }
+        } else if target_is_dir {
+            match source.filename() {
+                Some(name) => target.join(name),
+                None => {
+                    show_error!(1, "cannot copy '{}' into '{}'", source.display(), target.display());
+                    continue;
+                }
+            }
+        } else {
+            target.clone()
+        };
+        copy::copy_path(source, &dest, &options, &mut links);
+    }
 
-            full_dest.push(source.filename_str().unwrap());
+    match options.progress {
+        Some(ref progress) => progress.borrow_mut().finish(),
+        None => {}
+    }
+}
 
-            println!("{:s}", full_dest.display().to_str());
+// Adds up the sizes of the files a copy is going to read, for --progress.
+struct SizeCounter {
+    total: u64,
+    recursive: bool,
+}
 
-            let io_result = fs::copy(source, &full_dest);
+impl Visitor for SizeCounter {
+    fn enter_dir(&mut self, _dir: &WalkEntry) -> bool {
+        self.recursive
+    }
 
-            if io_result.is_err() {
-                let err = io_result.unwrap_err();
-                error!("error: {:s}", err.to_str());
-                fail!()
-            }
+    fn visit_file(&mut self, file: &WalkEntry) {
+        if fsext::is_reg(file.stat.st_mode) {
+            self.total += file.stat.st_size as u64;
         }
     }
 }
 
-pub fn paths_refer_to_same_file(p1: &Path, p2: &Path) -> io::IoResult<bool> {
-    let mut raw_p1 = p1.clone();
-    let mut raw_p2 = p2.clone();
-
-    let p1_lstat = match fs::lstat(&raw_p1) {
-        Ok(stat) => stat,
-        Err(e)   => return Err(e),
-    };
-
-    let p2_lstat = match fs::lstat(&raw_p2) {
-        Ok(stat) => stat,
-        Err(e)   => return Err(e),
-    };
-
-    // We have to take symlinks and relative paths into account.
-    if p1_lstat.kind == io::TypeSymlink {
-        raw_p1 = fs::readlink(&raw_p1).unwrap();
+// For --parents: creates each directory leading up to source under target,
+// returning where source itself should go.
+fn make_parents(source: &Path, target: &Path, options: &Options) -> IoResult<Path> {
+    let mut dest = target.clone();
+    let mut src_dir = if source.is_absolute() { Path::new("/") } else { Path::new(".") };
+    let components: Vec<&[u8]> = source.components().collect();
+    for component in components.init().iter() {
+        dest.push(*component);
+        src_dir.push(*component);
+        match fsext::stat_path(&dest) {
+            Ok(ref stat) if fsext::is_dir(stat.st_mode) => continue,
+            Ok(_) => return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "not a directory",
+                detail: Some(dest.display().to_str())
+            }),
+            Err(_) => {}
+        }
+        try!(fs::mkdir(&dest, io::UserRWX | io::GroupRWX | io::OtherRWX));
+        match fsext::stat_path(&src_dir) {
+            Ok(src_stat) => copy::preserve_attrs(&src_dir, &src_stat, &dest, options),
+            Err(_) => {}
+        }
     }
-    raw_p1 = os::make_absolute(&raw_p1);
-
-    if p2_lstat.kind == io::TypeSymlink {
-        raw_p2 = fs::readlink(&raw_p2).unwrap();
+    match components.last() {
+        Some(name) => dest.push(*name),
+        None => {}
     }
-    raw_p2 = os::make_absolute(&raw_p2);
-
-    Ok(raw_p1 == raw_p2)
+    Ok(dest)
 }
@@ -0,0 +1,78 @@
+use std::io;
+use std::io::fs;
+use std::io::process::Command;
+
+static PROG: &'static str = "build/cp";
+
+fn write_file(name: &str, contents: &str) {
+    io::File::create(&Path::new(name)).write_str(contents).unwrap();
+}
+
+fn read_file(name: &str) -> String {
+    io::File::open(&Path::new(name)).read_to_str().unwrap()
+}
+
+// Copies over an existing destination, answering any prompt with `answer`,
+// and gives back what the destination holds afterwards.
+fn overwrite(args: &[&'static str], src: &str, dst: &str, answer: &str) -> String {
+    write_file(src, "new");
+    write_file(dst, "old");
+    let mut process = Command::new(PROG).args(args).arg(src).arg(dst).spawn().unwrap();
+    process.stdin.take_unwrap().write_str(answer).unwrap();
+    process.wait_with_output().unwrap();
+    let contents = read_file(dst);
+    fs::unlink(&Path::new(src)).unwrap();
+    fs::unlink(&Path::new(dst)).unwrap();
+    contents
+}
+
+fn make_sparse(name: &str, size: i64) {
+    let mut file = io::File::create(&Path::new(name)).unwrap();
+    file.seek(size - 1, io::SeekSet).unwrap();
+    file.write([1u8]).unwrap();
+}
+
+// The bytes actually allocated to the file, and its size.
+fn usage(name: &str) -> (u64, u64) {
+    let stat = fs::stat(&Path::new(name)).unwrap();
+    (stat.unstable.blocks * 512, stat.size)
+}
+
+#[test]
+fn test_sparse_stays_sparse() {
+    let (src, dst) = ("tmp/cp_sparse_src", "tmp/cp_sparse_dst");
+    make_sparse(src, 4 * 1024 * 1024);
+    let (allocated, _) = usage(src);
+    if !Command::new(PROG).args([src, dst]).status().unwrap().success() {
+        fail!();
+    }
+    let (copied, size) = usage(dst);
+    assert_eq!(size, 4 * 1024 * 1024);
+    // only meaningful where the file system made the source sparse at all
+    if allocated < size {
+        assert!(copied < size);
+    }
+    fs::unlink(&Path::new(src)).unwrap();
+    fs::unlink(&Path::new(dst)).unwrap();
+}
+
+#[test]
+fn test_sparse_never_fills_holes() {
+    let (src, dst) = ("tmp/cp_never_src", "tmp/cp_never_dst");
+    make_sparse(src, 1024 * 1024);
+    if !Command::new(PROG).args(["--sparse=never", src, dst]).status().unwrap().success() {
+        fail!();
+    }
+    let (copied, size) = usage(dst);
+    assert!(copied >= size);
+    fs::unlink(&Path::new(src)).unwrap();
+    fs::unlink(&Path::new(dst)).unwrap();
+}
+
+#[test]
+fn test_last_of_no_clobber_and_interactive_wins() {
+    let (src, dst) = ("tmp/cp_clobber_src", "tmp/cp_clobber_dst");
+    assert_eq!(overwrite(["-i", "-n"], src, dst, "y\n").as_slice(), "old");
+    assert_eq!(overwrite(["-n", "-i"], src, dst, "y\n").as_slice(), "new");
+    assert_eq!(overwrite(["-n", "-i"], src, dst, "n\n").as_slice(), "old");
+}
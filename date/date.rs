@@ -0,0 +1,273 @@
+#![crate_id(name="date", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+extern crate time;
+
+use std::os;
+use std::io::{print, stdin, BufferedReader, File};
+use time::{Timespec, Tm};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/strftime.rs"]
+mod strftime;
+
+#[path = "../common/datetime.rs"]
+mod datetime;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+static NAME: &'static str = "date";
+static VERSION: &'static str = "1.0.0";
+
+// What date prints with no +FORMAT, as in the C locale.
+static DEFAULT_FORMAT: &'static str = "%a %b %e %H:%M:%S %Z %Y";
+
+static RFC_EMAIL_FORMAT: &'static str = "%a, %d %b %Y %H:%M:%S %z";
+
+// The precisions --iso-8601 and --rfc-3339 take, with their formats.
+static ISO_8601_FORMATS: [(&'static str, &'static str), ..5] = [
+    ("date", "%Y-%m-%d"),
+    ("hours", "%Y-%m-%dT%H%:z"),
+    ("minutes", "%Y-%m-%dT%H:%M%:z"),
+    ("seconds", "%Y-%m-%dT%H:%M:%S%:z"),
+    ("ns", "%Y-%m-%dT%H:%M:%S,%N%:z"),
+];
+static RFC_3339_FORMATS: [(&'static str, &'static str), ..3] = [
+    ("date", "%Y-%m-%d"),
+    ("seconds", "%Y-%m-%d %H:%M:%S%:z"),
+    ("ns", "%Y-%m-%d %H:%M:%S.%N%:z"),
+];
+
+extern {
+    #[cfg(target_os = "linux")]
+    fn clock_settime(clock: libc::c_int, tp: *libc::timespec) -> libc::c_int;
+    #[cfg(not(target_os = "linux"))]
+    fn settimeofday(tv: *libc::timeval, tz: *libc::c_void) -> libc::c_int;
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optopt("d", "date", "display time described by STRING, not 'now'", "STRING"),
+        getopts::optopt("f", "file", "like --date; once for each line of DATEFILE", "DATEFILE"),
+        getopts::optflagopt("I", "iso-8601", "output date/time in ISO 8601 format.  FMT='date' \
+                                              for date only (the default), 'hours', 'minutes', \
+                                              'seconds', or 'ns' for date and time to the \
+                                              indicated precision", "FMT"),
+        getopts::optflag("R", "rfc-email", "output date and time in RFC 5322 format.  \
+                                            Example: Mon, 14 Aug 2006 02:34:56 -0600"),
+        getopts::optflag("", "rfc-2822", "same as --rfc-email"),
+        getopts::optopt("", "rfc-3339", "output date/time in RFC 3339 format.  FMT='date', \
+                                         'seconds', or 'ns' for date and time to the indicated \
+                                         precision.  Example: 2006-08-14 02:34:56-06:00", "FMT"),
+        getopts::optopt("r", "reference", "display the last modification time of FILE", "FILE"),
+        getopts::optopt("s", "set", "set time described by STRING", "STRING"),
+        getopts::optflag("u", "utc", "print Coordinated Universal Time (UTC)"),
+        getopts::optflag("", "universal", "same as --utc"),
+        getopts::optflag("", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [+FORMAT]", args.get(0));
+        println!("  {0} [OPTION]... -s STRING", args.get(0));
+        println!("");
+        print(getopts::usage("Display the current time in the given FORMAT, or set the system \
+                              date.", opts.as_slice()).as_slice());
+        println!("");
+        println!("FORMAT controls the output.  Interpreted sequences are those of strftime(3),");
+        println!("along with %N for nanoseconds, %:z, %::z and %:::z for the time zone as");
+        println!("+hh:mm, +hh:mm:ss and with only the precision needed, and %P for am or pm.");
+        println!("");
+        println!("By default, date pads numeric fields with zeroes.  The following optional");
+        println!("flags may follow '%':");
+        println!("");
+        println!("  -  (hyphen) do not pad the field");
+        println!("  _  (underscore) pad with spaces");
+        println!("  0  (zero) pad with zeros");
+        println!("  ^  use upper case if possible");
+        println!("  #  use opposite case if possible");
+        println!("");
+        println!("After any flags comes an optional field width, as a decimal number.");
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let mut formats = vec!();
+    match matches.free.len() {
+        0 => {}
+        1 if matches.free.get(0).as_slice().starts_with("+") => {
+            formats.push(matches.free.get(0).as_slice().slice_from(1).to_string());
+        }
+        1 => crash!(1, "invalid date '{}'", matches.free.get(0)),
+        _ => crash!(1, "extra operand '{}'", matches.free.get(1))
+    }
+    if matches.opt_present("iso-8601") {
+        let precision = matches.opt_str("iso-8601").unwrap_or("date".to_string());
+        formats.push(standard_format("--iso-8601", ISO_8601_FORMATS, precision.as_slice()));
+    }
+    match matches.opt_str("rfc-3339") {
+        Some(precision) => {
+            formats.push(standard_format("--rfc-3339", RFC_3339_FORMATS, precision.as_slice()))
+        }
+        None => {}
+    }
+    if matches.opt_present("rfc-email") || matches.opt_present("rfc-2822") {
+        formats.push(RFC_EMAIL_FORMAT.to_string());
+    }
+    if formats.len() > 1 {
+        crash!(1, "multiple output formats specified");
+    }
+    let format = formats.pop().unwrap_or(DEFAULT_FORMAT.to_string());
+    let utc = matches.opt_present("utc") || matches.opt_present("universal");
+
+    let sources = ["date", "file", "reference"].iter()
+                                               .filter(|name| matches.opt_present(**name))
+                                               .count();
+    if sources > 1 {
+        crash!(1, "the options to specify dates for printing are mutually exclusive");
+    }
+    if sources > 0 && matches.opt_present("set") {
+        crash!(1, "the options to print and set the time may not be used together");
+    }
+
+    let now = time::get_time();
+    match matches.opt_str("file") {
+        Some(file) => {
+            print_file(file.as_slice(), format.as_slice(), utc, now);
+            return;
+        }
+        None => {}
+    }
+    let t = match (matches.opt_str("date"), matches.opt_str("reference"), matches.opt_str("set")) {
+        (Some(date), _, _) => parse_date(date.as_slice(), now, utc),
+        (_, Some(file), _) => match fsext::stat_path(&Path::new(file.as_slice())) {
+            Ok(stat) => {
+                let (sec, nsec) = fsext::mtime(&stat);
+                Timespec::new(sec, nsec as i32)
+            }
+            Err(e) => crash!(1, "{}: {}", file, util::strerror(&e))
+        },
+        (_, _, Some(date)) => {
+            let t = parse_date(date.as_slice(), now, utc);
+            // the new time is printed even when it could not be set
+            match set_clock(t) {
+                Ok(()) => {}
+                Err(e) => show_error!(1, "cannot set date: {}", e)
+            }
+            t
+        }
+        _ => now
+    };
+
+    println!("{}", strftime::format(format.as_slice(), &broken_down(t, utc)));
+}
+
+// Converts each line of the file, or of stdin for -, going on past the
+// ones that are not dates.
+fn print_file(file: &str, format: &str, utc: bool, now: Timespec) {
+    let reader = if file == "-" {
+        box stdin() as Box<Reader>
+    } else {
+        match File::open(&Path::new(file)) {
+            Ok(f) => box f as Box<Reader>,
+            Err(e) => crash!(1, "{}: {}", file, util::strerror(&e))
+        }
+    };
+    for line in BufferedReader::new(reader).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => crash!(1, "{}: {}", file, util::strerror(&e))
+        };
+        let line = line.as_slice().trim_right_chars('\n');
+        match datetime::parse_datetime(line, now, utc) {
+            Some(t) => println!("{}", strftime::format(format, &broken_down(t, utc))),
+            None => show_error!(1, "invalid date '{}'", line)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_clock(t: Timespec) -> Result<(), String> {
+    static CLOCK_REALTIME: libc::c_int = 0;
+    let ts = libc::timespec { tv_sec: t.sec as libc::time_t, tv_nsec: t.nsec as libc::c_long };
+    if unsafe { clock_settime(CLOCK_REALTIME, &ts) } == 0 {
+        Ok(())
+    } else {
+        Err(os::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_clock(t: Timespec) -> Result<(), String> {
+    let tv = libc::timeval {
+        tv_sec: t.sec as libc::time_t,
+        tv_usec: (t.nsec / 1000) as libc::suseconds_t
+    };
+    if unsafe { settimeofday(&tv, ::std::ptr::null()) } == 0 {
+        Ok(())
+    } else {
+        Err(os::last_os_error())
+    }
+}
+
+fn parse_date(s: &str, now: Timespec, utc: bool) -> Timespec {
+    match datetime::parse_datetime(s, now, utc) {
+        Some(t) => t,
+        None => crash!(1, "invalid date '{}'", s)
+    }
+}
+
+// The format for a precision, which may be cut short as long as it is
+// still only the start of one of them.
+fn standard_format(option: &str, formats: &[(&str, &'static str)], precision: &str) -> String {
+    let matching: Vec<&'static str> = formats.iter().filter(|&&(name, _)| {
+        !precision.is_empty() && name.starts_with(precision)
+    }).map(|&(_, format)| format).collect();
+    match matching.len() {
+        0 => crash!(1, "invalid argument '{}' for '{}'", precision, option),
+        1 => matching.get(0).to_string(),
+        _ => crash!(1, "ambiguous argument '{}' for '{}'", precision, option)
+    }
+}
+
+fn broken_down(t: Timespec, utc: bool) -> Tm {
+    if utc { time::at_utc(t) } else { time::at(t) }
+}
@@ -0,0 +1,821 @@
+#![crate_id(name="dd", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate libc;
+extern crate time;
+
+use std::ascii::StrAsciiExt;
+use std::os;
+use std::io::EndOfFile;
+
+use fdio::{AlignedBuf, Fd};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/fdio.rs"]
+mod fdio;
+
+#[path = "../common/size.rs"]
+mod size;
+
+#[path = "../common/signal.rs"]
+mod signal;
+
+static NAME: &'static str = "dd";
+static VERSION: &'static str = "1.0.0";
+
+static DEFAULT_BLOCK_SIZE: uint = 512;
+
+#[cfg(target_os = "linux", target_arch = "arm")]
+static O_DIRECT: libc::c_int = 0o200000;
+#[cfg(target_os = "linux", not(target_arch = "arm"))]
+static O_DIRECT: libc::c_int = 0o40000;
+// elsewhere there is no O_DIRECT to open with
+#[cfg(not(target_os = "linux"))]
+static O_DIRECT: libc::c_int = 0;
+
+// Set by the signal handlers, and looked at between blocks.
+static mut INTERRUPTED: bool = false;
+static mut INFO_REQUESTED: bool = false;
+
+#[deriving(PartialEq)]
+enum Status {
+    StatusDefault,
+    // no statistics at all, only errors
+    StatusNone,
+    // the records lines but not the transfer line
+    StatusNoxfer,
+    // the transfer line every second as the copy goes
+    StatusProgress,
+}
+
+struct Options {
+    input: Option<String>,
+    output: Option<String>,
+    ibs: uint,
+    obs: uint,
+    // bs= was given: every block read is written as it is
+    same_blocks: bool,
+    count: Option<u64>,
+    skip: u64,
+    seek: u64,
+    // the record size for conv=block and conv=unblock
+    cbs: Option<uint>,
+    conv: Conv,
+    iflags: Flags,
+    oflags: Flags,
+    status: Status,
+}
+
+// What iflag= or oflag= asked for.
+struct Flags {
+    // bypass the page cache with O_DIRECT
+    direct: bool,
+}
+
+// What conv= asked for.
+struct Conv {
+    notrunc: bool,
+    noerror: bool,
+    sync: bool,
+    fsync: bool,
+    fdatasync: bool,
+    sparse: bool,
+    ucase: bool,
+    lcase: bool,
+    swab: bool,
+    block: bool,
+    unblock: bool,
+}
+
+impl Conv {
+    // Whether the data itself changes, which means output is always
+    // gathered into obs-sized blocks, bs= or not.
+    fn transforms(&self) -> bool {
+        self.ucase || self.lcase || self.swab || self.block || self.unblock
+    }
+}
+
+struct Output {
+    fd: Fd,
+    name: String,
+    // whether conv=sparse seeked past the last block rather than writing it
+    in_hole: bool,
+    // O_DIRECT is still on, which a last short block cannot be written with
+    direct: bool,
+}
+
+struct Stats {
+    full_in: u64,
+    partial_in: u64,
+    full_out: u64,
+    partial_out: u64,
+    bytes: u64,
+    // records conv=block had to cut short
+    truncated: u64,
+    start: u64,
+    // when status=progress last showed how far the copy is
+    last_progress: u64,
+    // a progress line is on the terminal without a newline after it
+    progress_shown: bool,
+    status: Status,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let mut options = Options {
+        input: None,
+        output: None,
+        ibs: DEFAULT_BLOCK_SIZE,
+        obs: DEFAULT_BLOCK_SIZE,
+        same_blocks: false,
+        count: None,
+        skip: 0,
+        seek: 0,
+        cbs: None,
+        conv: Conv {
+            notrunc: false,
+            noerror: false,
+            sync: false,
+            fsync: false,
+            fdatasync: false,
+            sparse: false,
+            ucase: false,
+            lcase: false,
+            swab: false,
+            block: false,
+            unblock: false,
+        },
+        iflags: Flags { direct: false },
+        oflags: Flags { direct: false },
+        status: StatusDefault,
+    };
+
+    for arg in args.tail().iter() {
+        match arg.as_slice() {
+            "--help" => {
+                help(args.get(0).as_slice());
+                return;
+            }
+            "--version" => {
+                println!("{} {}", NAME, VERSION);
+                return;
+            }
+            "--" => continue,
+            _ => {}
+        }
+        let (key, value) = match arg.as_slice().find('=') {
+            Some(i) => (arg.as_slice().slice_to(i), arg.as_slice().slice_from(i + 1)),
+            None => crash!(1, "unrecognized operand '{}'", arg)
+        };
+        match key {
+            "if" => options.input = Some(value.to_string()),
+            "of" => options.output = Some(value.to_string()),
+            "bs" => {
+                let bs = block_size(key, value);
+                options.ibs = bs;
+                options.obs = bs;
+                options.same_blocks = true;
+            }
+            "ibs" => options.ibs = block_size(key, value),
+            "obs" => options.obs = block_size(key, value),
+            "count" => options.count = Some(number(key, value)),
+            "skip" => options.skip = number(key, value),
+            "seek" => options.seek = number(key, value),
+            "cbs" => options.cbs = Some(block_size(key, value)),
+            "conv" => parse_conv(value, &mut options.conv),
+            "iflag" => parse_flags(value, &mut options.iflags, "input"),
+            "oflag" => parse_flags(value, &mut options.oflags, "output"),
+            "status" => options.status = match value {
+                "none" => StatusNone,
+                "noxfer" => StatusNoxfer,
+                "progress" => StatusProgress,
+                _ => crash!(1, "invalid status level: '{}'", value)
+            },
+            _ => crash!(1, "unrecognized operand '{}'", arg)
+        }
+    }
+    if options.conv.block && options.conv.unblock {
+        crash!(1, "cannot combine block and unblock");
+    } else if options.conv.lcase && options.conv.ucase {
+        crash!(1, "cannot combine lcase and ucase");
+    }
+    // without a record size there is nothing to block or unblock
+    if options.cbs.is_none() {
+        options.conv.block = false;
+        options.conv.unblock = false;
+    }
+
+    if (options.iflags.direct || options.oflags.direct) && O_DIRECT == 0 {
+        crash!(1, "direct I/O is not supported on this system");
+    }
+    let iflags = if options.iflags.direct { O_DIRECT } else { 0 };
+    let oflags = if options.oflags.direct { O_DIRECT } else { 0 };
+
+    let mut input = match options.input {
+        Some(ref name) => match Fd::open(&Path::new(name.as_slice()), libc::O_RDONLY | iflags, 0) {
+            Ok(fd) => fd,
+            Err(e) => crash!(1, "failed to open '{}': {}", name, util::strerror(&e))
+        },
+        None => Fd::from_raw(libc::STDIN_FILENO)
+    };
+    // truncated after seeking rather than on opening, so that seek= keeps
+    // what comes before
+    let fd = match options.output {
+        Some(ref name) => {
+            let flags = libc::O_WRONLY | libc::O_CREAT | oflags;
+            match Fd::open(&Path::new(name.as_slice()), flags, 0o666) {
+                Ok(fd) => fd,
+                Err(e) => crash!(1, "failed to open '{}': {}", name, util::strerror(&e))
+            }
+        }
+        None => Fd::from_raw(libc::STDOUT_FILENO)
+    };
+    let mut output = Output {
+        fd: fd,
+        name: output_name(&options),
+        in_hole: false,
+        direct: options.oflags.direct && options.output.is_some(),
+    };
+
+    signal::catch(signal::SIGINT, on_interrupt);
+    signal::catch(signal::SIGUSR1, on_info);
+
+    let start = time::precise_time_ns();
+    let mut stats = Stats {
+        full_in: 0,
+        partial_in: 0,
+        full_out: 0,
+        partial_out: 0,
+        bytes: 0,
+        truncated: 0,
+        start: start,
+        last_progress: start,
+        progress_shown: false,
+        status: options.status,
+    };
+    let ok = skip_input(&mut input, &options) && seek_output(&mut output, &options)
+             && copy(&mut input, &mut output, &options, &mut stats);
+    print_stats(&mut stats);
+    if !ok {
+        util::set_status(1);
+    }
+}
+
+fn help(program: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0} [OPERAND]...", program);
+    println!("  {0} OPTION", program);
+    println!("");
+    println!("Copy a file, converting and formatting according to the operands.");
+    println!("");
+    println!("  bs=BYTES        read and write up to BYTES bytes at a time");
+    println!("  cbs=BYTES       convert BYTES bytes at a time");
+    println!("  conv=CONVS      convert the file as per the comma separated symbol list");
+    println!("  count=N         copy only N input blocks");
+    println!("  ibs=BYTES       read up to BYTES bytes at a time (default: 512)");
+    println!("  if=FILE         read from FILE instead of stdin");
+    println!("  iflag=FLAGS     read as per the comma separated symbol list");
+    println!("  obs=BYTES       write BYTES bytes at a time (default: 512)");
+    println!("  of=FILE         write to FILE instead of stdout");
+    println!("  oflag=FLAGS     write as per the comma separated symbol list");
+    println!("  seek=N          skip N obs-sized blocks at start of output");
+    println!("  skip=N          skip N ibs-sized blocks at start of input");
+    println!("  status=LEVEL    The LEVEL of information to print to stderr;");
+    println!("                  'none' suppresses everything but error messages,");
+    println!("                  'noxfer' suppresses the final transfer statistics,");
+    println!("                  'progress' shows periodic transfer statistics");
+    println!("");
+    println!("Each CONV symbol may be:");
+    println!("");
+    println!("  block     pad newline-terminated records with spaces to cbs-size");
+    println!("  unblock   replace trailing spaces in cbs-size records with newline");
+    println!("  lcase     change upper case to lower case");
+    println!("  ucase     change lower case to upper case");
+    println!("  sparse    try to seek rather than write the output for NUL input blocks");
+    println!("  swab      swap every pair of input bytes");
+    println!("  sync      pad every input block with NULs to ibs-size; when used with block");
+    println!("            or unblock, pad with spaces rather than NULs");
+    println!("  notrunc   do not truncate the output file");
+    println!("  noerror   continue after read errors");
+    println!("  fdatasync physically write output file data before finishing");
+    println!("  fsync     likewise, but also write metadata");
+    println!("");
+    println!("Each FLAG symbol may be:");
+    println!("");
+    println!("  direct    use direct I/O for data");
+    println!("");
+    println!("N and BYTES may be followed by the following multiplicative suffixes:");
+    println!("c=1, w=2, b=512, kB=1000, K=1024, MB=1000*1000, M=1024*1024, xM=M,");
+    println!("GB=1000*1000*1000, G=1024*1024*1024, and so on for T, P, E, Z, Y.");
+    println!("");
+    println!("Sending a USR1 signal to a running '{}' process makes it print I/O", NAME);
+    println!("statistics to standard error and then resume copying; an INT signal makes");
+    println!("it print them and then stop.");
+    println!("");
+    println!("  --help     display this help and exit");
+    println!("  --version  output version information and exit");
+}
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    unsafe { INTERRUPTED = true; }
+}
+
+extern "C" fn on_info(_: libc::c_int) {
+    unsafe { INFO_REQUESTED = true; }
+}
+
+// Deals with whatever the signal handlers have asked for since the last
+// block, and keeps the progress line up to date.
+fn check_signals(stats: &mut Stats) {
+    if unsafe { INTERRUPTED } {
+        print_stats(stats);
+        signal::raise(signal::SIGINT);
+    }
+    if unsafe { INFO_REQUESTED } {
+        unsafe { INFO_REQUESTED = false; }
+        print_stats(stats);
+    }
+    if stats.status == StatusProgress {
+        let now = time::precise_time_ns();
+        if now - stats.last_progress >= 1000000000 {
+            stats.last_progress = now;
+            stats.progress_shown = true;
+            safe_write!(&mut std::io::stderr(), "\r{}", transfer_line(stats));
+        }
+    }
+}
+
+// N with an optional unit, or a product of them such as 2x512 or 1Mx4.
+fn parse_number(s: &str) -> Option<u64> {
+    let mut result = 1u64;
+    for factor in s.split('x') {
+        if factor.is_empty() {
+            return None;
+        }
+        let value = match factor.char_at_reverse(factor.len()) {
+            'c' => from_str(factor.slice_to(factor.len() - 1)),
+            'w' => from_str::<u64>(factor.slice_to(factor.len() - 1)).map(|n| n * 2),
+            _ => size::parse_size(factor).map(|(n, _)| n)
+        };
+        result = match value.and_then(|n| result.checked_mul(&n)) {
+            Some(n) => n,
+            None => return None
+        };
+    }
+    Some(result)
+}
+
+fn number(key: &str, value: &str) -> u64 {
+    match parse_number(value) {
+        Some(n) => n,
+        None => crash!(1, "invalid number: '{}' for '{}'", value, key)
+    }
+}
+
+fn block_size(key: &str, value: &str) -> uint {
+    match parse_number(value) {
+        Some(0) | None => crash!(1, "invalid number: '{}' for '{}'", value, key),
+        Some(n) => n as uint
+    }
+}
+
+fn parse_conv(list: &str, conv: &mut Conv) {
+    for name in list.split(',') {
+        match name {
+            "notrunc" => conv.notrunc = true,
+            "noerror" => conv.noerror = true,
+            "sync" => conv.sync = true,
+            "fsync" => conv.fsync = true,
+            "fdatasync" => conv.fdatasync = true,
+            "sparse" => conv.sparse = true,
+            "ucase" => conv.ucase = true,
+            "lcase" => conv.lcase = true,
+            "swab" => conv.swab = true,
+            "block" => conv.block = true,
+            "unblock" => conv.unblock = true,
+            _ => crash!(1, "invalid conversion: '{}'", name)
+        }
+    }
+}
+
+fn parse_flags(list: &str, flags: &mut Flags, what: &str) {
+    for name in list.split(',') {
+        match name {
+            "direct" => flags.direct = true,
+            _ => crash!(1, "invalid {} flag: '{}'", what, name)
+        }
+    }
+}
+
+// O_DIRECT wants memory aligned to the device's blocks, which the page size
+// always is a multiple of.
+fn new_buffer(size: uint) -> AlignedBuf {
+    match AlignedBuf::new(size, os::page_size()) {
+        Ok(buf) => buf,
+        Err(e) => crash!(1, "memory exhausted: {}", util::strerror(&e))
+    }
+}
+
+// Seeks past skip= blocks, or reads through them when the input cannot seek.
+fn skip_input(input: &mut Fd, options: &Options) -> bool {
+    if options.skip == 0 {
+        return true;
+    }
+    match input.skip((options.skip * options.ibs as u64) as i64) {
+        Ok(_) => return true,
+        Err(_) => {}
+    }
+    let mut buf = new_buffer(options.ibs);
+    for _ in range(0, options.skip) {
+        match input.read(buf.whole_mut()) {
+            Ok(_) => {}
+            Err(ref e) if e.kind == EndOfFile => {
+                show_warning!("'{}': cannot skip to specified offset", input_name(options));
+                break;
+            }
+            Err(e) => {
+                show_error!(1, "error reading '{}': {}", input_name(options), util::strerror(&e));
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Seeks past seek= blocks and, for a file that was asked for by name, cuts
+// off what came after them unless conv=notrunc.
+fn seek_output(output: &mut Output, options: &Options) -> bool {
+    let offset = options.seek * options.obs as u64;
+    if offset > 0 {
+        match output.fd.skip(offset as i64) {
+            Ok(_) => {}
+            Err(e) => {
+                show_error!(1, "'{}': cannot seek: {}", output.name, util::strerror(&e));
+                return false;
+            }
+        }
+    }
+    let regular = match fsext::stat_fd(output.fd.raw()) {
+        Ok(stat) => fsext::is_reg(stat.st_mode),
+        Err(_) => false
+    };
+    if options.output.is_some() && regular && !options.conv.notrunc {
+        match output.fd.truncate(offset) {
+            Ok(()) => {}
+            Err(e) => {
+                show_error!(1, "failed to truncate to {} bytes in output file '{}': {}", offset,
+                            output.name, util::strerror(&e));
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn copy(input: &mut Fd, output: &mut Output, options: &Options, stats: &mut Stats) -> bool {
+    let conv = &options.conv;
+    // both buffers are allocated once and reused for every block
+    let mut ibuf = new_buffer(options.ibs);
+    // what has been read but not yet written in obs-sized blocks
+    let mut obuf = new_buffer(options.obs);
+    // each block once block or unblock is done with it
+    let mut converted: Vec<u8> = vec!();
+    let mut records = Records {
+        cbs: options.cbs.unwrap_or(0),
+        unblock: conv.unblock,
+        record: vec!(),
+        truncating: false,
+    };
+    // blocks go out just as they came in, without going through obuf
+    let passthrough = options.same_blocks && !conv.transforms();
+    let pad = if conv.block || conv.unblock { ' ' as u8 } else { 0u8 };
+    let mut blocks = 0u64;
+
+    loop {
+        if options.count.map_or(false, |count| blocks >= count) {
+            break;
+        }
+        check_signals(stats);
+        let n = match input.read_interruptible(ibuf.whole_mut()) {
+            Ok(Some(n)) => Some(n),
+            // a signal came while the read was waiting, which is seen to
+            // before it waits again
+            Ok(None) => continue,
+            Err(ref e) if e.kind == EndOfFile => break,
+            Err(e) => {
+                show_error!(1, "error reading '{}': {}", input_name(options), util::strerror(&e));
+                if !conv.noerror {
+                    return false;
+                }
+                // carry on past the bad block, which sync stands in for with
+                // zeros so that the output stays in step with the input
+                let _ = input.skip(options.ibs as i64);
+                if conv.sync {
+                    for b in ibuf.whole_mut().mut_iter() {
+                        *b = 0;
+                    }
+                }
+                None
+            }
+        };
+        blocks += 1;
+        let n = match n {
+            Some(n) if n == options.ibs => {
+                stats.full_in += 1;
+                n
+            }
+            Some(n) => {
+                stats.partial_in += 1;
+                if conv.sync {
+                    for b in ibuf.whole_mut().mut_slice_from(n).mut_iter() {
+                        *b = pad;
+                    }
+                    options.ibs
+                } else {
+                    n
+                }
+            }
+            None if conv.sync => {
+                stats.partial_in += 1;
+                options.ibs
+            }
+            None => continue
+        };
+
+        {
+            ibuf.set_len(n);
+            let data = ibuf.as_mut_slice();
+            if conv.swab {
+                for pair in data.mut_chunks(2) {
+                    if pair.len() == 2 {
+                        pair.swap(0, 1);
+                    }
+                }
+            }
+            if conv.ucase {
+                for b in data.mut_iter() {
+                    if *b >= 'a' as u8 && *b <= 'z' as u8 { *b -= 32; }
+                }
+            } else if conv.lcase {
+                for b in data.mut_iter() {
+                    if *b >= 'A' as u8 && *b <= 'Z' as u8 { *b += 32; }
+                }
+            }
+        }
+
+        let data = ibuf.as_slice();
+        let ok = if passthrough {
+            write_block(output, data, options, stats)
+        } else if conv.block || conv.unblock {
+            converted.clear();
+            records.convert(data, &mut converted, stats);
+            gather(output, &mut obuf, converted.as_slice(), options, stats)
+        } else {
+            gather(output, &mut obuf, data, options, stats)
+        };
+        if !ok {
+            return false;
+        }
+    }
+
+    converted.clear();
+    records.finish(&mut converted);
+    if !gather(output, &mut obuf, converted.as_slice(), options, stats) {
+        return false;
+    }
+    if !obuf.is_empty() && !write_block(output, obuf.as_slice(), options, stats) {
+        return false;
+    }
+    finish_output(output, options)
+}
+
+// Adds data to what is waiting to go out, writing each block as it fills.
+fn gather(output: &mut Output, obuf: &mut AlignedBuf, data: &[u8], options: &Options,
+          stats: &mut Stats) -> bool {
+    let mut data = data;
+    while !data.is_empty() {
+        let taken = obuf.push_all(data);
+        data = data.slice_from(taken);
+        if obuf.len() == options.obs {
+            if !write_block(output, obuf.as_slice(), options, stats) {
+                return false;
+            }
+            obuf.clear();
+        }
+    }
+    true
+}
+
+fn write_block(output: &mut Output, block: &[u8], options: &Options, stats: &mut Stats) -> bool {
+    // a block of zeros can be a hole instead, in a file but not a pipe
+    let hole = options.conv.sparse && options.output.is_some() && block.iter().all(|&b| b == 0);
+    if output.direct && block.len() % os::page_size() != 0 {
+        // a short last block is written through the cache, as GNU dd does
+        match output.fd.clear_flags(O_DIRECT) {
+            Ok(()) => output.direct = false,
+            Err(e) => {
+                show_error!(1, "failed to turn off O_DIRECT: '{}': {}", output.name,
+                            util::strerror(&e));
+                return false;
+            }
+        }
+    }
+    let result = if hole {
+        output.fd.skip(block.len() as i64).map(|_| ())
+    } else {
+        output.fd.write(block)
+    };
+    match result {
+        Ok(()) => output.in_hole = hole,
+        Err(e) => {
+            show_error!(1, "error writing '{}': {}", output.name, util::strerror(&e));
+            return false;
+        }
+    }
+    if block.len() == options.obs {
+        stats.full_out += 1;
+    } else {
+        stats.partial_out += 1;
+    }
+    stats.bytes += block.len() as u64;
+    true
+}
+
+// Makes sure a hole at the end still counts toward the file's size, and waits for the disk with
+// conv=fsync or conv=fdatasync.
+fn finish_output(output: &mut Output, options: &Options) -> bool {
+    if output.in_hole {
+        let result = output.fd.skip(0).and_then(|offset| {
+            match fsext::stat_fd(output.fd.raw()) {
+                Ok(ref stat) if stat.st_size as u64 >= offset => Ok(()),
+                _ => output.fd.truncate(offset)
+            }
+        });
+        match result {
+            Ok(()) => {}
+            Err(e) => {
+                show_error!(1, "failed to extend '{}': {}", output.name, util::strerror(&e));
+                return false;
+            }
+        }
+    }
+    if options.conv.fsync || options.conv.fdatasync {
+        match output.fd.sync(!options.conv.fsync) {
+            Ok(()) => {}
+            Err(e) => {
+                show_error!(1, "fsync failed for '{}': {}", output.name, util::strerror(&e));
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// conv=block turns newline terminated lines into records of cbs bytes padded
+// with spaces, cutting longer lines short; conv=unblock turns such records
+// back into lines without the trailing spaces.
+struct Records {
+    cbs: uint,
+    unblock: bool,
+    record: Vec<u8>,
+    // the rest of the current line is being dropped
+    truncating: bool,
+}
+
+impl Records {
+    fn convert(&mut self, data: &[u8], out: &mut Vec<u8>, stats: &mut Stats) {
+        for &b in data.iter() {
+            if self.unblock {
+                self.record.push(b);
+                if self.record.len() == self.cbs {
+                    self.end_record(out);
+                }
+            } else if b == '\n' as u8 {
+                self.end_record(out);
+            } else if self.record.len() < self.cbs {
+                self.record.push(b);
+            } else if !self.truncating {
+                self.truncating = true;
+                stats.truncated += 1;
+            }
+        }
+    }
+
+    fn end_record(&mut self, out: &mut Vec<u8>) {
+        if self.unblock {
+            let len = self.record.iter().rposition(|&b| b != ' ' as u8).map_or(0, |i| i + 1);
+            out.push_all(self.record.slice_to(len));
+            out.push('\n' as u8);
+        } else {
+            out.push_all(self.record.as_slice());
+            for _ in range(self.record.len(), self.cbs) {
+                out.push(' ' as u8);
+            }
+        }
+        self.record.clear();
+        self.truncating = false;
+    }
+
+    // What is left over at the end of the input.
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        if !self.record.is_empty() {
+            self.end_record(out);
+        }
+    }
+}
+
+fn input_name(options: &Options) -> String {
+    options.input.clone().unwrap_or("standard input".to_string())
+}
+
+fn output_name(options: &Options) -> String {
+    options.output.clone().unwrap_or("standard output".to_string())
+}
+
+fn print_stats(stats: &mut Stats) {
+    let mut stderr = std::io::stderr();
+    if stats.progress_shown {
+        safe_writeln!(&mut stderr, "");
+        stats.progress_shown = false;
+    }
+    if stats.status == StatusNone {
+        return;
+    }
+    safe_writeln!(&mut stderr, "{}+{} records in", stats.full_in, stats.partial_in);
+    safe_writeln!(&mut stderr, "{}+{} records out", stats.full_out, stats.partial_out);
+    if stats.truncated > 0 {
+        safe_writeln!(&mut stderr, "{} truncated record{}", stats.truncated,
+                      if stats.truncated == 1 { "" } else { "s" });
+    }
+    if stats.status != StatusNoxfer {
+        safe_writeln!(&mut stderr, "{}", transfer_line(stats));
+    }
+}
+
+// How much has been copied, in how long, and how fast.
+fn transfer_line(stats: &Stats) -> String {
+    let elapsed = (time::precise_time_ns() - stats.start) as f64 / 1e9;
+    let size = if stats.bytes < 1000 {
+        String::new()
+    } else if stats.bytes < 1024 {
+        format!(" ({})", scaled(stats.bytes as f64, 1000.0, "B"))
+    } else {
+        format!(" ({}, {})", scaled(stats.bytes as f64, 1000.0, "B"),
+                scaled(stats.bytes as f64, 1024.0, "iB"))
+    };
+    let rate = if elapsed > 0.0 {
+        format!("{}/s", scaled(stats.bytes as f64 / elapsed, 1000.0, "B"))
+    } else {
+        "Infinity B/s".to_string()
+    };
+    format!("{} byte{}{} copied, {:.6} s, {}", stats.bytes,
+            if stats.bytes == 1 { "" } else { "s" }, size, elapsed, rate)
+}
+
+// 1.5 kB, 23 MB, 1.1 GiB: at most three significant digits, as GNU dd
+// prints them.
+fn scaled(value: f64, base: f64, unit: &str) -> String {
+    static PREFIXES: &'static [&'static str] = &["k", "M", "G", "T", "P", "E", "Z", "Y"];
+    if value < base {
+        return format!("{} B", value as u64);
+    }
+    let mut value = value / base;
+    let mut i = 0;
+    while value >= base && i < PREFIXES.len() - 1 {
+        value /= base;
+        i += 1;
+    }
+    // binary units are all upper case
+    let prefix = if base == 1024.0 {
+        PREFIXES[i].to_ascii_upper()
+    } else {
+        PREFIXES[i].to_string()
+    };
+    if value < 10.0 {
+        format!("{:.1} {}{}", value, prefix, unit)
+    } else {
+        format!("{:.0} {}{}", value, prefix, unit)
+    }
+}
@@ -0,0 +1,51 @@
+use std::io;
+use std::io::fs;
+use std::io::process::Command;
+
+static PROG: &'static str = "build/dd";
+
+// Runs dd with input on stdin, giving back what it wrote and whether it
+// succeeded.
+fn run(args: &[&'static str], input: &[u8]) -> (Vec<u8>, bool) {
+    let mut process = Command::new(PROG).args(args).spawn().unwrap();
+    process.stdin.take_unwrap().write(input).unwrap();
+    let po = process.wait_with_output().unwrap();
+    (po.output, po.status.success())
+}
+
+#[test]
+fn test_copy() {
+    assert_eq!(run(["bs=3"], b"hello world"), (Vec::from_slice(b"hello world"), true));
+}
+
+#[test]
+fn test_sync_pads_short_blocks() {
+    assert_eq!(run(["bs=8", "conv=sync"], b"abc"), (Vec::from_slice(b"abc\0\0\0\0\0"), true));
+    // blocks of records are padded with spaces instead
+    assert_eq!(run(["bs=8", "cbs=4", "conv=sync,block"], b"ab\n"),
+               (Vec::from_slice(b"ab      "), true));
+}
+
+#[test]
+fn test_noerror_sync_stands_in_for_bad_blocks() {
+    // every read of a directory fails, and each failed block becomes zeros
+    let dir = "tmp/dd_test_dir";
+    fs::mkdir(&Path::new(dir), io::UserRWX).unwrap();
+    let po = Command::new(PROG).args(["if=tmp/dd_test_dir", "bs=4", "count=2",
+                                      "conv=noerror,sync"]).output().unwrap();
+    fs::rmdir(&Path::new(dir)).unwrap();
+    assert_eq!(po.output, Vec::from_elem(8, 0u8));
+    assert!(!po.status.success());
+}
+
+#[test]
+fn test_block() {
+    assert_eq!(run(["cbs=4", "conv=block"], b"ab\ncdefgh\n"),
+               (Vec::from_slice(b"ab  cdef"), true));
+}
+
+#[test]
+fn test_unblock() {
+    assert_eq!(run(["cbs=4", "conv=unblock"], b"ab  cd  "),
+               (Vec::from_slice(b"ab\ncd\n"), true));
+}
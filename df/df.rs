@@ -0,0 +1,388 @@
+#![crate_id(name="df", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::print;
+use mounts::{Mount, Usage};
+use size::{BlockSize, Blocks, Human};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/mounts.rs"]
+mod mounts;
+
+#[path = "../common/size.rs"]
+mod size;
+
+#[path = "../common/json.rs"]
+mod json;
+
+#[path = "../common/tsv.rs"]
+mod tsv;
+
+#[path = "../common/mbchar.rs"]
+mod mbchar;
+
+static NAME: &'static str = "df";
+static VERSION: &'static str = "1.0.0";
+
+// Output for other programs rather than people: every field whatever the
+// options, sizes in bytes, and names escaped.
+enum Records {
+    // one object to a line
+    JsonRecords,
+    // with a header line first
+    TsvRecords,
+}
+
+static TSV_HEADER: &'static str =
+    "filesystem\ttype\tsize\tused\tavailable\tinodes\tiused\tifree\tmounted_on\n";
+
+struct Options {
+    all: bool,
+    block_size: BlockSize,
+    // inode counts instead of blocks
+    inodes: bool,
+    print_type: bool,
+    // only these file system types, if any are given
+    types: Vec<String>,
+    exclude_types: Vec<String>,
+    records: Option<Records>,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("a", "all", "include pseudo, duplicate, inaccessible file systems"),
+        getopts::optopt("B", "block-size", "scale sizes by SIZE before printing them; e.g., \
+                                            '-BM' prints sizes in units of 1,048,576 bytes",
+                        "SIZE"),
+        getopts::optflag("h", "human-readable", "print sizes in powers of 1024 (e.g., 1023M)"),
+        getopts::optflag("H", "si", "print sizes in powers of 1000 (e.g., 1.1G)"),
+        getopts::optflag("i", "inodes", "list inode information instead of block usage"),
+        getopts::optflag("k", "", "like --block-size=1K"),
+        getopts::optmulti("t", "type", "limit listing to file systems of type TYPE", "TYPE"),
+        getopts::optflag("T", "print-type", "print file system type"),
+        getopts::optmulti("x", "exclude-type", "limit listing to file systems not of type TYPE",
+                          "TYPE"),
+        getopts::optflag("", "json", "print a JSON object for each file system instead, with \
+                                      all of the fields and sizes in bytes"),
+        getopts::optflag("", "tsv", "print tab-separated fields with a header line instead, \
+                                     with all of the fields and sizes in bytes"),
+        getopts::optflag("", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [FILE]...", args.get(0));
+        println!("");
+        print(getopts::usage("Show information about the file system on which each FILE \
+                              resides,\nor all file systems by default.", opts.as_slice()).as_slice());
+        println!("");
+        println!("Display values are in units of the first available SIZE from --block-size,");
+        println!("and the DF_BLOCK_SIZE, BLOCK_SIZE and BLOCKSIZE environment variables.");
+        println!("Otherwise, units default to 1024 bytes (or 512 if POSIXLY_CORRECT is set).");
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let block_size = if matches.opt_present("human-readable") {
+        Human(1024)
+    } else if matches.opt_present("si") {
+        Human(1000)
+    } else if matches.opt_present("k") {
+        Blocks(1024, None)
+    } else {
+        match size::block_size(matches.opt_str("block-size"), "DF_BLOCK_SIZE") {
+            Ok(Some(size)) => size,
+            Ok(None) => size::default_block_size(),
+            Err(e) => crash!(1, "{}", e)
+        }
+    };
+
+    let options = Options {
+        all: matches.opt_present("all"),
+        block_size: block_size,
+        inodes: matches.opt_present("inodes"),
+        print_type: matches.opt_present("print-type"),
+        types: matches.opt_strs("type"),
+        exclude_types: matches.opt_strs("exclude-type"),
+        records: match (matches.opt_present("json"), matches.opt_present("tsv")) {
+            (true, true) => crash!(1, "--json and --tsv are mutually exclusive"),
+            (true, false) => Some(JsonRecords),
+            (false, true) => Some(TsvRecords),
+            (false, false) => None
+        },
+    };
+    for t in options.types.iter() {
+        if options.exclude_types.contains(t) {
+            crash!(1, "file system type '{}' both selected and excluded", t);
+        }
+    }
+
+    let mounts = match mounts::mounts() {
+        Ok(mounts) => mounts,
+        Err(e) => crash!(1, "cannot read table of mounted file systems: {}", util::strerror(&e))
+    };
+
+    let shown = if matches.free.is_empty() {
+        all_mounts(&mounts, &options)
+    } else {
+        let mut shown = vec!();
+        for name in matches.free.iter() {
+            match containing_mount(&mounts, &Path::new(name.as_slice())) {
+                Ok(Some(mount)) if !selected(mount, &options) => {}
+                Ok(Some(mount)) => match mounts::usage(&Path::new(mount.dir.as_slice())) {
+                    Ok(usage) => shown.push((mount, usage)),
+                    Err(e) => show_error!(1, "{}: {}", mount.dir, util::strerror(&e))
+                },
+                Ok(None) => show_error!(1, "{}: cannot find mount point", name),
+                Err(e) => show_error!(1, "{}: {}", name, e)
+            }
+        }
+        shown
+    };
+
+    if shown.is_empty() {
+        if matches.free.is_empty() {
+            crash!(1, "no file systems processed");
+        }
+        return;
+    }
+    match options.records {
+        Some(ref records) => print_records(&shown, records),
+        None => print_table(&shown, &options)
+    }
+}
+
+// What df with no operands lists: the real file systems, each once, unless
+// -a asks for the rest as well.
+fn all_mounts<'a>(mounts: &'a Vec<Mount>, options: &Options) -> Vec<(&'a Mount, Usage)> {
+    let mut shown = vec!();
+    let mut devices = vec!();
+    for mount in mounts.iter() {
+        if !options.all && mount.is_dummy() || !selected(mount, &options) {
+            continue;
+        }
+        let path = Path::new(mount.dir.as_slice());
+        let usage = match mounts::usage(&path) {
+            Ok(usage) => usage,
+            Err(e) => {
+                // such as a FUSE mount belonging to someone else
+                if options.all {
+                    show_error!(1, "{}: {}", mount.dir, util::strerror(&e));
+                }
+                continue;
+            }
+        };
+        if !options.all {
+            if usage.blocks == 0 {
+                continue;
+            }
+            // a file system mounted in several places, as bind mounts are,
+            // only shows up at the first
+            match fsext::stat_path(&path) {
+                Ok(stat) => {
+                    if devices.contains(&stat.st_dev) {
+                        continue;
+                    }
+                    devices.push(stat.st_dev);
+                }
+                Err(_) => {}
+            }
+        }
+        shown.push((mount, usage));
+    }
+    shown
+}
+
+// The file system a file is on: the last one mounted on a directory with the
+// same device, since a later mount hides whatever was there before.
+fn containing_mount<'a>(mounts: &'a Vec<Mount>, path: &Path)
+                        -> Result<Option<&'a Mount>, String> {
+    let dev = match fsext::stat_path(path) {
+        Ok(stat) => stat.st_dev,
+        Err(e) => return Err(util::strerror(&e))
+    };
+    Ok(mounts.iter().rev().find(|mount| {
+        match fsext::stat_path(&Path::new(mount.dir.as_slice())) {
+            Ok(stat) => stat.st_dev == dev,
+            Err(_) => false
+        }
+    }))
+}
+
+// Whether -t and -x leave the mount in.
+fn selected(mount: &Mount, options: &Options) -> bool {
+    (options.types.is_empty() || options.types.contains(&mount.fs_type))
+    && !options.exclude_types.contains(&mount.fs_type)
+}
+
+// e.g. 1K-blocks, 1MB-blocks or Size, as GNU df heads the column.
+fn size_header(block_size: &BlockSize) -> String {
+    match *block_size {
+        Human(_) => "Size".to_string(),
+        Blocks(_, Some(ref unit)) => format!("{}-blocks", unit),
+        Blocks(bytes, None) if bytes % 1024 == 0 => format!("{}K-blocks", bytes / 1024),
+        Blocks(bytes, None) => format!("{}B-blocks", bytes)
+    }
+}
+
+// Used space as a share of what non-root users could fill, rounded up.
+fn percent(used: u64, avail: u64) -> String {
+    let total = used + avail;
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{}%", (used * 100 + total - 1) / total)
+    }
+}
+
+// Inode counts are plain numbers, scaled only with -h or -H.
+fn count(n: u64, block_size: &BlockSize) -> String {
+    match *block_size {
+        Human(base) => size::human_readable(n, base),
+        Blocks(..) => n.to_str()
+    }
+}
+
+fn print_records(shown: &Vec<(&Mount, Usage)>, records: &Records) {
+    let mut out = String::new();
+    match *records {
+        TsvRecords => out.push_str(TSV_HEADER),
+        JsonRecords => {}
+    }
+    for &(mount, ref usage) in shown.iter() {
+        let size = usage.blocks * usage.block_size;
+        let used = (usage.blocks - usage.blocks_free) * usage.block_size;
+        let avail = usage.blocks_avail * usage.block_size;
+        let iused = usage.files - usage.files_free;
+        match *records {
+            JsonRecords => {
+                let mut object = json::Object::new();
+                object.bytes("filesystem", mount.device_bytes.as_slice());
+                object.string("type", mount.fs_type.as_slice());
+                object.number("size", size);
+                object.number("used", used);
+                object.number("available", avail);
+                object.number("inodes", usage.files);
+                object.number("iused", iused);
+                object.number("ifree", usage.files_free);
+                object.bytes("mounted_on", mount.dir_bytes.as_slice());
+                out.push_str(object.encode().as_slice());
+                out.push_char('\n');
+            }
+            TsvRecords => {
+                let fields = [tsv::field(mount.device_bytes.as_slice()),
+                              tsv::field(mount.fs_type.as_bytes()),
+                              size.to_str(), used.to_str(), avail.to_str(),
+                              usage.files.to_str(), iused.to_str(), usage.files_free.to_str(),
+                              tsv::field(mount.dir_bytes.as_slice())];
+                out.push_str(tsv::line(fields.as_slice()).as_slice());
+            }
+        }
+    }
+    print(out.as_slice());
+}
+
+fn print_table(shown: &Vec<(&Mount, Usage)>, options: &Options) {
+    let mut header = vec!("Filesystem".to_string());
+    if options.print_type {
+        header.push("Type".to_string());
+    }
+    if options.inodes {
+        header.push_all(["Inodes".to_string(), "IUsed".to_string(), "IFree".to_string(),
+                         "IUse%".to_string()]);
+    } else {
+        header.push_all([size_header(&options.block_size), "Used".to_string(),
+                         "Available".to_string(), "Use%".to_string()]);
+    }
+    header.push("Mounted on".to_string());
+
+    let mut rows = vec!(header);
+    for &(mount, ref usage) in shown.iter() {
+        let mut row = vec!(mount.device.clone());
+        if options.print_type {
+            row.push(mount.fs_type.clone());
+        }
+        if options.inodes {
+            let used = usage.files - usage.files_free;
+            row.push_all([count(usage.files, &options.block_size),
+                          count(used, &options.block_size),
+                          count(usage.files_free, &options.block_size),
+                          percent(used, usage.files_free)]);
+        } else {
+            let used = usage.blocks - usage.blocks_free;
+            let bytes = |blocks: u64| size::format_size(blocks * usage.block_size,
+                                                        &options.block_size);
+            row.push_all([bytes(usage.blocks), bytes(used), bytes(usage.blocks_avail),
+                          percent(used, usage.blocks_avail)]);
+        }
+        row.push(mount.dir.clone());
+        rows.push(row);
+    }
+
+    let columns = rows.get(0).len();
+    let mut widths = Vec::from_elem(columns, 0u);
+    for row in rows.iter() {
+        for (i, cell) in row.iter().enumerate() {
+            if cell.len() > *widths.get(i) {
+                *widths.get_mut(i) = cell.len();
+            }
+        }
+    }
+    for row in rows.iter() {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            let width = *widths.get(i);
+            if i == 0 || i == 1 && options.print_type {
+                line.push_str(format!("{:<1$}", *cell, width).as_slice());
+            } else if i == columns - 1 {
+                // the last column is left as it is, names and all
+                line.push_str(format!(" {}", *cell).as_slice());
+            } else {
+                line.push_str(format!(" {:>1$}", *cell, width).as_slice());
+            }
+        }
+        println!("{}", line);
+    }
+}
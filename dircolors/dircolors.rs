@@ -0,0 +1,252 @@
+#![crate_id(name="dircolors", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::{print, stdin, File};
+use std::str;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+mod database;
+
+static NAME: &'static str = "dircolors";
+static VERSION: &'static str = "1.0.0";
+
+enum Shell {
+    Bourne,
+    C,
+}
+
+// The two letter LS_COLORS classes for the database's keywords.
+static KEYWORDS: [(&'static str, &'static str), ..37] = [
+    ("NORMAL", "no"), ("NORM", "no"),
+    ("FILE", "fi"),
+    ("RESET", "rs"),
+    ("DIR", "di"),
+    ("LNK", "ln"), ("LINK", "ln"), ("SYMLINK", "ln"),
+    ("ORPHAN", "or"),
+    ("MISSING", "mi"),
+    ("FIFO", "pi"), ("PIPE", "pi"),
+    ("SOCK", "so"),
+    ("BLK", "bd"), ("BLOCK", "bd"),
+    ("CHR", "cd"), ("CHAR", "cd"),
+    ("DOOR", "do"),
+    ("EXEC", "ex"),
+    ("LEFT", "lc"), ("LEFTCODE", "lc"),
+    ("RIGHT", "rc"), ("RIGHTCODE", "rc"),
+    ("END", "ec"), ("ENDCODE", "ec"),
+    ("SUID", "su"), ("SETUID", "su"),
+    ("SGID", "sg"), ("SETGID", "sg"),
+    ("STICKY", "st"),
+    ("OTHER_WRITABLE", "ow"), ("OWR", "ow"),
+    ("STICKY_OTHER_WRITABLE", "tw"), ("OWT", "tw"),
+    ("CAPABILITY", "ca"),
+    ("MULTIHARDLINK", "mh"),
+    ("CLRTOEOL", "cl"),
+];
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("b", "sh", "output Bourne shell code to set LS_COLORS"),
+        getopts::optflag("", "bourne-shell", "same as --sh"),
+        getopts::optflag("c", "csh", "output C shell code to set LS_COLORS"),
+        getopts::optflag("", "c-shell", "same as --csh"),
+        getopts::optflag("p", "print-database", "output defaults"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [FILE]", args.get(0));
+        println!("");
+        print(getopts::usage("Output commands to set the LS_COLORS environment variable.", opts.as_slice())
+                      .as_slice());
+        println!("");
+        println!("If FILE is specified, read it to determine which colors to use for which");
+        println!("file types and extensions.  Otherwise, a precompiled database is used.");
+        println!("For details on the format of these files, run '{} --print-database'.", NAME);
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let bourne = matches.opt_present("sh") || matches.opt_present("bourne-shell");
+    let csh = matches.opt_present("csh") || matches.opt_present("c-shell");
+    if matches.opt_present("print-database") {
+        if bourne || csh {
+            crash!(1, "the options to output dircolors' internal database and\n\
+                       to select a shell syntax are mutually exclusive");
+        }
+        if !matches.free.is_empty() {
+            crash!(1, "extra operand '{}'\nfile operands cannot be combined with \
+                       --print-database (-p)", matches.free.get(0));
+        }
+        print(database::DEFAULT);
+        return;
+    }
+
+    let shell = if bourne && csh {
+        crash!(1, "cannot specify both --sh and --csh")
+    } else if bourne {
+        Bourne
+    } else if csh {
+        C
+    } else {
+        guess_shell()
+    };
+
+    let (name, db) = match matches.free.len() {
+        0 => ("<internal>".to_string(), database::DEFAULT.to_string()),
+        1 => {
+            let name = matches.free.get(0).clone();
+            let contents = if name.as_slice() == "-" {
+                stdin().read_to_end()
+            } else {
+                File::open(&Path::new(name.as_slice())).read_to_end()
+            };
+            match contents {
+                Ok(bytes) => (name, str::from_utf8_lossy(bytes.as_slice()).into_string()),
+                Err(e) => crash!(1, "{}: {}", name, util::strerror(&e))
+            }
+        }
+        _ => crash!(1, "extra operand '{}'", matches.free.get(1))
+    };
+
+    let term = os::getenv("TERM").unwrap_or("none".to_string());
+    let ls_colors = match ls_colors(name.as_slice(), db.as_slice(), term.as_slice()) {
+        Ok(ls_colors) => ls_colors,
+        Err(e) => crash!(1, "{}", e)
+    };
+    match shell {
+        Bourne => println!("LS_COLORS='{}';\nexport LS_COLORS", ls_colors),
+        C => println!("setenv LS_COLORS '{}'", ls_colors)
+    }
+}
+
+// The shell $SHELL names, where anything that is not a csh is taken to be
+// a Bourne shell.
+fn guess_shell() -> Shell {
+    let shell = match os::getenv("SHELL") {
+        Some(shell) => shell,
+        None => crash!(1, "no SHELL environment variable, and no shell type option given")
+    };
+    let base = Path::new(shell.as_slice()).filename_str().unwrap_or("").to_string();
+    if base.as_slice() == "csh" || base.as_slice() == "tcsh" { C } else { Bourne }
+}
+
+// Turns the database into the value of LS_COLORS, in the form ls reads: the
+// entries that apply to term as KEY=SGR, each followed by a colon.
+fn ls_colors(name: &str, db: &str, term: &str) -> Result<String, String> {
+    let mut out = String::new();
+    // entries apply unless a run of TERM lines comes before them that term
+    // matches none of
+    let mut term_matches = true;
+    let mut in_terms = false;
+    for (n, line) in db.lines().enumerate() {
+        let line = strip_comment(line);
+        let mut words = line.words();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue
+        };
+        let value = words.next();
+        if value.is_none() || words.next().is_some() {
+            return Err(format!("{}:{}: invalid line; missing second token", name, n + 1));
+        }
+        let value = value.unwrap();
+
+        if keyword == "TERM" {
+            if !in_terms {
+                term_matches = false;
+                in_terms = true;
+            }
+            term_matches |= glob_match(value, term);
+            continue;
+        }
+        in_terms = false;
+        if !term_matches {
+            continue;
+        }
+
+        match keyword {
+            // left over from the Slackware dircolors
+            "OPTIONS" | "COLOR" | "EIGHTBIT" => continue,
+            _ if keyword.starts_with(".") => out.push_str(format!("*{}", keyword).as_slice()),
+            _ if keyword.starts_with("*") => out.push_str(keyword),
+            _ => match KEYWORDS.iter().find(|&&(word, _)| word == keyword) {
+                Some(&(_, class)) => out.push_str(class),
+                None => {
+                    return Err(format!("{}:{}: unrecognized keyword {}", name, n + 1, keyword))
+                }
+            }
+        }
+        out.push_str(format!("={}:", value).as_slice());
+    }
+    Ok(out)
+}
+
+// A # starts a comment at the beginning of the line or after a space.
+fn strip_comment<'a>(line: &'a str) -> &'a str {
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        if c == '#' && prev.is_whitespace() {
+            return line.slice_to(i);
+        }
+        prev = c;
+    }
+    line
+}
+
+// TERM patterns are shell globs, with * and ? in them.
+fn glob_match(pattern: &str, s: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let s: Vec<char> = s.chars().collect();
+    glob_match_from(pattern.as_slice(), s.as_slice())
+}
+
+fn glob_match_from(pattern: &[char], s: &[char]) -> bool {
+    match pattern.head() {
+        None => s.is_empty(),
+        Some(&'*') => {
+            range(0, s.len() + 1).any(|i| glob_match_from(pattern.tail(), s.slice_from(i)))
+        }
+        Some(&'?') => !s.is_empty() && glob_match_from(pattern.tail(), s.tail()),
+        Some(c) => s.head() == Some(c) && glob_match_from(pattern.tail(), s.tail())
+    }
+}
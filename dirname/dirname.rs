@@ -9,27 +9,45 @@
  * file that was distributed with this source code.
  */
 
+#![feature(macro_rules)]
+
 extern crate getopts;
+extern crate libc;
 
 use std::os;
 use std::io::print;
 
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+static NAME: &'static str = "dirname";
 static VERSION: &'static str = "1.0.0";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("z", "zero", "separate output with NUL rather than newline"),
         getopts::optflag("", "help", "display this help and exit"),
         getopts::optflag("", "version", "output version information and exit"),
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
-        Err(f) => fail!("Invalid options\n{}", f.to_err_msg())
+        Err(f) => crash!(1, "Invalid options\n{}", f.to_err_msg())
     };
 
     if matches.opt_present("help") {
@@ -40,7 +58,7 @@ pub fn uumain(args: Vec<String>) {
         println!("");
         print(getopts::usage("Output each NAME with its last non-slash component and trailing slashes
 removed; if NAME contains no  /'s,  output  '.'  (meaning  the  current
-directory).", opts).as_slice());
+directory).", opts.as_slice()).as_slice());
         return;
     }
 
@@ -55,15 +73,23 @@ directory).", opts).as_slice());
 
     if !matches.free.is_empty() {
         for path in matches.free.iter() {
-            let p = std::path::Path::new(path.clone());
-            let d = std::str::from_utf8(p.dirname());
-            if d.is_some() {
-                print(d.unwrap());
-            }
+            print(dirname(path.as_slice()));
             print(separator);
         }
     } else {
-        println!("{0:s}: missing operand", program);
-        println!("Try '{0:s} --help' for more information.", program);
+        crash!(1, "missing operand\nTry '{0:s} --help' for more information.", program);
     }
 }
+
+// Works on the name as written: a Path would fold "a/.." down to "." and
+// give the wrong parent.
+fn dirname<'a>(path: &'a str) -> &'a str {
+    let trimmed = path.trim_right_chars('/');
+    // the last component goes, and the slashes before it
+    let dir = match trimmed.rfind('/') {
+        Some(i) => trimmed.slice_to(i).trim_right_chars('/'),
+        None if path.starts_with("/") => "",
+        None => return "."
+    };
+    if dir.is_empty() { "/" } else { dir }
+}
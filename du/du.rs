@@ -9,362 +9,734 @@
  * file that was distributed with this source code.
  */
 
-#![allow(uppercase_variables)]
 #![feature(macro_rules)]
 
+extern crate collections;
 extern crate getopts;
+extern crate glob;
 extern crate libc;
 extern crate sync;
 extern crate time;
 
+use std::cmp;
+use std::mem;
 use std::os;
-use std::io::{stderr, fs, FileStat, TypeDirectory};
-use std::option::Option;
-use std::path::Path;
+use std::io::{print, stdin, stdout, File, IoError, IoResult};
+use collections::hashmap::HashSet;
+use glob::Pattern;
+use sync::{Arc, Mutex};
 use time::Timespec;
-use sync::{Arc, Future};
+use size::{BlockSize, Blocks, Human};
+use walk::{Visitor, WalkEntry, Walker};
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/size.rs"]
+mod size;
+
+#[path = "../common/walk.rs"]
+mod walk;
+
+#[path = "../common/json.rs"]
+mod json;
+
+#[path = "../common/tsv.rs"]
+mod tsv;
+
+#[path = "../common/mbchar.rs"]
+mod mbchar;
+
 static NAME: &'static str = "du";
 static VERSION: &'static str = "1.0.0";
 
+// Output for other programs rather than people: sizes in bytes, times in
+// seconds since the Epoch, and names escaped.
+#[deriving(PartialEq)]
+enum Records {
+    // one object to a line
+    JsonRecords,
+    // with a header line first
+    TsvRecords,
+}
+
 struct Options {
     all: bool,
-    program_name: String,
     max_depth: Option<uint>,
     total: bool,
     separate_dirs: bool,
+    apparent_size: bool,
+    count_links: bool,
+    // stay on the file system each argument is on
+    one_file_system: bool,
+    exclude: Vec<Pattern>,
+    block_size: BlockSize,
+    // which of a file's times --time shows, the latest in each directory
+    time: Option<fn(&fsext::c_stat) -> (i64, i64)>,
+    time_format: String,
+    line_end: &'static str,
+    records: Option<Records>,
+    // how many subdirectories of an argument are walked at once
+    jobs: uint,
 }
 
-struct Stat {
+// What a directory holds, so far: bytes, and the latest time seen.
+#[deriving(Clone)]
+struct Total {
+    size: u64,
+    time: i64,
+    // which of a pooled walk's claims went into the size
+    claims: Vec<uint>,
+}
+
+impl Total {
+    fn new(size: u64, time: i64) -> Total {
+        Total { size: size, time: time, claims: vec!() }
+    }
+}
+
+// A file with several links that a walk in the pool counted, and how much
+// it added.
+struct Claim {
+    id: (u64, u64),
+    size: u64,
+}
+
+// A line a walk in the pool would have printed, held back until its claims
+// are settled.
+struct Line {
+    total: Total,
     path: Path,
-    fstat: FileStat,
+    // the line for a file rather than a directory
+    file: bool,
+}
+
+// What walking one entry of an argument came to in the pool.
+struct Subtree {
+    lines: Vec<Line>,
+    total: Total,
+    claimed: Vec<Claim>,
+    ok: bool,
 }
-// this takes `my_stat` to avoid having to stat files multiple times.
-fn du(path: &Path, mut my_stat: Stat,
-      options: Arc<Options>, depth: uint) -> Vec<Arc<Stat>> {
-    let mut stats = vec!();
-    let mut futures = vec!();
-
-    if my_stat.fstat.kind == TypeDirectory {
-        let read = match fs::readdir(path) {
-            Ok(read) => read,
-            Err(e) => {
-                safe_writeln!(&mut stderr(), "{}: cannot read directory ‘{}‘: {}",
-                              options.program_name, path.display(), e);
-                return vec!(Arc::new(my_stat))
+
+// Totals each directory on the way out of it, after all of its contents
+// have been added in, which is also when du prints it.
+//
+// Walking is dominated by waiting for stat(2), so the entries of each
+// argument are handed to a pool of tasks, each with a visitor of its own.
+// Their output is held back and printed in order, so it comes out just as
+// a walk on one task would have printed it.  A file linked from two of the
+// entries is counted by both walks, and once they are collected it is taken
+// off every line of the later entry that counted it, so that what is
+// printed does not depend on which task got to it first.
+struct DuVisitor {
+    options: Arc<Options>,
+    // files with several links that were counted before the pool started on
+    // the argument
+    earlier: Arc<HashSet<(u64, u64)>>,
+    // and those that have been counted since
+    seen: HashSet<(u64, u64)>,
+    // the files in seen that a walk in the pool counted, for collect() to
+    // settle
+    claimed: Vec<Claim>,
+    // the directories being walked, innermost last
+    dirs: Vec<Total>,
+    // what the entry the walk started at came to
+    root: Total,
+    // the device the argument is on, for -x
+    root_dev: u64,
+    // how far below the argument the walk started
+    base_depth: uint,
+    // the lines held back until the whole subtree is done, for the pool
+    lines: Vec<Line>,
+    // the entries of the argument that the pool is walking
+    pending: Option<(Receiver<(uint, Subtree)>, uint)>,
+    ok: bool,
+}
+
+impl DuVisitor {
+    fn new(options: Arc<Options>, earlier: Arc<HashSet<(u64, u64)>>,
+           base_depth: uint) -> DuVisitor {
+        DuVisitor {
+            options: options,
+            earlier: earlier,
+            seen: HashSet::new(),
+            claimed: vec!(),
+            dirs: vec!(),
+            root: Total::new(0, 0),
+            root_dev: 0,
+            base_depth: base_depth,
+            lines: vec!(),
+            pending: None,
+            ok: true,
+        }
+    }
+
+    fn size_of(&self, entry: &WalkEntry) -> u64 {
+        if self.options.apparent_size {
+            entry.stat.st_size as u64
+        } else {
+            // st_blocks is always in 512-byte units, whatever the block size
+            entry.stat.st_blocks as u64 * 512
+        }
+    }
+
+    fn time_of(&self, entry: &WalkEntry) -> i64 {
+        match self.options.time {
+            Some(time) => { let (secs, _) = time(&entry.stat); secs }
+            None => 0
+        }
+    }
+
+    // How far below the argument the entry is.
+    fn depth(&self, entry: &WalkEntry) -> uint {
+        entry.depth + self.base_depth
+    }
+
+    // Whether this is another link to a file that has already been counted.
+    // A walk in the pool claims the file the first time, in the total it
+    // goes into.
+    fn already_seen(&mut self, entry: &WalkEntry, total: &mut Total) -> bool {
+        if self.options.count_links || entry.stat.st_nlink <= 1 {
+            return false;
+        }
+        let id = (entry.stat.st_dev as u64, entry.stat.st_ino as u64);
+        if self.earlier.contains(&id) || !self.seen.insert(id) {
+            return true;
+        }
+        if self.base_depth > 0 {
+            total.claims.push(self.claimed.len());
+            self.claimed.push(Claim { id: id, size: total.size });
+        }
+        false
+    }
+
+    // Prints the line for an entry, or for a walk in the pool holds it back
+    // until collect() has settled its claims.
+    fn show(&mut self, total: &Total, path: &Path, file: bool) {
+        if self.base_depth > 0 {
+            self.lines.push(Line { total: total.clone(), path: path.clone(), file: file });
+        } else {
+            self.print(total, path);
+        }
+    }
+
+    fn shown(&self, depth: uint) -> bool {
+        self.options.max_depth.map_or(true, |max| depth <= max)
+    }
+
+    // Names are written as they are, not as Path would display them, so
+    // that with -0 any name comes out intact.
+    fn print(&mut self, total: &Total, path: &Path) {
+        if self.options.records.is_some() {
+            let record = record(&*self.options, total, Some(path.as_vec()));
+            self.write(record.as_bytes());
+            return;
+        }
+        let mut line = {
+            let options = &self.options;
+            let size = size::format_size(total.size, &options.block_size);
+            match options.time {
+                Some(_) => {
+                    let time = time::at(Timespec::new(total.time, 0));
+                    format!("{}\t{}\t", size, time.strftime(options.time_format.as_slice()))
+                }
+                None => format!("{}\t", size)
             }
-        };
+        }.into_bytes();
+        line.push_all(path.as_vec());
+        line.push_all(self.options.line_end.as_bytes());
+        self.write(line.as_slice());
+    }
 
-        for f in read.move_iter() {
-            let this_stat = Stat{path: f.clone(), fstat: safe_unwrap!(fs::lstat(&f))};
-            if this_stat.fstat.kind == TypeDirectory {
-                let oa_clone = options.clone();
-                futures.push(Future::spawn(proc() { du(&f, this_stat, oa_clone, depth + 1) }))
-            } else {
-                my_stat.fstat.size += this_stat.fstat.size;
-                my_stat.fstat.unstable.blocks += this_stat.fstat.unstable.blocks;
-                if options.all {
-                    stats.push(Arc::new(this_stat))
+    fn write(&mut self, bytes: &[u8]) {
+        match stdout().write(bytes) {
+            Ok(()) => {}
+            Err(e) => crash!(1, "write error: {}", util::strerror(&e))
+        }
+    }
+
+    // Adds what an entry came to into the directory it is in, depth being
+    // how far below where the walk started it is.
+    fn add(&mut self, total: Total, depth: uint) {
+        if depth == 0 {
+            self.root = total;
+            return;
+        }
+        match self.dirs.mut_last() {
+            Some(parent) => merge(parent, &total),
+            None => {}
+        }
+    }
+
+    // Waits for the pool to finish with the argument's entries, printing
+    // what each came to in order as soon as everything before it has been.
+    fn collect(&mut self) {
+        let (results, count) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return
+        };
+        let mut done: Vec<Option<Subtree>> = Vec::from_fn(count, |_| None);
+        let mut next = 0;
+        while next < count {
+            let (i, subtree) = results.recv();
+            *done.get_mut(i) = Some(subtree);
+            while next < count && done.get(next).is_some() {
+                let mut subtree = done.get_mut(next).take().unwrap();
+                self.settle(&mut subtree);
+                for line in subtree.lines.iter() {
+                    self.print(&line.total, &line.path);
+                }
+                match self.dirs.mut_last() {
+                    Some(parent) => merge(parent, &subtree.total),
+                    None => {}
                 }
+                self.ok &= subtree.ok;
+                next += 1;
             }
         }
     }
 
-    for future in futures.mut_iter() {
-        for stat in future.get().move_iter().rev() {
-            if !options.separate_dirs && stat.path.dir_path() == my_stat.path {
-                my_stat.fstat.size += stat.fstat.size;
-                my_stat.fstat.unstable.blocks += stat.fstat.unstable.blocks;
+    // Files an earlier entry already counted come off every total in the
+    // subtree they went into, and their own lines under -a are dropped, as
+    // a walk on one task would never have counted them.
+    fn settle(&mut self, subtree: &mut Subtree) {
+        let taken: Vec<bool> = subtree.claimed.iter().map(|claim| !self.seen.insert(claim.id))
+                                                     .collect();
+        let claimed = subtree.claimed.as_slice();
+        let taken = taken.as_slice();
+        let size = retaken(&subtree.total, claimed, taken);
+        subtree.total.size -= size;
+        subtree.lines.retain(|line| !line.file || retaken(&line.total, claimed, taken) == 0);
+        for line in subtree.lines.mut_iter() {
+            let size = retaken(&line.total, claimed, taken);
+            line.total.size -= size;
+        }
+        // the claims mean nothing to the walk the total goes into next
+        subtree.total.claims.clear();
+    }
+}
+
+// A line of --json or --tsv output; the grand total is the one without a
+// name, which no file can be.
+fn record(options: &Options, total: &Total, name: Option<&[u8]>) -> String {
+    match options.records {
+        Some(JsonRecords) => {
+            let mut object = json::Object::new();
+            object.number("size", total.size);
+            if options.time.is_some() {
+                object.number("time", total.time);
             }
-            if options.max_depth == None || depth < options.max_depth.unwrap() {
-                stats.push(stat.clone());
+            match name {
+                Some(name) => object.bytes("path", name),
+                None => object.boolean("total", true)
+            }
+            let mut line = object.encode();
+            line.push_char('\n');
+            line
+        }
+        _ => {
+            let mut fields = vec!(total.size.to_str());
+            if options.time.is_some() {
+                fields.push(total.time.to_str());
             }
+            fields.push(name.map_or(String::new(), |name| tsv::field(name)));
+            tsv::line(fields.as_slice())
+        }
+    }
+}
+
+// How much of the total went to files an earlier entry had already counted.
+fn retaken(total: &Total, claimed: &[Claim], taken: &[bool]) -> u64 {
+    total.claims.iter().filter(|&&i| taken[i]).fold(0, |sum, &i| sum + claimed[i].size)
+}
+
+fn merge(parent: &mut Total, total: &Total) {
+    parent.size += total.size;
+    parent.claims.push_all(total.claims.as_slice());
+    if total.time > parent.time {
+        parent.time = total.time;
+    }
+}
+
+impl Visitor for DuVisitor {
+    fn enter_dir(&mut self, dir: &WalkEntry) -> bool {
+        if self.depth(dir) == 0 {
+            self.root_dev = dir.stat.st_dev as u64;
+        }
+        let total = Total::new(self.size_of(dir), self.time_of(dir));
+        self.dirs.push(total);
+        true
+    }
+
+    fn children(&mut self, dir: &WalkEntry, children: &mut Vec<WalkEntry>) {
+        {
+            let options = &self.options;
+            let root_dev = self.root_dev;
+            // what is left out is neither shown nor counted
+            children.retain(|child| {
+                !excluded(&**options, &child.path)
+                && !(options.one_file_system && child.stat.st_dev as u64 != root_dev)
+            });
+        }
+        if self.depth(dir) == 0 && self.options.jobs > 1 && children.len() > 1 {
+            // the walker is left nothing to do here, the pool does it all
+            let entries = mem::replace(children, vec!());
+            let count = entries.len();
+            let results = walk_in_pool(entries, self);
+            self.pending = Some((results, count));
+        }
+    }
+
+    fn leave_dir(&mut self, dir: &WalkEntry) {
+        self.collect();
+        let total = self.dirs.pop().unwrap();
+        let depth = self.depth(dir);
+        if self.shown(depth) {
+            self.show(&total, &dir.path, false);
+        }
+        if self.options.separate_dirs && depth > 0 {
+            // only the entries directly inside count toward the parent
+            self.add(Total::new(0, total.time), dir.depth);
+        } else {
+            self.add(total, dir.depth);
+        }
+    }
+
+    fn visit_file(&mut self, file: &WalkEntry) {
+        let mut total = Total::new(self.size_of(file), self.time_of(file));
+        if self.already_seen(file, &mut total) {
+            return;
+        }
+        let depth = self.depth(file);
+        // a file named on the command line is always shown
+        if depth == 0 || self.options.all && self.shown(depth) {
+            self.show(&total, &file.path, true);
         }
+        self.add(total, file.depth);
+    }
+
+    fn error(&mut self, path: &Path, err: IoError) {
+        show_error!(1, "cannot access '{}': {}", path.display(), util::strerror(&err));
+        self.ok = false;
+    }
+}
+
+// Starts as many tasks as there are jobs to walk the entries, each taking
+// the next one from the queue until none are left.  Results come back with
+// the index of the entry they are for.
+fn walk_in_pool(entries: Vec<WalkEntry>, parent: &DuVisitor) -> Receiver<(uint, Subtree)> {
+    let (tx, rx) = channel();
+    let tasks = cmp::min(parent.options.jobs, entries.len());
+    // reversed, so that popping hands them out first to last
+    let mut queue: Vec<(uint, WalkEntry)> = entries.move_iter().enumerate().collect();
+    queue.reverse();
+    let queue = Arc::new(Mutex::new(queue));
+    // every walk starts from the same files as counted, whichever order the
+    // tasks take the entries in
+    let mut counted = (*parent.earlier).clone();
+    for &id in parent.seen.iter() {
+        counted.insert(id);
     }
+    let earlier = Arc::new(counted);
+    for _ in range(0, tasks) {
+        let tx = tx.clone();
+        let queue = queue.clone();
+        let options = parent.options.clone();
+        let earlier = earlier.clone();
+        let root_dev = parent.root_dev;
+        spawn(proc() {
+            loop {
+                let (i, entry) = match queue.lock().pop() {
+                    Some(job) => job,
+                    None => break
+                };
+                let mut visitor = DuVisitor::new(options.clone(), earlier.clone(), 1);
+                visitor.root_dev = root_dev;
+                Walker::new().walk(&entry.path, &mut visitor);
+                tx.send((i, Subtree {
+                    lines: visitor.lines,
+                    total: visitor.root,
+                    claimed: visitor.claimed,
+                    ok: visitor.ok,
+                }));
+            }
+        });
+    }
+    rx
+}
 
-    stats.push(Arc::new(my_stat));
+// A pattern can match the whole name as given, or just the last part of it,
+// so --exclude='*.o' leaves out object files at any depth.
+fn excluded(options: &Options, path: &Path) -> bool {
+    if options.exclude.is_empty() {
+        return false;
+    }
+    let full = path.as_str().unwrap_or("");
+    let base = path.filename_str().unwrap_or("");
+    options.exclude.iter().any(|p| p.matches(full) || p.matches(base))
+}
 
-    return stats;
+// The names in a file of NUL-terminated ones, the last of which may be
+// missing its NUL.
+fn read_files0(from: &str) -> IoResult<Vec<Vec<u8>>> {
+    let contents = if from == "-" {
+        try!(stdin().read_to_end())
+    } else {
+        try!(File::open(&Path::new(from)).read_to_end())
+    };
+    let mut names: Vec<Vec<u8>> = contents.as_slice().split(|b| *b == 0)
+                                          .map(|name| Vec::from_slice(name)).collect();
+    if contents.last() == Some(&0) || contents.is_empty() {
+        names.pop();
+    }
+    Ok(names)
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
-
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).as_slice();
-    let opts = [
-        // In task
-        getopts::optflag("a", "all", " write counts for all files, not just directories"),
-        // In main
-        getopts::optflag("", "apparent-size", "print apparent sizes,  rather  than  disk  usage;
-            although  the apparent  size is usually smaller, it may be larger due to holes
-            in ('sparse') files, internal  fragmentation,  indirect  blocks, and the like"),
-        // In main
-        getopts::optopt("B", "block-size", "scale sizes  by  SIZE before printing them.
-            E.g., '-BM' prints sizes in units of 1,048,576 bytes.  See SIZE format below.",
-            "SIZE"),
-        // In main
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("a", "all", "write counts for all files, not just directories"),
+        getopts::optflag("", "apparent-size", "print apparent sizes, rather than disk usage; \
+                                               although the apparent size is usually smaller, \
+                                               it may be larger due to holes in ('sparse') \
+                                               files, internal fragmentation, indirect blocks, \
+                                               and the like"),
+        getopts::optopt("B", "block-size", "scale sizes by SIZE before printing them; e.g., \
+                                            '-BM' prints sizes in units of 1,048,576 bytes",
+                        "SIZE"),
         getopts::optflag("b", "bytes", "equivalent to '--apparent-size --block-size=1'"),
-        // In main
         getopts::optflag("c", "total", "produce a grand total"),
-        // In task
-        // getopts::optflag("D", "dereference-args", "dereference only symlinks that are listed
-        //     on the command line"),
-        // In main
-        // getopts::optopt("", "files0-from", "summarize disk usage of the NUL-terminated file
-        //                   names specified in file F;
-        //                   If F is - then read names from standard input", "F"),
-        // // In task
-        // getopts::optflag("H", "", "equivalent to --dereference-args (-D)"),
-        // In main
-        getopts::optflag("h", "human-readable", "print sizes in human readable format (e.g., 1K 234M 2G)"),
-        // In main
+        getopts::optopt("", "files0-from", "summarize disk usage of the NUL-terminated file \
+                                            names specified in file F; if F is -, then read \
+                                            names from standard input", "F"),
+        getopts::optflag("h", "human-readable", "print sizes in human readable format \
+                                                 (e.g., 1K 234M 2G)"),
         getopts::optflag("", "si", "like -h, but use powers of 1000 not 1024"),
-        // In main
         getopts::optflag("k", "", "like --block-size=1K"),
-        // In task
         getopts::optflag("l", "count-links", "count sizes many times if hard linked"),
-        // // In main
         getopts::optflag("m", "", "like --block-size=1M"),
-        // // In task
-        // getopts::optflag("L", "dereference", "dereference all symbolic links"),
-        // // In task
-        // getopts::optflag("P", "no-dereference", "don't follow any symbolic links (this is the default)"),
-        // // In main
-        getopts::optflag("0", "null", "end each output line with 0 byte rather than newline"),
-        // In main
-        getopts::optflag("S", "separate-dirs", "do not include size of subdirectories"),
-        // In main
+        getopts::optflag("0", "null", "end each output line with NUL, not newline"),
+        getopts::optflag("S", "separate-dirs", "for directories do not include size of \
+                                                subdirectories"),
         getopts::optflag("s", "summarize", "display only a total for each argument"),
-        // // In task
-        // getopts::optflag("x", "one-file-system", "skip directories on different file systems"),
-        // // In task
-        // getopts::optopt("X", "exclude-from", "exclude files that match any pattern in FILE", "FILE"),
-        // // In task
-        // getopts::optopt("", "exclude", "exclude files that match PATTERN", "PATTERN"),
-        // In main
-        getopts::optopt("d", "max-depth", "print the total for a directory (or file, with --all)
-            only if it is N or fewer levels below the command
-            line argument;  --max-depth=0 is the same as --summarize", "N"),
-        // In main
-        getopts::optflagopt("", "time", "show time of the last modification of any file in the
-            directory, or any of its subdirectories.  If WORD is given, show time as WORD instead of modification time:
-            atime, access, use, ctime or status", "WORD"),
-        // In main
-        getopts::optopt("", "time-style", "show times using style STYLE:
-            full-iso, long-iso, iso, +FORMAT FORMAT is interpreted like 'date'", "STYLE"),
+        getopts::optflag("x", "one-file-system", "skip directories on different file systems"),
+        getopts::optopt("X", "exclude-from", "exclude files that match any pattern in FILE",
+                        "FILE"),
+        getopts::optmulti("", "exclude", "exclude files that match PATTERN", "PATTERN"),
+        getopts::optopt("d", "max-depth", "print the total for a directory (or file, with \
+                                           --all) only if it is N or fewer levels below the \
+                                           command line argument; --max-depth=0 is the same \
+                                           as --summarize", "N"),
+        getopts::optflagopt("", "time", "show time of the last modification of any file in \
+                                         the directory, or any of its subdirectories; with \
+                                         WORD, show that time instead: atime, access, use, \
+                                         ctime or status", "WORD"),
+        getopts::optopt("", "time-style", "show times using STYLE, which can be: full-iso, \
+                                           long-iso, iso, or +FORMAT; FORMAT is interpreted \
+                                           like in 'date'", "STYLE"),
+        getopts::optflag("", "json", "print a JSON object for each line of output instead, \
+                                      with sizes in bytes and times in seconds"),
+        getopts::optflag("", "tsv", "print tab-separated fields with a header line instead, \
+                                     with sizes in bytes, times in seconds and names escaped"),
         getopts::optflag("", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
-        Err(f) => {
-            show_error!(1, "Invalid options\n{}", f.to_err_msg());
-            return
-        }
+        Err(f) => crash!(1, "{}", f.to_err_msg())
     };
 
     if matches.opt_present("help") {
-        println!("{program} {version} - estimate file space usage
-
-Usage
-  {program} [OPTION]... [FILE]...
-  {program} [OPTION]... --files0-from=F
-
-{usage}
-
-Display  values  are  in  units  of  the  first  available  SIZE from
---block-size,  and the DU_BLOCK_SIZE, BLOCK_SIZE and BLOCKSIZE environ‐
-ment variables.  Otherwise, units default to  1024  bytes  (or  512  if
-POSIXLY_CORRECT is set).
-
-SIZE  is  an  integer and optional unit (example: 10M is 10*1024*1024).
-Units are K, M, G, T, P, E, Z, Y (powers of 1024) or KB, MB, ...  (pow‐
-ers of 1000).",
-                 program = program,
-                 version = VERSION,
-                 usage = getopts::usage("Summarize disk usage of each FILE, recursively for directories.", opts));
-        return
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [FILE]...", args.get(0));
+        println!("  {0} [OPTION]... --files0-from=F", args.get(0));
+        println!("");
+        print(getopts::usage("Summarize disk usage of each FILE, recursively for directories.",
+                             opts.as_slice()).as_slice());
+        println!("");
+        println!("Display values are in units of the first available SIZE from --block-size,");
+        println!("and the DU_BLOCK_SIZE, BLOCK_SIZE and BLOCKSIZE environment variables.");
+        println!("Otherwise, units default to 1024 bytes (or 512 if POSIXLY_CORRECT is set).");
+        println!("");
+        println!("SIZE is an integer and optional unit (example: 10M is 10*1024*1024).  Units");
+        println!("are K, M, G, T, P, E, Z, Y (powers of 1024) or KB, MB, ... (powers of 1000).");
+        return;
     } else if matches.opt_present("version") {
-        println!("{} version: {}", program, VERSION);
-        return
+        println!("{} {}", NAME, VERSION);
+        return;
     }
 
     let summarize = matches.opt_present("summarize");
-
-    let max_depth_str = matches.opt_str("max-depth");
-    let max_depth = max_depth_str.as_ref().and_then(|s| from_str::<uint>(s.as_slice()));
-    match (max_depth_str, max_depth) {
-        (Some(ref s), _) if summarize => {
-            println!("{}: warning: summarizing conflicts with --max-depth={:s}", program, *s);
-            return
-        }
-        (Some(ref s), None) => {
-            println!("{}: invalid maximum depth '{:s}'", program, *s);
-            return
-        }
-        (Some(_), Some(_)) | (None, _) => { /* valid */ }
+    let all = matches.opt_present("all");
+    if summarize && all {
+        show_error!(1, "cannot both summarize and show all entries");
+        return;
     }
-
-    let options = Options {
-        all: matches.opt_present("all"),
-        program_name: program.to_string(),
-        max_depth: max_depth,
-        total: matches.opt_present("total"),
-        separate_dirs: matches.opt_present("S"),
-    };
-
-    let strs = if matches.free.is_empty() {vec!("./".to_string())} else {matches.free.clone()};
-
-    let options_arc = Arc::new(options);
-
-    let MB = match matches.opt_present("si") {
-        true => 1000 * 1000,
-        false => 1024 * 1024,
-    };
-    let KB = match matches.opt_present("si") {
-        true => 1000,
-        false => 1024,
-    };
-
-    let block_size = match matches.opt_str("block-size") {
-        Some(s) => {
-            let mut found_number = false;
-            let mut found_letter = false;
-            let mut numbers = vec!();
-            let mut letters = vec!();
-            for c in s.as_slice().chars() {
-                if found_letter && c.is_digit() || !found_number && !c.is_digit() {
-                    println!("{}: invalid --block-size argument '{}'", program, s);
-                    return
-                } else if c.is_digit() {
-                    found_number = true;
-                    numbers.push(c as u8);
-                } else if c.is_alphabetic() {
-                    found_letter = true;
-                    letters.push(c);
-                }
+    let max_depth = match matches.opt_str("max-depth") {
+        Some(s) => match from_str::<uint>(s.as_slice()) {
+            Some(0) => Some(0),
+            Some(_) if summarize => {
+                show_error!(1, "warning: summarizing conflicts with --max-depth={}", s);
+                return;
             }
-            let number = std::uint::parse_bytes(numbers.as_slice(), 10).unwrap();
-            let multiple = match std::str::from_chars(letters.as_slice()).as_slice() {
-                "K" => 1024, "M" => 1024 * 1024, "G" => 1024 * 1024 * 1024,
-                "T" => 1024 * 1024 * 1024 * 1024, "P" => 1024 * 1024 * 1024 * 1024 * 1024,
-                "E" => 1024 * 1024 * 1024 * 1024 * 1024 * 1024,
-                "Z" => 1024 * 1024 * 1024 * 1024 * 1024 * 1024 * 1024,
-                "Y" => 1024 * 1024 * 1024 * 1024 * 1024 * 1024 * 1024 * 1024,
-                "KB" => 1000, "MB" => 1000 * 1000, "GB" => 1000 * 1000 * 1000,
-                "TB" => 1000 * 1000 * 1000 * 1000, "PB" => 1000 * 1000 * 1000 * 1000 * 1000,
-                "EB" => 1000 * 1000 * 1000 * 1000 * 1000 * 1000,
-                "ZB" => 1000 * 1000 * 1000 * 1000 * 1000 * 1000 * 1000,
-                "YB" => 1000 * 1000 * 1000 * 1000 * 1000 * 1000 * 1000 * 1000,
-                _ => {
-                    println!("{}: invalid --block-size argument '{}'", program, s); return
-                }
-            };
-            number * multiple
+            Some(n) => Some(n),
+            None => crash!(1, "invalid maximum depth '{}'", s)
         },
-        None => 1024
+        None if summarize => Some(0),
+        None => None
     };
 
-    let convert_size = |size: u64| -> String {
-        if matches.opt_present("human-readable") || matches.opt_present("si") {
-            if size > MB {
-                format!("{:.1f}M", (size as f64) / (MB as f64))
-            } else if size > KB {
-                format!("{:.1f}K", (size as f64) / (KB as f64))
-            } else {
-                format!("{}B", size)
+    let block_size = if matches.opt_present("human-readable") {
+        Human(1024)
+    } else if matches.opt_present("si") {
+        Human(1000)
+    } else if matches.opt_present("bytes") {
+        Blocks(1, None)
+    } else if matches.opt_present("k") {
+        Blocks(1024, None)
+    } else if matches.opt_present("m") {
+        Blocks(1024 * 1024, None)
+    } else {
+        match size::block_size(matches.opt_str("block-size"), "DU_BLOCK_SIZE") {
+            Ok(Some(size)) => size,
+            Ok(None) => size::default_block_size(),
+            Err(e) => crash!(1, "{}", e)
+        }
+    };
+
+    let time = if matches.opt_present("time") {
+        match matches.opt_str("time") {
+            None => Some(fsext::mtime),
+            Some(word) => match word.as_slice() {
+                "atime" | "access" | "use" => Some(fsext::atime),
+                "ctime" | "status" => Some(fsext::ctime),
+                _ => crash!(1, "invalid argument '{}' for '--time'", word)
             }
-        } else if matches.opt_present("k") {
-            format!("{}", ((size as f64) / (KB as f64)).ceil())
-        } else if matches.opt_present("m") {
-            format!("{}", ((size as f64) / (MB as f64)).ceil())
-        } else {
-            format!("{}", ((size as f64) / (block_size as f64)).ceil())
         }
+    } else {
+        None
+    };
+    let time_format = match matches.opt_str("time-style") {
+        Some(style) => match style.as_slice() {
+            "full-iso" => "%Y-%m-%d %H:%M:%S.%f %z".to_string(),
+            "long-iso" => "%Y-%m-%d %H:%M".to_string(),
+            "iso" => "%Y-%m-%d".to_string(),
+            s if s.starts_with("+") => s.slice_from(1).to_string(),
+            _ => crash!(1, "invalid argument '{}' for 'time style'", style)
+        },
+        None => "%Y-%m-%d %H:%M".to_string()
     };
 
-    let time_format_str = match matches.opt_str("time-style") {
-        Some(s) => {
-            match s.as_slice() {
-                "full-iso" => "%Y-%m-%d %H:%M:%S.%f %z",
-                "long-iso" => "%Y-%m-%d %H:%M",
-                "iso" => "%Y-%m-%d",
-                _ => {
-                    println!("{program}: invalid argument '{}' for 'time style'
-Valid arguments are:
-- 'full-iso'
-- 'long-iso'
-- 'iso'
-Try '{program} --help' for more information.", s, program = program);
-                    return
-                }
+    let mut patterns = matches.opt_strs("exclude");
+    match matches.opt_str("exclude-from") {
+        Some(name) => match File::open(&Path::new(name.as_slice())).read_to_str() {
+            Ok(contents) => {
+                patterns.extend(contents.as_slice().lines().filter(|l| !l.is_empty())
+                                        .map(|l| l.to_string()));
             }
+            Err(e) => crash!(1, "cannot read exclude file '{}': {}", name, util::strerror(&e))
         },
-        None => "%Y-%m-%d %H:%M"
+        None => {}
+    }
+
+    let records = match (matches.opt_present("json"), matches.opt_present("tsv")) {
+        (true, true) => crash!(1, "--json and --tsv are mutually exclusive"),
+        (true, false) => Some(JsonRecords),
+        (false, true) => Some(TsvRecords),
+        (false, false) => None
     };
+    if records.is_some() && matches.opt_present("null") {
+        crash!(1, "--null cannot be combined with --json or --tsv");
+    }
 
-    let line_separator = match matches.opt_present("0") {
-        true => "\0",
-        false => "\n",
+    let options = Options {
+        all: all,
+        max_depth: max_depth,
+        total: matches.opt_present("total"),
+        separate_dirs: matches.opt_present("separate-dirs"),
+        apparent_size: matches.opt_present("apparent-size") || matches.opt_present("bytes"),
+        count_links: matches.opt_present("count-links"),
+        one_file_system: matches.opt_present("one-file-system"),
+        exclude: patterns.iter().map(|p| Pattern::new(p.as_slice())).collect(),
+        block_size: block_size,
+        time: time,
+        time_format: time_format,
+        line_end: if matches.opt_present("null") { "\0" } else { "\n" },
+        records: records,
+        jobs: os::num_cpus(),
     };
 
-    let mut grand_total = 0;
-    for path_str in strs.move_iter() {
-        let path = Path::new(path_str);
-        let stat = safe_unwrap!(fs::lstat(&path));
-        let iter = du(&path, Stat{path: path.clone(), fstat: stat}, options_arc.clone(), 0).move_iter();
-        let (_, len) = iter.size_hint();
-        let len = len.unwrap();
-        for (index, stat) in iter.enumerate() {
-            let size = match matches.opt_present("apparent-size") {
-                true => stat.fstat.unstable.nlink * stat.fstat.size,
-                // C's stat is such that each block is assume to be 512 bytes
-                // See: http://linux.die.net/man/2/stat
-                false => stat.fstat.unstable.blocks * 512,
-            };
-            if matches.opt_present("time") {
-                let time_str = {
-                    let (secs, nsecs) = {
-                        let time = match matches.opt_str("time") {
-                            Some(s) => match s.as_slice() {
-                                "accessed" => stat.fstat.accessed,
-                                "created" => stat.fstat.created,
-                                "modified" => stat.fstat.modified,
-                                _ => {
-                                    println!("{program}: invalid argument 'modified' for '--time'
-    Valid arguments are:
-      - 'accessed', 'created', 'modified'
-    Try '{program} --help' for more information.", program = program);
-                                    return
-                                }
-                            },
-                            None => stat.fstat.modified
-                        };
-                        ((time / 1000) as i64, (time % 1000 * 1000000) as i32)
-                    };
-                    let time_spec = Timespec::new(secs, nsecs);
-                    time::at(time_spec).strftime(time_format_str)
-                };
-                print!("{:<10} {:<30} {}", convert_size(size), time_str, stat.path.display());
-            } else {
-                print!("{:<10} {}", convert_size(size), stat.path.display());
+    let files = match matches.opt_str("files0-from") {
+        Some(from) => {
+            if !matches.free.is_empty() {
+                show_error!(1, "extra operand '{}'", matches.free.get(0));
+                crash!(1, "file operands cannot be combined with --files0-from");
             }
-            print!("{}", line_separator);
-            if options_arc.total && index == (len - 1) {
-                // The last element will be the total size of the the path under
-                // path_str.  We add it to the grand total.
-                grand_total += size;
+            match read_files0(from.as_slice()) {
+                Ok(files) => files,
+                Err(e) => crash!(1, "cannot read file names from '{}': {}", from,
+                                 util::strerror(&e))
             }
         }
+        None if matches.free.is_empty() => vec!(Vec::from_slice(b".")),
+        None => matches.free.iter().map(|f| Vec::from_slice(f.as_bytes())).collect()
+    };
+
+    let options = Arc::new(options);
+    let mut visitor = DuVisitor::new(options.clone(), Arc::new(HashSet::new()), 0);
+    let walker = Walker::new();
+    if options.records == Some(TsvRecords) {
+        let header = if options.time.is_some() { "size\ttime\tpath\n" } else { "size\tpath\n" };
+        visitor.write(header.as_bytes());
+    }
+    let mut grand_total = 0;
+    let mut latest = 0;
+    for (i, file) in files.iter().enumerate() {
+        if file.is_empty() {
+            // only --files0-from can give an empty name
+            show_error!(1, "{}:{}: invalid zero-length file name",
+                        matches.opt_str("files0-from").unwrap(), i + 1);
+            visitor.ok = false;
+            continue;
+        }
+        if excluded(&*options, &Path::new(file.as_slice())) {
+            continue;
+        }
+        visitor.root = Total::new(0, 0);
+        walker.walk(&Path::new(file.as_slice()), &mut visitor);
+        grand_total += visitor.root.size;
+        latest = cmp::max(latest, visitor.root.time);
     }
 
-    if options_arc.total {
-        print!("{:<10} total", convert_size(grand_total));
-        print!("{}", line_separator);
+    if options.total && options.records.is_some() {
+        let line = record(&*options, &Total::new(grand_total, latest), None);
+        visitor.write(line.as_bytes());
+    } else if options.total {
+        let line = format!("{}\ttotal{}", size::format_size(grand_total, &options.block_size),
+                           options.line_end);
+        visitor.write(line.as_bytes());
+    }
+    if !visitor.ok {
+        util::set_status(1);
     }
 }
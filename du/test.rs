@@ -0,0 +1,90 @@
+use std::io;
+use std::io::fs;
+use std::io::process::Command;
+use std::str;
+
+static PROG: &'static str = "build/du";
+
+// Makes dir/a/b, with a file in each directory.
+fn make_tree(dir: &str) {
+    let root = Path::new(dir);
+    fs::mkdir_recursive(&root.join_many(["a", "b"]), io::UserRWX).unwrap();
+    for sub in ["", "a", "a/b"].iter() {
+        io::File::create(&root.join(*sub).join("file")).write([0u8, ..4096]).unwrap();
+    }
+}
+
+// The paths du shows, in the order it shows them.
+fn paths(args: &[&'static str]) -> Vec<String> {
+    let po = Command::new(PROG).args(args).output().unwrap();
+    assert!(po.status.success());
+    str::from_utf8(po.output.as_slice()).unwrap().lines()
+        .map(|line| line.split('\t').last().unwrap().to_string()).collect()
+}
+
+#[test]
+fn test_directories_bottom_up() {
+    let dir = "tmp/du_test_all";
+    make_tree(dir);
+    assert_eq!(paths([dir]), vec!("tmp/du_test_all/a/b".to_string(),
+                                  "tmp/du_test_all/a".to_string(),
+                                  "tmp/du_test_all".to_string()));
+    fs::rmdir_recursive(&Path::new(dir)).unwrap();
+}
+
+#[test]
+fn test_max_depth() {
+    let dir = "tmp/du_test_depth";
+    make_tree(dir);
+    assert_eq!(paths(["--max-depth=1", dir]), vec!("tmp/du_test_depth/a".to_string(),
+                                                   "tmp/du_test_depth".to_string()));
+    assert_eq!(paths(["--max-depth=0", dir]), vec!("tmp/du_test_depth".to_string()));
+    fs::rmdir_recursive(&Path::new(dir)).unwrap();
+}
+
+#[test]
+fn test_exclude() {
+    let dir = "tmp/du_test_exclude";
+    make_tree(dir);
+    assert_eq!(paths(["--exclude=b", dir]), vec!("tmp/du_test_exclude/a".to_string(),
+                                                 "tmp/du_test_exclude".to_string()));
+    assert_eq!(paths(["-a", "--exclude=file", dir]),
+               vec!("tmp/du_test_exclude/a/b".to_string(),
+                    "tmp/du_test_exclude/a".to_string(),
+                    "tmp/du_test_exclude".to_string()));
+    fs::rmdir_recursive(&Path::new(dir)).unwrap();
+}
+
+#[test]
+fn test_one_file_system() {
+    // everything here is on the one file system, so nothing is left out
+    let dir = "tmp/du_test_xdev";
+    make_tree(dir);
+    assert_eq!(paths(["-x", dir]), paths([dir]));
+    fs::rmdir_recursive(&Path::new(dir)).unwrap();
+}
+
+fn apparent_size(path: &str) -> u64 {
+    fs::stat(&Path::new(path)).unwrap().size
+}
+
+#[test]
+fn test_hard_link_counted_once() {
+    // the second link is in a later entry of the argument, however the
+    // walk is split up
+    let dir = "tmp/du_test_links";
+    fs::mkdir_recursive(&Path::new(dir).join_many(["b", "sub"]), io::UserRWX).unwrap();
+    fs::mkdir(&Path::new(dir).join("a"), io::UserRWX).unwrap();
+    io::File::create(&Path::new("tmp/du_test_links/a/f")).write([1u8, ..5000]).unwrap();
+    fs::link(&Path::new("tmp/du_test_links/a/f"),
+             &Path::new("tmp/du_test_links/b/sub/g")).unwrap();
+    let po = Command::new(PROG).args(["-b", dir]).output().unwrap();
+    let out = str::from_utf8(po.output.as_slice()).unwrap().to_string();
+    let sub = apparent_size("tmp/du_test_links/b/sub");
+    let b = apparent_size("tmp/du_test_links/b") + sub;
+    let a = apparent_size("tmp/du_test_links/a") + 5000;
+    let top = apparent_size(dir) + a + b;
+    assert_eq!(out, format!("{}\ttmp/du_test_links/a\n{}\ttmp/du_test_links/b/sub\n\
+                             {}\ttmp/du_test_links/b\n{}\ttmp/du_test_links\n", a, sub, b, top));
+    fs::rmdir_recursive(&Path::new(dir)).unwrap();
+}
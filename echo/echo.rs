@@ -14,87 +14,85 @@ extern crate getopts;
 extern crate libc;
 
 use std::os;
-use std::io::{print, println};
-use std::uint;
+use std::io::println;
+use std::io::stdio::stdout_raw;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/escape.rs"]
+mod escape;
+
 static NAME: &'static str = "echo";
 static VERSION: &'static str = "1.0.0";
 
-fn print_char(c: char) {
-    print!("{}", c);
-}
-
-fn to_char(bytes: &Vec<u8>, base: uint) -> char {
-    uint::parse_bytes(bytes.as_slice(), base).unwrap() as u8 as char
+struct EchoOptions {
+    newline: bool,
+    escapes: bool,
 }
 
-fn isxdigit(c: u8) -> bool {
-    match c as char {
-        '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' |
-        '8' | '9' | 'A' | 'B' | 'C' | 'D' | 'E' | 'F' => true,
-        _ => false
-    }
-}
-
-fn isodigit(c: u8) -> bool {
-    match c as char {
-        '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' => true,
-        _ => false
-    }
-}
-
-fn convert_str(string: &str, index: uint, base: uint) -> (char, int) {
-    let (max_digits, is_legal_digit) = match base {
-        8u => (3, isodigit),
-        16u => (2, isxdigit),
-        _ => fail!(),
-    };
-
-    let mut bytes = vec!();
-    for offset in range(0, max_digits) {
-        let c = string[index + offset as uint];
-        if is_legal_digit(c) {
-            bytes.push(c as u8);
-        } else {
-            if bytes.len() > 0 {
-                return (to_char(&bytes, base), offset);
-            } else {
-                return (' ', offset);
+// GNU echo does not use getopt: an argument is an option only if it is made
+// up entirely of valid option letters, and `--` is printed like any other
+// string.  Returns the index of the first operand.
+fn parse_options(args: &[String], options: &mut EchoOptions) -> uint {
+    let mut index = 0;
+    for arg in args.iter() {
+        let arg = arg.as_slice();
+        if arg.len() < 2 || !arg.starts_with("-") ||
+           !arg.slice_from(1).chars().all(|c| c == 'n' || c == 'e' || c == 'E') {
+            break;
+        }
+        for c in arg.slice_from(1).chars() {
+            match c {
+                'n' => options.newline = false,
+                'e' => options.escapes = true,
+                'E' => options.escapes = false,
+                _ => unreachable!()
             }
         }
+        index += 1;
     }
-    return (to_char(&bytes, base), max_digits)
+    index
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("n", "", "do not output the trailing newline"),
         getopts::optflag("e", "", "enable interpretation of backslash escapes"),
         getopts::optflag("E", "", "disable interpretation of backslash escapes (default)"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    )
+}
 
-    let matches = match getopts::getopts(args.tail(), opts) {
-        Ok(m) => m,
-        Err(f) => crash!(1, "Invalid options\n{}", f.to_err_msg())
-    };
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+
+    // POSIX has echo take no options and always interpret escapes; as with
+    // GNU echo, a leading -n still counts, and brings the others with it.
+    let posix = util::posixly_correct();
+    let allow_options = !posix || args.len() > 1 && args.get(1).as_slice() == "-n";
 
-    if matches.opt_present("help") {
+    // --help and --version are only honored as the sole argument; anything
+    // else is echoed.
+    let lone = if allow_options && args.len() == 2 { args.get(1).as_slice() } else { "" };
+
+    if lone == "--help" {
         println!("echo {:s} - display a line of text", VERSION);
         println!("");
         println!("Usage:");
         println!("  {0:s} [SHORT-OPTION]... [STRING]...", program);
         println!("  {0:s} LONG-OPTION", program);
         println!("");
-        println(getopts::usage("Echo the STRING(s) to standard output.", opts).as_slice());
+        println(getopts::usage("Echo the STRING(s) to standard output.", opts.as_slice()).as_slice());
         println("If -e is in effect, the following sequences are recognized:
 
 \\\\      backslash
@@ -112,77 +110,40 @@ pub fn uumain(args: Vec<String>) {
         return;
     }
 
-    if matches.opt_present("version") {
+    if lone == "--version" {
         return println!("echo version: {:s}", VERSION);
     }
 
-    if !matches.free.is_empty() {
-        let string = matches.free.connect(" ");
-        if matches.opt_present("e") {
-            let mut prev_was_slash = false;
-            let mut iter = string.as_slice().chars().enumerate();
-            loop {
-                match iter.next() {
-                    Some((index, c)) => {
-                        if !prev_was_slash {
-                            if c != '\\' {
-                                print_char(c);
-                            } else {
-                                prev_was_slash = true;
-                            }
-                        } else {
-                            prev_was_slash = false;
-                            match c {
-                                '\\' => print_char('\\'),
-                                'a' => print_char('\x07'),
-                                'b' => print_char('\x08'),
-                                'c' => break,
-                                'e' => print_char('\x1B'),
-                                'f' => print_char('\x0C'),
-                                'n' => print_char('\n'),
-                                'r' => print_char('\r'),
-                                't' => print_char('\t'),
-                                'v' => print_char('\x0B'),
-                                'x' => {
-                                    let (c, num_char_used) = convert_str(string.as_slice(), index + 1, 16u);
-                                    if num_char_used == 0 {
-                                        print_char('\\');
-                                        print_char('x');
-                                    } else {
-                                        print_char(c);
-                                        for _ in range(0, num_char_used) {
-                                            iter.next(); // consume used characters
-                                        }
-                                    }
-                                },
-                                '0' => {
-                                    let (c, num_char_used) = convert_str(string.as_slice(), index + 1, 8u);
-                                    if num_char_used == 0 {
-                                        print_char('\\');
-                                        print_char('0');
-                                    } else {
-                                        print_char(c);
-                                        for _ in range(0, num_char_used) {
-                                            iter.next(); // consume used characters
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    print_char('\\');
-                                    print_char(c);
-                                }
-                            }
-                        }
-                    }
-                    None => break
-                }
+    let mut options = EchoOptions { newline: true, escapes: false };
+    let operands = {
+        let free = args.tail();
+        if allow_options { free.slice_from(parse_options(free, &mut options)) } else { free }
+    };
+    if posix {
+        options.escapes = true;
+    }
+
+    let mut output = Vec::new();
+    let mut keep_going = true;
+    for (i, operand) in operands.iter().enumerate() {
+        if i > 0 {
+            output.push(' ' as u8);
+        }
+        if options.escapes {
+            keep_going = escape::unescape(operand.as_bytes(), escape::OctalZeroPrefixed, &mut output);
+            if !keep_going {
+                break;
             }
         } else {
-            print(string.as_slice());
+            output.push_all(operand.as_bytes());
         }
     }
+    if keep_going && options.newline {
+        output.push('\n' as u8);
+    }
 
-    if !matches.opt_present("n") {
-        println!("")
+    match stdout_raw().write(output.as_slice()) {
+        Ok(_) => {}
+        Err(f) => crash!(1, "write error: {}", util::strerror(&f))
     }
 }
@@ -0,0 +1,47 @@
+use std::io::process::Command;
+use std::str;
+
+static PROG: &'static str = "build/echo";
+
+fn run(args: &[&'static str]) -> String {
+    let po = match Command::new(PROG).args(args).output() {
+        Ok(p) => p,
+        Err(err) => fail!("{}", err),
+    };
+    str::from_utf8(po.output.as_slice()).unwrap().to_string()
+}
+
+#[test]
+fn test_default() {
+    assert_eq!(run(["hello", "world"]).as_slice(), "hello world\n");
+}
+
+#[test]
+fn test_no_newline() {
+    assert_eq!(run(["-n", "hello"]).as_slice(), "hello");
+}
+
+#[test]
+fn test_escapes() {
+    assert_eq!(run(["-e", "a\\tb\\x41\\0101"]).as_slice(), "a\tbAA\n");
+}
+
+#[test]
+fn test_stop_output() {
+    assert_eq!(run(["-e", "one\\ctwo", "three"]).as_slice(), "one");
+}
+
+#[test]
+fn test_escapes_disabled() {
+    assert_eq!(run(["-e", "-E", "a\\nb"]).as_slice(), "a\\nb\n");
+}
+
+#[test]
+fn test_double_dash_is_echoed() {
+    assert_eq!(run(["--", "-n"]).as_slice(), "-- -n\n");
+}
+
+#[test]
+fn test_invalid_option_is_echoed() {
+    assert_eq!(run(["-nx", "a"]).as_slice(), "-nx a\n");
+}
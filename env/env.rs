@@ -12,8 +12,20 @@
 /* last synced with: env (GNU coreutils) 8.13 */
 
 #![allow(non_camel_case_types)]
+#![feature(macro_rules)]
+
+extern crate libc;
 
 use std::os;
+use std::io::stdout;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/exec.rs"]
+mod exec;
+
+static NAME: &'static str = "env";
 
 struct options {
     ignore_env: bool,
@@ -30,6 +42,8 @@ fn usage(prog: &str) {
     println!("  -i --ignore-environment\t start with an empty environment");
     println!("  -0 --null              \t end each output line with a 0 byte rather than newline");
     println!("  -u --unset NAME        \t remove variable from the environment");
+    println!("  -S --split-string S    \t process and split S into separate arguments;");
+    println!("                         \t used to pass multiple arguments on shebang lines");
     println!("  -h --help              \t display this help and exit");
     println!("  -V --version           \t output version information and exit\n");
     println!("A mere - implies -i. If no COMMAND, print the resulting environment");
@@ -42,22 +56,194 @@ fn version() {
 // print name=value env pairs on screen
 // if null is true, separate pairs with a \0, \n otherwise
 fn print_env(null: bool) {
-    let env = std::os::env();
-
-    for &(ref n, ref v) in env.iter() {
-        print!("{:s}={:s}{:c}",
-            n.as_slice(),
-            v.as_slice(),
-            if null { '\0' } else { '\n' }
-        );
+    let mut out = stdout();
+    for &(ref n, ref v) in os::env_as_bytes().iter() {
+        let result = out.write(n.as_slice())
+                        .and_then(|_| out.write(['=' as u8]))
+                        .and_then(|_| out.write(v.as_slice()))
+                        .and_then(|_| out.write([if null { 0u8 } else { '\n' as u8 }]));
+        match result {
+            Ok(()) => {}
+            Err(e) => crash!(exec::EXIT_CANCELED, "write error: {}", util::strerror(&e))
+        }
+    }
+}
+
+// A bad option is env's own failure, which 125 tells apart from the
+// command's.
+fn bad_usage(prog: &str, msg: String) -> ! {
+    show_error!(exec::EXIT_CANCELED, "{}", msg);
+    crash!(exec::EXIT_CANCELED, "Try '{:s} --help' for more information.", prog)
+}
+
+// Puts what -S split its string into where the string was, for the options
+// and the command in it to be read as if they had been given separately.
+fn splice(args: Vec<String>, at: uint, string: &str) -> Vec<String> {
+    let words = match split_string(string) {
+        Ok(words) => words,
+        Err(e) => crash!(exec::EXIT_CANCELED, "{}", e)
+    };
+    let mut spliced = Vec::from_slice(args.slice_to(at));
+    spliced.push_all(words.as_slice());
+    spliced.push_all(args.slice_from(at));
+    spliced
+}
+
+// Splits a string at whitespace the way a shell would, more or less: with
+// single and double quotes, backslash escapes, ${NAME} for a variable, and
+// # starting a comment where a word would.
+fn split_string(s: &str) -> Result<Vec<String>, String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut words = vec!();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = *chars.get(i);
+        i += 1;
+        match c {
+            ' ' | '\t' | '\n' | '\r' | '\x0b' | '\x0c' => {
+                if in_word {
+                    words.push(word.clone());
+                    word.truncate(0);
+                    in_word = false;
+                }
+            }
+            '#' if !in_word => break,
+            '\'' => {
+                in_word = true;
+                loop {
+                    if i == chars.len() {
+                        return Err("no terminating quote in -S string".to_string());
+                    }
+                    let c = *chars.get(i);
+                    i += 1;
+                    match c {
+                        '\'' => break,
+                        // only a quote or a backslash can be escaped in here
+                        '\\' if i < chars.len() && (*chars.get(i) == '\'' ||
+                                                     *chars.get(i) == '\\') => {
+                            word.push_char(*chars.get(i));
+                            i += 1;
+                        }
+                        _ => word.push_char(c)
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    if i == chars.len() {
+                        return Err("no terminating quote in -S string".to_string());
+                    }
+                    let c = *chars.get(i);
+                    i += 1;
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            if i == chars.len() {
+                                return Err("no terminating quote in -S string".to_string());
+                            }
+                            let e = *chars.get(i);
+                            i += 1;
+                            match e {
+                                '_' => word.push_char(' '),
+                                'c' => return Err("'\\c' must not appear in double-quoted -S \
+                                                   string".to_string()),
+                                _ => word.push_char(try!(escape(e)))
+                            }
+                        }
+                        '$' => i = try!(expand(&chars, i, &mut word)),
+                        _ => word.push_char(c)
+                    }
+                }
+            }
+            '\\' => {
+                if i == chars.len() {
+                    return Err("invalid backslash at end of string in -S".to_string());
+                }
+                let e = *chars.get(i);
+                i += 1;
+                match e {
+                    // \_ separates words where a space would be awkward to write
+                    '_' => {
+                        if in_word {
+                            words.push(word.clone());
+                            word.truncate(0);
+                            in_word = false;
+                        }
+                    }
+                    // and \c ends the string
+                    'c' => break,
+                    _ => {
+                        word.push_char(try!(escape(e)));
+                        in_word = true;
+                    }
+                }
+            }
+            '$' => {
+                i = try!(expand(&chars, i, &mut word));
+                in_word = true;
+            }
+            _ => {
+                word.push_char(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn escape(c: char) -> Result<char, String> {
+    match c {
+        'f' => Ok('\x0c'),
+        'n' => Ok('\n'),
+        'r' => Ok('\r'),
+        't' => Ok('\t'),
+        'v' => Ok('\x0b'),
+        '#' | '$' | '"' | '\'' | '\\' => Ok(c),
+        _ => Err(format!("invalid sequence '\\{}' in -S", c))
+    }
+}
+
+// Appends the value of the ${NAME} that starts at chars[i], just after the
+// $, returning where it ends.
+fn expand(chars: &Vec<char>, i: uint, word: &mut String) -> Result<uint, String> {
+    let rest: String = chars.slice_from(i).iter().map(|&c| c).collect();
+    let error = || Err(format!("only ${{VARNAME}} expansion is supported, error at: {}", rest));
+    if i == chars.len() || *chars.get(i) != '{' {
+        return error();
     }
+    let mut end = i + 1;
+    while end < chars.len() && (chars.get(end).is_alphanumeric() || *chars.get(end) == '_') {
+        end += 1;
+    }
+    if end == i + 1 || end == chars.len() || *chars.get(end) != '}' ||
+       chars.get(i + 1).is_digit() {
+        return error();
+    }
+    let name: String = chars.slice(i + 1, end).iter().map(|&c| c).collect();
+    match os::getenv(name.as_slice()) {
+        Some(value) => word.push_str(value.as_slice()),
+        None => {}
+    }
+    Ok(end + 1)
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    exec::catch_exit(proc() run(args))
+}
 
-pub fn uumain(args: Vec<String>) {
-    let prog = args.get(0).as_slice();
+fn run(args: Vec<String>) {
+    let mut args = args;
+    let prog = args.get(0).clone();
+    let prog = prog.as_slice();
 
     // to handle arguments the same way than GNU env, we can't use getopts
     let mut opts = box options {
@@ -68,67 +254,78 @@ pub fn uumain(args: Vec<String>) {
         program: vec!()
     };
 
-    let mut wait_cmd = false;
-    let mut iter = args.iter();
-    iter.next(); // skip program
-    let mut item = iter.next();
-
-    // the for loop doesn't work here,
-    // because we need sometines to read 2 items forward,
-    // and the iter can't be borrowed twice
-    while item != None {
-        let opt = item.unwrap();
-
-        if wait_cmd {
-            // we still accept NAME=VAL here but not other options
-            let mut sp = opt.as_slice().splitn('=', 1);
-            let name = sp.next();
-            let value = sp.next();
-
-            match (name, value) {
-                (Some(n), Some(v)) => {
-                    opts.sets.push((n.into_string(), v.into_string()));
+    let mut i = 1;
+    let mut options_done = false;
+    while i < args.len() {
+        let opt = args.get(i).clone();
+        let opt = opt.as_slice();
+        i += 1;
+
+        if options_done || !opt.starts_with("-") {
+            // NAME=VALUE, until the first word that is not one
+            match opt.find('=') {
+                Some(eq) if eq > 0 => {
+                    opts.sets.push((opt.slice_to(eq).to_string(),
+                                    opt.slice_from(eq + 1).to_string()));
+                    options_done = true;
+                    continue;
                 }
                 _ => {
-                    // read the program now
-                    opts.program.push(opt.to_string());
+                    i -= 1;
                     break;
                 }
             }
-        } else if opt.as_slice().starts_with("--") {
-            match opt.as_slice() {
+        }
+
+        if opt == "-" {
+            // implies -i and stop parsing opts
+            opts.ignore_env = true;
+            options_done = true;
+        } else if opt == "--" {
+            options_done = true;
+        } else if opt.starts_with("--") {
+            let (name, value) = match opt.find('=') {
+                Some(eq) => (opt.slice_to(eq), Some(opt.slice_from(eq + 1).to_string())),
+                None => (opt, None)
+            };
+            match name {
                 "--help" => { usage(prog); return }
                 "--version" => { version(); return }
-
                 "--ignore-environment" => opts.ignore_env = true,
                 "--null" => opts.null = true,
                 "--unset" => {
-                    let var = iter.next();
-
-                    match var {
-                        None => println!("{:s}: this option requires an argument: {:s}", prog, opt.as_slice()),
-                        Some(s) => opts.unsets.push(s.to_string())
-                    }
+                    let var = match value {
+                        Some(var) => var,
+                        None if i < args.len() => {
+                            i += 1;
+                            args.get(i - 1).clone()
+                        }
+                        None => bad_usage(prog, format!("option '{}' requires an argument",
+                                                        name))
+                    };
+                    opts.unsets.push(var);
                 }
-
-                _ => {
-                    println!("{:s}: invalid option \"{:s}\"", prog, *opt);
-                    println!("Type \"{:s} --help\" for detailed informations", prog);
-                    return
+                "--split-string" => {
+                    let string = match value {
+                        Some(string) => string,
+                        None if i < args.len() => {
+                            i += 1;
+                            args.get(i - 1).clone()
+                        }
+                        None => bad_usage(prog, format!("option '{}' requires an argument",
+                                                        name))
+                    };
+                    args = splice(args, i, string.as_slice());
                 }
+                _ => bad_usage(prog, format!("unrecognized option '{}'", opt))
             }
-        } else if opt.as_slice().starts_with("-") {
-            if opt.len() == 0 {
-                // implies -i and stop parsing opts
-                wait_cmd = true;
-                opts.ignore_env = true;
-                continue;
-            }
-
-            let mut chars = opt.as_slice().chars();
-            chars.next();
-
-            for c in chars {
+        } else {
+            let mut chars = opt.slice_from(1).char_indices();
+            loop {
+                let (pos, c) = match chars.next() {
+                    Some(next) => next,
+                    None => break
+                };
                 // short versions of options
                 match c {
                     'h' => { usage(prog); return }
@@ -136,76 +333,66 @@ pub fn uumain(args: Vec<String>) {
                     'i' => opts.ignore_env = true,
                     '0' => opts.null = true,
                     'u' => {
-                        let var = iter.next();
-
-                        match var {
-                            None => println!("{:s}: this option requires an argument: {:s}", prog, opt.as_slice()),
-                            Some(s) => opts.unsets.push(s.to_string())
-                        }
+                        // the name may follow at once, as in -uHOME
+                        let rest = opt.slice_from(1 + pos + 1);
+                        let var = if !rest.is_empty() {
+                            rest.to_string()
+                        } else if i < args.len() {
+                            i += 1;
+                            args.get(i - 1).clone()
+                        } else {
+                            bad_usage(prog, "option requires an argument -- 'u'".to_string())
+                        };
+                        opts.unsets.push(var);
+                        break;
                     }
-                    _ => {
-                        println!("{:s}: illegal option -- {:c}", prog, c);
-                        println!("Type \"{:s} --help\" for detailed informations", prog);
-                        return
+                    'S' => {
+                        let rest = opt.slice_from(1 + pos + 1);
+                        let string = if !rest.is_empty() {
+                            rest.to_string()
+                        } else if i < args.len() {
+                            i += 1;
+                            args.get(i - 1).clone()
+                        } else {
+                            bad_usage(prog, "option requires an argument -- 'S'".to_string())
+                        };
+                        args = splice(args, i, string.as_slice());
+                        break;
                     }
-                }
-            }
-        } else {
-            // is it a NAME=VALUE like opt ?
-            let mut sp = opt.as_slice().splitn('=', 1);
-            let name = sp.next();
-            let value = sp.next();
-
-            match (name, value) {
-                (Some(n), Some(v)) => {
-                    // yes
-                    opts.sets.push((n.into_string(), v.into_string()));
-                    wait_cmd = true;
-                }
-                // no, its a program-like opt
-                _ => {
-                    opts.program.push(opt.to_string());
-                    break;
+                    _ => bad_usage(prog, format!("invalid option -- '{}'", c))
                 }
             }
         }
-
-        item = iter.next();
     }
 
     // read program arguments
-    for opt in iter {
+    for opt in args.slice_from(i).iter() {
         opts.program.push(opt.to_string());
     }
 
-    let env = std::os::env();
+    if opts.null && opts.program.len() > 0 {
+        bad_usage(prog, "cannot specify --null (-0) with command".to_string());
+    }
 
     if opts.ignore_env {
-        for &(ref name, _) in env.iter() {
-            std::os::unsetenv(name.as_slice())
+        for &(ref name, _) in os::env().iter() {
+            os::unsetenv(name.as_slice())
         }
     }
 
-    for ref name in opts.unsets.iter() {
-        std::os::unsetenv(name.as_slice())
+    for name in opts.unsets.iter() {
+        if name.is_empty() || name.as_slice().contains_char('=') {
+            crash!(exec::EXIT_CANCELED, "cannot unset '{}': Invalid argument", name);
+        }
+        os::unsetenv(name.as_slice())
     }
 
     for &(ref name, ref val) in opts.sets.iter() {
-        std::os::setenv(name.as_slice(), val.as_slice())
+        os::setenv(name.as_slice(), val.as_slice())
     }
 
     if opts.program.len() >= 1 {
-        use std::io::process::{Command, InheritFd};
-        let prog = opts.program.get(0).clone();
-        let args = opts.program.slice_from(1);
-        match Command::new(prog).args(args).stdin(InheritFd(0)).stdout(InheritFd(1)).stderr(InheritFd(2)).status() {
-            Ok(exit) =>
-                std::os::set_exit_status(match exit {
-                    std::io::process::ExitStatus(s) => s,
-                    _ => 1
-                }),
-            Err(_) => std::os::set_exit_status(1)
-        }
+        exec::exec_or_exit(opts.program.as_slice());
     } else {
         // no program provided
         print_env(opts.null);
@@ -0,0 +1,460 @@
+#![crate_id(name="expr", vers="1.0.0", author="agent")]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+#![feature(macro_rules)]
+
+extern crate libc;
+extern crate regex;
+
+use std::os;
+use std::io::stdio::stdout_raw;
+use regex::Regex;
+
+#[path = "../common/util.rs"]
+mod util;
+
+static NAME: &'static str = "expr";
+static VERSION: &'static str = "1.0.0";
+
+// The result is neither null nor 0
+static EXIT_TRUE: int = 0;
+// The result is null or 0
+static EXIT_FALSE: int = 1;
+// The expression is syntactically invalid
+static EXIT_INVALID: int = 2;
+// Some other error occurred
+static EXIT_FAILURE: int = 3;
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+
+    if args.len() == 2 {
+        match args.get(1).as_slice() {
+            "--help" => { help(program.as_slice()); return }
+            "--version" => { println!("{} {}", NAME, VERSION); return }
+            _ => {}
+        }
+    }
+
+    let tokens = if args.len() > 1 && args.get(1).as_slice() == "--" {
+        args.slice_from(2)
+    } else {
+        args.tail()
+    };
+    if tokens.is_empty() {
+        show_error!(EXIT_INVALID, "missing operand");
+        show_error!(EXIT_INVALID, "for help, try '{0:s} --help'", program);
+        return;
+    }
+
+    let mut parser = Parser { tokens: tokens, pos: 0 };
+    let value = match parser.parse() {
+        Ok(v) => v,
+        Err(msg) => crash!(EXIT_INVALID, "{}", msg)
+    };
+
+    let mut out = stdout_raw();
+    match writeln!(&mut out, "{}", value.to_str()) {
+        Ok(_) => {}
+        Err(f) => crash!(EXIT_FAILURE, "write error: {}", util::strerror(&f))
+    }
+    util::set_status(if value.is_null() { EXIT_FALSE } else { EXIT_TRUE });
+}
+
+fn help(program: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0:s} EXPRESSION", program);
+    println!("  {0:s} OPTION", program);
+    println!("");
+    println!("Print the value of EXPRESSION to standard output.  A blank line below
+separates increasing precedence groups.  EXPRESSION may be:
+
+  ARG1 | ARG2       ARG1 if it is neither null nor 0, otherwise ARG2
+
+  ARG1 & ARG2       ARG1 if neither argument is null or 0, otherwise 0
+
+  ARG1 < ARG2       ARG1 is less than ARG2
+  ARG1 <= ARG2      ARG1 is less than or equal to ARG2
+  ARG1 = ARG2       ARG1 is equal to ARG2
+  ARG1 != ARG2      ARG1 is unequal to ARG2
+  ARG1 >= ARG2      ARG1 is greater than or equal to ARG2
+  ARG1 > ARG2       ARG1 is greater than ARG2
+
+  ARG1 + ARG2       arithmetic sum of ARG1 and ARG2
+  ARG1 - ARG2       arithmetic difference of ARG1 and ARG2
+
+  ARG1 * ARG2       arithmetic product of ARG1 and ARG2
+  ARG1 / ARG2       arithmetic quotient of ARG1 divided by ARG2
+  ARG1 % ARG2       arithmetic remainder of ARG1 divided by ARG2
+
+  STRING : REGEXP   anchored pattern match of REGEXP in STRING
+
+  match STRING REGEXP        same as STRING : REGEXP
+  substr STRING POS LENGTH   substring of STRING, POS counted from 1
+  index STRING CHARS         index in STRING where any CHARS is found, or 0
+  length STRING              length of STRING
+  + TOKEN                    interpret TOKEN as a string, even if it is a
+                               keyword like 'match' or an operator like '/'
+
+  ( EXPRESSION )             value of EXPRESSION
+
+Beware that many operators need to be escaped or quoted for shells.
+Comparisons are arithmetic if both ARGs are numbers, else lexicographical.
+Pattern matches return the string matched between \\( and \\) or null; if
+\\( and \\) are not used, they return the number of characters matched or 0.
+
+Exit status is 0 if EXPRESSION is neither null nor 0, 1 if EXPRESSION is null
+or 0, 2 if EXPRESSION is syntactically invalid, and 3 if an error occurred.");
+}
+
+#[deriving(Clone)]
+enum Value {
+    Str(String),
+    Int(i64),
+}
+
+impl Value {
+    fn to_str(&self) -> String {
+        match *self {
+            Str(ref s) => s.clone(),
+            Int(i) => i.to_str()
+        }
+    }
+
+    fn to_int(&self) -> Option<i64> {
+        match *self {
+            Int(i) => Some(i),
+            Str(ref s) => parse_int(s.as_slice())
+        }
+    }
+
+    fn is_null(&self) -> bool {
+        match *self {
+            Int(i) => i == 0,
+            Str(ref s) => s.is_empty() || parse_int(s.as_slice()) == Some(0)
+        }
+    }
+}
+
+// Integers are an optional minus sign followed by decimal digits only.
+fn parse_int(s: &str) -> Option<i64> {
+    let digits = if s.starts_with("-") { s.slice_from(1) } else { s };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit()) {
+        return None;
+    }
+    from_str::<i64>(s)
+}
+
+fn bool_value(b: bool) -> Value {
+    Int(if b { 1 } else { 0 })
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: uint,
+}
+
+type ExprResult = Result<Value, String>;
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        if self.pos < self.tokens.len() {
+            Some(self.tokens[self.pos].as_slice())
+        } else {
+            None
+        }
+    }
+
+    fn next(&mut self) -> Result<&'a str, String> {
+        match self.peek() {
+            Some(s) => { self.pos += 1; Ok(s) }
+            None => Err(format!("syntax error: missing argument after '{}'",
+                                self.tokens[self.pos - 1]))
+        }
+    }
+
+    fn accept(&mut self, ops: &[&str]) -> Option<&'a str> {
+        match self.peek() {
+            Some(s) if ops.contains(&s) => { self.pos += 1; Some(s) }
+            _ => None
+        }
+    }
+
+    fn parse(&mut self) -> ExprResult {
+        let value = try!(self.or(true));
+        match self.peek() {
+            Some(s) => Err(format!("syntax error: unexpected argument '{}'", s)),
+            None => Ok(value)
+        }
+    }
+
+    // `evaluate` is false in the unused branch of | and &, where errors
+    // such as division by zero must not be reported.
+    fn or(&mut self, evaluate: bool) -> ExprResult {
+        let mut lhs = try!(self.and(evaluate));
+        while self.accept(["|"]).is_some() {
+            let rhs_needed = evaluate && lhs.is_null();
+            let rhs = try!(self.and(rhs_needed));
+            if rhs_needed {
+                lhs = if rhs.is_null() { Int(0) } else { rhs };
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self, evaluate: bool) -> ExprResult {
+        let mut lhs = try!(self.comparison(evaluate));
+        while self.accept(["&"]).is_some() {
+            let rhs_needed = evaluate && !lhs.is_null();
+            let rhs = try!(self.comparison(rhs_needed));
+            if evaluate && (!rhs_needed || rhs.is_null()) {
+                lhs = Int(0);
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn comparison(&mut self, evaluate: bool) -> ExprResult {
+        let mut lhs = try!(self.additive(evaluate));
+        loop {
+            let op = match self.accept(["<", "<=", "=", "==", "!=", ">=", ">"]) {
+                Some(op) => op,
+                None => return Ok(lhs)
+            };
+            let rhs = try!(self.additive(evaluate));
+            if !evaluate {
+                continue;
+            }
+            let ordering = match (lhs.to_int(), rhs.to_int()) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                _ => lhs.to_str().cmp(&rhs.to_str())
+            };
+            lhs = bool_value(match op {
+                "<" => ordering == Less,
+                "<=" => ordering != Greater,
+                "=" | "==" => ordering == Equal,
+                "!=" => ordering != Equal,
+                ">=" => ordering != Less,
+                ">" => ordering == Greater,
+                _ => unreachable!()
+            });
+        }
+    }
+
+    fn additive(&mut self, evaluate: bool) -> ExprResult {
+        let mut lhs = try!(self.multiplicative(evaluate));
+        loop {
+            let op = match self.accept(["+", "-"]) {
+                Some(op) => op,
+                None => return Ok(lhs)
+            };
+            let rhs = try!(self.multiplicative(evaluate));
+            if evaluate {
+                let (a, b) = try!(int_operands(&lhs, &rhs));
+                let result = if op == "+" { a.checked_add(&b) } else { a.checked_sub(&b) };
+                lhs = match result {
+                    Some(r) => Int(r),
+                    None => return Err(format!("result out of range"))
+                };
+            }
+        }
+    }
+
+    fn multiplicative(&mut self, evaluate: bool) -> ExprResult {
+        let mut lhs = try!(self.matching(evaluate));
+        loop {
+            let op = match self.accept(["*", "/", "%"]) {
+                Some(op) => op,
+                None => return Ok(lhs)
+            };
+            let rhs = try!(self.matching(evaluate));
+            if evaluate {
+                let (a, b) = try!(int_operands(&lhs, &rhs));
+                if op != "*" && b == 0 {
+                    return Err(format!("division by zero"));
+                }
+                let result = match op {
+                    "*" => a.checked_mul(&b),
+                    "/" => a.checked_div(&b),
+                    _ => if a == std::i64::MIN && b == -1 { Some(0) } else { Some(a % b) }
+                };
+                lhs = match result {
+                    Some(r) => Int(r),
+                    None => return Err(format!("result out of range"))
+                };
+            }
+        }
+    }
+
+    fn matching(&mut self, evaluate: bool) -> ExprResult {
+        let mut lhs = try!(self.primary(evaluate));
+        while self.accept([":"]).is_some() {
+            let rhs = try!(self.primary(evaluate));
+            if evaluate {
+                lhs = try!(match_regex(lhs.to_str().as_slice(), rhs.to_str().as_slice()));
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn primary(&mut self, evaluate: bool) -> ExprResult {
+        let token = try!(self.next());
+        // keywords are only recognized when there is something after them
+        if self.peek().is_some() {
+            match token {
+                "+" => return Ok(Str(try!(self.next()).to_string())),
+                "(" => {
+                    let value = try!(self.or(evaluate));
+                    return match self.next() {
+                        Ok(")") => Ok(value),
+                        _ => Err(format!("syntax error: expecting ')'"))
+                    };
+                }
+                "match" => {
+                    let string = try!(self.primary(evaluate));
+                    let pattern = try!(self.primary(evaluate));
+                    if !evaluate { return Ok(Int(0)); }
+                    return match_regex(string.to_str().as_slice(), pattern.to_str().as_slice());
+                }
+                "substr" => {
+                    let string = try!(self.primary(evaluate));
+                    let pos = try!(self.primary(evaluate));
+                    let len = try!(self.primary(evaluate));
+                    if !evaluate { return Ok(Int(0)); }
+                    return Ok(Str(substr(string.to_str().as_slice(), pos.to_int(), len.to_int())));
+                }
+                "index" => {
+                    let string = try!(self.primary(evaluate));
+                    let chars = try!(self.primary(evaluate));
+                    if !evaluate { return Ok(Int(0)); }
+                    let chars = chars.to_str();
+                    let string = string.to_str();
+                    let found = string.as_slice().chars().position(|c| chars.as_slice().contains_char(c));
+                    return Ok(Int(found.map_or(0, |i| i as i64 + 1)));
+                }
+                "length" => {
+                    let string = try!(self.primary(evaluate));
+                    return Ok(Int(string.to_str().as_slice().char_len() as i64));
+                }
+                _ => {}
+            }
+        } else if token == "(" {
+            return Err(format!("syntax error: expecting ')' after '('"));
+        }
+        Ok(Str(token.to_string()))
+    }
+}
+
+fn int_operands(lhs: &Value, rhs: &Value) -> Result<(i64, i64), String> {
+    match (lhs.to_int(), rhs.to_int()) {
+        (Some(a), Some(b)) => Ok((a, b)),
+        _ => Err(format!("non-integer argument"))
+    }
+}
+
+fn substr(string: &str, pos: Option<i64>, len: Option<i64>) -> String {
+    match (pos, len) {
+        (Some(pos), Some(len)) if pos >= 1 && len >= 1 => {
+            string.chars().skip((pos - 1) as uint).take(len as uint).collect()
+        }
+        _ => String::new()
+    }
+}
+
+/*
+ * Converts a POSIX basic regular expression into the syntax of the regex
+ * crate.  In a BRE the grouping and interval characters must be escaped to
+ * be special, while their unescaped forms (and +, ?, |) are literals.
+ */
+fn bre_to_regex(pattern: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    // position at which a `*` is literal: pattern start or after `\(`
+    let mut star_literal_at = 0;
+    while i < chars.len() {
+        let c = *chars.get(i);
+        match c {
+            '\\' if i + 1 < chars.len() => {
+                let n = *chars.get(i + 1);
+                match n {
+                    '(' => { out.push_char('('); star_literal_at = i + 2; }
+                    ')' | '{' | '}' | '+' | '?' | '|' => out.push_char(n),
+                    '1' .. '9' => { out.push_char('\\'); out.push_char(n); }
+                    _ => out.push_str(regex::quote(n.to_str().as_slice()).as_slice())
+                }
+                i += 2;
+                continue;
+            }
+            '*' if i == star_literal_at => out.push_str("\\*"),
+            '(' | ')' | '{' | '}' | '+' | '?' | '|' | '\\' => {
+                out.push_char('\\');
+                out.push_char(c);
+            }
+            '[' => {
+                // copy the bracket expression through; a `]` right after the
+                // opening (or after `^`) is a literal member
+                let start = i;
+                i += 1;
+                if i < chars.len() && *chars.get(i) == '^' { i += 1; }
+                if i < chars.len() && *chars.get(i) == ']' { i += 1; }
+                while i < chars.len() && *chars.get(i) != ']' {
+                    if *chars.get(i) == '[' && i + 1 < chars.len() &&
+                       (*chars.get(i + 1) == ':' || *chars.get(i + 1) == '=' || *chars.get(i + 1) == '.') {
+                        let close = *chars.get(i + 1);
+                        i += 2;
+                        while i + 1 < chars.len() && !(*chars.get(i) == close && *chars.get(i + 1) == ']') {
+                            i += 1;
+                        }
+                        i += 2;
+                    } else {
+                        i += 1;
+                    }
+                }
+                for j in range(start, std::cmp::min(i + 1, chars.len())) {
+                    let m = *chars.get(j);
+                    if m == '\\' { out.push_char('\\'); }
+                    out.push_char(m);
+                }
+            }
+            _ => out.push_char(c)
+        }
+        i += 1;
+    }
+    out
+}
+
+fn match_regex(string: &str, pattern: &str) -> ExprResult {
+    let re = match Regex::new(format!("^(?:{})", bre_to_regex(pattern)).as_slice()) {
+        Ok(re) => re,
+        Err(e) => return Err(format!("invalid regular expression: {}", e))
+    };
+    let has_group = re.captures_len() > 1;
+    Ok(match re.captures(string) {
+        Some(caps) => {
+            if has_group {
+                Str(caps.at(1).to_string())
+            } else {
+                Int(caps.at(0).char_len() as i64)
+            }
+        }
+        None => if has_group { Str(String::new()) } else { Int(0) }
+    })
+}
@@ -0,0 +1,59 @@
+use std::io::process::{Command, ExitStatus};
+use std::str;
+
+static PROG: &'static str = "build/expr";
+
+fn run(args: &[&'static str]) -> (String, int) {
+    let po = match Command::new(PROG).args(args).output() {
+        Ok(p) => p,
+        Err(err) => fail!("{}", err),
+    };
+    let code = match po.status {
+        ExitStatus(c) => c,
+        _ => -1
+    };
+    (str::from_utf8(po.output.as_slice()).unwrap().to_string(), code)
+}
+
+#[test]
+fn test_arithmetic() {
+    assert_eq!(run(["1", "+", "2", "*", "3"]), ("7\n".to_string(), 0));
+    assert_eq!(run(["7", "%", "3"]), ("1\n".to_string(), 0));
+}
+
+#[test]
+fn test_zero_result_is_false() {
+    assert_eq!(run(["2", "-", "2"]), ("0\n".to_string(), 1));
+}
+
+#[test]
+fn test_comparison() {
+    assert_eq!(run(["10", "<", "9"]), ("0\n".to_string(), 1));
+    assert_eq!(run(["abc", "<", "abd"]), ("1\n".to_string(), 0));
+}
+
+#[test]
+fn test_logic_short_circuit() {
+    assert_eq!(run(["1", "|", "1", "/", "0"]), ("1\n".to_string(), 0));
+    assert_eq!(run(["", "&", "x"]), ("0\n".to_string(), 1));
+}
+
+#[test]
+fn test_match() {
+    assert_eq!(run(["abcabc", ":", "a.c"]), ("3\n".to_string(), 0));
+    assert_eq!(run(["foo.txt", ":", "\\(.*\\)\\.txt"]), ("foo\n".to_string(), 0));
+    assert_eq!(run(["match", "xyz", "y"]), ("0\n".to_string(), 1));
+}
+
+#[test]
+fn test_string_functions() {
+    assert_eq!(run(["substr", "hello", "2", "3"]), ("ell\n".to_string(), 0));
+    assert_eq!(run(["index", "hello", "lo"]), ("3\n".to_string(), 0));
+    assert_eq!(run(["length", "hello"]), ("5\n".to_string(), 0));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(run(["1", "/", "0"]).val1(), 2);
+    assert_eq!(run(["a", "+", "1"]).val1(), 2);
+}
@@ -0,0 +1,276 @@
+#![crate_id(name="factor", vers="1.0.0", author="agent")]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+#![feature(macro_rules)]
+
+extern crate getopts;
+extern crate libc;
+
+use std::cmp;
+use std::os;
+use std::io::{print, stdin, BufferedReader, BufferedWriter};
+use std::io::stdio::stdout_raw;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+static NAME: &'static str = "factor";
+static VERSION: &'static str = "1.0.0";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "Invalid options\n{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0:s} [NUMBER]...", program);
+        println!("  {0:s} OPTION", program);
+        println!("");
+        print(getopts::usage("Print the prime factors of each specified integer NUMBER.  If none
+are specified on the command line, read them from standard input.", opts.as_slice()).as_slice());
+        return;
+    }
+    if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let mut out = BufferedWriter::new(stdout_raw());
+    if matches.free.is_empty() {
+        for line in BufferedReader::new(stdin()).lines() {
+            let line = safe_unwrap!(line);
+            for word in line.as_slice().words() {
+                print_factors_str(word, &mut out);
+            }
+        }
+    } else {
+        for number in matches.free.iter() {
+            print_factors_str(number.as_slice(), &mut out);
+        }
+    }
+    safe_unwrap!(out.flush());
+}
+
+fn print_factors_str<W: Writer>(s: &str, out: &mut W) {
+    match from_str::<u64>(s.trim()) {
+        Some(n) => {
+            safe_write!(out, "{}:", n);
+            for f in factor(n).iter() {
+                safe_write!(out, " {}", f);
+            }
+            safe_write!(out, "\n");
+        }
+        None => {
+            safe_unwrap!(out.flush());
+            show_error!(1, "'{}' is not a valid positive integer", s);
+        }
+    }
+}
+
+// Returns the prime factors of n in ascending order.
+pub fn factor(mut n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    if n < 2 {
+        return factors;
+    }
+
+    // Trial division takes care of the small factors cheaply; only what is
+    // left needs the probabilistic machinery.
+    for &p in SMALL_PRIMES.iter() {
+        if p * p > n {
+            break;
+        }
+        while n % p == 0 {
+            factors.push(p);
+            n /= p;
+        }
+    }
+    if n > 1 {
+        let mut stack = vec!(n);
+        while !stack.is_empty() {
+            let m = stack.pop().unwrap();
+            if m == 1 {
+                continue;
+            }
+            if is_prime(m) {
+                factors.push(m);
+            } else {
+                let d = pollard_rho(m);
+                stack.push(d);
+                stack.push(m / d);
+            }
+        }
+    }
+    factors.sort();
+    factors
+}
+
+static SMALL_PRIMES: [u64, ..25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67,
+    71, 73, 79, 83, 89, 97
+];
+
+// (a + b) mod m without overflowing, for a, b < m
+fn add_mod(a: u64, b: u64, m: u64) -> u64 {
+    if a >= m - b { a - (m - b) } else { a + b }
+}
+
+// (a * b) mod m without overflowing 64 bits
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    if a < (1 << 32) && b < (1 << 32) {
+        return (a * b) % m;
+    }
+    let mut result = 0;
+    let mut a = a % m;
+    let mut b = b;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    result
+}
+
+fn pow_mod(base: u64, exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    let mut base = base % m;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+// Deterministic Miller-Rabin: these bases are sufficient for every n < 2^64.
+fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for &p in SMALL_PRIMES.slice_to(12).iter() {
+        if n % p == 0 {
+            return n == p;
+        }
+    }
+    let mut d = n - 1;
+    let mut s = 0;
+    while d & 1 == 0 {
+        d >>= 1;
+        s += 1;
+    }
+    'witness: for &a in SMALL_PRIMES.slice_to(12).iter() {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in range(1, s) {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+// Finds a non-trivial divisor of the composite n using Brent's variant of
+// Pollard's rho.
+fn pollard_rho(n: u64) -> u64 {
+    if n % 2 == 0 {
+        return 2;
+    }
+    let mut c = 1;
+    loop {
+        let g = brent(n, c);
+        if g != n {
+            return g;
+        }
+        // this polynomial cycled without exposing a factor; try another
+        c += 1;
+    }
+}
+
+fn brent(n: u64, c: u64) -> u64 {
+    let f = |x: u64| add_mod(mul_mod(x, x, n), c, n);
+    let diff = |a: u64, b: u64| if a > b { a - b } else { b - a };
+    let mut y = 2;
+    let mut r = 1u;
+    let mut q = 1;
+    let mut x = y;
+    let mut ys = y;
+    let mut g = 1;
+    while g == 1 {
+        x = y;
+        for _ in range(0, r) {
+            y = f(y);
+        }
+        let mut k = 0;
+        while k < r && g == 1 {
+            ys = y;
+            for _ in range(0, cmp::min(128, r - k)) {
+                y = f(y);
+                q = mul_mod(q, diff(x, y), n);
+            }
+            g = gcd(q, n);
+            k += 128;
+        }
+        r *= 2;
+    }
+    if g == n {
+        // the batched product hit zero; redo the last batch one step at a time
+        loop {
+            ys = f(ys);
+            g = gcd(diff(x, ys), n);
+            if g != 1 {
+                break;
+            }
+        }
+    }
+    g
+}
@@ -9,6 +9,14 @@
  * file that was distributed with this source code.
  */
 
-fn main() {
-    std::os::set_exit_status(1);
-}
+#![feature(macro_rules)]
+
+use std::os;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(_: Vec<String>) -> util::Outcome { util::outcome(1) }
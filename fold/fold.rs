@@ -23,26 +23,40 @@ use std::uint;
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/mbchar.rs"]
+mod mbchar;
+
 static NAME: &'static str = "fold";
 static VERSION: &'static str = "1.0.0";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-
-    let (args, obs_width) = handle_obsolete(args.as_slice());
-    let program = args.get(0).clone();
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("b", "bytes", "count using bytes rather than columns (meaning control characters such as newline are not treated specially)"),
         getopts::optflag("s", "spaces", "break lines at word boundaries rather than a hard cut-off"),
         getopts::optopt("w", "width", "set WIDTH as the maximum line width rather than 80", "WIDTH"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit")
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let (args, obs_width) = handle_obsolete(args.as_slice());
+    let program = args.get(0).clone();
+
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => crash!(1, "{}", f.to_err_msg())
     };
@@ -53,7 +67,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {} [OPTION]... [FILE]...", program);
         println!("");
-        print!("{}", getopts::usage("Writes each file (or standard input if no files are given) to standard output whilst breaking long lines", opts));
+        print!("{}", getopts::usage("Writes each file (or standard input if no files are given) to standard output whilst breaking long lines", opts.as_slice()));
     } else if matches.opt_present("V") {
         println!("{} v{}", NAME, VERSION);
     } else {
@@ -121,13 +135,19 @@ fn fold_file<T: io::Reader>(file: BufferedReader<T>, bytes: bool, spaces: bool,
         }
         let line = line.as_slice().slice_to(line.len() - 1);
         if bytes {
+            // a width may end in the middle of a character, so this goes by
+            // the bytes rather than the string
+            let line = line.as_bytes();
+            let mut out = io::stdout();
             let mut i = 0;
             while i < line.len() {
                 let width = if line.len() - i >= width { width } else { line.len() - i };
                 let slice = {
                     let slice = line.slice(i, i + width);
                     if spaces && i + width != line.len() {
-                        match slice.rfind(|ch: char| ch.is_whitespace()) {
+                        // only ASCII blanks: a byte such as 0xa0 may be the
+                        // middle of a character
+                        match slice.iter().rposition(|&b| b == b' ' || b == b'\t') {
                             Some(m) => slice.slice_to(m + 1),
                             None => slice
                         }
@@ -135,10 +155,12 @@ fn fold_file<T: io::Reader>(file: BufferedReader<T>, bytes: bool, spaces: bool,
                         slice
                     }
                 };
-                println!("{}", slice);
+                crash_if_err!(1, out.write(slice));
+                crash_if_err!(1, out.write_u8('\n' as u8));
                 i += slice.len();
             }
         } else {
+            let chars = line.char_len();
             let mut output = String::new();
             let mut count = 0;
             for (i, ch) in line.chars().enumerate() {
@@ -154,8 +176,7 @@ fn fold_file<T: io::Reader>(file: BufferedReader<T>, bytes: bool, spaces: bool,
                     '\x08' => {
                         if count > 0 {
                             count -= 1;
-                            let len = output.len() - 1;
-                            output.truncate(len);
+                            output.pop_char();
                         }
                         continue;
                     }
@@ -164,18 +185,29 @@ fn fold_file<T: io::Reader>(file: BufferedReader<T>, bytes: bool, spaces: bool,
                         count = 0;
                         continue;
                     }
-                    _ => count += 1
+                    _ => {
+                        // a wide character that does not fit starts the next line
+                        let columns = mbchar::width(ch);
+                        count += columns;
+                        if count > width && !output.is_empty() {
+                            println!("{}", output.as_slice());
+                            output.truncate(0);
+                            count = columns;
+                        }
+                    }
                 };
                 output.push_char(ch);
                 if count == width {
                     let (val, ncount) = {
                         let slice = output.as_slice();
                         let (out, val, ncount) =
-                            if spaces && i + 1 != line.len() {
+                            if spaces && i + 1 != chars {
                                 match slice.rfind(|ch: char| ch.is_whitespace()) {
                                     Some(m) => {
                                         let routput = slice.slice_from(m + 1).to_string();
-                                        let ncount = routput.as_slice().chars().fold(0, |out, ch: char| out + if ch == '\t' { 8 } else { 1 });
+                                        let ncount = routput.as_slice().chars().fold(0, |out, ch: char| {
+                                            out + if ch == '\t' { 8 } else { mbchar::width(ch) }
+                                        });
                                         (slice.slice_to(m + 1), routput, ncount)
                                     },
                                     None => (slice, "".to_string(), 0)
@@ -11,35 +11,69 @@
 #![feature(macro_rules)]
 
 extern crate getopts;
+extern crate libc;
 
+use std::io::print;
 use std::os;
 use getopts::{
     optflag,
-    getopts,
     usage
 };
-use c_types::{get_pw_from_args, group};
+use c_types::{getpw, group, group_line};
 
 #[path = "../common/util.rs"] mod util;
+#[path = "../common/posix.rs"] mod posix;
 #[path = "../common/c_types.rs"] mod c_types;
 
 static NAME: &'static str = "groups";
+static VERSION: &'static str = "1.0.0";
 
 #[allow(dead_code)]
-fn main () { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let options = [
-            optflag("h", "", "Help")
-        ];
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        optflag("h", "help", "display this help and exit"),
+        optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let options = opts();
 
-    let matches = match getopts(args.tail(), options) {
+    let matches = match posix::getopts(args.tail(), options.as_slice()) {
         Ok(m) => { m },
-        Err(_) => {
-            show_error!(1, "{}", usage(NAME, options));
-            return;
-        }
+        Err(f) => crash!(1, "{}", f.to_err_msg())
     };
 
-    group(get_pw_from_args(&matches.free), true);
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [USERNAME]...", args.get(0));
+        println!("");
+        print(usage("Print group memberships for each USERNAME or, if no USERNAME is \
+                     specified, for\nthe current process (which may differ if the groups \
+                     database has changed).", options.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    if matches.free.is_empty() {
+        group(None, true);
+        return;
+    }
+
+    for name in matches.free.iter() {
+        match getpw(name.as_slice()) {
+            Some(pw) => println!("{} : {}", name, group_line(Some(pw), true)),
+            None => show_error!(1, "'{}': no such user", name)
+        }
+    }
 }
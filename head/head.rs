@@ -10,22 +10,44 @@
  * Synced with: https://raw.github.com/avsm/src/master/usr.bin/head/head.c
  */
 
+#![feature(macro_rules)]
+
 extern crate getopts;
+extern crate libc;
 
 use std::os;
 use std::char;
-use std::io::{stdin};
+use std::io::{stderr, stdin, stdout, IoError, IoResult};
 use std::io::BufferedReader;
 use std::io::fs::File;
 use std::path::Path;
-use getopts::{optopt, optflag, getopts, usage};
+use getopts::{optopt, optflag, usage};
+
+#[path = "../common/util.rs"]
+mod util;
 
-static PROGRAM: &'static str = "head";
+#[path = "../common/posix.rs"]
+mod posix;
+
+static NAME: &'static str = "head";
 
 #[allow(dead_code)]
-fn main () { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    uumain_with(args, &mut stdout(), &mut stderr())
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        optopt("n", "number", "Number of lines to print", "n"),
+        optflag("h", "help", "help"),
+        optflag("V", "version", "version")
+    )
+}
 
-pub fn uumain(args: Vec<String>) {
+// uumain with the output and the diagnostics going to the given writers.
+pub fn uumain_with(args: Vec<String>, out: &mut Writer, err: &mut Writer) -> util::Outcome {
     let mut line_count = 10u;
 
     // handle obsolete -number syntax
@@ -36,25 +58,21 @@ pub fn uumain(args: Vec<String>) {
 
     let args = options;
 
-    let possible_options = [
-        optopt("n", "number", "Number of lines to print", "n"),
-        optflag("h", "help", "help"),
-        optflag("V", "version", "version")
-    ];
+    let possible_options = opts();
 
-    let given_options = match getopts(args.as_slice(), possible_options) {
+    let given_options = match posix::getopts(args.as_slice(), possible_options.as_slice()) {
         Ok (m) => { m }
         Err(_) => {
-            println!("{:s}", usage(PROGRAM, possible_options));
-            return
+            try_write!(err, writeln!(out, "{:s}", usage(NAME, possible_options.as_slice())));
+            return Ok(())
         }
     };
 
     if given_options.opt_present("h") {
-        println!("{:s}", usage(PROGRAM, possible_options));
-        return;
+        try_write!(err, writeln!(out, "{:s}", usage(NAME, possible_options.as_slice())));
+        return Ok(());
     }
-    if given_options.opt_present("V") { version(); return }
+    if given_options.opt_present("V") { try_write!(err, version(out)); return Ok(()) }
 
     match given_options.opt_str("n") {
         Some(n) => {
@@ -66,11 +84,42 @@ pub fn uumain(args: Vec<String>) {
         None => {}
     };
 
-    let files = given_options.free;
+    run(&Config::lines(line_count).files(given_options.free), out, err)
+}
+
+// What to print: the first lines of each of the files, or of standard input
+// when there are none.
+pub struct Config {
+    lines: uint,
+    files: Vec<String>,
+}
+
+impl Config {
+    pub fn lines(count: uint) -> Config {
+        Config { lines: count, files: vec!() }
+    }
+
+    pub fn files(self, files: Vec<String>) -> Config {
+        Config { files: files, ..self }
+    }
+}
+
+// Prints what the config asks for, returning the exit status.
+pub fn run(config: &Config, out: &mut Writer, err: &mut Writer) -> util::Outcome {
+    let files = &config.files;
+    let line_count = config.lines;
+    let mut status = 0;
 
     if files.is_empty() {
         let mut buffer = BufferedReader::new(stdin());
-        head(&mut buffer, line_count);
+        match head(&mut buffer, line_count, out) {
+            Ok(()) => {}
+            Err(ReadError(e)) => {
+                write_error!(err, "error reading 'standard input': {}", util::strerror(&e));
+                status = 1;
+            }
+            Err(WriteError(e)) => try_write!(err, Err(e))
+        }
     } else {
         let mut multiple = false;
         let mut firstime = true;
@@ -79,20 +128,35 @@ pub fn uumain(args: Vec<String>) {
             multiple = true;
         }
 
-
         for file in files.iter() {
             if multiple {
-                if !firstime { println!(""); }
-                println!("==> {:s} <==", file.as_slice());
+                if !firstime { try_write!(err, writeln!(out, "")); }
+                try_write!(err, writeln!(out, "==> {:s} <==", file.as_slice()));
             }
             firstime = false;
 
             let path = Path::new(file.as_slice());
-            let reader = File::open(&path).unwrap();
+            let reader = match File::open(&path) {
+                Ok(f) => f,
+                Err(e) => {
+                    write_error!(err, "cannot open '{}' for reading: {}", file, util::strerror(&e));
+                    status = 1;
+                    continue;
+                }
+            };
             let mut buffer = BufferedReader::new(reader);
-            head(&mut buffer, line_count);
+            match head(&mut buffer, line_count, out) {
+                Ok(()) => {}
+                Err(ReadError(e)) => {
+                    write_error!(err, "error reading '{}': {}", file, util::strerror(&e));
+                    status = 1;
+                }
+                Err(WriteError(e)) => try_write!(err, Err(e))
+            }
         }
     }
+    try_write!(err, out.flush());
+    util::outcome(status)
 }
 
 // It searches for an option in the form of -123123
@@ -129,10 +193,28 @@ fn obsolete (options: &[String]) -> (Vec<String>, Option<uint>) {
     (options, None)
 }
 
-fn head<T: Reader> (reader: &mut BufferedReader<T>, line_count:uint) {
-    for line in reader.lines().take(line_count) { print!("{}", line.unwrap()); }
+// Why head stopped short: a file that could not be read goes on to the next
+// one, but output that cannot be written ends head.
+enum HeadError {
+    ReadError(IoError),
+    WriteError(IoError),
+}
+
+fn head<T: Reader> (reader: &mut BufferedReader<T>, line_count:uint,
+                    out: &mut Writer) -> Result<(), HeadError> {
+    for line in reader.lines().take(line_count) {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => return Err(ReadError(e))
+        };
+        match out.write_str(line.as_slice()) {
+            Ok(()) => {}
+            Err(e) => return Err(WriteError(e))
+        }
+    }
+    Ok(())
 }
 
-fn version () {
-    println!("head version 1.0.0");
+fn version (out: &mut Writer) -> IoResult<()> {
+    writeln!(out, "head version 1.0.0")
 }
@@ -23,16 +23,19 @@ extern crate libc;
 use std::os;
 
 use getopts::{
-    getopts,
     optflag,
     usage,
 };
 
-use libc::{c_long};
-
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/utsname.rs"]
+mod utsname;
+
 static NAME:     &'static str = "hostid";
 static VERSION:  &'static str = "0.0.1";
 
@@ -44,25 +47,28 @@ pub enum Mode {
     Version,
 }
 
-//currently rust libc interface doesn't include gethostid
-extern {
-    pub fn gethostid() -> c_long;
-}
-
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         optflag("", "help", "display this help and exit"),
         optflag("", "version", "output version information and exit"),
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
+
+    let opts = opts();
 
-    let usage = usage("[options]", opts);
+    let usage = usage("[options]", opts.as_slice());
 
 
-    let matches = match getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(e) => {
             show_error!(EXIT_ERR, "{}\n{}", e.to_err_msg(),  get_help_text(NAME, usage.as_slice()));
@@ -98,16 +104,5 @@ fn help(progname: &str, usage: &str) {
 }
 
 fn hostid() {
-
-  /* POSIX says gethostid returns a "32-bit identifier" but is silent
-     whether it's sign-extended.  Turn off any sign-extension.  This
-     is a no-op unless unsigned int is wider than 32 bits.  */
-
-    let mut result:c_long;
-    unsafe {
-        result = gethostid();
-    }
-    
-    result &= 0xffffffff; 
-    println!("{:0>8x}", result);
+    println!("{:08x}", utsname::hostid());
 }
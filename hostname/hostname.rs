@@ -12,37 +12,48 @@
  * https://www.opensource.apple.com/source/shell_cmds/shell_cmds-170/hostname/hostname.c?txt
  */
 
+#![feature(macro_rules)]
+
 extern crate getopts;
 extern crate libc;
 
-use std::{os,str};
-use getopts::{optflag, getopts, usage};
+use std::os;
+use getopts::{optflag, usage};
 
-extern {
-    fn gethostname(name: *libc::c_char, namelen: libc::size_t) -> libc::c_int;
-    fn sethostname(name: *libc::c_char, namelen: libc::c_int) -> libc::c_int;
-}
+#[path = "../common/util.rs"] mod util;
+#[path = "../common/posix.rs"] mod posix;
+#[path = "../common/utsname.rs"] mod utsname;
+
+static NAME: &'static str = "hostname";
 
 #[allow(dead_code)]
-fn main () { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0);
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let options = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         optflag("f", "full", "Default option to show full name"),
-        optflag("s", "slice subdomain", "Cuts the subdomain off if any"),
+        optflag("s", "short", "Cuts the subdomain off if any"),
         optflag("h", "help", "Show help"),
         optflag("V", "version", "Show program's version")
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0);
+
+    let options = opts();
 
-    let matches = match getopts(args.tail(), options) {
+    let matches = match posix::getopts(args.tail(), options.as_slice()) {
         Ok(m) => { m }
-        _ => { help_menu(program.as_slice(), options); return; }
+        Err(f) => crash!(1, "{}", f.to_err_msg())
     };
 
     if matches.opt_present("h") {
-        help_menu(program.as_slice(), options);
+        help_menu(program.as_slice(), options.as_slice());
         return
     }
     if matches.opt_present("V") { version(); return }
@@ -62,7 +73,7 @@ pub fn uumain(args: Vec<String>) {
             println!("{:s}", hostname.as_slice());
         }
         1 => { xsethostname( matches.free.last().unwrap().as_slice() ) }
-        _ => { help_menu(program.as_slice(), options); }
+        _ => crash!(1, "extra operand '{}'", matches.free.get(1))
     };
 }
 
@@ -80,31 +91,15 @@ fn help_menu(program: &str, options: &[getopts::OptGroup]) {
 }
 
 fn xgethostname() -> String {
-    let namelen = 256u;
-    let mut name = Vec::from_elem(namelen, 0u8);
-
-    let err = unsafe {
-        gethostname (name.as_mut_ptr() as *libc::c_char,
-                                        namelen as libc::size_t)
-    };
-
-    if err != 0 {
-        fail!("Cannot determine hostname");
+    match utsname::hostname() {
+        Ok(name) => name,
+        Err(e) => crash!(1, "cannot determine hostname: {}", util::strerror(&e))
     }
-
-    let last_char = name.iter().position(|byte| *byte == 0).unwrap_or(namelen);
-
-    str::from_utf8(name.slice_to(last_char)).unwrap().to_string()
 }
 
 fn xsethostname(name: &str) {
-    let vec_name: Vec<libc::c_char> = name.bytes().map(|c| c as i8).collect();
-
-    let err = unsafe {
-        sethostname (vec_name.as_ptr(), vec_name.len() as i32)
-    };
-
-    if err != 0 {
-        println!("Cannot set hostname to {:s}", name);
+    match utsname::set_hostname(name) {
+        Ok(()) => {}
+        Err(e) => crash!(1, "cannot set name to '{}': {}", name, util::strerror(&e))
     }
 }
@@ -18,27 +18,30 @@
 extern crate getopts;
 extern crate libc;
 
+use std::io::{print, File};
 use std::os;
 use std::ptr::read;
 use libc::{
-    c_char,
-    c_int,
+    gid_t,
     uid_t,
     getgid,
     getuid
 };
-use libc::funcs::posix88::unistd::{getegid, geteuid, getgroups, getlogin};
+use libc::funcs::posix88::unistd::{getegid, geteuid, getlogin};
 use std::str::raw::from_c_str;
-use getopts::{getopts, optflag, usage};
+use getopts::{optflag, usage};
 use c_types::{
     c_passwd,
-    c_group,
-    get_pw_from_args,
+    getpw,
     getpwuid,
-    group
+    gid2grp,
+    process_groups,
+    uid2usr,
+    user_groups
 };
 
 #[path = "../common/util.rs"] mod util;
+#[path = "../common/posix.rs"] mod posix;
 #[path = "../common/c_types.rs"] mod c_types;
 
 #[cfg(not(target_os = "linux"))]
@@ -77,44 +80,55 @@ mod audit {
     }
 }
 
-extern {
-    fn getgrgid(gid: uid_t) -> *c_group;
-    fn getgrouplist(name:   *c_char,
-                    basegid: c_int,
-                    groups: *c_int,
-                    ngroups: *mut c_int) -> c_int;
-}
-
 static NAME: &'static str = "id";
+static VERSION: &'static str = "1.0.0";
 
 #[allow(dead_code)]
-fn main () { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        optflag("A", "", "display the process audit (not available on Linux)"),
+        optflag("G", "groups", "print all group IDs"),
+        optflag("g", "group", "print only the effective group ID"),
+        optflag("n", "name", "print a name instead of a number, for -ugG"),
+        optflag("P", "", "display the id as a password file entry"),
+        optflag("p", "", "make the output human-readable"),
+        optflag("r", "real", "print the real ID instead of the effective ID, with -ugG"),
+        optflag("u", "user", "print only the effective user ID"),
+        optflag("Z", "context", "print only the security context of the process"),
+        optflag("h", "help", "display this help and exit"),
+        optflag("V", "version", "output version information and exit"),
+    )
+}
 
-pub fn uumain(args: Vec<String>) {
+fn run(args: Vec<String>) {
     let args_t = args.tail();
 
-    let options = [
-        optflag("h", "", "Show help"),
-        optflag("A", "", "Display the process audit (not available on Linux)"),
-        optflag("G", "", "Display the different group IDs"),
-        optflag("g", "", "Display the effective group ID as a number"),
-        optflag("n", "", "Display the name of the user or group ID for the -G, -g and -u options"),
-        optflag("P", "", "Display the id as a password file entry"),
-        optflag("p", "", "Make the output human-readable"),
-        optflag("r", "", "Display the real ID for the -g and -u options"),
-        optflag("u", "", "Display the effective user ID as a number")
-    ];
-
-    let matches = match getopts(args_t, options) {
+    let options = opts();
+
+    let matches = match posix::getopts(args_t, options.as_slice()) {
         Ok(m) => { m },
-        Err(_) => {
-            println!("{:s}", usage(NAME, options));
-            return;
-        }
+        Err(f) => crash!(1, "{}", f.to_err_msg())
     };
 
-    if matches.opt_present("h") {
-        println!("{:s}", usage(NAME, options));
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [USER]", args.get(0));
+        println!("");
+        print(usage("Print user and group information for the specified USER,\nor (when USER \
+                     omitted) for the current user.", options.as_slice()).as_slice());
+        println!("");
+        println!("Without any OPTION, print some useful set of identified information.");
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
         return;
     }
 
@@ -123,59 +137,69 @@ pub fn uumain(args: Vec<String>) {
         return;
     }
 
-
-    let possible_pw = get_pw_from_args(&matches.free);
+    let possible_pw = match matches.free.len() {
+        0 => None,
+        1 => {
+            let name = matches.free.get(0).as_slice();
+            match getpw(name) {
+                Some(pw) => Some(pw),
+                None => crash!(1, "'{}': no such user", name)
+            }
+        }
+        _ => crash!(1, "extra operand '{}'", matches.free.get(1))
+    };
 
     let nflag = matches.opt_present("n");
     let uflag = matches.opt_present("u");
     let gflag = matches.opt_present("g");
     let rflag = matches.opt_present("r");
+    let groups_flag = matches.opt_present("G");
+    let zflag = matches.opt_present("Z");
 
-    if gflag {
-        let id = if possible_pw.is_some() {
-            possible_pw.unwrap().pw_gid
-        } else {
-            if rflag {
-                unsafe { getgid() as i32 }
-            } else {
-                unsafe { getegid() as i32 }
-            }
-        } as u32;
-        let gr = unsafe { getgrgid(id) };
-
-        if nflag && gr.is_not_null() {
-            let gr_name = unsafe { from_c_str(read(gr).gr_name) };
-            println!("{:s}", gr_name);
-        } else {
-            println!("{:u}", id);
+    let choices = [uflag, gflag, groups_flag, zflag].iter().filter(|&&f| f).count();
+    if choices > 1 {
+        crash!(1, "cannot print \"only\" of more than one choice");
+    }
+    if choices == 0 && (nflag || rflag) {
+        crash!(1, "cannot print only names or real IDs in default format");
+    }
+
+    if zflag {
+        if possible_pw.is_some() {
+            crash!(1, "cannot print security context when user specified");
+        }
+        match context() {
+            Some(context) => println!("{}", context),
+            None => crash!(1, "--context (-Z) works only on an SELinux-enabled kernel")
         }
         return;
     }
 
-    if uflag {
-        let id = if possible_pw.is_some() {
-            possible_pw.unwrap().pw_uid
-        } else if rflag {
-            unsafe { getgid() as i32 }
-        } else {
-            unsafe { getegid() as i32 }
+    if gflag {
+        let id = match possible_pw {
+            Some(pw) => pw.pw_gid as gid_t,
+            None if rflag => unsafe { getgid() },
+            None => unsafe { getegid() }
         };
+        println!("{}", group_str(id, nflag));
+        return;
+    }
 
-        let pw = unsafe { getpwuid(id) };
-        if nflag && pw.is_not_null() {
-            let pw_name = unsafe {
-                from_c_str(read(pw).pw_name)
-            };
-            println!("{:s}", pw_name);
-        } else {
-            println!("{:d}", id);
-        }
-
+    if uflag {
+        let id = match possible_pw {
+            Some(pw) => pw.pw_uid as uid_t,
+            None if rflag => unsafe { getuid() },
+            None => unsafe { geteuid() }
+        };
+        println!("{}", user_str(id, nflag));
         return;
     }
 
-    if matches.opt_present("G") {
-        group(possible_pw, nflag);
+    if groups_flag {
+        let names: Vec<String> = group_ids(possible_pw, rflag).iter()
+                                                              .map(|&g| group_str(g, nflag))
+                                                              .collect();
+        println!("{}", names.connect(" "));
         return;
     }
 
@@ -189,11 +213,69 @@ pub fn uumain(args: Vec<String>) {
         return;
     }
 
-    if possible_pw.is_some() {
-        id_print(possible_pw, true, false, false)
-    } else {
-        id_print(possible_pw, false, true, true)
+    id_print(possible_pw);
+}
+
+fn user_str(uid: uid_t, nflag: bool) -> String {
+    if !nflag {
+        return uid.to_str();
+    }
+    match uid2usr(uid) {
+        Some(name) => name,
+        None => {
+            show_error!(1, "cannot find name for user ID {}", uid);
+            uid.to_str()
+        }
+    }
+}
+
+fn group_str(gid: gid_t, nflag: bool) -> String {
+    if !nflag {
+        return gid.to_str();
+    }
+    match gid2grp(gid) {
+        Some(name) => name,
+        None => {
+            show_error!(1, "cannot find name for group ID {}", gid);
+            gid.to_str()
+        }
+    }
+}
+
+// The groups of the user, or of the process: its real group, then its
+// effective group unless -r or it is the same, then the rest, each once.
+fn group_ids(possible_pw: Option<c_passwd>, rflag: bool) -> Vec<gid_t> {
+    let (first, rest) = match possible_pw {
+        Some(ref pw) => (vec!(pw.pw_gid as gid_t), user_groups(pw)),
+        None if rflag => (vec!(unsafe { getgid() }), process_groups()),
+        None => (unsafe { vec!(getgid(), getegid()) }, process_groups())
+    };
+    let mut groups = vec!();
+    for &g in first.iter().chain(rest.iter()) {
+        if !groups.contains(&g) {
+            groups.push(g);
+        }
     }
+    groups
+}
+
+// The SELinux context of the process, when SELinux is there to give one.
+#[cfg(target_os = "linux")]
+fn context() -> Option<String> {
+    if !Path::new("/sys/fs/selinux/enforce").exists() && !Path::new("/selinux/enforce").exists() {
+        return None;
+    }
+    match File::open(&Path::new("/proc/self/attr/current")).read_to_str() {
+        Ok(context) => Some(context.as_slice().trim_right_chars(|c: char| {
+            c == '\0' || c == '\n'
+        }).to_string()),
+        Err(_) => None
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn context() -> Option<String> {
+    None
 }
 
 fn pretty(possible_pw: Option<c_passwd>) {
@@ -202,88 +284,87 @@ fn pretty(possible_pw: Option<c_passwd>) {
 
         let pw_name = unsafe { from_c_str(pw.pw_name) };
         print!("uid\t{:s}\ngroups\t", pw_name);
-        group(possible_pw, true);
+        c_types::group(possible_pw, true);
     } else {
-        let login = unsafe { from_c_str(getlogin()) };
+        let login = unsafe { getlogin() };
+        let login = if login.is_not_null() { Some(unsafe { from_c_str(login) }) } else { None };
         let rid = unsafe { getuid() };
-        let pw = unsafe { getpwuid(rid as i32) };
-
-        let is_same_user = unsafe {
-            from_c_str(read(pw).pw_name) == login
-        };
+        let name = uid2usr(rid);
 
-        if pw.is_null() || is_same_user {
-            println!("login\t{:s}", login);
+        match login {
+            Some(ref login) if name.as_ref() != Some(login) => println!("login\t{:s}", *login),
+            _ => {}
         }
 
-        if pw.is_not_null() {
-            println!(
-                "uid\t{:s}",
-                unsafe { from_c_str(read(pw).pw_name) })
-        } else {
-            println!("uid\t{:u}\n", rid);
+        match name {
+            Some(name) => println!("uid\t{:s}", name),
+            None => println!("uid\t{:u}", rid)
         }
 
-        let eid = unsafe { getegid() };
-        if eid == rid {
-            let pw = unsafe { getpwuid(eid as i32) };
-            if pw.is_not_null() {
-                println!(
-                    "euid\t{:s}",
-                    unsafe { from_c_str(read(pw).pw_name) });
-            } else {
-                println!("euid\t{:u}", eid);
+        let eid = unsafe { geteuid() };
+        if eid != rid {
+            match uid2usr(eid) {
+                Some(name) => println!("euid\t{:s}", name),
+                None => println!("euid\t{:u}", eid)
             }
         }
 
-        let rid = unsafe { getgid() };
-
-        if rid != eid {
-            let gr = unsafe { getgrgid(rid) };
-            if gr.is_not_null() {
-                println!(
-                    "rgid\t{:s}",
-                    unsafe { from_c_str(read(gr).gr_name) });
-            } else {
-                println!("rgid\t{:u}", rid);
+        let rgid = unsafe { getgid() };
+        if rgid != unsafe { getegid() } {
+            match gid2grp(rgid) {
+                Some(name) => println!("rgid\t{:s}", name),
+                None => println!("rgid\t{:u}", rgid)
             }
         }
 
         print!("groups\t");
-        group(None, true);
+        c_types::group(None, true);
     }
 }
 
 fn pline(possible_pw: Option<c_passwd>) {
     let pw = if possible_pw.is_none() {
-        unsafe { read(getpwuid(getuid() as i32)) }
+        let pw = unsafe { getpwuid(getuid() as i32) };
+        if pw.is_null() {
+            crash!(1, "cannot find name for user ID {}", unsafe { getuid() });
+        }
+        unsafe { read(pw) }
     } else {
         possible_pw.unwrap()
     };
 
-    let pw_name     = unsafe { from_c_str(pw.pw_name)  };
-    let pw_passwd   = unsafe { from_c_str(pw.pw_passwd)};
-    let pw_class    = unsafe { from_c_str(pw.pw_class) };
-    let pw_gecos    = unsafe { from_c_str(pw.pw_gecos) };
-    let pw_dir      = unsafe { from_c_str(pw.pw_dir)   };
-    let pw_shell    = unsafe { from_c_str(pw.pw_shell) };
+    print_pw_entry(&pw);
+}
 
+#[cfg(target_os = "linux")]
+fn print_pw_entry(pw: &c_passwd) {
+    println!(
+        "{:s}:{:s}:{:d}:{:d}:{:s}:{:s}:{:s}",
+        unsafe { from_c_str(pw.pw_name) },
+        unsafe { from_c_str(pw.pw_passwd) },
+        pw.pw_uid,
+        pw.pw_gid,
+        unsafe { from_c_str(pw.pw_gecos) },
+        unsafe { from_c_str(pw.pw_dir) },
+        unsafe { from_c_str(pw.pw_shell) });
+}
+
+#[cfg(not(target_os = "linux"))]
+fn print_pw_entry(pw: &c_passwd) {
     println!(
         "{:s}:{:s}:{:d}:{:d}:{:s}:{:d}:{:d}:{:s}:{:s}:{:s}",
-        pw_name,
-        pw_passwd,
+        unsafe { from_c_str(pw.pw_name) },
+        unsafe { from_c_str(pw.pw_passwd) },
         pw.pw_uid,
         pw.pw_gid,
-        pw_class,
+        unsafe { from_c_str(pw.pw_class) },
         pw.pw_change,
         pw.pw_expire,
-        pw_gecos,
-        pw_dir,
-        pw_shell);
+        unsafe { from_c_str(pw.pw_gecos) },
+        unsafe { from_c_str(pw.pw_dir) },
+        unsafe { from_c_str(pw.pw_shell) });
 }
 
-static NGROUPS: i32 = 20;
-
 #[cfg(target_os = "linux")]
 fn auditid() { }
 
@@ -292,7 +373,7 @@ fn auditid() {
     let auditinfo: audit::c_auditinfo_addr_t = unsafe { audit::uninitialized() };
     let address = &auditinfo as *audit::c_auditinfo_addr_t;
     if  unsafe { audit::getaudit(address) } < 0 {
-        println!("Couldlnt retrieve information");
+        println!("Couldn't retrieve information");
         return;
     }
 
@@ -303,94 +384,46 @@ fn auditid() {
     println!("asid={:d}", auditinfo.ai_asid);
 }
 
-fn id_print(possible_pw: Option<c_passwd>,
-            use_ggl: bool,
-            p_euid: bool,
-            p_egid: bool) {
-
-    let uid;
-    let gid;
-
-    if possible_pw.is_some() {
-        uid = possible_pw.unwrap().pw_uid;
-        gid = possible_pw.unwrap().pw_gid;
-    } else {
-        uid = unsafe { getuid() as i32 };
-        gid = unsafe { getgid() as i32 };
-    }
-
-    let mut ngroups;
-    let mut groups = Vec::with_capacity(NGROUPS as uint);
-
-    if use_ggl && possible_pw.is_some() {
-        ngroups = NGROUPS;
-        let pw_name = possible_pw.unwrap().pw_name;
-
-        unsafe { getgrouplist(pw_name, gid, groups.as_ptr(), &mut ngroups) };
-    } else {
-        ngroups = unsafe {
-            getgroups(NGROUPS, groups.as_mut_ptr() as *mut u32)
-        };
-    }
-
-    if possible_pw.is_some() {
-        print!(
-            "uid={:d}({:s})",
-            uid,
-            unsafe { from_c_str(possible_pw.unwrap().pw_name) });
-    } else {
-        print!("uid={:u}", unsafe { getuid() });
-    }
+// e.g. uid=1000(me) gid=1000(me) groups=1000(me),27(sudo), with the
+// effective ids as well when they differ and the process is being shown.
+fn id_print(possible_pw: Option<c_passwd>) {
+    let (uid, gid) = match possible_pw {
+        Some(pw) => (pw.pw_uid as uid_t, pw.pw_gid as gid_t),
+        None => unsafe { (getuid(), getgid()) }
+    };
 
-    print!(" gid={:d}", gid);
-    let gr = unsafe { getgrgid(gid as u32) };
-    if gr.is_not_null() {
-        print!(
-            "({:s})",
-            unsafe { from_c_str(read(gr).gr_name) });
-    }
+    let mut line = format!("uid={}{}", uid, paren(uid2usr(uid)));
+    line.push_str(format!(" gid={}{}", gid, paren(gid2grp(gid))).as_slice());
 
-    let euid = unsafe { geteuid() };
-    if p_euid && (euid != uid as u32) {
-        print!(" euid={:u}", euid);
-        let pw = unsafe { getpwuid(euid as i32) };
-        if pw.is_not_null() {
-            print!(
-                "({:s})",
-                unsafe { from_c_str(read(pw).pw_name) });
+    if possible_pw.is_none() {
+        let euid = unsafe { geteuid() };
+        if euid != uid {
+            line.push_str(format!(" euid={}{}", euid, paren(uid2usr(euid))).as_slice());
+        }
+        let egid = unsafe { getegid() };
+        if egid != gid {
+            line.push_str(format!(" egid={}{}", egid, paren(gid2grp(egid))).as_slice());
         }
     }
 
-    let egid = unsafe { getegid() };
-    if p_egid && (egid != gid as u32) {
-        print!(" egid={:u}", egid);
-        unsafe {
-            let grp = getgrgid(egid);
-            if grp.is_not_null() {
-                print!("({:s})", from_c_str(read(grp).gr_name));
-            }
+    let groups: Vec<String> = group_ids(possible_pw, false).iter().map(|&g| {
+        format!("{}{}", g, paren(gid2grp(g)))
+    }).collect();
+    line.push_str(format!(" groups={}", groups.connect(",")).as_slice());
+
+    if possible_pw.is_none() {
+        match context() {
+            Some(context) => line.push_str(format!(" context={}", context).as_slice()),
+            None => {}
         }
     }
 
-    unsafe { groups.set_len(ngroups as uint) };
-
-    if ngroups > 0 {
-        print!(" groups=");
-
-        let mut first = true;
-        for &gr in groups.iter() {
-            if !first { print!(",") }
-            print!("{:d}", gr);
-            let group = unsafe { getgrgid(gr as u32) };
-            if group.is_not_null() {
-                let name = unsafe {
-                    from_c_str(read(group).gr_name)
-                };
-                print!("({:s})", name);
-            }
-            first = false
-        }
+    println!("{}", line);
+}
 
-        println!("");
+fn paren(name: Option<String>) -> String {
+    match name {
+        Some(name) => format!("({})", name),
+        None => String::new()
     }
 }
@@ -0,0 +1,422 @@
+#![crate_id(name="install", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate collections;
+extern crate getopts;
+extern crate libc;
+extern crate time;
+
+use std::os;
+use std::io::{fs, FilePermission};
+use std::io::process::{Command, ExitStatus};
+
+use collections::hashmap::HashMap;
+use getopts::{
+    optflag,
+    optflagopt,
+    optopt,
+    usage,
+};
+
+use backup::BackupMode;
+use copy::{Preserve, OverwriteAlways, ReflinkAuto, SparseAuto};
+use walk::FollowAll;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/fdio.rs"]
+mod fdio;
+
+#[path = "../common/walk.rs"]
+mod walk;
+
+#[path = "../common/xattr.rs"]
+mod xattr;
+
+#[path = "../common/size.rs"]
+mod size;
+
+#[path = "../common/progress.rs"]
+mod progress;
+
+#[path = "../common/reflink.rs"]
+mod reflink;
+
+#[path = "../common/copy.rs"]
+mod copy;
+
+#[path = "../common/backup.rs"]
+mod backup;
+
+#[path = "../common/mode.rs"]
+mod mode;
+
+#[path = "../common/c_types.rs"]
+mod c_types;
+
+#[path = "../common/owner.rs"]
+mod owner;
+
+static NAME: &'static str = "install";
+static VERSION: &'static str = "1.0.0";
+
+// rwxr-xr-x, for what is installed and for the directories made on the way
+static DEFAULT_MODE: libc::mode_t = 0o755;
+
+struct Options {
+    mode: libc::mode_t,
+    uid: Option<libc::uid_t>,
+    gid: Option<libc::gid_t>,
+    preserve_timestamps: bool,
+    // the program to strip with, for -s
+    strip: Option<String>,
+    backup: BackupMode,
+    suffix: String,
+    verbose: bool,
+    copy: copy::Options,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        optflagopt("", "backup", "make a backup of each existing destination file", "CONTROL"),
+        optflag("b", "", "like --backup but does not accept an argument"),
+        optflag("c", "", "(ignored)"),
+        optflag("d", "directory", "treat all arguments as directory names; create all \
+                                   components of the specified directories"),
+        optflag("D", "", "create all leading components of DEST except the last, then copy \
+                          SOURCE to DEST"),
+        optopt("g", "group", "set group ownership, instead of process' current group", "GROUP"),
+        optopt("m", "mode", "set permission mode (as in chmod), instead of rwxr-xr-x", "MODE"),
+        optopt("o", "owner", "set ownership (super-user only)", "OWNER"),
+        optflag("p", "preserve-timestamps", "apply access/modification times of SOURCE files \
+                                             to corresponding destination files"),
+        optflag("s", "strip", "strip symbol tables"),
+        optopt("", "strip-program", "program used to strip binaries", "PROGRAM"),
+        optopt("S", "suffix", "override the usual backup suffix", "SUFFIX"),
+        optopt("t", "target-directory", "copy all SOURCE arguments into DIRECTORY", "DIRECTORY"),
+        optflag("T", "no-target-directory", "treat DEST as a normal file"),
+        optflag("v", "verbose", "print the name of each directory as it is created"),
+        optflag("h", "help", "display this help and exit"),
+        optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg()),
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [-T] SOURCE DEST", args.get(0));
+        println!("  {0} [OPTION]... SOURCE... DIRECTORY", args.get(0));
+        println!("  {0} [OPTION]... -t DIRECTORY SOURCE...", args.get(0));
+        println!("  {0} [OPTION]... -d DIRECTORY...", args.get(0));
+        println!("");
+        print!("{}", usage("Copy SOURCE to DEST or multiple SOURCE(s) to the existing \
+                            DIRECTORY, while setting permission modes and owner/group.  \
+                            In the 4th form, create all components of the given \
+                            DIRECTORY(ies).", opts.as_slice()));
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let directories = matches.opt_present("directory");
+    let mode = match matches.opt_str("mode") {
+        // relative modes start from nothing, and ignore the umask
+        Some(m) => match mode::parse(m.as_slice(), 0, 0, directories) {
+            Ok(mode) => mode,
+            Err(msg) => crash!(1, "{}", msg)
+        },
+        None => DEFAULT_MODE
+    };
+    let uid = match matches.opt_str("owner") {
+        Some(user) => match owner::parse_user(user.as_slice()) {
+            Ok(uid) => Some(uid),
+            Err(msg) => crash!(1, "{}", msg)
+        },
+        None => None
+    };
+    let gid = match matches.opt_str("group") {
+        Some(group) => match owner::parse_group(group.as_slice()) {
+            Ok(gid) => Some(gid),
+            Err(msg) => crash!(1, "{}", msg)
+        },
+        None => None
+    };
+    let backup_mode = match backup::mode(matches.opt_present("backup") || matches.opt_present("b"),
+                                         matches.opt_str("backup")) {
+        Ok(mode) => mode,
+        Err(msg) => crash!(1, "{}", msg)
+    };
+    let strip = if matches.opt_present("strip") {
+        Some(matches.opt_str("strip-program").unwrap_or("strip".to_string()))
+    } else {
+        None
+    };
+
+    let copy_options = copy::Options {
+        force: true,
+        recursive: false,
+        follow: FollowAll,
+        umask: fsext::umask(),
+        // the times go on after stripping, which would change them
+        preserve: Preserve::none(),
+        reflink: ReflinkAuto,
+        sparse: SparseAuto,
+        overwrite: OverwriteAlways,
+        update: false,
+        progress: None,
+    };
+    let options = Options {
+        mode: mode,
+        uid: uid,
+        gid: gid,
+        preserve_timestamps: matches.opt_present("preserve-timestamps"),
+        strip: strip,
+        backup: backup_mode,
+        suffix: backup::suffix(matches.opt_str("suffix")),
+        verbose: matches.opt_present("verbose"),
+        copy: copy_options,
+    };
+
+    if directories {
+        if matches.free.is_empty() {
+            crash!(1, "missing file operand");
+        }
+        for dir in matches.free.iter() {
+            let path = Path::new(dir.as_slice());
+            if make_dirs(&path, &options) {
+                set_attrs(&path, &options);
+            }
+        }
+        return;
+    }
+
+    let target_dir = matches.opt_str("target-directory");
+    let no_target_dir = matches.opt_present("no-target-directory");
+    if target_dir.is_some() && no_target_dir {
+        crash!(1, "cannot combine --target-directory (-t) and --no-target-directory (-T)");
+    }
+    let (sources, target): (Vec<Path>, Path) = match target_dir {
+        Some(dir) => {
+            if matches.free.is_empty() {
+                crash!(1, "missing file operand");
+            }
+            (matches.free.iter().map(|arg| Path::new(arg.as_slice())).collect(),
+             Path::new(dir.as_slice()))
+        }
+        None => {
+            if matches.free.len() < 1 {
+                crash!(1, "missing file operand");
+            } else if matches.free.len() < 2 {
+                crash!(1, "missing destination file operand after '{}'", matches.free.get(0));
+            } else if no_target_dir && matches.free.len() > 2 {
+                crash!(1, "extra operand '{}'", matches.free.get(2));
+            }
+            (matches.free.init().iter().map(|arg| Path::new(arg.as_slice())).collect(),
+             Path::new(matches.free.last().unwrap().as_slice()))
+        }
+    };
+
+    // -D makes what leads up to DEST, or DIRECTORY itself with -t
+    if matches.opt_present("D") {
+        let leading = if matches.opt_present("target-directory") {
+            target.clone()
+        } else {
+            target.dir_path()
+        };
+        if !make_dirs(&leading, &options) {
+            return;
+        }
+    }
+
+    let target_is_dir = !no_target_dir && is_dir(&target);
+    if (sources.len() > 1 || matches.opt_present("target-directory")) && !target_is_dir {
+        crash!(1, "target '{}' is not a directory", target.display());
+    }
+
+    for source in sources.iter() {
+        let dest = if target_is_dir {
+            match source.filename() {
+                Some(name) => target.join(name),
+                None => {
+                    show_error!(1, "cannot install '{}' into '{}'",
+                                source.display(), target.display());
+                    continue;
+                }
+            }
+        } else {
+            target.clone()
+        };
+        install(source, &dest, &options);
+    }
+}
+
+fn is_dir(path: &Path) -> bool {
+    match fsext::stat_path(path) {
+        Ok(stat) => fsext::is_dir(stat.st_mode),
+        Err(_) => false
+    }
+}
+
+// Makes path and whatever leads up to it.  The leading directories get the
+// default mode whatever -m says, as GNU install does.
+fn make_dirs(path: &Path, options: &Options) -> bool {
+    let mut missing = vec!();
+    let mut dir = path.clone();
+    while !is_dir(&dir) && dir != dir.dir_path() {
+        missing.push(dir.clone());
+        dir = dir.dir_path();
+    }
+    for dir in missing.iter().rev() {
+        match fs::mkdir(dir, FilePermission::from_bits_truncate(DEFAULT_MODE as u32)) {
+            Ok(()) => {}
+            // someone else may have just made it
+            Err(_) if is_dir(dir) => continue,
+            Err(e) => {
+                show_error!(1, "cannot create directory '{}': {}", dir.display(),
+                            util::strerror(&e));
+                return false;
+            }
+        }
+        if options.verbose {
+            println!("{}: creating directory '{}'", NAME, dir.display());
+        }
+    }
+    true
+}
+
+fn install(source: &Path, dest: &Path, options: &Options) {
+    let src_stat = match fsext::stat_path(source) {
+        Ok(stat) => stat,
+        Err(e) => {
+            show_error!(1, "cannot stat '{}': {}", source.display(), util::strerror(&e));
+            return;
+        }
+    };
+    if fsext::is_dir(src_stat.st_mode) {
+        show_error!(1, "omitting directory '{}'", source.display());
+        return;
+    }
+
+    let mut backup = None;
+    match fsext::lstat_path(dest) {
+        Ok(dest_stat) => {
+            if copy::same_file(&src_stat, &dest_stat) {
+                show_error!(1, "'{}' and '{}' are the same file",
+                            source.display(), dest.display());
+                return;
+            }
+            match backup::make_backup(dest, options.backup, options.suffix.as_slice()) {
+                Ok(path) => backup = path,
+                Err(e) => {
+                    show_error!(1, "cannot backup '{}': {}", dest.display(), util::strerror(&e));
+                    return;
+                }
+            }
+            // a fresh file, so that a program that is running keeps its copy
+            if backup.is_none() {
+                match fs::unlink(dest) {
+                    Ok(()) => {}
+                    Err(e) => {
+                        show_error!(1, "cannot remove '{}': {}", dest.display(),
+                                    util::strerror(&e));
+                        return;
+                    }
+                }
+            }
+        }
+        Err(_) => {}
+    }
+
+    let mut links = HashMap::new();
+    if !copy::copy_path(source, dest, &options.copy, &mut links) {
+        return;
+    }
+
+    match options.strip {
+        Some(ref program) => match Command::new(program.as_slice()).arg(dest).status() {
+            Ok(ExitStatus(0)) => {}
+            Ok(_) => {
+                show_error!(1, "strip process terminated abnormally");
+                return;
+            }
+            Err(e) => {
+                show_error!(1, "cannot run '{}': {}", program, util::strerror(&e));
+                return;
+            }
+        },
+        None => {}
+    }
+    if !set_attrs(dest, options) {
+        return;
+    }
+    if options.preserve_timestamps {
+        match fsext::set_times(dest, fsext::atime(&src_stat), fsext::mtime(&src_stat), true) {
+            Ok(()) => {}
+            Err(e) => show_error!(1, "cannot set time stamps for '{}': {}", dest.display(),
+                                  util::strerror(&e))
+        }
+    }
+
+    if options.verbose {
+        match backup {
+            Some(backup) => println!("'{}' -> '{}' (backup: '{}')", source.display(),
+                                     dest.display(), backup.display()),
+            None => println!("'{}' -> '{}'", source.display(), dest.display())
+        }
+    }
+}
+
+// The owner goes first, as chown(2) may clear the set-ID bits of the mode.
+fn set_attrs(path: &Path, options: &Options) -> bool {
+    if options.uid.is_some() || options.gid.is_some() {
+        match fsext::set_owner(path, options.uid.unwrap_or(fsext::UNCHANGED),
+                               options.gid.unwrap_or(fsext::UNCHANGED), true) {
+            Ok(()) => {}
+            Err(e) => {
+                show_error!(1, "cannot change ownership of '{}': {}", path.display(),
+                            util::strerror(&e));
+                return false;
+            }
+        }
+    }
+    match fsext::set_mode(path, options.mode) {
+        Ok(()) => true,
+        Err(e) => {
+            show_error!(1, "cannot change permissions of '{}': {}", path.display(),
+                        util::strerror(&e));
+            false
+        }
+    }
+}
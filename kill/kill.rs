@@ -1,6 +1,5 @@
 #![crate_id(name="kill", vers="0.0.1", author="Maciej Dziardziel")]
 #![feature(macro_rules)]
-#![feature(phase)]
 
 /*
  * This file is part of the uutils coreutils package.
@@ -12,188 +11,185 @@
  */
 
 
-extern crate getopts;
 extern crate libc;
-extern crate collections;
-extern crate serialize;
-
-#[phase(syntax, link)] extern crate log;
 
 use std::os;
-use std::from_str::from_str;
-use std::io::process::Process;
-
-use getopts::{
-    getopts,
-    optopt,
-    optflag,
-    optflagopt,
-    usage,
-};
-
-use signals::ALL_SIGNALS;
-
-mod signals;
+use libc::c_int;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/signal.rs"]
+mod signal;
+
 static NAME: &'static str = "kill";
 static VERSION:  &'static str = "0.0.1";
 
-static EXIT_OK:  i32 = 0;
 static EXIT_ERR: i32 = 1;
 
 pub enum Mode {
     Kill,
     Table,
     List,
-    Help,
-    Version,
 }
 
-#[allow(dead_code)]
-fn main() { uumain(os::args()); }
-
-pub fn uumain(args: Vec<String>) {
-
-    let opts = [
-        optflag("h", "help", "display this help and exit"),
-        optflag("V", "version", "output version information and exit"),
-        optopt("s", "signal", "specify the <signal> to be sent", "SIGNAL"),
-        optflagopt("l", "list", "list all signal names, or convert one to a name", "LIST"),
-        optflag("L", "table", "list all signal names in a nice table"),
-    ];
-
-    let usage = usage("[options] <pid> [...]", opts);
-
-
-    let matches = match getopts(args.tail(), opts) {
-        Ok(m) => m,
-        Err(e) => {
-            show_error!(EXIT_ERR, "{}\n{}", e.to_err_msg(),  get_help_text(NAME, usage.as_slice()));
-            return
-        },
-    };
-
-
-    let mode = if matches.opt_present("version") {
-        Version
-    } else if matches.opt_present("help") {
-        Help
-    } else if matches.opt_present("table") {
-        Table
-    } else if matches.opt_present("list") {
-        List
-    } else {
-        Kill
-    };
-
-    match mode {
-        Kill    => kill(matches.opt_str("signal").unwrap_or("9".to_string()).as_slice(), matches.free),
-        Table   => table(),
-        List    => list(matches.opt_str("list")),
-        Help    => help(NAME, usage.as_slice()),
-        Version => version(),
-    }
+extern {
+    fn strsignal(sig: c_int) -> *libc::c_char;
 }
 
-fn version() {
-    println!("{} {}", NAME, VERSION);
-}
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-fn table() {
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let mut name_width = 0;
-    /* Compute the maximum width of a signal name. */
-    for s in ALL_SIGNALS.iter() {
-        if s.name.len() > name_width {
-            name_width = s.name.len()
+fn run(args: Vec<String>) {
+    let prog = args.get(0).as_slice();
+    let mut mode = Kill;
+    let mut sig = None;
+
+    // stops at the first PID, which may be negative for a process group, as
+    // long as it is not where the signal could be
+    let mut i = 1;
+    while i < args.len() {
+        let opt = args.get(i).as_slice();
+        if !opt.starts_with("-") || opt == "-" {
+            break;
+        }
+        i += 1;
+        match opt {
+            "--" => break,
+            "--help" => { help(prog); return }
+            "--version" => { println!("{} {}", NAME, VERSION); return }
+            "-l" | "--list" => mode = List,
+            "-t" | "-L" | "--table" => mode = Table,
+            "-s" | "--signal" => {
+                if i == args.len() {
+                    bad_usage(prog, format!("option requires an argument -- '{}'", opt));
+                }
+                i += 1;
+                sig = Some(parse_signal(args.get(i - 1).as_slice()));
+            }
+            _ if opt.starts_with("--signal=") => {
+                sig = Some(parse_signal(opt.slice_from("--signal=".len())));
+            }
+            _ if opt.slice_from(1).chars().all(|c| c.is_digit()) && i != 2 => {
+                i -= 1;
+                break;
+            }
+            _ if sig.is_none() && signal::parse(opt.slice_from(1)).is_some() => {
+                sig = signal::parse(opt.slice_from(1));
+            }
+            _ => bad_usage(prog, format!("invalid option -- '{}'", opt.slice_from(1)))
         }
     }
+    let operands = args.slice_from(i);
 
-    for (idx, signal) in ALL_SIGNALS.iter().enumerate() {
-        print!("{0: >#2} {1: <#8}", idx+1, signal.name);
-        //TODO: obtain max signal width here
-        
-        if (idx+1) % 7 == 0 {
-            println!("");
+    match mode {
+        Kill => {
+            if operands.is_empty() {
+                bad_usage(prog, "no process ID specified".to_string());
+            }
+            kill(sig.unwrap_or(signal::SIGTERM), operands);
+        }
+        _ if sig.is_some() => {
+            bad_usage(prog, "cannot combine signal with -l or -t".to_string());
         }
+        List => list(operands),
+        Table => table(operands),
     }
 }
 
-fn print_signal(signal_name_or_value: &str) {
-    for signal in ALL_SIGNALS.iter() {
-        if signal.name == signal_name_or_value  || (format!("SIG{}", signal.name).as_slice()) == signal_name_or_value {
-            println!("{}", signal.value)
-            exit!(EXIT_OK)
-        } else if signal_name_or_value == signal.value.to_str().as_slice() {
-            println!("{}", signal.name);
-            exit!(EXIT_OK)
-        }
-    }
-    crash!(EXIT_ERR, "unknown signal name {}", signal_name_or_value)
+fn help(prog: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0} [-s SIGNAL | -SIGNAL] PID...", prog);
+    println!("  {0} -l [SIGNAL]...", prog);
+    println!("  {0} -t [SIGNAL]...", prog);
+    println!("");
+    println!("Send signals to processes, or list signals.");
+    println!("");
+    println!("  -s, --signal=SIGNAL, -SIGNAL");
+    println!("                   specify the name or number of the signal to be sent");
+    println!("  -l, --list       list signal names, or convert signal names to/from numbers");
+    println!("  -t, --table      print a table of signal information");
+    println!("      --help       display this help and exit");
+    println!("      --version    output version information and exit");
+    println!("");
+    println!("SIGNAL may be a signal name like 'HUP', or a signal number like '1',");
+    println!("or the exit status of a process terminated by a signal.");
+    println!("PID is an integer; if negative it identifies a process group.");
 }
 
-fn print_signals() {
-    let mut pos = 0;
-    for (idx, signal) in ALL_SIGNALS.iter().enumerate() {
-        pos += signal.name.len();
-        print!("{}", signal.name);
-        if idx > 0 && pos > 73 {
-            println!("");
-            pos = 0;
-        } else {
-            pos += 1;
-            print!(" ");
-        } 
-    }
+fn bad_usage(prog: &str, msg: String) -> ! {
+    show_error!(EXIT_ERR, "{}", msg);
+    crash!(EXIT_ERR, "Try '{} --help' for more information.", prog)
 }
 
-fn list(arg: Option<String>) {
-    match arg {
-      Some(x) => print_signal(x.as_slice()),
-      None => print_signals(),
-    };
+fn parse_signal(s: &str) -> c_int {
+    match signal::parse(s) {
+        Some(sig) => sig,
+        None => crash!(EXIT_ERR, "{}: invalid signal", s)
+    }
 }
 
-fn get_help_text(progname: &str, usage: &str) -> String {
-    format!("Usage: \n {0} {1}", progname, usage)
+// The signal given as a name or a number, where a number past 128 is the
+// exit status a shell gives a process the signal killed.
+fn signal_operand(s: &str) -> Option<c_int> {
+    match from_str::<c_int>(s) {
+        Some(n) if n > 128 => signal::name(n - 128).map(|_| n - 128),
+        Some(n) => signal::name(n).map(|_| n),
+        None => signal::parse(s)
+    }
 }
 
-fn help(progname: &str, usage: &str) {
-    println!("{}", get_help_text(progname, usage));
+fn list(operands: &[String]) {
+    if operands.is_empty() {
+        for sig in signal::ALL_SIGNALS.iter() {
+            println!("{}", sig.name);
+        }
+        return;
+    }
+    // each name is turned into its number and each number into its name
+    for operand in operands.iter() {
+        match signal_operand(operand.as_slice()) {
+            Some(sig) if from_str::<c_int>(operand.as_slice()).is_some() => {
+                println!("{}", signal::name(sig).unwrap())
+            }
+            Some(sig) => println!("{}", sig),
+            None => show_error!(EXIT_ERR, "'{}': invalid signal", operand)
+        }
+    }
 }
 
-fn signal_by_name_or_value(signal_name_or_value: &str) -> Option<uint> {
-    if signal_name_or_value == "0" {
-        return Some(0);
+fn table(operands: &[String]) {
+    let width = signal::ALL_SIGNALS.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    let mut signals = vec!();
+    if operands.is_empty() {
+        signals.extend(signal::ALL_SIGNALS.iter().map(|s| s.value));
     }
-    for signal in ALL_SIGNALS.iter() {
-        let long_name = format!("SIG{}", signal.name);
-        if signal.name == signal_name_or_value  || (signal_name_or_value == signal.value.to_str().as_slice()) || (long_name.as_slice() == signal_name_or_value) {
-            return Some(signal.value);
+    for operand in operands.iter() {
+        match signal_operand(operand.as_slice()) {
+            Some(sig) => signals.push(sig),
+            None => show_error!(EXIT_ERR, "'{}': invalid signal", operand)
         }
     }
-    return None;
+    for &sig in signals.iter() {
+        let description = unsafe { std::str::raw::from_c_str(strsignal(sig)) };
+        println!("{0:>2} {1:<3$} {2}", sig, signal::name(sig).unwrap(), description, width);
+    }
 }
 
-fn kill(signalname: &str, pids: std::vec::Vec<String>) {
-    let optional_signal_value = signal_by_name_or_value(signalname);
-    let signal_value = match optional_signal_value {
-        Some(x) => x,
-        None => crash!(EXIT_ERR, "unknown signal name {}", signalname)
-    };
+fn kill(sig: c_int, pids: &[String]) {
     for pid in pids.iter() {
-        match from_str::<i32>(pid.as_slice()) {
-            Some(x) => {
-                let result = Process::kill(x, signal_value as int);
-                match result {
-                  Ok(_) => (),
-                  Err(_) => ()
-                };
+        match from_str::<libc::pid_t>(pid.as_slice()) {
+            Some(pid) => if !signal::send(pid, sig) {
+                show_error!(EXIT_ERR, "sending signal to {} failed: {}", pid,
+                            os::last_os_error());
             },
-            None => crash!(EXIT_ERR, "failed to parse argument {}", signalname)
-        };
+            None => show_error!(EXIT_ERR, "'{}': invalid process id", pid)
+        }
     }
 }
@@ -0,0 +1,82 @@
+#![crate_id(name="link", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::fs;
+use std::io::print;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+static NAME: &'static str = "link";
+static VERSION: &'static str = "1.0.0";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} FILE1 FILE2", program);
+        println!("  {0} OPTION", program);
+        println!("");
+        print(getopts::usage("Call the link function to create a link named FILE2 to an \
+                              existing FILE1.", opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    match matches.free.len() {
+        0 => crash!(1, "missing operand\nTry '{0} --help' for more information.", program),
+        1 => crash!(1, "missing operand after '{1}'\nTry '{0} --help' for more information.",
+                    program, matches.free.get(0)),
+        2 => {}
+        _ => crash!(1, "extra operand '{1}'\nTry '{0} --help' for more information.",
+                    program, matches.free.get(2))
+    }
+
+    let old = matches.free.get(0);
+    let new = matches.free.get(1);
+    match fs::link(&Path::new(old.as_slice()), &Path::new(new.as_slice())) {
+        Ok(()) => {}
+        Err(e) => crash!(1, "cannot create link '{}' to '{}': {}", new, old, util::strerror(&e))
+    }
+}
@@ -0,0 +1,325 @@
+#![crate_id(name="ln", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::str;
+use std::io::{fs, IoError, IoResult};
+
+use getopts::{
+    optflag,
+    optflagopt,
+    optopt,
+    usage,
+};
+
+use backup::{BackupMode, NoBackup};
+use canon::CanonMissing;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/backup.rs"]
+mod backup;
+
+#[path = "../common/canon.rs"]
+mod canon;
+
+extern {
+    fn symlink(target: *libc::c_char, link: *libc::c_char) -> libc::c_int;
+}
+
+static NAME: &'static str = "ln";
+static VERSION: &'static str = "1.0.0";
+
+#[deriving(PartialEq)]
+enum Overwrite {
+    NoOverwrite,
+    Force,
+    Interactive,
+}
+
+struct Options {
+    symbolic: bool,
+    relative: bool,
+    overwrite: Overwrite,
+    backup: BackupMode,
+    suffix: String,
+    verbose: bool,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        optflagopt("", "backup", "make a backup of each existing destination file", "CONTROL"),
+        optflag("b", "", "like --backup but does not accept an argument"),
+        optflag("f", "force", "remove existing destination files"),
+        optflag("i", "interactive", "prompt whether to remove destinations"),
+        optflag("n", "no-dereference", "treat LINK_NAME as a normal file if it is a symbolic \
+                                        link to a directory"),
+        optflag("r", "relative", "create symbolic links relative to link location"),
+        optflag("s", "symbolic", "make symbolic links instead of hard links"),
+        optopt("S", "suffix", "override the usual backup suffix", "SUFFIX"),
+        optopt("t", "target-directory", "specify the DIRECTORY in which to create the links",
+               "DIRECTORY"),
+        optflag("T", "no-target-directory", "treat LINK_NAME as a normal file always"),
+        optflag("v", "verbose", "print name of each linked file"),
+        optflag("h", "help", "display this help and exit"),
+        optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg()),
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [-T] TARGET LINK_NAME", args.get(0));
+        println!("  {0} [OPTION]... TARGET", args.get(0));
+        println!("  {0} [OPTION]... TARGET... DIRECTORY", args.get(0));
+        println!("  {0} [OPTION]... -t DIRECTORY TARGET...", args.get(0));
+        println!("");
+        print!("{}", usage("Create a link to TARGET with the name LINK_NAME, a link to TARGET \
+                            in the\ncurrent directory, or links to each TARGET in DIRECTORY.  \
+                            Hard links are\nmade by default, symbolic links with --symbolic.",
+                           opts.as_slice()));
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let target_dir = matches.opt_str("target-directory");
+    let no_target_dir = matches.opt_present("no-target-directory");
+    if target_dir.is_some() && no_target_dir {
+        crash!(1, "cannot combine --target-directory (-t) and --no-target-directory (-T)");
+    }
+
+    // the second form links into the current directory
+    let (targets, dest, explicit_dir) = match target_dir {
+        Some(dir) => {
+            if matches.free.is_empty() {
+                crash!(1, "missing file operand");
+            }
+            (matches.free.clone(), dir, true)
+        }
+        None => match matches.free.len() {
+            0 => crash!(1, "missing file operand"),
+            1 if no_target_dir => {
+                crash!(1, "missing destination file operand after '{}'", matches.free.get(0))
+            }
+            1 => (matches.free.clone(), ".".to_string(), true),
+            n if no_target_dir && n > 2 => crash!(1, "extra operand '{}'", matches.free.get(2)),
+            _ => (matches.free.init().to_owned(), matches.free.last().unwrap().clone(), false)
+        }
+    };
+
+    let dest_path = Path::new(dest.as_slice());
+    // with -n a link to a directory is just a name to replace
+    let dest_stat = if matches.opt_present("no-dereference") {
+        fsext::lstat_path(&dest_path)
+    } else {
+        fsext::stat_path(&dest_path)
+    };
+    let dest_is_dir = !no_target_dir && match dest_stat {
+        Ok(stat) => fsext::is_dir(stat.st_mode),
+        Err(_) => false
+    };
+    if (targets.len() > 1 || explicit_dir) && !dest_is_dir {
+        crash!(1, "target '{}' is not a directory", dest);
+    }
+
+    let backup_mode = match backup::mode(matches.opt_present("backup") || matches.opt_present("b"),
+                                         matches.opt_str("backup")) {
+        Ok(mode) => mode,
+        Err(msg) => crash!(1, "{}", msg)
+    };
+    // whichever of -f and -i comes last wins
+    let overwrite = match (matches.opt_positions("force").last(),
+                           matches.opt_positions("interactive").last()) {
+        (Some(f), Some(i)) => if f > i { Force } else { Interactive },
+        (Some(_), None) => Force,
+        (None, Some(_)) => Interactive,
+        (None, None) => NoOverwrite
+    };
+    if matches.opt_present("relative") && !matches.opt_present("symbolic") {
+        crash!(1, "cannot do --relative without --symbolic");
+    }
+    let options = Options {
+        symbolic: matches.opt_present("symbolic"),
+        relative: matches.opt_present("relative"),
+        overwrite: overwrite,
+        backup: backup_mode,
+        suffix: backup::suffix(matches.opt_str("suffix")),
+        verbose: matches.opt_present("verbose"),
+    };
+
+    for target in targets.iter() {
+        let link = if dest_is_dir {
+            match Path::new(target.as_slice()).filename() {
+                Some(name) => dest_path.join(name),
+                None => {
+                    show_error!(1, "cannot link '{}' into '{}'", target, dest);
+                    continue;
+                }
+            }
+        } else {
+            dest_path.clone()
+        };
+        make_link(target.as_slice(), &link, &options);
+    }
+}
+
+fn make_link(target: &str, link: &Path, options: &Options) {
+    let target_path = if options.relative {
+        match relative_target(target, link) {
+            Ok(path) => path,
+            Err(e) => {
+                show_error!(1, "cannot resolve '{}': {}", target, util::strerror(&e));
+                return;
+            }
+        }
+    } else {
+        Path::new(target)
+    };
+    // a symbolic link gets the operand exactly as given, which going through
+    // Path would tidy, dropping a trailing slash or a ./ for instance
+    let target_bytes = if options.relative {
+        Vec::from_slice(target_path.as_vec())
+    } else {
+        Vec::from_slice(target.as_bytes())
+    };
+    let target = str::from_utf8_lossy(target_bytes.as_slice()).into_string();
+    let target = target.as_slice();
+    let kind = if options.symbolic { "symbolic link" } else { "hard link" };
+
+    if !options.symbolic {
+        match fsext::lstat_path(&target_path) {
+            Ok(stat) if fsext::is_dir(stat.st_mode) => {
+                show_error!(1, "'{}': hard link not allowed for directory", target);
+                return;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                show_error!(1, "failed to access '{}': {}", target, util::strerror(&e));
+                return;
+            }
+        }
+    }
+
+    let mut backup = None;
+    match fsext::lstat_path(link) {
+        Ok(link_stat) => {
+            match options.overwrite {
+                NoOverwrite if options.backup == NoBackup => {
+                    show_error!(1, "failed to create {} '{}': File exists", kind, link.display());
+                    return;
+                }
+                Interactive => {
+                    if !prompt_yes!("replace '{}'?", link.display()) {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            // replacing a file with a hard link to itself would lose it
+            if !options.symbolic {
+                match fsext::stat_path(&target_path) {
+                    Ok(stat) if stat.st_dev == link_stat.st_dev
+                                && stat.st_ino == link_stat.st_ino => {
+                        show_error!(1, "'{}' and '{}' are the same file", target, link.display());
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+            if fsext::is_dir(link_stat.st_mode) {
+                show_error!(1, "cannot overwrite directory '{}'", link.display());
+                return;
+            }
+            match backup::make_backup(link, options.backup, options.suffix.as_slice()) {
+                Ok(path) => backup = path,
+                Err(e) => {
+                    show_error!(1, "cannot backup '{}': {}", link.display(), util::strerror(&e));
+                    return;
+                }
+            }
+            if backup.is_none() {
+                match fs::unlink(link) {
+                    Ok(()) => {}
+                    Err(e) => {
+                        show_error!(1, "cannot remove '{}': {}", link.display(),
+                                    util::strerror(&e));
+                        return;
+                    }
+                }
+            }
+        }
+        Err(_) => {}
+    }
+
+    let result = if options.symbolic {
+        make_symlink(target_bytes.as_slice(), link)
+    } else {
+        fs::link(&target_path, link)
+    };
+    match result {
+        Ok(()) => {}
+        Err(e) => {
+            show_error!(1, "failed to create {} '{}': {}", kind, link.display(),
+                        util::strerror(&e));
+            return;
+        }
+    }
+
+    if options.verbose {
+        let arrow = if options.symbolic { "->" } else { "=>" };
+        match backup {
+            Some(backup) => println!("'{}' {} '{}' (backup: '{}')", link.display(), arrow,
+                                     target, backup.display()),
+            None => println!("'{}' {} '{}'", link.display(), arrow, target)
+        }
+    }
+}
+
+fn make_symlink(target: &[u8], link: &Path) -> IoResult<()> {
+    let ret = target.with_c_str(|t| link.with_c_str(|l| unsafe { symlink(t, l) }));
+    if ret == 0 { Ok(()) } else { Err(IoError::last_error()) }
+}
+
+// The way from the directory link is in to target, once the links in both
+// have been resolved, so that it still works with the link in place.
+fn relative_target(target: &str, link: &Path) -> IoResult<Path> {
+    let target = try!(canon::canonicalize(target.as_bytes(), CanonMissing));
+    let dir = try!(canon::canonicalize(link.dir_path().as_vec(), CanonMissing));
+    Ok(target.path_relative_from(&dir).unwrap_or(target))
+}
@@ -21,19 +21,34 @@ extern crate libc;
 use std::io::print;
 use std::os;
 use std::str;
-use libc::c_char;
 
 #[path = "../common/util.rs"] mod util;
+#[path = "../common/posix.rs"] mod posix;
+#[path = "../common/utmpx.rs"] mod utmpx;
 
 extern {
     // POSIX requires using getlogin (or equivalent code)
     pub fn getlogin() -> *libc::c_char;
 }
 
-unsafe fn get_userlogin() -> String {
-    let login: *libc::c_char = getlogin();
+// getlogin(3), or else whoever utmp says logged in on the terminal, which
+// is where getlogin looks on most systems anyway.
+fn get_userlogin() -> Option<String> {
+    let login: *libc::c_char = unsafe { getlogin() };
+    if login.is_not_null() {
+        return Some(unsafe { str::raw::from_c_str(login) });
+    }
 
-    str::raw::from_c_str(login)
+    let line = match utmpx::stdin_line() {
+        Some(line) => line,
+        None => return None
+    };
+    match utmpx::read_utmpx(utmpx::DEFAULT_FILE) {
+        Ok(records) => records.move_iter()
+                              .find(|r| r.is_user_process() && r.line == line)
+                              .map(|r| r.user),
+        Err(_) => None
+    }
 }
 
 static NAME: &'static str = "logname";
@@ -44,20 +59,28 @@ fn version() {
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
     let program = args.get(0).clone();
 
     //
     // Argument parsing
     //
-    let opts = [
-        getopts::optflag("h", "help", "display this help and exit"),
-        getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    let opts = opts();
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => crash!(1, "Invalid options\n{}", f.to_err_msg())
     };
@@ -68,7 +91,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {:s}", program);
         println!("");
-        print(getopts::usage("print user's login name", opts).as_slice());
+        print(getopts::usage("print user's login name", opts.as_slice()).as_slice());
         return;
     }
     if matches.opt_present("version") {
@@ -80,8 +103,8 @@ pub fn uumain(args: Vec<String>) {
 }
 
 fn exec() {
-    unsafe {
-        let userlogin = get_userlogin();
-        println!("{:s}", userlogin);
+    match get_userlogin() {
+        Some(userlogin) => println!("{:s}", userlogin),
+        None => crash!(1, "no login name")
     }
 }
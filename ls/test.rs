@@ -0,0 +1,36 @@
+use std::io;
+use std::io::fs;
+use std::io::process::Command;
+use std::str;
+
+static PROG: &'static str = "build/ls";
+
+fn make_files(dir: &str, names: &[&str]) {
+    let dir = Path::new(dir);
+    fs::mkdir(&dir, io::UserRWX).unwrap();
+    for name in names.iter() {
+        io::File::create(&dir.join(*name)).unwrap();
+    }
+}
+
+fn run(args: &[&'static str]) -> String {
+    let po = Command::new(PROG).args(args).env("LC_ALL", "C").output().unwrap();
+    str::from_utf8(po.output.as_slice()).unwrap().to_string()
+}
+
+#[test]
+fn test_sorted_by_name() {
+    let dir = "tmp/ls_test_sort";
+    make_files(dir, ["b", "a", "C", ".hidden"]);
+    assert_eq!(run(["-1", dir]).as_slice(), "C\na\nb\n");
+    assert_eq!(run(["-1A", dir]).as_slice(), ".hidden\nC\na\nb\n");
+    fs::rmdir_recursive(&Path::new(dir)).unwrap();
+}
+
+#[test]
+fn test_zero() {
+    let dir = "tmp/ls_test_zero";
+    make_files(dir, ["x", "y"]);
+    assert_eq!(run(["--zero", dir]).as_slice(), "x\0y\0");
+    fs::rmdir_recursive(&Path::new(dir)).unwrap();
+}
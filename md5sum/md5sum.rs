@@ -24,15 +24,21 @@ use crypto::digest::Digest;
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "md5sum";
 static VERSION: &'static str = "1.0.0";
 
-fn main() {
-    let args = os::args();
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-    let program = args.get(0).clone();
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("b", "binary", "read in binary mode"),
         getopts::optflag("c", "check", "read MD5 sums from the FILEs and check them"),
         getopts::optflag("", "tag", "create a BSD-style checksum"),
@@ -43,9 +49,15 @@ fn main() {
         getopts::optflag("w", "warn", "warn about improperly formatted checksum lines"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit")
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+
+    let opts = opts();
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => crash!(1, "{}", f.to_err_msg())
     };
@@ -56,7 +68,7 @@ fn main() {
         println!("Usage:");
         println!("  {} [OPTION]... [FILE]...", program);
         println!("");
-        print!("{}", getopts::usage("Compute and check MD5 message digests.", opts));
+        print!("{}", getopts::usage("Compute and check MD5 message digests.", opts.as_slice()));
     } else if matches.opt_present("version") {
         println!("{} v{}", NAME, VERSION);
     } else {
@@ -102,7 +114,7 @@ fn md5sum(files: Vec<String>, binary: bool, check: bool, tag: bool, status: bool
                         None => {
                             bad_format += 1;
                             if strict {
-                                os::set_exit_status(1);
+                                util::set_status(1);
                             }
                             if warn {
                                 show_warning!("{}: {}: improperly formatted MD5 checksum line", filename, i + 1);
@@ -121,7 +133,7 @@ fn md5sum(files: Vec<String>, binary: bool, check: bool, tag: bool, status: bool
                         println!("{}: FAILED", ck_filename);
                     }
                     failed += 1;
-                    os::set_exit_status(1);
+                    util::set_status(1);
                 }
             }
         } else {
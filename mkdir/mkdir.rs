@@ -17,11 +17,19 @@ extern crate libc;
 use std::os;
 use std::io::fs;
 use std::io::FilePermission;
-use std::num::strconv;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/mode.rs"]
+mod mode;
+
 static NAME: &'static str = "mkdir";
 static VERSION: &'static str = "1.0.0";
 
@@ -29,23 +37,31 @@ static VERSION: &'static str = "1.0.0";
  * Handles option parsing
  */
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         // Linux-specific options, not implemented
         // getopts::optflag("Z", "context", "set SELinux secutiry context" +
         // " of each created directory to CTX"),
-        getopts::optopt("m", "mode", "set file mode", "755"),
+        getopts::optopt("m", "mode", "set file mode (as in chmod), not a=rwx - umask", "MODE"),
         getopts::optflag("p", "parents", "make parent directories as needed"),
         getopts::optflag("v", "verbose",
                         "print a message for each printed directory"),
         getopts::optflag("h", "help", "display this help"),
         getopts::optflag("V", "version", "display this version")
-    ];
+    )
+}
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+fn run(args: Vec<String>) {
+
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
             crash!(1, "Invalid options\n{}", f.to_err_msg());
@@ -53,7 +69,7 @@ pub fn uumain(args: Vec<String>) {
     };
 
     if args.len() == 1 || matches.opt_present("help") {
-        print_help(opts);
+        print_help(opts.as_slice());
         return;
     }
     if matches.opt_present("version") {
@@ -63,28 +79,22 @@ pub fn uumain(args: Vec<String>) {
     let verbose_flag = matches.opt_present("verbose");
     let mk_parents = matches.opt_present("parents");
 
-    // Translate a ~str in octal form to u32, default to 755
-    // Not tested on Windows
-    let mode_match = matches.opts_str(&["mode".to_string()]);
-    let mode: FilePermission = if mode_match.is_some() {
-        let m = mode_match.unwrap();
-        let res: Option<u32> = strconv::from_str_common(m.as_slice(), 8, false, false, false,
-                                                        strconv::ExpNone,
-                                                        false, false);
-        if res.is_some() {
-            unsafe { std::mem::transmute(res.unwrap()) }
-        } else {
-            crash!(1, "no mode given");
-        }
-    } else {
-        unsafe { std::mem::transmute(0o755 as u32) }
+    // -m is applied to a=rwx, leaving the umask to any clause that names
+    // nobody; without it, mkdir(2) applies the umask itself
+    let umask = fsext::umask();
+    let mode = match matches.opt_str("mode") {
+        Some(m) => match mode::parse(m.as_slice(), 0o777, umask, true) {
+            Ok(mode) => Some(mode),
+            Err(msg) => crash!(1, "{}", msg)
+        },
+        None => None
     };
 
     let dirs = matches.free;
     if dirs.is_empty() {
         crash!(1, "missing operand");
     }
-    exec(dirs, mk_parents, mode, verbose_flag);
+    exec(dirs, mk_parents, mode, umask, verbose_flag);
 }
 
 fn print_help(opts: &[getopts::OptGroup]) {
@@ -97,60 +107,79 @@ fn print_help(opts: &[getopts::OptGroup]) {
 /**
  * Create the list of new directories
  */
-fn exec(dirs: Vec<String>, mk_parents: bool, mode: FilePermission, verbose: bool) {
-    let mut parent_dirs = Vec::new();
-    if mk_parents {
-        for dir in dirs.iter() {
-            let path = Path::new((*dir).clone());
-            // Build list of parent dirs which need to be created
-            let parent = path.dirname_str();
-            match parent {
-                Some(p) => {
-                    if !Path::new(p).exists() {
-                        parent_dirs.push(p.to_string())
-                    }
-                },
-                None => ()
+fn exec(dirs: Vec<String>, mk_parents: bool, mode: Option<libc::mode_t>, umask: libc::mode_t,
+        verbose: bool) {
+    // parents made by -p have to let us make what goes in them, whatever the
+    // umask says
+    let parent_mode = (0o777 & !umask) | 0o300;
+    for dir in dirs.iter() {
+        let path = Path::new(dir.as_slice());
+        if mk_parents {
+            // everything between the nearest existing ancestor and path
+            let mut missing = vec!();
+            let mut parent = path.dir_path();
+            while !is_dir(&parent) && parent != parent.dir_path() {
+                missing.push(parent.clone());
+                parent = parent.dir_path();
+            }
+            if !missing.iter().rev().all(|parent| make_parent(parent, parent_mode, verbose)) {
+                continue;
+            }
+            // -p is happy with directories that are already there
+            if is_dir(&path) {
+                continue;
             }
         }
+        mkdir(&path, mode, verbose);
     }
-    // Recursively build parent dirs that are needed
-    if !parent_dirs.is_empty() {
-        exec(parent_dirs, mk_parents, mode, verbose);
+}
+
+fn is_dir(path: &Path) -> bool {
+    match fsext::stat_path(path) {
+        Ok(stat) => fsext::is_dir(stat.st_mode),
+        Err(_) => false
     }
+}
 
-    for dir in dirs.iter() {
-        let path = Path::new((*dir).clone());
-        // Determine if parent directory to the one to 
-        // be created exists
-        let parent = match path.dirname_str() {
-            Some(p) => p,
-            None => ""
-        };
-        let parent_exists = Path::new(parent).exists();
-        if parent_exists && !path.exists() {
-            mkdir(&path, mode);
-            if verbose {println!("{}", *dir);}
-        } else if !mk_parents {
-            let error_msg =
-                if !parent_exists {
-                    format!("parent directory '{}' does not exist", parent)
-                } else {
-                    format!("directory '{}' already exists", *dir)
-                };
-            show_error!(1, "{}", error_msg);
+fn make_parent(path: &Path, mode: libc::mode_t, verbose: bool) -> bool {
+    match fsext::stat_path(path) {
+        Ok(ref stat) if fsext::is_dir(stat.st_mode) => return true,
+        Ok(_) => {
+            show_error!(1, "'{}' exists but is not a directory", path.display());
+            return false;
         }
+        Err(_) => {}
     }
+    mkdir(path, Some(mode), verbose)
 }
 
 /**
  * Wrapper to catch errors, return false if failed
  */
-fn mkdir(path: &Path, mode: FilePermission) {
-    match fs::mkdir(path, mode) {
-        Ok(_) => {},
+fn mkdir(path: &Path, mode: Option<libc::mode_t>, verbose: bool) -> bool {
+    let perm = FilePermission::from_bits_truncate(mode.unwrap_or(0o777) as u32);
+    match fs::mkdir(path, perm) {
+        Ok(_) => {}
         Err(e) => {
-            crash!(1, "test {}", e.to_str());
+            show_error!(1, "cannot create directory '{}': {}", path.display(), util::strerror(&e));
+            return false;
         }
     }
+    // mkdir(2) takes the umask off and may ignore the special bits, so an
+    // explicit mode has to be set again
+    match mode {
+        Some(mode) => match fsext::set_mode(path, mode) {
+            Ok(_) => {}
+            Err(e) => {
+                show_error!(1, "cannot set permissions of '{}': {}", path.display(),
+                            util::strerror(&e));
+                return false;
+            }
+        },
+        None => {}
+    }
+    if verbose {
+        println!("{}: created directory '{}'", NAME, path.display());
+    }
+    true
 }
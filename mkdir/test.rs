@@ -7,6 +7,7 @@ static test_dir2: &'static str = "tmp/mkdir_test2";
 static test_dir3: &'static str = "tmp/mkdir_test3";
 static test_dir4: &'static str = "tmp/mkdir_test4/mkdir_test4_1";
 static test_dir5: &'static str = "tmp/mkdir_test5/mkdir_test5_1";
+static test_dir6: &'static str = "tmp/mkdir_test6";
 
 fn cleanup(dir: &'static str) {
     let d = dir.into_string();
@@ -66,3 +67,18 @@ fn test_mkdir_no_parent() {
     cleanup(test_dir5);
     assert_eq!(exit_success, false);
 }
+
+#[test]
+fn test_mkdir_parent_exists() {
+    cleanup(test_dir6);
+    let prog = Command::new(exe).arg(test_dir6).status();
+    let exit_success = prog.unwrap().success();
+    if !exit_success {
+        cleanup(test_dir6);
+        fail!();
+    }
+    let prog2 = Command::new(exe).arg("-p").arg(test_dir6).status();
+    let exit_success2 = prog2.unwrap().success();
+    cleanup(test_dir6);
+    assert_eq!(exit_success2, true);
+}
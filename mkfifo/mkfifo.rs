@@ -0,0 +1,102 @@
+#![crate_id(name="mkfifo", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::print;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/mode.rs"]
+mod mode;
+
+static NAME: &'static str = "mkfifo";
+static VERSION: &'static str = "1.0.0";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optopt("m", "mode", "set file permission bits to MODE, not a=rw - umask", "MODE"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... NAME...", args.get(0));
+        println!("");
+        print(getopts::usage("Create named pipes (FIFOs) with the given NAMEs.", opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    if matches.free.is_empty() {
+        crash!(1, "missing operand");
+    }
+
+    // as with mkdir, -m is applied to a=rw and then set exactly, as
+    // mkfifo(3) would take the umask off it
+    let mode = match matches.opt_str("mode") {
+        Some(m) => match mode::parse(m.as_slice(), 0o666, fsext::umask(), false) {
+            Ok(mode) => Some(mode),
+            Err(msg) => crash!(1, "{}", msg)
+        },
+        None => None
+    };
+
+    for name in matches.free.iter() {
+        let path = Path::new(name.as_slice());
+        match fsext::make_fifo(&path, mode.unwrap_or(0o666)) {
+            Ok(()) => {}
+            Err(e) => {
+                show_error!(1, "cannot create fifo '{}': {}", name, util::strerror(&e));
+                continue;
+            }
+        }
+        match mode {
+            Some(mode) => match fsext::set_mode(&path, mode) {
+                Ok(()) => {}
+                Err(e) => show_error!(1, "cannot set permissions of '{}': {}", name,
+                                      util::strerror(&e))
+            },
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,155 @@
+#![crate_id(name="mknod", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::num;
+use std::io::print;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/mode.rs"]
+mod mode;
+
+static NAME: &'static str = "mknod";
+static VERSION: &'static str = "1.0.0";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optopt("m", "mode", "set file permission bits to MODE, not a=rw - umask", "MODE"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... NAME TYPE [MAJOR MINOR]", args.get(0));
+        println!("");
+        print(getopts::usage("Create the special file NAME of the given TYPE.", opts.as_slice()).as_slice());
+        println!("");
+        println!("Both MAJOR and MINOR must be specified when TYPE is b, c, or u, and they");
+        println!("must be omitted when TYPE is p.  If MAJOR or MINOR begins with 0x or 0X,");
+        println!("it is interpreted as hexadecimal; otherwise, if it begins with 0, as octal;");
+        println!("otherwise, as decimal.  TYPE may be:");
+        println!("");
+        println!("  b      create a block (buffered) special file");
+        println!("  c, u   create a character (unbuffered) special file");
+        println!("  p      create a FIFO");
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let free = &matches.free;
+    match free.len() {
+        0 => crash!(1, "missing operand"),
+        1 => crash!(1, "missing operand after '{}'", free.get(0)),
+        _ => {}
+    }
+    let name = free.get(0);
+    let kind = match free.get(1).as_slice() {
+        "b" => fsext::S_IFBLK,
+        "c" | "u" => fsext::S_IFCHR,
+        "p" => fsext::S_IFIFO,
+        other => crash!(1, "invalid device type '{}'", other)
+    };
+
+    let dev = if kind == fsext::S_IFIFO {
+        if free.len() > 2 {
+            show_error!(1, "extra operand '{}'", free.get(2));
+            crash!(1, "Fifos do not have major and minor device numbers.");
+        }
+        0
+    } else {
+        match free.len() {
+            2 => {
+                show_error!(1, "missing operand after '{}'", free.get(1));
+                crash!(1, "Special files require major and minor device numbers.");
+            }
+            3 => crash!(1, "missing operand after '{}'", free.get(2)),
+            4 => {}
+            _ => crash!(1, "extra operand '{}'", free.get(4))
+        }
+        let major = match parse_number(free.get(2).as_slice()) {
+            Some(n) => n,
+            None => crash!(1, "invalid major device number '{}'", free.get(2))
+        };
+        let minor = match parse_number(free.get(3).as_slice()) {
+            Some(n) => n,
+            None => crash!(1, "invalid minor device number '{}'", free.get(3))
+        };
+        fsext::make_dev(major, minor)
+    };
+
+    // as with mkdir, -m is applied to a=rw and then set exactly, as mknod(2)
+    // would take the umask off it
+    let mode = match matches.opt_str("mode") {
+        Some(m) => match mode::parse(m.as_slice(), 0o666, fsext::umask(), false) {
+            Ok(mode) => Some(mode),
+            Err(msg) => crash!(1, "{}", msg)
+        },
+        None => None
+    };
+
+    let path = Path::new(name.as_slice());
+    match fsext::make_node(&path, kind | mode.unwrap_or(0o666), dev) {
+        Ok(()) => {}
+        Err(e) => crash!(1, "{}: {}", name, util::strerror(&e))
+    }
+    match mode {
+        Some(mode) => match fsext::set_mode(&path, mode) {
+            Ok(()) => {}
+            Err(e) => crash!(1, "cannot set permissions of '{}': {}", name, util::strerror(&e))
+        },
+        None => {}
+    }
+}
+
+// Decimal, or hexadecimal after 0x, or octal after 0, as strtoul(3) reads
+// them.
+fn parse_number(s: &str) -> Option<u64> {
+    if s.starts_with("0x") || s.starts_with("0X") {
+        num::from_str_radix(s.slice_from(2), 16)
+    } else if s.len() > 1 && s.starts_with("0") {
+        num::from_str_radix(s.slice_from(1), 8)
+    } else {
+        from_str(s)
+    }
+}
@@ -0,0 +1,186 @@
+#![crate_id(name="mktemp", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::{fs, print, IoResult, PathAlreadyExists, UserRWX};
+use std::rand::{task_rng, Rng};
+
+use fdio::Fd;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fdio.rs"]
+mod fdio;
+
+static NAME: &'static str = "mktemp";
+static VERSION: &'static str = "1.0.0";
+
+static DEFAULT_TEMPLATE: &'static str = "tmp.XXXXXXXXXX";
+static CHARS: &'static str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+// As many names as glibc tries before giving up.
+static ATTEMPTS: uint = 62 * 62 * 62;
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("d", "directory", "create a directory, not a file"),
+        getopts::optflag("u", "dry-run", "do not create anything; merely print a name (unsafe)"),
+        getopts::optflag("q", "quiet", "suppress diagnostics about file/dir-creation failure"),
+        getopts::optopt("", "suffix", "append SUFF to TEMPLATE; SUFF must not contain a slash",
+                        "SUFF"),
+        getopts::optopt("p", "", "interpret TEMPLATE relative to DIR; if DIR is not specified, \
+                                  use $TMPDIR if set, else /tmp", "DIR"),
+        getopts::optflagopt("", "tmpdir", "same as -p, with DIR optional", "DIR"),
+        getopts::optflag("t", "", "interpret TEMPLATE as a single file name component, \
+                                   relative to a directory: $TMPDIR, if set; else the \
+                                   directory specified via -p; else /tmp [deprecated]"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [TEMPLATE]", args.get(0));
+        println!("");
+        print(getopts::usage("Create a temporary file or directory, safely, and print its name.  \
+                              TEMPLATE must\ncontain at least 3 consecutive 'X's in last \
+                              component.  If TEMPLATE is not\nspecified, use tmp.XXXXXXXXXX, \
+                              and --tmpdir is implied.  Files are created\nu+rw, and \
+                              directories u+rwx, minus umask restrictions.", opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    if matches.free.len() > 1 {
+        crash!(1, "too many templates");
+    }
+    let quiet = matches.opt_present("quiet");
+
+    let given = matches.free.as_slice().get(0).map(|t| t.clone());
+    let template = given.clone().unwrap_or(DEFAULT_TEMPLATE.to_string());
+    let suffix = matches.opt_str("suffix").unwrap_or(String::new());
+    if suffix.as_slice().contains_char('/') {
+        fail(quiet, format!("invalid suffix '{}', contains directory separator", suffix));
+    }
+
+    // where a relative template goes, if anywhere
+    let dir = if matches.opt_present("t") {
+        match os::getenv("TMPDIR") {
+            Some(ref dir) if !dir.is_empty() => Some(dir.clone()),
+            _ => Some(matches.opt_str("p").unwrap_or("/tmp".to_string()))
+        }
+    } else if matches.opt_present("p") {
+        matches.opt_str("p")
+    } else if matches.opt_present("tmpdir") {
+        Some(matches.opt_str("tmpdir").unwrap_or_else(|| tmpdir()))
+    } else if given.is_none() {
+        Some(tmpdir())
+    } else {
+        None
+    };
+    if dir.is_some() && template.as_slice().contains_char('/') {
+        fail(quiet, format!("invalid template, '{}', contains directory separator", template));
+    }
+
+    let xs = template.as_slice().len() - template.as_slice().trim_right_chars('X').len();
+    if xs < 3 {
+        fail(quiet, format!("too few X's in template '{}'", template));
+    }
+    let prefix = template.as_slice().slice_to(template.len() - xs);
+
+    let directory = matches.opt_present("directory");
+    let dry_run = matches.opt_present("dry-run");
+    let mut rng = task_rng();
+    for _ in range(0, ATTEMPTS) {
+        let mut name = prefix.to_string();
+        for _ in range(0, xs) {
+            name.push_char(CHARS.char_at(rng.gen_range(0, CHARS.len())));
+        }
+        name.push_str(suffix.as_slice());
+        let path = match dir {
+            Some(ref dir) => Path::new(dir.as_slice()).join(name.as_slice()),
+            None => Path::new(name.as_slice())
+        };
+
+        let result = if dry_run {
+            if path.exists() { continue; }
+            Ok(())
+        } else {
+            create(&path, directory)
+        };
+        match result {
+            Ok(()) => {
+                println!("{}", path.display());
+                return;
+            }
+            Err(ref e) if e.kind == PathAlreadyExists => {}
+            Err(e) => {
+                let what = if directory { "directory" } else { "file" };
+                fail(quiet, format!("failed to create {} via template '{}': {}", what,
+                                    template, util::strerror(&e)));
+            }
+        }
+    }
+    fail(quiet, format!("failed to create a name from template '{}': too many names already \
+                         exist", template));
+}
+
+// Makes path only if nothing goes by that name yet, readable and writable
+// by its owner alone.
+fn create(path: &Path, directory: bool) -> IoResult<()> {
+    if directory {
+        fs::mkdir(path, UserRWX)
+    } else {
+        Fd::open(path, libc::O_CREAT | libc::O_EXCL | libc::O_RDWR, 0o600).map(|_| ())
+    }
+}
+
+fn tmpdir() -> String {
+    match os::getenv("TMPDIR") {
+        Some(ref dir) if !dir.is_empty() => dir.clone(),
+        _ => "/tmp".to_string()
+    }
+}
+
+// -q keeps quiet about why, but not about failing.
+fn fail(quiet: bool, msg: String) -> ! {
+    if quiet {
+        exit!(1)
+    }
+    crash!(1, "{}", msg)
+}
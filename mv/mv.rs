@@ -0,0 +1,399 @@
+#![crate_id(name="mv", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate collections;
+extern crate getopts;
+extern crate libc;
+extern crate time;
+
+use std::os;
+use std::io::{fs, IoError};
+
+use collections::hashmap::HashMap;
+use getopts::{
+    optflag,
+    optflagopt,
+    optopt,
+    usage,
+};
+
+use backup::{BackupMode, NoBackup};
+use copy::{Overwrite, Preserve, OverwriteAlways, OverwriteInteractive, OverwriteNever,
+           ReflinkAuto, SparseAuto};
+use walk::{FollowNone, Visitor, WalkEntry, Walker};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/fdio.rs"]
+mod fdio;
+
+#[path = "../common/walk.rs"]
+mod walk;
+
+#[path = "../common/xattr.rs"]
+mod xattr;
+
+#[path = "../common/size.rs"]
+mod size;
+
+#[path = "../common/progress.rs"]
+mod progress;
+
+#[path = "../common/reflink.rs"]
+mod reflink;
+
+#[path = "../common/copy.rs"]
+mod copy;
+
+#[path = "../common/backup.rs"]
+mod backup;
+
+static NAME: &'static str = "mv";
+static VERSION: &'static str = "1.0.0";
+
+struct Options {
+    overwrite: Overwrite,
+    update: bool,
+    verbose: bool,
+    backup: BackupMode,
+    suffix: String,
+    // only used when a rename has to become a copy, which should leave
+    // everything as it was, hard links included
+    copy: copy::Options,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        optflagopt("", "backup", "make a backup of each existing destination file", "CONTROL"),
+        optflag("b", "", "like --backup but does not accept an argument"),
+        optflag("f", "force", "do not prompt before overwriting"),
+        optflag("i", "interactive", "prompt before overwrite"),
+        optflag("n", "no-clobber", "do not overwrite an existing file"),
+        optopt("S", "suffix", "override the usual backup suffix", "SUFFIX"),
+        optopt("t", "target-directory", "move all SOURCE arguments into DIRECTORY", "DIRECTORY"),
+        optflag("T", "no-target-directory", "treat DEST as a normal file"),
+        optflag("u", "update", "move only when the SOURCE file is newer than the destination file \
+                                or when the destination file is missing"),
+        optflag("v", "verbose", "explain what is being done"),
+        optflag("h", "help", "display this help and exit"),
+        optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg()),
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [-T] SOURCE DEST", args.get(0));
+        println!("  {0} [OPTION]... SOURCE... DIRECTORY", args.get(0));
+        println!("  {0} [OPTION]... -t DIRECTORY SOURCE...", args.get(0));
+        println!("");
+        print!("{}", usage("Rename SOURCE to DEST, or move SOURCE(s) to DIRECTORY.", opts.as_slice()));
+        println!("");
+        println!("The backup suffix is '~', unless set with --suffix or SIMPLE_BACKUP_SUFFIX.");
+        println!("The version control method may be selected via the --backup option or through");
+        println!("the VERSION_CONTROL environment variable.  Here are the values:");
+        println!("");
+        println!("  none, off       never make backups (even if --backup is given)");
+        println!("  numbered, t     make numbered backups");
+        println!("  existing, nil   numbered if numbered backups exist, simple otherwise");
+        println!("  simple, never   always make simple backups");
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let target_dir = matches.opt_str("target-directory");
+    let no_target_dir = matches.opt_present("no-target-directory");
+    if target_dir.is_some() && no_target_dir {
+        crash!(1, "cannot combine --target-directory (-t) and --no-target-directory (-T)");
+    }
+
+    let (sources, target): (Vec<Path>, Path) = match target_dir {
+        Some(dir) => {
+            if matches.free.is_empty() {
+                crash!(1, "missing file operand");
+            }
+            (matches.free.iter().map(|arg| Path::new(arg.as_slice())).collect(),
+             Path::new(dir.as_slice()))
+        }
+        None => {
+            if matches.free.len() < 1 {
+                crash!(1, "missing file operand");
+            } else if matches.free.len() < 2 {
+                crash!(1, "missing destination file operand after '{}'", matches.free.get(0));
+            } else if no_target_dir && matches.free.len() > 2 {
+                crash!(1, "extra operand '{}'", matches.free.get(2));
+            }
+            (matches.free.init().iter().map(|arg| Path::new(arg.as_slice())).collect(),
+             Path::new(matches.free.last().unwrap().as_slice()))
+        }
+    };
+
+    let target_is_dir = !no_target_dir && match fsext::stat_path(&target) {
+        Ok(stat) => fsext::is_dir(stat.st_mode),
+        Err(_) => false
+    };
+    if (sources.len() > 1 || matches.opt_present("target-directory")) && !target_is_dir {
+        crash!(1, "target '{}' is not a directory", target.display());
+    }
+
+    let backup_mode = match backup::mode(matches.opt_present("backup") || matches.opt_present("b"),
+                                         matches.opt_str("backup")) {
+        Ok(mode) => mode,
+        Err(msg) => crash!(1, "{}", msg)
+    };
+    // whichever of -f, -i and -n comes last wins
+    let last = |name: &str| matches.opt_positions(name).last().map(|&pos| pos + 1).unwrap_or(0);
+    let (force, ask, no_clobber) = (last("force"), last("interactive"), last("no-clobber"));
+    let overwrite = if no_clobber > force && no_clobber > ask {
+        OverwriteNever
+    } else if ask > force {
+        OverwriteInteractive
+    } else {
+        OverwriteAlways
+    };
+    if overwrite == OverwriteNever && backup_mode != NoBackup {
+        crash!(1, "options --backup and --no-clobber are mutually exclusive");
+    }
+
+    let copy_options = copy::Options {
+        force: true,
+        recursive: true,
+        follow: FollowNone,
+        umask: fsext::umask(),
+        preserve: Preserve::all(),
+        reflink: ReflinkAuto,
+        sparse: SparseAuto,
+        overwrite: OverwriteAlways,
+        update: false,
+        progress: None,
+    };
+    let options = Options {
+        overwrite: overwrite,
+        update: matches.opt_present("update"),
+        verbose: matches.opt_present("verbose"),
+        backup: backup_mode,
+        suffix: backup::suffix(matches.opt_str("suffix")),
+        copy: copy_options,
+    };
+    let mut links = HashMap::new();
+
+    for source in sources.iter() {
+        let dest = if target_is_dir {
+            match source.filename() {
+                Some(name) => target.join(name),
+                None => {
+                    show_error!(1, "cannot move '{}' into '{}'",
+                                source.display(), target.display());
+                    continue;
+                }
+            }
+        } else {
+            target.clone()
+        };
+        move_path(source, &dest, &options, &mut links);
+    }
+}
+
+fn move_path(source: &Path, dest: &Path, options: &Options,
+             links: &mut HashMap<(u64, u64), Path>) {
+    let src_stat = match fsext::lstat_path(source) {
+        Ok(stat) => stat,
+        Err(e) => {
+            show_error!(1, "cannot stat '{}': {}", source.display(), util::strerror(&e));
+            return;
+        }
+    };
+    let is_dir = fsext::is_dir(src_stat.st_mode);
+    if is_dir && copy::is_inside(dest, source) {
+        show_error!(1, "cannot move '{}' to a subdirectory of itself, '{}'",
+                    source.display(), dest.display());
+        return;
+    }
+
+    let mut backup = None;
+    match fsext::lstat_path(dest) {
+        Ok(dest_stat) => {
+            if copy::same_file(&src_stat, &dest_stat) {
+                show_error!(1, "'{}' and '{}' are the same file",
+                            source.display(), dest.display());
+                return;
+            }
+            if !may_overwrite(&src_stat, dest, &dest_stat, options) {
+                return;
+            }
+            match backup::make_backup(dest, options.backup, options.suffix.as_slice()) {
+                Ok(path) => backup = path,
+                Err(e) => {
+                    show_error!(1, "cannot backup '{}': {}", dest.display(), util::strerror(&e));
+                    return;
+                }
+            }
+        }
+        Err(_) => {}
+    }
+
+    let moved = match rename(source, dest) {
+        Ok(()) => true,
+        // the file systems differ, so it has to be copied
+        Err((_, errno)) if errno == libc::EXDEV => {
+            move_across(source, dest, &src_stat, options, links)
+        }
+        Err((e, _)) => {
+            show_error!(1, "cannot move '{}' to '{}': {}", source.display(), dest.display(),
+                        util::strerror(&e));
+            false
+        }
+    };
+    if !moved {
+        restore_backup(&backup, dest);
+        return;
+    }
+    if options.verbose {
+        match backup {
+            Some(backup) => println!("renamed '{}' -> '{}' (backup: '{}')", source.display(),
+                                     dest.display(), backup.display()),
+            None => println!("renamed '{}' -> '{}'", source.display(), dest.display())
+        }
+    }
+}
+
+// rename(2), failing with the errno as well, which says more than IoError's
+// kind can: EXDEV when the rename would have to cross file systems.
+fn rename(source: &Path, dest: &Path) -> Result<(), (IoError, libc::c_int)> {
+    let ret = source.with_c_str(|s| dest.with_c_str(|d| unsafe { libc::rename(s, d) }));
+    if ret == 0 {
+        Ok(())
+    } else {
+        let errno = os::errno() as libc::c_int;
+        Err((IoError::last_error(), errno))
+    }
+}
+
+// The move failed, so the destination it was to replace comes back from its
+// backup, unless the move got as far as putting something in its place.
+fn restore_backup(backup: &Option<Path>, dest: &Path) {
+    match *backup {
+        Some(ref backup) if fsext::lstat_path(dest).is_err() => match fs::rename(backup, dest) {
+            Ok(()) => {}
+            Err(e) => show_error!(1, "cannot restore '{}' from '{}': {}", dest.display(),
+                                  backup.display(), util::strerror(&e))
+        },
+        _ => {}
+    }
+}
+
+// -u skips destinations at least as new as their source without asking, -i
+// asks about the rest.
+fn may_overwrite(src_stat: &fsext::c_stat, dest: &Path, dest_stat: &fsext::c_stat,
+                 options: &Options) -> bool {
+    if options.overwrite == OverwriteNever {
+        return false;
+    }
+    if options.update && fsext::mtime(dest_stat) >= fsext::mtime(src_stat) {
+        return false;
+    }
+    if options.overwrite == OverwriteInteractive {
+        return prompt_yes!("overwrite '{}'?", dest.display());
+    }
+    true
+}
+
+fn move_across(source: &Path, dest: &Path, src_stat: &fsext::c_stat, options: &Options,
+               links: &mut HashMap<(u64, u64), Path>) -> bool {
+    let is_dir = fsext::is_dir(src_stat.st_mode);
+
+    // The rename crossed file systems, so do by hand what it would have
+    // done: replace dest, keeping to the rules rename(2) has about types.
+    match fsext::lstat_path(dest) {
+        Ok(dest_stat) => {
+            let dest_is_dir = fsext::is_dir(dest_stat.st_mode);
+            let result = if is_dir && !dest_is_dir {
+                show_error!(1, "cannot overwrite non-directory '{}' with directory '{}'",
+                            dest.display(), source.display());
+                return false;
+            } else if !is_dir && dest_is_dir {
+                show_error!(1, "cannot overwrite directory '{}' with non-directory",
+                            dest.display());
+                return false;
+            } else if is_dir {
+                fs::rmdir(dest)
+            } else {
+                fs::unlink(dest)
+            };
+            match result {
+                Ok(()) => {}
+                Err(e) => {
+                    show_error!(1, "cannot move '{}' to '{}': {}",
+                                source.display(), dest.display(), util::strerror(&e));
+                    return false;
+                }
+            }
+        }
+        Err(_) => {}
+    }
+
+    // the source only goes once all of it has made it across
+    if !copy::copy_path(source, dest, &options.copy, links) {
+        return false;
+    }
+    Walker::new().follow(FollowNone).walk(source, &mut Remover);
+    true
+}
+
+// Deletes a tree from the bottom up.
+struct Remover;
+
+impl Visitor for Remover {
+    fn leave_dir(&mut self, dir: &WalkEntry) {
+        match fs::rmdir(&dir.path) {
+            Ok(()) => {}
+            Err(e) => show_error!(1, "cannot remove '{}': {}", dir.path.display(),
+                                  util::strerror(&e))
+        }
+    }
+
+    fn visit_file(&mut self, file: &WalkEntry) {
+        match fs::unlink(&file.path) {
+            Ok(()) => {}
+            Err(e) => show_error!(1, "cannot remove '{}': {}", file.path.display(),
+                                  util::strerror(&e))
+        }
+    }
+
+    fn error(&mut self, path: &Path, err: IoError) {
+        show_error!(1, "cannot remove '{}': {}", path.display(), util::strerror(&err));
+    }
+}
@@ -0,0 +1,49 @@
+use std::io;
+use std::io::fs;
+use std::io::process::Command;
+
+static PROG: &'static str = "build/mv";
+
+fn write_file(name: &str, contents: &str) {
+    io::File::create(&Path::new(name)).write_str(contents).unwrap();
+}
+
+fn read_file(name: &str) -> String {
+    io::File::open(&Path::new(name)).read_to_str().unwrap()
+}
+
+// Moves onto an existing destination, answering any prompt with `answer`,
+// and gives back what the destination holds afterwards.
+fn overwrite(args: &[&'static str], src: &str, dst: &str, answer: &str) -> String {
+    write_file(src, "new");
+    write_file(dst, "old");
+    let mut process = Command::new(PROG).args(args).arg(src).arg(dst).spawn().unwrap();
+    process.stdin.take_unwrap().write_str(answer).unwrap();
+    process.wait_with_output().unwrap();
+    let contents = read_file(dst);
+    let _ = fs::unlink(&Path::new(src));
+    fs::unlink(&Path::new(dst)).unwrap();
+    contents
+}
+
+#[test]
+fn test_rename() {
+    let (src, dst) = ("tmp/mv_rename_src", "tmp/mv_rename_dst");
+    write_file(src, "moved");
+    if !Command::new(PROG).args([src, dst]).status().unwrap().success() {
+        fail!();
+    }
+    assert!(!Path::new(src).exists());
+    assert_eq!(read_file(dst).as_slice(), "moved");
+    fs::unlink(&Path::new(dst)).unwrap();
+}
+
+#[test]
+fn test_last_of_force_interactive_and_no_clobber_wins() {
+    let (src, dst) = ("tmp/mv_clobber_src", "tmp/mv_clobber_dst");
+    assert_eq!(overwrite(["-i", "-f"], src, dst, "n\n").as_slice(), "new");
+    assert_eq!(overwrite(["-f", "-i"], src, dst, "n\n").as_slice(), "old");
+    assert_eq!(overwrite(["-n", "-i"], src, dst, "y\n").as_slice(), "new");
+    assert_eq!(overwrite(["-i", "-n"], src, dst, "y\n").as_slice(), "old");
+    assert_eq!(overwrite(["-n", "-f"], src, dst, "").as_slice(), "new");
+}
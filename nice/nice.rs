@@ -0,0 +1,162 @@
+#![crate_id(name="nice", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate libc;
+
+use std::os;
+use libc::{c_int, id_t};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/exec.rs"]
+mod exec;
+
+static NAME: &'static str = "nice";
+static VERSION: &'static str = "1.0.0";
+
+static PRIO_PROCESS: c_int = 0;
+
+extern {
+    fn getpriority(which: c_int, who: id_t) -> c_int;
+    fn setpriority(which: c_int, who: id_t, prio: c_int) -> c_int;
+
+    #[cfg(target_os = "linux")]
+    #[link_name = "__errno_location"]
+    fn errno_location() -> *mut c_int;
+    #[cfg(not(target_os = "linux"))]
+    #[link_name = "__error"]
+    fn errno_location() -> *mut c_int;
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    exec::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let prog = args.get(0).as_slice();
+    let mut adjustment = None;
+
+    // options stop at the command, whose own options are left to it
+    let mut i = 1;
+    while i < args.len() {
+        let opt = args.get(i).as_slice();
+        if opt == "--" {
+            i += 1;
+            break;
+        } else if !opt.starts_with("-") || opt == "-" {
+            break;
+        }
+        i += 1;
+
+        let value = if opt == "-n" || opt == "--adjustment" {
+            if i == args.len() {
+                bad_usage(prog, format!("option requires an argument -- '{}'", opt));
+            }
+            i += 1;
+            args.get(i - 1).as_slice()
+        } else if opt.starts_with("--adjustment=") {
+            opt.slice_from("--adjustment=".len())
+        } else if opt.starts_with("-n") {
+            opt.slice_from(2)
+        } else if opt == "--help" {
+            help(prog);
+            return;
+        } else if opt == "--version" {
+            println!("{} {}", NAME, VERSION);
+            return;
+        } else if parse_adjustment(opt.slice_from(1)).is_some() {
+            // the old -N form, where --N is a negative N
+            opt.slice_from(1)
+        } else {
+            bad_usage(prog, format!("invalid option -- '{}'", opt.slice_from(1)))
+        };
+        adjustment = match parse_adjustment(value) {
+            Some(n) => Some(n),
+            None => crash!(exec::EXIT_CANCELED, "invalid adjustment '{}'", value)
+        };
+    }
+
+    let command = args.slice_from(i);
+    if command.is_empty() {
+        if adjustment.is_some() {
+            bad_usage(prog, "a command must be given with an adjustment".to_string());
+        }
+        match niceness() {
+            Ok(n) => println!("{}", n),
+            Err(e) => crash!(exec::EXIT_CANCELED, "cannot get niceness: {}", e)
+        }
+        return;
+    }
+
+    let current = match niceness() {
+        Ok(n) => n,
+        Err(e) => crash!(exec::EXIT_CANCELED, "cannot get niceness: {}", e)
+    };
+    let niceness = current + adjustment.unwrap_or(10);
+    if unsafe { setpriority(PRIO_PROCESS, 0, niceness) } == -1 {
+        // without the privilege to raise the priority the command is still
+        // run, only at the priority it already had
+        let errno = os::errno() as c_int;
+        if errno == libc::EPERM || errno == libc::EACCES {
+            show_warning!("cannot set niceness: {}", os::last_os_error());
+        } else {
+            crash!(exec::EXIT_CANCELED, "cannot set niceness: {}", os::last_os_error());
+        }
+    }
+
+    exec::exec_or_exit(command);
+}
+
+fn help(prog: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0} [OPTION] [COMMAND [ARG]...]", prog);
+    println!("");
+    println!("Run COMMAND with an adjusted niceness, which affects process scheduling.");
+    println!("With no COMMAND, print the current niceness.  Niceness values range from");
+    println!("-20 (most favorable to the process) to 19 (least favorable to the process).");
+    println!("");
+    println!("  -n, --adjustment=N   add integer N to the niceness (default 10)");
+    println!("      --help           display this help and exit");
+    println!("      --version        output version information and exit");
+}
+
+fn bad_usage(prog: &str, msg: String) -> ! {
+    show_error!(exec::EXIT_CANCELED, "{}", msg);
+    crash!(exec::EXIT_CANCELED, "Try '{} --help' for more information.", prog)
+}
+
+// An integer with an optional sign, clamped to what a niceness can be
+// moved by so that adding it cannot overflow.
+fn parse_adjustment(s: &str) -> Option<c_int> {
+    let digits = if s.starts_with("+") { s.slice_from(1) } else { s };
+    from_str::<i64>(digits).map(|n| n.max(-40).min(40) as c_int)
+}
+
+// -1 is a niceness as well as the error return, so only errno tells them
+// apart.
+fn niceness() -> Result<c_int, String> {
+    unsafe {
+        *errno_location() = 0;
+        let n = getpriority(PRIO_PROCESS, 0);
+        if n == -1 && os::errno() != 0 {
+            Err(os::last_os_error())
+        } else {
+            Ok(n)
+        }
+    }
+}
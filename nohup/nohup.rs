@@ -0,0 +1,161 @@
+#![crate_id(name="nohup", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate libc;
+
+use std::os;
+use std::io::stderr;
+use libc::{c_int, O_APPEND, O_CREAT, O_RDONLY, O_WRONLY, STDERR_FILENO, STDIN_FILENO,
+           STDOUT_FILENO};
+use libc::funcs::posix88::unistd::{dup2, isatty};
+use fdio::Fd;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/exec.rs"]
+mod exec;
+
+#[path = "../common/fdio.rs"]
+mod fdio;
+
+#[path = "../common/signal.rs"]
+mod signal;
+
+static NAME: &'static str = "nohup";
+static VERSION: &'static str = "1.0.0";
+
+static OUTPUT_FILE: &'static str = "nohup.out";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    exec::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let prog = args.get(0).as_slice();
+    let start = match args.get(1).map(|s| s.as_slice()) {
+        Some("--help") => { help(prog); return }
+        Some("--version") => { println!("{} {}", NAME, VERSION); return }
+        Some("--") => 2,
+        Some(opt) if opt.starts_with("-") && opt.len() > 1 => {
+            show_error!(failure_status(), "invalid option -- '{}'", opt.slice_from(1));
+            crash!(failure_status(), "Try '{} --help' for more information.", prog)
+        }
+        _ => 1
+    };
+    let command = args.slice_from(start.min(args.len()));
+    if command.is_empty() {
+        show_error!(failure_status(), "missing operand");
+        crash!(failure_status(), "Try '{} --help' for more information.", prog)
+    }
+
+    let input_tty = is_tty(STDIN_FILENO);
+    let output_tty = is_tty(STDOUT_FILENO);
+    let error_tty = is_tty(STDERR_FILENO);
+
+    // a terminal that goes away with the hangup is no use to read from
+    if input_tty {
+        match Fd::open(&Path::new("/dev/null"), O_RDONLY, 0) {
+            Ok(null) => redirect(&null, STDIN_FILENO),
+            Err(e) => crash!(failure_status(), "failed to open '/dev/null': {}",
+                             util::strerror(&e))
+        }
+    }
+
+    if output_tty {
+        let (out, path) = open_output();
+        let message = if input_tty { "ignoring input and appending" } else { "appending" };
+        safe_writeln!(&mut stderr(), "{}: {} output to '{}'", NAME, message, path.display());
+        redirect(&out, STDOUT_FILENO);
+    } else if input_tty && !error_tty {
+        // otherwise the message about stderr says it
+        safe_writeln!(&mut stderr(), "{}: ignoring input", NAME);
+    }
+
+    if error_tty {
+        if !output_tty {
+            safe_writeln!(&mut stderr(), "{}: {}redirecting stderr to stdout", NAME,
+                          if input_tty { "ignoring input and " } else { "" });
+        }
+        if unsafe { dup2(STDOUT_FILENO, STDERR_FILENO) } < 0 {
+            crash!(failure_status(), "failed to redirect standard error: {}",
+                   os::last_os_error());
+        }
+    }
+
+    signal::ignore(signal::SIGHUP);
+    exec::exec_or_exit(command);
+}
+
+fn help(prog: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0} COMMAND [ARG]...", prog);
+    println!("  {0} OPTION", prog);
+    println!("");
+    println!("Run COMMAND, ignoring hangup signals.");
+    println!("");
+    println!("      --help     display this help and exit");
+    println!("      --version  output version information and exit");
+    println!("");
+    println!("If standard input is a terminal, redirect it from /dev/null.");
+    println!("If standard output is a terminal, append output to '{}' if possible,", OUTPUT_FILE);
+    println!("'$HOME/{}' otherwise.", OUTPUT_FILE);
+    println!("If standard error is a terminal, redirect it to standard output.");
+    println!("");
+    println!("Exit status is 125 if nohup itself fails (127 with POSIXLY_CORRECT set), 126 if");
+    println!("COMMAND is found but cannot be invoked, 127 if it cannot be found, and otherwise");
+    println!("the exit status of COMMAND.");
+}
+
+// nohup's own failures, which POSIX gives 127 rather than GNU's usual 125.
+fn failure_status() -> int {
+    if util::posixly_correct() { exec::EXIT_ENOENT } else { exec::EXIT_CANCELED }
+}
+
+fn is_tty(fd: c_int) -> bool {
+    unsafe { isatty(fd) == 1 }
+}
+
+// nohup.out in the current directory, or in $HOME when that cannot be
+// opened, appended to so that an earlier run's output is kept.
+fn open_output() -> (Fd, Path) {
+    let flags = O_WRONLY | O_CREAT | O_APPEND;
+    let path = Path::new(OUTPUT_FILE);
+    let err = match Fd::open(&path, flags, 0o600) {
+        Ok(out) => return (out, path),
+        Err(e) => e
+    };
+    match os::getenv("HOME") {
+        Some(home) => {
+            let path = Path::new(home).join(OUTPUT_FILE);
+            match Fd::open(&path, flags, 0o600) {
+                Ok(out) => (out, path),
+                Err(e) => crash!(failure_status(), "failed to open '{}': {}",
+                                 path.display(), util::strerror(&e))
+            }
+        }
+        None => crash!(failure_status(), "failed to open '{}': {}", OUTPUT_FILE,
+                       util::strerror(&err))
+    }
+}
+
+// Puts the file where fd was; the Fd itself is closed once that is done.
+fn redirect(file: &Fd, fd: c_int) {
+    if unsafe { dup2(file.raw(), fd) } < 0 {
+        crash!(failure_status(), "failed to redirect: {}", os::last_os_error());
+    }
+}
@@ -0,0 +1,160 @@
+#![crate_id(name="nproc", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::print;
+use libc::{c_int, c_long};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+static NAME: &'static str = "nproc";
+static VERSION: &'static str = "1.0.0";
+
+#[cfg(target_os = "linux")]
+static _SC_NPROCESSORS_CONF: c_int = 83;
+#[cfg(target_os = "linux")]
+static _SC_NPROCESSORS_ONLN: c_int = 84;
+#[cfg(not(target_os = "linux"))]
+static _SC_NPROCESSORS_CONF: c_int = 57;
+#[cfg(not(target_os = "linux"))]
+static _SC_NPROCESSORS_ONLN: c_int = 58;
+
+extern {
+    fn sysconf(name: c_int) -> c_long;
+    #[cfg(target_os = "linux")]
+    fn sched_getaffinity(pid: libc::pid_t, cpusetsize: libc::size_t, mask: *mut u8) -> c_int;
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("", "all", "print the number of installed processors"),
+        getopts::optopt("", "ignore", "if possible, exclude N processing units", "N"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]...", args.get(0));
+        println!("");
+        print(getopts::usage("Print the number of processing units available to the current \
+                              process,\nwhich may be less than the number of online processors",
+                             opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+    if !matches.free.is_empty() {
+        crash!(1, "extra operand '{}'", matches.free.get(0));
+    }
+
+    let ignore = match matches.opt_str("ignore") {
+        Some(n) => match from_str::<u64>(n.as_slice()) {
+            Some(n) => n,
+            None => crash!(1, "invalid number: '{}'", n)
+        },
+        None => 0
+    };
+
+    let count = if matches.opt_present("all") { installed() } else { available() };
+    println!("{}", if count > ignore { count - ignore } else { 1 });
+}
+
+fn installed() -> u64 {
+    match unsafe { sysconf(_SC_NPROCESSORS_CONF) } {
+        n if n > 0 => n as u64,
+        _ => available()
+    }
+}
+
+// What the process may run on, unless OpenMP's variables say to use some
+// other number of threads, up to its limit.
+fn available() -> u64 {
+    let limit = omp_env("OMP_THREAD_LIMIT");
+    let count = match omp_env("OMP_NUM_THREADS") {
+        Some(n) => n,
+        None => match affinity() {
+            Some(n) => n,
+            None => match unsafe { sysconf(_SC_NPROCESSORS_ONLN) } {
+                n if n > 0 => n as u64,
+                _ => 1
+            }
+        }
+    };
+    match limit {
+        Some(limit) if limit < count => limit,
+        _ => count
+    }
+}
+
+// The first of a comma-separated list of positive numbers, as OpenMP reads
+// the variable, or nothing if it is not one.
+fn omp_env(name: &str) -> Option<u64> {
+    let value = match os::getenv(name) {
+        Some(value) => value,
+        None => return None
+    };
+    let first = value.as_slice().split(',').next().unwrap_or("").trim();
+    match from_str::<u64>(first) {
+        Some(n) if n > 0 => Some(n),
+        _ => None
+    }
+}
+
+// The processors in the CPU affinity mask, which is bigger than the default
+// cpu_set_t on machines with more than 1024 of them.
+#[cfg(target_os = "linux")]
+fn affinity() -> Option<u64> {
+    let mut size = 128u;
+    loop {
+        let mut mask: Vec<u8> = Vec::from_elem(size, 0);
+        if unsafe { sched_getaffinity(0, size as libc::size_t, mask.as_mut_ptr()) } == 0 {
+            let count = mask.iter().fold(0u64, |n, &b| n + b.count_ones() as u64);
+            return if count > 0 { Some(count) } else { None };
+        }
+        if os::errno() != libc::EINVAL as int || size >= 1 << 16 {
+            return None;
+        }
+        size *= 2;
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn affinity() -> Option<u64> {
+    None
+}
@@ -20,22 +20,33 @@ use std::os;
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "paste";
 static VERSION: &'static str = "1.0.0";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("s", "serial", "paste one file at a time instead of in parallel"),
         getopts::optopt("d", "delimiters", "reuse characters from LIST instead of TABs", "LIST"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit")
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => crash!(1, "{}", f.to_err_msg())
     };
@@ -45,7 +56,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {0:s} [OPTION]... [FILE]...", program);
         println!("");
-        print!("{}", getopts::usage("Write lines consisting of the sequentially corresponding lines from each FILE, separated by TABs, to standard output.", opts));
+        print!("{}", getopts::usage("Write lines consisting of the sequentially corresponding lines from each FILE, separated by TABs, to standard output.", opts.as_slice()));
     } else if matches.opt_present("version") {
         println!("{} {}", NAME, VERSION);
     } else {
@@ -0,0 +1,169 @@
+#![crate_id(name="pathchk", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::print;
+use libc::{c_char, c_int, c_long};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+static NAME: &'static str = "pathchk";
+static VERSION: &'static str = "1.0.0";
+
+// The smallest limits POSIX allows a system to have.
+static POSIX_NAME_MAX: uint = 14;
+static POSIX_PATH_MAX: uint = 256;
+
+#[cfg(target_os = "linux")]
+static PC_NAME_MAX: c_int = 3;
+#[cfg(target_os = "linux")]
+static PC_PATH_MAX: c_int = 4;
+#[cfg(not(target_os = "linux"))]
+static PC_NAME_MAX: c_int = 4;
+#[cfg(not(target_os = "linux"))]
+static PC_PATH_MAX: c_int = 5;
+
+extern {
+    fn pathconf(path: *c_char, name: c_int) -> c_long;
+}
+
+struct Options {
+    // -p: hold to what POSIX promises rather than to this system
+    posix: bool,
+    // -P: also refuse empty names and components starting with '-'
+    extra: bool,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("p", "", "check for most POSIX systems"),
+        getopts::optflag("P", "", "check for empty names and leading \"-\""),
+        getopts::optflag("", "portability", "check for all POSIX systems (equivalent to -p -P)"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... NAME...", args.get(0));
+        println!("");
+        print(getopts::usage("Diagnose invalid or unportable file names.", opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    if matches.free.is_empty() {
+        crash!(1, "missing operand");
+    }
+
+    let portability = matches.opt_present("portability");
+    let options = Options {
+        posix: matches.opt_present("p") || portability,
+        extra: matches.opt_present("P") || portability,
+    };
+    for name in matches.free.iter() {
+        check(name.as_slice(), &options);
+    }
+}
+
+// Reports what is wrong with name, if anything, and says whether it passed.
+fn check(name: &str, options: &Options) -> bool {
+    if name.is_empty() {
+        // an empty name never resolves, but only the portability checks
+        // call it out
+        if options.posix || options.extra {
+            show_error!(1, "empty file name");
+        } else {
+            show_error!(1, "'': No such file or directory");
+        }
+        return false;
+    }
+
+    let components: Vec<&str> = name.split('/').filter(|c| !c.is_empty()).collect();
+    if options.extra && components.iter().any(|c| c.starts_with("-")) {
+        show_error!(1, "leading '-' in a component of file name '{}'", name);
+        return false;
+    }
+    if options.posix {
+        match name.chars().find(|&c| !is_portable(c)) {
+            Some(c) => {
+                show_error!(1, "nonportable character '{}' in file name '{}'", c, name);
+                return false;
+            }
+            None => {}
+        }
+    }
+
+    let path_max = if options.posix { POSIX_PATH_MAX } else { limit(name, PC_PATH_MAX, 4096) };
+    // the limit counts the terminating NUL
+    if name.len() + 1 > path_max {
+        show_error!(1, "limit {} exceeded by length {} of file name '{}'",
+                    path_max - 1, name.len(), name);
+        return false;
+    }
+    let name_max = if options.posix { POSIX_NAME_MAX } else { limit(name, PC_NAME_MAX, 255) };
+    match components.iter().find(|c| c.len() > name_max) {
+        Some(component) => {
+            show_error!(1, "limit {} exceeded by length {} of file name component '{}'",
+                        name_max, component.len(), component);
+            false
+        }
+        None => true
+    }
+}
+
+// The portable file name character set.
+fn is_portable(c: char) -> bool {
+    (c as u32) < 128 && (c.is_alphanumeric() || c == '.' || c == '_' || c == '-' || c == '/')
+}
+
+// The limit for the directory the name would be in, as far along as name
+// exists, or fallback when the system has no opinion.
+fn limit(name: &str, which: c_int, fallback: uint) -> uint {
+    let mut dir = Path::new(name);
+    if !dir.is_dir() {
+        dir.pop();
+        while !dir.is_dir() && dir.pop() {}
+    }
+    if !dir.is_dir() {
+        dir = Path::new(".");
+    }
+    let value = dir.with_c_str(|p| unsafe { pathconf(p, which) });
+    if value < 0 { fallback } else { value as uint }
+}
@@ -0,0 +1,288 @@
+#![crate_id(name="pinky", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+extern crate time;
+
+use std::os;
+use std::io::{print, stdout, File};
+use std::str::raw::from_c_str;
+use time::Timespec;
+use utmpx::Utmpx;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/c_types.rs"]
+mod c_types;
+
+#[path = "../common/utmpx.rs"]
+mod utmpx;
+
+static NAME: &'static str = "pinky";
+static VERSION: &'static str = "1.0.0";
+
+static S_IWGRP: libc::mode_t = 0o020;
+
+struct Options {
+    heading: bool,
+    fullname: bool,
+    idle: bool,
+    // the remote host
+    where_: bool,
+    home_and_shell: bool,
+    project: bool,
+    plan: bool,
+}
+
+// What pinky knows of a user from the password database.
+struct Passwd {
+    fullname: String,
+    dir: String,
+    shell: String,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("l", "", "produce long format output for the specified USERs"),
+        getopts::optflag("b", "", "omit the user's home directory and shell in long format"),
+        getopts::optflag("h", "", "omit the user's project file in long format"),
+        getopts::optflag("p", "", "omit the user's plan file in long format"),
+        getopts::optflag("s", "", "do short format output, this is the default"),
+        getopts::optflag("f", "", "omit the line of column headings in short format"),
+        getopts::optflag("w", "", "omit the user's full name in short format"),
+        getopts::optflag("i", "", "omit the user's full name and remote host in short format"),
+        getopts::optflag("q", "", "omit the user's full name, remote host and idle time in \
+                                   short format"),
+        getopts::optflag("", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [USER]...", args.get(0));
+        println!("");
+        let brief = format!("A lightweight 'finger' program;  print user information.\n\
+                             The utmp file will be {}.", utmpx::DEFAULT_FILE);
+        print(getopts::usage(brief.as_slice(), opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let quick = matches.opt_present("q");
+    let omit_where = matches.opt_present("i") || quick;
+    let options = Options {
+        heading: !matches.opt_present("f"),
+        fullname: !(matches.opt_present("w") || omit_where),
+        idle: !quick,
+        where_: !omit_where,
+        home_and_shell: !matches.opt_present("b"),
+        project: !matches.opt_present("h"),
+        plan: !matches.opt_present("p"),
+    };
+
+    // -s after -l goes back to the short format, as the last one given wins
+    let long = match (matches.opt_positions("l").last(), matches.opt_positions("s").last()) {
+        (Some(l), Some(s)) => l > s,
+        (Some(_), None) => true,
+        _ => false
+    };
+
+    if long {
+        if matches.free.is_empty() {
+            crash!(1, "no username specified; at least one must be specified when using -l");
+        }
+        for name in matches.free.iter() {
+            long_pinky(name.as_slice(), &options);
+        }
+    } else {
+        short_pinky(&matches.free, &options);
+    }
+}
+
+fn short_pinky(users: &Vec<String>, options: &Options) {
+    let records = match utmpx::read_utmpx(utmpx::DEFAULT_FILE) {
+        Ok(records) => records,
+        Err(e) => crash!(1, "{}: {}", utmpx::DEFAULT_FILE, util::strerror(&e))
+    };
+
+    if options.heading {
+        let mut line = format!("{:<8}", "Login");
+        if options.fullname {
+            line.push_str(format!(" {:<19}", " Name").as_slice());
+        }
+        line.push_str(format!(" {:<9}", " TTY").as_slice());
+        if options.idle {
+            line.push_str(format!(" {:<6}", "Idle").as_slice());
+        }
+        line.push_str(format!(" {:<16}", "When").as_slice());
+        if options.where_ {
+            line.push_str(" Where");
+        }
+        println!("{}", line);
+    }
+
+    for record in records.iter().filter(|r| r.is_user_process()) {
+        if users.is_empty() || users.contains(&record.user) {
+            print_entry(record, options);
+        }
+    }
+}
+
+fn print_entry(record: &Utmpx, options: &Options) {
+    let mut line = format!("{:<8}", record.user);
+    if options.fullname {
+        let name = match passwd(record.user.as_slice()) {
+            Some(pw) => pw.fullname,
+            None => "        ???".to_string()
+        };
+        line.push_str(format!(" {:<19}", truncate(name.as_slice(), 19)).as_slice());
+    }
+
+    // '*' for a terminal that cannot be written to with write(1), and '?'
+    // for one that is not there at all
+    let tty = Path::new(format!("/dev/{}", record.line));
+    let (mesg, last_change) = match fsext::stat_path(&tty) {
+        Ok(stat) => {
+            let writable = stat.st_mode as libc::mode_t & S_IWGRP != 0;
+            (if writable { ' ' } else { '*' }, Some(fsext::atime(&stat)))
+        }
+        Err(_) => ('?', None)
+    };
+    line.push_char(' ');
+    line.push_char(mesg);
+    line.push_str(format!("{:<8}", record.line).as_slice());
+
+    if options.idle {
+        let idle = match last_change {
+            Some((secs, _)) => idle_string(secs),
+            None => "???".to_string()
+        };
+        line.push_str(format!(" {:<6}", idle).as_slice());
+    }
+    line.push_str(format!(" {}", format_time(record.time)).as_slice());
+    if options.where_ && !record.host.is_empty() {
+        line.push_str(format!(" {}", record.host).as_slice());
+    }
+    println!("{}", line);
+}
+
+fn long_pinky(name: &str, options: &Options) {
+    let pw = match passwd(name) {
+        Some(pw) => pw,
+        None => {
+            println!("Login name: {:<28}In real life:  ???", name);
+            return;
+        }
+    };
+
+    println!("Login name: {:<28}In real life:  {}", name, pw.fullname);
+    if options.home_and_shell {
+        println!("Directory: {:<29}Shell:  {}", pw.dir, pw.shell);
+    }
+
+    let home = Path::new(pw.dir.as_slice());
+    if options.project {
+        match File::open(&home.join(".project")).read_to_end() {
+            Ok(project) => {
+                copy_out(b"Project: ");
+                copy_out(project.as_slice());
+            }
+            Err(_) => {}
+        }
+    }
+    if options.plan {
+        match File::open(&home.join(".plan")).read_to_end() {
+            Ok(plan) => {
+                copy_out(b"Plan:\n");
+                copy_out(plan.as_slice());
+            }
+            Err(_) => {}
+        }
+    }
+    println!("");
+}
+
+fn copy_out(contents: &[u8]) {
+    match stdout().write(contents) {
+        Ok(()) => {}
+        Err(e) => crash!(1, "write error: {}", util::strerror(&e))
+    }
+}
+
+fn passwd(name: &str) -> Option<Passwd> {
+    c_types::getpw(name).map(|pw| unsafe {
+        Passwd {
+            fullname: fullname(from_c_str(pw.pw_gecos).as_slice(), name),
+            dir: from_c_str(pw.pw_dir),
+            shell: from_c_str(pw.pw_shell),
+        }
+    })
+}
+
+// The GECOS field up to the first comma, with any & in it standing for the
+// login name, capitalised.
+fn fullname(gecos: &str, login: &str) -> String {
+    let name = gecos.split(',').next().unwrap_or("");
+    let mut capitalised = String::new();
+    for (i, c) in login.chars().enumerate() {
+        capitalised.push_char(if i == 0 { c.to_uppercase() } else { c });
+    }
+    name.replace("&", capitalised.as_slice())
+}
+
+fn truncate(s: &str, width: uint) -> String {
+    s.chars().take(width).collect()
+}
+
+// Nothing for under a minute, then hours and minutes, then days.
+fn idle_string(last_change: i64) -> String {
+    let idle = time::get_time().sec - last_change;
+    if idle < 60 {
+        "     ".to_string()
+    } else if idle < 24 * 60 * 60 {
+        format!("{:02}:{:02}", idle / 3600, idle % 3600 / 60)
+    } else {
+        format!("{}d", idle / (24 * 60 * 60))
+    }
+}
+
+fn format_time(secs: i64) -> String {
+    time::at(Timespec::new(secs, 0)).strftime("%Y-%m-%d %H:%M")
+}
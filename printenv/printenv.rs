@@ -17,27 +17,38 @@ extern crate getopts;
 extern crate libc;
 
 use std::os;
-use std::io::print;
+use std::io::{print, stdout};
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "printenv";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("0", "null", "end each output line with 0 byte rather than newline"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
-            crash!(1, "Invalid options\n{}", f.to_err_msg())
+            crash!(2, "Invalid options\n{}", f.to_err_msg())
         }
     };
     if matches.opt_present("help") {
@@ -46,7 +57,9 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {0:s} [VARIABLE]... [OPTION]...", program);
         println!("");
-        print(getopts::usage("Prints the given environment VARIABLE(s), otherwise prints them all.", opts).as_slice());
+        print(getopts::usage("Prints the given environment VARIABLE(s), otherwise prints them all.", opts.as_slice()).as_slice());
+        println!("");
+        println!("Exit status is 0 if every VARIABLE is set, 1 if any is not, and 2 for an error.");
         return;
     }
     if matches.opt_present("version") {
@@ -61,23 +74,37 @@ pub fn uumain(args: Vec<String>) {
     exec(matches.free, separator);
 }
 
+// Prints every variable, or the values of the ones asked for, setting the
+// exit status to 1 when one of those is not set.
 pub fn exec(args: Vec<String>, separator: &str) {
+    let vars = os::env_as_bytes();
     if args.is_empty() {
-        let vars = os::env();
-        for (env_var, value) in vars.move_iter() {
-            print!("{0:s}={1:s}", env_var, value);
-            print(separator);
+        for &(ref env_var, ref value) in vars.iter() {
+            write_out([env_var.as_slice(), b"=", value.as_slice(), separator.as_bytes()]);
         }
         return;
     }
 
     for env_var in args.iter() {
-        match os::getenv(env_var.as_slice()) {
-            Some(var) => {
-                print(var.as_slice());
-                print(separator);
-            }
-            _ => ()
+        // a name with = in it could only match part of a variable
+        let found = if env_var.as_slice().contains_char('=') {
+            None
+        } else {
+            vars.iter().find(|&&(ref name, _)| name.as_slice() == env_var.as_bytes())
+        };
+        match found {
+            Some(&(_, ref value)) => write_out([value.as_slice(), separator.as_bytes()]),
+            None => util::set_status(1)
+        }
+    }
+}
+
+fn write_out(parts: &[&[u8]]) {
+    let mut out = stdout();
+    for part in parts.iter() {
+        match out.write(*part) {
+            Ok(()) => {}
+            Err(e) => crash!(2, "write error: {}", util::strerror(&e))
         }
     }
 }
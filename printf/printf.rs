@@ -0,0 +1,438 @@
+#![crate_id(name="printf", vers="1.0.0", author="agent")]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+#![feature(macro_rules)]
+
+extern crate libc;
+
+use std::os;
+use std::char;
+use std::io::stdio::stdout_raw;
+use libc::{c_char, c_double, c_int, c_longlong, c_ulonglong, size_t};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/escape.rs"]
+mod escape;
+
+static NAME: &'static str = "printf";
+static VERSION: &'static str = "1.0.0";
+
+extern {
+    fn snprintf(s: *mut c_char, n: size_t, format: *c_char, ...) -> c_int;
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+
+    if args.len() == 2 {
+        match args.get(1).as_slice() {
+            "--help" => { help(program.as_slice()); return }
+            "--version" => { println!("{} {}", NAME, VERSION); return }
+            _ => {}
+        }
+    }
+
+    // printf takes no options, but `--` is allowed before the format.
+    let rest = if args.len() > 1 && args.get(1).as_slice() == "--" {
+        args.slice_from(2)
+    } else {
+        args.tail()
+    };
+    if rest.is_empty() {
+        show_error!(1, "missing operand");
+        show_error!(1, "for help, try '{0:s} --help'", program);
+        return;
+    }
+
+    let format = rest[0].as_bytes();
+    let mut state = State {
+        args: rest.slice_from(1),
+        pos: 0,
+        out: Vec::new(),
+        stopped: false,
+    };
+
+    // The format is reused as often as necessary to consume all of the
+    // arguments.  A format without any conversions is printed only once.
+    loop {
+        let used = state.format(format);
+        if state.stopped || state.pos >= state.args.len() || used == 0 {
+            if !state.stopped && used == 0 && state.pos < state.args.len() {
+                show_warning!("ignoring excess arguments, starting with '{}'",
+                              state.args[state.pos]);
+            }
+            break;
+        }
+    }
+
+    match stdout_raw().write(state.out.as_slice()) {
+        Ok(_) => {}
+        Err(f) => crash!(1, "write error: {}", util::strerror(&f))
+    }
+}
+
+fn help(program: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0:s} FORMAT [ARGUMENT]...", program);
+    println!("  {0:s} OPTION", program);
+    println!("");
+    println!("Print ARGUMENT(s) according to FORMAT.");
+    println!("");
+    println!("  --help     display this help and exit");
+    println!("  --version  output version information and exit");
+    println!("");
+    println!("FORMAT controls the output as in C printf.  Interpreted sequences are:
+\\\"      double quote
+\\\\      backslash
+\\a      alert (BEL)
+\\b      backspace
+\\c      produce no further output
+\\e      escape
+\\f      form feed
+\\n      new line
+\\r      carriage return
+\\t      horizontal tab
+\\v      vertical tab
+\\NNN    byte with octal value NNN (1 to 3 digits)
+\\xHH    byte with hexadecimal value HH (1 to 2 digits)
+%%      a single %
+%b      ARGUMENT as a string with '\\' escapes interpreted,
+        except that octal escapes are of the form \\0 or \\0NNN
+
+and all C format specifications ending with one of diouxXfeEgGcs, with
+ARGUMENTs converted to proper type first.  Variable widths are handled.");
+}
+
+struct State<'a> {
+    args: &'a [String],
+    pos: uint,
+    out: Vec<u8>,
+    // set once \c has been seen
+    stopped: bool,
+}
+
+struct Spec {
+    flags: String,
+    width: Option<int>,
+    precision: Option<int>,
+    conversion: u8,
+}
+
+impl<'a> State<'a> {
+    fn next_arg(&mut self) -> Option<&'a str> {
+        if self.pos < self.args.len() {
+            self.pos += 1;
+            Some(self.args[self.pos - 1].as_slice())
+        } else {
+            None
+        }
+    }
+
+    // Processes the format once, returning the number of arguments used.
+    fn format(&mut self, format: &[u8]) -> uint {
+        let start = self.pos;
+        let mut i = 0;
+        while i < format.len() && !self.stopped {
+            match format[i] as char {
+                '\\' => {
+                    let (escape, used) = escape::parse(format.slice_from(i + 1), escape::OctalBare);
+                    match escape {
+                        escape::Byte(b) => self.out.push(b),
+                        escape::Stop => self.stopped = true,
+                        escape::NotEscape => self.out.push('\\' as u8)
+                    }
+                    i += used + 1;
+                }
+                '%' => {
+                    i += 1;
+                    if i < format.len() && format[i] == '%' as u8 {
+                        self.out.push('%' as u8);
+                        i += 1;
+                        continue;
+                    }
+                    match self.parse_spec(format, &mut i) {
+                        Some(spec) => self.convert(spec),
+                        None => {
+                            crash!(1, "{}: invalid conversion specification",
+                                   String::from_utf8_lossy(format.slice_from(i - 1)))
+                        }
+                    }
+                }
+                _ => {
+                    self.out.push(format[i]);
+                    i += 1;
+                }
+            }
+        }
+        self.pos - start
+    }
+
+    fn star_or_number(&mut self, format: &[u8], i: &mut uint) -> Option<int> {
+        if *i < format.len() && format[*i] == '*' as u8 {
+            *i += 1;
+            let arg = self.next_arg();
+            return Some(parse_signed(arg) as int);
+        }
+        let begin = *i;
+        while *i < format.len() && char::is_digit(format[*i] as char) {
+            *i += 1;
+        }
+        if *i == begin {
+            None
+        } else {
+            from_str::<int>(std::str::from_utf8(format.slice(begin, *i)).unwrap())
+        }
+    }
+
+    fn parse_spec(&mut self, format: &[u8], i: &mut uint) -> Option<Spec> {
+        let mut flags = String::new();
+        while *i < format.len() {
+            match format[*i] as char {
+                '-' | '+' | ' ' | '#' | '0' | '\'' => flags.push_char(format[*i] as char),
+                _ => break
+            }
+            *i += 1;
+        }
+        let mut width = self.star_or_number(format, i);
+        // a negative width from `*` means left-justification
+        match width {
+            Some(w) if w < 0 => {
+                flags.push_char('-');
+                width = Some(-w);
+            }
+            _ => {}
+        }
+        let mut precision = None;
+        if *i < format.len() && format[*i] == '.' as u8 {
+            *i += 1;
+            precision = match self.star_or_number(format, i) {
+                Some(p) if p < 0 => None,
+                Some(p) => Some(p),
+                None => Some(0)
+            };
+        }
+        // length modifiers are accepted and ignored
+        while *i < format.len() {
+            match format[*i] as char {
+                'h' | 'l' | 'L' | 'q' | 'j' | 'z' | 't' => *i += 1,
+                _ => break
+            }
+        }
+        if *i >= format.len() {
+            return None;
+        }
+        let conversion = format[*i];
+        *i += 1;
+        match conversion as char {
+            'd' | 'i' | 'o' | 'u' | 'x' | 'X' | 'e' | 'E' | 'f' | 'F' |
+            'g' | 'G' | 'a' | 'A' | 'c' | 's' | 'b' => {
+                Some(Spec { flags: flags, width: width, precision: precision, conversion: conversion })
+            }
+            _ => None
+        }
+    }
+
+    fn convert(&mut self, spec: Spec) {
+        match spec.conversion as char {
+            'd' | 'i' => {
+                let value = parse_signed(self.next_arg());
+                let cspec = c_spec(&spec, "ll");
+                self.out.push_all(c_format(cspec.as_slice(), |buf, len, fmt| unsafe {
+                    snprintf(buf, len, fmt, value as c_longlong)
+                }).as_slice());
+            }
+            'o' | 'u' | 'x' | 'X' => {
+                let value = parse_unsigned(self.next_arg());
+                let cspec = c_spec(&spec, "ll");
+                self.out.push_all(c_format(cspec.as_slice(), |buf, len, fmt| unsafe {
+                    snprintf(buf, len, fmt, value as c_ulonglong)
+                }).as_slice());
+            }
+            'e' | 'E' | 'f' | 'F' | 'g' | 'G' | 'a' | 'A' => {
+                let value = parse_float(self.next_arg());
+                let cspec = c_spec(&spec, "");
+                self.out.push_all(c_format(cspec.as_slice(), |buf, len, fmt| unsafe {
+                    snprintf(buf, len, fmt, value as c_double)
+                }).as_slice());
+            }
+            'c' => {
+                let bytes = match self.next_arg() {
+                    Some(s) if s.len() > 0 => vec!(s.as_bytes()[0]),
+                    _ => vec!(0u8)
+                };
+                self.pad(&spec, bytes.as_slice());
+            }
+            's' => {
+                let arg = self.next_arg().unwrap_or("");
+                let bytes = truncate(arg.as_bytes(), spec.precision);
+                self.pad(&spec, bytes);
+            }
+            'b' => {
+                let arg = self.next_arg().unwrap_or("");
+                let mut expanded = Vec::new();
+                if !escape::unescape(arg.as_bytes(), escape::OctalZeroPrefixed, &mut expanded) {
+                    self.stopped = true;
+                }
+                let bytes = truncate(expanded.as_slice(), spec.precision);
+                self.pad(&spec, bytes);
+            }
+            _ => unreachable!()
+        }
+    }
+
+    fn pad(&mut self, spec: &Spec, bytes: &[u8]) {
+        let width = spec.width.unwrap_or(0) as uint;
+        let fill = if width > bytes.len() { width - bytes.len() } else { 0 };
+        let left = spec.flags.as_slice().contains_char('-');
+        if !left {
+            for _ in range(0, fill) { self.out.push(' ' as u8); }
+        }
+        self.out.push_all(bytes);
+        if left {
+            for _ in range(0, fill) { self.out.push(' ' as u8); }
+        }
+    }
+}
+
+fn truncate<'a>(bytes: &'a [u8], precision: Option<int>) -> &'a [u8] {
+    match precision {
+        Some(p) if (p as uint) < bytes.len() => bytes.slice_to(p as uint),
+        _ => bytes
+    }
+}
+
+// Builds the C equivalent of `spec` with the given length modifier.
+fn c_spec(spec: &Spec, length: &str) -> String {
+    let mut cspec = "%".to_string();
+    for c in spec.flags.as_slice().chars() {
+        // grouping depends on the C locale and is not portable to snprintf
+        if c != '\'' {
+            cspec.push_char(c);
+        }
+    }
+    match spec.width {
+        Some(w) => cspec.push_str(w.to_str().as_slice()),
+        None => {}
+    }
+    match spec.precision {
+        Some(p) => { cspec.push_char('.'); cspec.push_str(p.to_str().as_slice()); }
+        None => {}
+    }
+    cspec.push_str(length);
+    cspec.push_char(spec.conversion as char);
+    cspec
+}
+
+fn c_format(cspec: &str, print: |*mut c_char, size_t, *c_char| -> c_int) -> Vec<u8> {
+    let mut size = 64u;
+    loop {
+        let mut buf: Vec<u8> = Vec::from_elem(size, 0u8);
+        let written = cspec.with_c_str(|fmt| {
+            print(buf.as_mut_ptr() as *mut c_char, size as size_t, fmt)
+        });
+        if written < 0 {
+            crash!(1, "formatting error");
+        }
+        let written = written as uint;
+        if written < size {
+            buf.truncate(written);
+            return buf;
+        }
+        size = written + 1;
+    }
+}
+
+// Splits a numeric argument into a sign and the remaining digits, with
+// support for the 'c and "c forms which denote the value of character c.
+fn char_value(arg: &str) -> Option<i64> {
+    if arg.len() >= 2 && (arg.starts_with("'") || arg.starts_with("\"")) {
+        Some(arg.slice_from(1).char_at(0) as i64)
+    } else {
+        None
+    }
+}
+
+fn parse_integer(arg: &str) -> Option<(bool, u64)> {
+    let arg = arg.trim_left();
+    let (negative, digits) = if arg.starts_with("-") {
+        (true, arg.slice_from(1))
+    } else if arg.starts_with("+") {
+        (false, arg.slice_from(1))
+    } else {
+        (false, arg)
+    };
+    let (radix, digits) = if digits.starts_with("0x") || digits.starts_with("0X") {
+        (16, digits.slice_from(2))
+    } else if digits.len() > 1 && digits.starts_with("0") {
+        (8, digits.slice_from(1))
+    } else {
+        (10, digits)
+    };
+    std::num::from_str_radix::<u64>(digits, radix).map(|v| (negative, v))
+}
+
+fn invalid_number(arg: &str) {
+    show_error!(1, "'{}': expected a numeric value", arg);
+}
+
+fn parse_signed(arg: Option<&str>) -> i64 {
+    let arg = match arg { Some(a) => a, None => return 0 };
+    match char_value(arg) {
+        Some(v) => return v,
+        None => {}
+    }
+    match parse_integer(arg) {
+        Some((true, v)) => -(v as i64),
+        Some((false, v)) => v as i64,
+        None => { invalid_number(arg); 0 }
+    }
+}
+
+fn parse_unsigned(arg: Option<&str>) -> u64 {
+    let arg = match arg { Some(a) => a, None => return 0 };
+    match char_value(arg) {
+        Some(v) => return v as u64,
+        None => {}
+    }
+    match parse_integer(arg) {
+        // like strtoumax, negative values wrap around
+        Some((true, v)) => -(v as i64) as u64,
+        Some((false, v)) => v,
+        None => { invalid_number(arg); 0 }
+    }
+}
+
+fn parse_float(arg: Option<&str>) -> f64 {
+    let arg = match arg { Some(a) => a, None => return 0.0 };
+    match char_value(arg) {
+        Some(v) => return v as f64,
+        None => {}
+    }
+    match from_str::<f64>(arg.trim_left()) {
+        Some(v) => v,
+        None => match parse_integer(arg) {
+            Some((true, v)) => -(v as f64),
+            Some((false, v)) => v as f64,
+            None => { invalid_number(arg); 0.0 }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::io::process::Command;
+use std::str;
+
+static PROG: &'static str = "build/printf";
+
+fn run(args: &[&'static str]) -> String {
+    let po = match Command::new(PROG).args(args).output() {
+        Ok(p) => p,
+        Err(err) => fail!("{}", err),
+    };
+    str::from_utf8(po.output.as_slice()).unwrap().to_string()
+}
+
+#[test]
+fn test_string_width() {
+    assert_eq!(run(["[%5s|%-5s]", "ab", "cd"]).as_slice(), "[   ab|cd   ]");
+}
+
+#[test]
+fn test_integers() {
+    assert_eq!(run(["%d %i %o %u %x %X", "-12", "0x10", "8", "7", "255", "255"]).as_slice(),
+               "-12 16 10 7 ff FF");
+}
+
+#[test]
+fn test_char_value() {
+    assert_eq!(run(["%d", "'A"]).as_slice(), "65");
+}
+
+#[test]
+fn test_floats() {
+    assert_eq!(run(["%.2f %e %g", "3.14159", "1000", "0.0001"]).as_slice(),
+               "3.14 1.000000e+03 0.0001");
+}
+
+#[test]
+fn test_star_width_and_precision() {
+    assert_eq!(run(["%*.*s|", "6", "3", "abcdef"]).as_slice(), "   abc|");
+}
+
+#[test]
+fn test_argument_cycling() {
+    assert_eq!(run(["%s-%s\\n", "a", "b", "c"]).as_slice(), "a-b\nc-\n");
+}
+
+#[test]
+fn test_escapes() {
+    assert_eq!(run(["\\101\\x42%b", "\\0103\\c", "ignored"]).as_slice(), "ABC");
+}
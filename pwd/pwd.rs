@@ -15,25 +15,47 @@ extern crate getopts;
 extern crate libc;
 
 use std::os;
-use std::io::print;
+use std::io::{fs, print, stdout, IoError, IoResult};
+use libc::{c_char, size_t};
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
 static NAME: &'static str = "pwd";
 static VERSION: &'static str = "1.0.0";
 
+extern {
+    fn getcwd(buf: *mut c_char, size: size_t) -> *c_char;
+}
+
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("L", "logical", "use PWD from environment, even if it contains \
+                                          symlinks"),
+        getopts::optflag("P", "physical", "avoid all symlinks"),
         getopts::optflag("", "help", "display this help and exit"),
         getopts::optflag("", "version", "output version information and exit"),
-    ];
+    )
+}
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
             crash!(1, "Invalid options\n{}", f.to_err_msg())
@@ -44,13 +66,115 @@ pub fn uumain(args: Vec<String>) {
         println!("pwd {}", VERSION);
         println!("");
         println!("Usage:");
-        println!("  {0:s} [OPTION] NAME...", program);
+        println!("  {0:s} [OPTION]...", program);
         println!("");
-        print(getopts::usage("Print the full filename of the current working directory.", opts).as_slice());
+        print(getopts::usage("Print the full filename of the current working directory.", opts.as_slice()).as_slice());
+        println!("");
+        println!("If no option is specified, -L is assumed.");
     } else if matches.opt_present("version") {
         return println!("pwd version: {}", VERSION);
     } else {
-        let cwd = std::os::getcwd();
-        println!("{}", cwd.display());
+        // whichever of -L and -P comes last wins
+        let physical = match (matches.opt_positions("logical").last(),
+                              matches.opt_positions("physical").last()) {
+            (Some(l), Some(p)) => p > l,
+            (None, Some(_)) => true,
+            _ => false
+        };
+        let logical = if physical { None } else { logical_cwd() };
+        let cwd = match logical {
+            Some(cwd) => cwd,
+            None => match physical_cwd() {
+                Ok(cwd) => cwd,
+                Err(e) => crash!(1, "cannot determine current directory: {}", util::strerror(&e))
+            }
+        };
+        let mut out = stdout();
+        match out.write(cwd.as_slice()).and_then(|_| out.write(['\n' as u8])) {
+            Ok(()) => {}
+            Err(e) => crash!(1, "write error: {}", util::strerror(&e))
+        }
+    }
+}
+
+// $PWD, if it is an absolute name for the current directory without . or ..
+// in it, which is all that stops it from being trusted.
+fn logical_cwd() -> Option<Vec<u8>> {
+    let pwd = match os::getenv_as_bytes("PWD") {
+        Some(pwd) => pwd,
+        None => return None
+    };
+    if pwd.len() == 0 || pwd[0] != '/' as u8 {
+        return None;
+    }
+    if pwd.as_slice().split(|b| *b == '/' as u8).any(|c| c == b"." || c == b"..") {
+        return None;
+    }
+    match (fsext::stat_path(&Path::new(pwd.as_slice())), fsext::stat_path(&Path::new("."))) {
+        (Ok(a), Ok(b)) if a.st_dev == b.st_dev && a.st_ino == b.st_ino => Some(pwd),
+        _ => None
+    }
+}
+
+// getcwd(3), or working it out by hand when that fails, as it can when the
+// name is longer than PATH_MAX.
+fn physical_cwd() -> IoResult<Vec<u8>> {
+    let mut size = 256u;
+    loop {
+        let mut buf: Vec<u8> = Vec::from_elem(size, 0u8);
+        let ret = unsafe { getcwd(buf.as_mut_ptr() as *mut c_char, size as size_t) };
+        if ret.is_not_null() {
+            let len = buf.iter().position(|&b| b == 0).unwrap_or(size);
+            buf.truncate(len);
+            return Ok(buf);
+        }
+        let err = IoError::last_error();
+        if os::errno() == libc::ERANGE as int && size < 1 << 20 {
+            size *= 2;
+            continue;
+        }
+        return walk_up().or(Err(err));
+    }
+}
+
+// Finds the name of each directory in its parent, from the current one up
+// to the root, which is the directory that is its own parent.
+fn walk_up() -> IoResult<Vec<u8>> {
+    let mut names: Vec<Vec<u8>> = vec!();
+    let mut dir = Vec::from_slice(b".");
+    loop {
+        let here = try!(fsext::lstat_path(&Path::new(dir.as_slice())));
+        let mut parent = dir.clone();
+        parent.push_all(b"/..");
+        let up = try!(fsext::lstat_path(&Path::new(parent.as_slice())));
+        if here.st_dev == up.st_dev && here.st_ino == up.st_ino {
+            break;
+        }
+        let mut found = None;
+        for entry in try!(fs::readdir(&Path::new(parent.as_slice()))).iter() {
+            // a mount point has the inode of the root of what is mounted on
+            // it, so the device is compared as well
+            match fsext::lstat_path(entry) {
+                Ok(stat) if stat.st_dev == here.st_dev && stat.st_ino == here.st_ino => {
+                    found = entry.filename().map(|name| Vec::from_slice(name));
+                    break;
+                }
+                _ => {}
+            }
+        }
+        match found {
+            Some(name) => names.push(name),
+            None => return Err(IoError::from_errno(libc::ENOENT as uint, false))
+        }
+        dir = parent;
+    }
+    let mut cwd = vec!();
+    for name in names.iter().rev() {
+        cwd.push('/' as u8);
+        cwd.push_all(name.as_slice());
+    }
+    if cwd.is_empty() {
+        cwd.push('/' as u8);
     }
+    Ok(cwd)
 }
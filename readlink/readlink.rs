@@ -0,0 +1,138 @@
+#![crate_id(name="readlink", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::{print, stdout};
+
+use canon::{CanonMode, CanonExisting, CanonAllButLast, CanonMissing};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/canon.rs"]
+mod canon;
+
+static NAME: &'static str = "readlink";
+static VERSION: &'static str = "1.0.0";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("f", "canonicalize", "canonicalize by following every symlink in every \
+                                               component of the given name recursively; all but \
+                                               the last component must exist"),
+        getopts::optflag("e", "canonicalize-existing", "canonicalize by following every symlink \
+                                                        in every component of the given name \
+                                                        recursively, all components must exist"),
+        getopts::optflag("m", "canonicalize-missing", "canonicalize by following every symlink \
+                                                       in every component of the given name \
+                                                       recursively, without requirements on \
+                                                       components existence"),
+        getopts::optflag("n", "no-newline", "do not output the trailing delimiter"),
+        getopts::optflag("q", "quiet", "suppress most error messages"),
+        getopts::optflag("s", "silent", "suppress most error messages"),
+        getopts::optflag("v", "verbose", "report error messages"),
+        getopts::optflag("z", "zero", "end each output line with NUL, not newline"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... FILE...", args.get(0));
+        println!("");
+        print(getopts::usage("Print value of a symbolic link or canonical file name.",
+                             opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    if matches.free.is_empty() {
+        crash!(1, "missing operand");
+    }
+
+    // the last of -f, -e and -m wins
+    let mut mode: Option<CanonMode> = None;
+    let mut last = None;
+    for &(name, canon_mode) in [("f", CanonAllButLast), ("e", CanonExisting),
+                                ("m", CanonMissing)].iter() {
+        match matches.opt_positions(name).last() {
+            Some(&pos) if last.map_or(true, |last| pos > last) => {
+                mode = Some(canon_mode);
+                last = Some(pos);
+            }
+            _ => {}
+        }
+    }
+
+    let verbose = matches.opt_present("verbose")
+                  && !(matches.opt_present("quiet") || matches.opt_present("silent"));
+    let mut no_newline = matches.opt_present("no-newline");
+    if no_newline && matches.free.len() > 1 {
+        if verbose {
+            show_warning!("ignoring --no-newline with multiple arguments");
+        }
+        no_newline = false;
+    }
+    let delimiter = if matches.opt_present("zero") { 0u8 } else { '\n' as u8 };
+
+    let mut out = stdout();
+    for name in matches.free.iter() {
+        let result = match mode {
+            Some(mode) => canon::canonicalize(name.as_bytes(), mode).map(|path| path.into_vec()),
+            None => fsext::read_link(&Path::new(name.as_slice()))
+        };
+        match result {
+            Ok(target) => {
+                crash_if_err!(1, out.write(target.as_slice()));
+                if !no_newline {
+                    crash_if_err!(1, out.write_u8(delimiter));
+                }
+            }
+            Err(e) => {
+                if verbose {
+                    show_error!(1, "{}: {}", name, util::strerror(&e));
+                } else {
+                    util::set_status(1);
+                }
+            }
+        }
+    }
+}
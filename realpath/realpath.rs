@@ -0,0 +1,194 @@
+#![crate_id(name="realpath", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::{print, stdout, IoResult};
+
+use canon::{CanonMode, CanonExisting, CanonAllButLast, CanonMissing};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/canon.rs"]
+mod canon;
+
+static NAME: &'static str = "realpath";
+static VERSION: &'static str = "1.0.0";
+
+struct Options {
+    mode: CanonMode,
+    // -s: only tidy the name up, without looking at any links
+    strip: bool,
+    // -L: ".." takes away the component before it as written
+    logical: bool,
+    relative_to: Option<Path>,
+    relative_base: Option<Path>,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("e", "canonicalize-existing", "all components of the path must exist"),
+        getopts::optflag("m", "canonicalize-missing", "no path components need exist or be a \
+                                                       directory"),
+        getopts::optflag("L", "logical", "resolve '..' components before symlinks"),
+        getopts::optflag("P", "physical", "resolve symlinks as encountered (default)"),
+        getopts::optflag("q", "quiet", "suppress most error messages"),
+        getopts::optopt("", "relative-to", "print the resolved path relative to DIR", "DIR"),
+        getopts::optopt("", "relative-base", "print absolute paths unless paths below DIR",
+                        "DIR"),
+        getopts::optflag("s", "strip", "don't expand symlinks"),
+        getopts::optflag("", "no-symlinks", "same as -s"),
+        getopts::optflag("z", "zero", "end each output line with NUL, not newline"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... FILE...", args.get(0));
+        println!("");
+        print(getopts::usage("Print the resolved absolute file name; all but the last \
+                              component must exist.", opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    if matches.free.is_empty() {
+        crash!(1, "missing operand");
+    }
+
+    let mode = if matches.opt_present("canonicalize-existing") {
+        CanonExisting
+    } else if matches.opt_present("canonicalize-missing") {
+        CanonMissing
+    } else {
+        CanonAllButLast
+    };
+    let strip = matches.opt_present("strip") || matches.opt_present("no-symlinks");
+    let logical = strip || matches.opt_present("logical");
+    // the directories are resolved the same way as the names
+    let relative_to = match matches.opt_str("relative-to") {
+        Some(dir) => Some(resolve_dir(dir.as_slice(), mode, strip, logical)),
+        None => None
+    };
+    let relative_base = match matches.opt_str("relative-base") {
+        Some(dir) => Some(resolve_dir(dir.as_slice(), mode, strip, logical)),
+        None => None
+    };
+    // a base that does not hold the directory to be relative to never applies
+    let relative_base = match (&relative_to, relative_base) {
+        (&Some(ref to), Some(ref base)) if !base.is_ancestor_of(to) => None,
+        (_, base) => base
+    };
+    let options = Options {
+        mode: mode,
+        strip: strip,
+        logical: logical,
+        relative_to: relative_to,
+        relative_base: relative_base,
+    };
+
+    let quiet = matches.opt_present("quiet");
+    let delimiter = if matches.opt_present("zero") { 0u8 } else { '\n' as u8 };
+    let mut out = stdout();
+    for name in matches.free.iter() {
+        match resolve(name.as_slice(), &options) {
+            Ok(path) => {
+                let path = relativize(path, &options);
+                crash_if_err!(1, out.write(path.as_vec()));
+                crash_if_err!(1, out.write_u8(delimiter));
+            }
+            Err(e) => {
+                if quiet {
+                    util::set_status(1);
+                } else {
+                    show_error!(1, "{}: {}", name, util::strerror(&e));
+                }
+            }
+        }
+    }
+}
+
+fn resolve(name: &str, options: &Options) -> IoResult<Path> {
+    if !options.logical {
+        return canon::canonicalize(name.as_bytes(), options.mode);
+    }
+    // Path folds "." and ".." as written
+    let path = os::make_absolute(&Path::new(name));
+    if !options.strip {
+        return canon::canonicalize(path.as_vec(), options.mode);
+    }
+    // what has to exist still does, links or not
+    let must_exist = match options.mode {
+        CanonExisting => Some(path.clone()),
+        CanonAllButLast => Some(path.dir_path()),
+        CanonMissing => None
+    };
+    match must_exist {
+        Some(ref path) => { try!(fsext::stat_path(path)); }
+        None => {}
+    }
+    Ok(path)
+}
+
+fn resolve_dir(dir: &str, mode: CanonMode, strip: bool, logical: bool) -> Path {
+    let options = Options {
+        mode: mode,
+        strip: strip,
+        logical: logical,
+        relative_to: None,
+        relative_base: None,
+    };
+    match resolve(dir, &options) {
+        Ok(path) => path,
+        Err(e) => crash!(1, "{}: {}", dir, util::strerror(&e))
+    }
+}
+
+fn relativize(path: Path, options: &Options) -> Path {
+    let base = match (&options.relative_to, &options.relative_base) {
+        (_, &Some(ref base)) if !base.is_ancestor_of(&path) => return path,
+        (&Some(ref to), _) => to,
+        (&None, &Some(ref base)) => base,
+        (&None, &None) => return path
+    };
+    path.path_relative_from(base).unwrap_or(path)
+}
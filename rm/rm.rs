@@ -14,12 +14,25 @@
 extern crate getopts;
 extern crate libc;
 
+use std::cmp;
 use std::os;
-use std::io::{print, stdin, stdio, fs, BufferedReader};
+use std::io;
+use std::io::{print, fs, IoError};
+use libc::funcs::posix88::unistd::{access, isatty};
+use walk::{FollowNone, Visitor, WalkEntry, Walker};
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/walk.rs"]
+mod walk;
+
 #[deriving(Eq, PartialEq)]
 enum InteractiveMode {
     InteractiveNone,
@@ -27,21 +40,35 @@ enum InteractiveMode {
     InteractiveAlways
 }
 
+struct Options {
+    force: bool,
+    interactive: InteractiveMode,
+    one_fs: bool,
+    preserve_root: bool,
+    recursive: bool,
+    dir: bool,
+    verbose: bool,
+    // whether stdin is a terminal that can be asked about write-protected
+    // files
+    tty: bool,
+}
+
 static NAME: &'static str = "rm";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    // TODO: make getopts support -R in addition to -r
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("f", "force", "ignore nonexistent files and arguments, never prompt"),
         getopts::optflag("i", "", "prompt before every removal"),
         getopts::optflag("I", "", "prompt once before removing more than three files, or when removing recursively.  Less intrusive than -i, while still giving some protection against most mistakes"),
         getopts::optflagopt("", "interactive", "prompt according to WHEN: never, once (-I), or always (-i).  Without WHEN, prompts always", "WHEN"),
-        getopts::optflag("", "one-file-system", "when removing a hierarchy recursively, skip any directory that is on a file system different from that of the corresponding command line argument (NOT IMPLEMENTED)"),
+        getopts::optflag("", "one-file-system", "when removing a hierarchy recursively, skip any directory that is on a file system different from that of the corresponding command line argument"),
         getopts::optflag("", "no-preserve-root", "do not treat '/' specially"),
         getopts::optflag("", "preserve-root", "do not remove '/' (default)"),
         getopts::optflag("r", "recursive", "remove directories and their contents recursively"),
@@ -49,8 +76,15 @@ pub fn uumain(args: Vec<String>) {
         getopts::optflag("v", "verbose", "explain what is being done"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit")
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+
+    // TODO: make getopts support -R in addition to -r
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
             crash!(1, "{}", f.to_err_msg())
@@ -62,7 +96,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {0:s} [OPTION]... [FILE]...", program);
         println!("");
-        print(getopts::usage("Remove (unlink) the FILE(s).", opts).as_slice());
+        print(getopts::usage("Remove (unlink) the FILE(s).", opts.as_slice()).as_slice());
         println!("");
         println!("By default, rm does not remove directories.  Use the --recursive (-r)");
         println!("option to remove each listed directory, too, along with all of its contents");
@@ -79,146 +113,251 @@ pub fn uumain(args: Vec<String>) {
     } else if matches.opt_present("version") {
         println!("rm 1.0.0");
     } else if matches.free.is_empty() {
-        show_error!(1, "missing an argument");
-        show_error!(1, "for help, try '{0:s} --help'", program)
+        // -f is for scripts, which should not be told off for having nothing
+        // to remove
+        if !matches.opt_present("force") {
+            show_error!(1, "missing an argument");
+            show_error!(1, "for help, try '{0:s} --help'", program)
+        }
     } else {
-        let force = matches.opt_present("force");
-        let interactive =
-            if matches.opt_present("i") {
-                InteractiveAlways
-            } else if matches.opt_present("I") {
-                InteractiveOnce
-            } else if matches.opt_present("interactive") {
-                match matches.opt_str("interactive").unwrap().as_slice() {
-                    "none" => InteractiveNone,
-                    "once" => InteractiveOnce,
-                    "always" => InteractiveAlways,
-                    val => {
-                        crash!(1, "Invalid argument to interactive ({})", val)
-                    }
+        // whichever of -f, -i, -I and --interactive comes last wins
+        let last = |name: &str| matches.opt_positions(name).last().map(|&p| p + 1).unwrap_or(0);
+        let when = match matches.opt_strs("interactive").last() {
+            None => InteractiveAlways,
+            Some(val) => match val.as_slice() {
+                "never" | "no" | "none" => InteractiveNone,
+                "once" => InteractiveOnce,
+                "always" | "yes" => InteractiveAlways,
+                val => {
+                    crash!(1, "Invalid argument to interactive ({})", val)
                 }
-            } else {
-                InteractiveNone
-            };
+            }
+        };
+        let choices = [(last("force"), InteractiveNone), (last("i"), InteractiveAlways),
+                       (last("I"), InteractiveOnce), (last("interactive"), when)];
+        let (_, interactive) = choices.iter().fold((0, InteractiveNone), |best, &choice| {
+            if choice.val0() > best.val0() { choice } else { best }
+        });
+        // -f stops complaints about missing files until something asks for
+        // prompts again; --interactive=never leaves it be
+        let asked = if when == InteractiveNone { 0 } else { last("interactive") };
+        let prompted = cmp::max(cmp::max(last("i"), last("I")), asked);
+        let force = last("force") > prompted;
         let one_fs = matches.opt_present("one-file-system");
         let preserve_root = !matches.opt_present("no-preserve-root");
         let recursive = matches.opt_present("recursive");
         let dir = matches.opt_present("dir");
         let verbose = matches.opt_present("verbose");
         if interactive == InteractiveOnce && (recursive || matches.free.len() > 3) {
-            let msg =
-                if recursive {
-                    "Remove all arguments recursively? "
-                } else {
-                    "Remove all arguments? "
-                };
-            if !prompt(msg) {
+            let count = matches.free.len();
+            let answer = prompt_yes!("remove {} argument{}{}?", count,
+                                     if count == 1 { "" } else { "s" },
+                                     if recursive { " recursively" } else { "" });
+            if !answer {
                 return;
             }
         }
-        remove(matches.free, force, interactive, one_fs, preserve_root,
-               recursive, dir, verbose);
+        let options = Options {
+            force: force,
+            interactive: interactive,
+            one_fs: one_fs,
+            preserve_root: preserve_root,
+            recursive: recursive,
+            dir: dir,
+            verbose: verbose,
+            tty: unsafe { isatty(libc::STDIN_FILENO) } == 1,
+        };
+        remove(matches.free, &options);
     }
 }
 
-// TODO: implement one-file-system
-fn remove(files: Vec<String>, force: bool, interactive: InteractiveMode, one_fs: bool, preserve_root: bool, recursive: bool, dir: bool, verbose: bool) {
+fn remove(files: Vec<String>, options: &Options) {
+    let root = fsext::stat_path(&Path::new("/")).ok();
     for filename in files.iter() {
         let filename = filename.as_slice();
         let file = Path::new(filename);
-        if file.exists() {
-            if file.is_dir() {
-                if recursive && (filename != "/" || !preserve_root) {
-                    let walk_dir = match fs::walk_dir(&file) {
-                        Ok(m) => m,
-                        Err(f) => {
-                            crash!(1, "{}", f.to_str());
-                        }
-                    };
-                    remove(walk_dir.map(|x| x.as_str().unwrap().to_string()).collect(), force, interactive, one_fs, preserve_root, recursive, dir, verbose);
-                    remove_dir(&file, filename, interactive, verbose);
-                } else if dir && (filename != "/" || !preserve_root) {
-                    remove_dir(&file, filename, interactive, verbose);
+        // checked before Path gets to normalise the name
+        match filename.trim_right_chars('/').split('/').last() {
+            Some(".") | Some("..") => {
+                show_error!(1, "refusing to remove '.' or '..' directory: skipping '{}'",
+                            filename);
+                continue;
+            }
+            _ => {}
+        }
+        // symbolic links are removed, never followed
+        let stat = match fsext::lstat_path(&file) {
+            Ok(stat) => stat,
+            Err(ref e) if options.force && e.kind == io::FileNotFound => continue,
+            Err(e) => {
+                show_error!(1, "cannot remove '{}': {}", filename, util::strerror(&e));
+                continue;
+            }
+        };
+        if fsext::is_dir(stat.st_mode) {
+            // "/" can be spelt many ways, so compare the directories themselves
+            let is_root = match root {
+                Some(ref root) => root.st_dev == stat.st_dev && root.st_ino == stat.st_ino,
+                None => false
+            };
+            if options.recursive && is_root && options.preserve_root {
+                show_error!(1, "it is dangerous to operate recursively on '{}'", filename);
+                show_error!(1, "use --no-preserve-root to override this failsafe");
+            } else if options.recursive {
+                let mut visitor = RemoveVisitor {
+                    options: options,
+                    device: stat.st_dev as u64,
+                    failed: vec!(),
+                };
+                Walker::new().follow(FollowNone).sorted(false).walk(&file, &mut visitor);
+            } else if options.dir {
+                remove_dir(&file, &stat, options);
+            } else {
+                if options.recursive {
+                    show_error!(1, "could not remove directory '{}'",
+                                   filename);
                 } else {
-                    if recursive {
-                        show_error!(1, "could not remove directory '{}'",
-                                       filename);
-                    } else {
-                        show_error!(1,
-                                    "could not remove directory '{}' (did you mean to pass '-r'?)",
-                                    filename);
-                    }
+                    show_error!(1,
+                                "could not remove directory '{}' (did you mean to pass '-r'?)",
+                                filename);
                 }
-            } else {
-                remove_file(&file, filename.as_slice(), interactive, verbose);
             }
-        } else if !force {
-            show_error!(1, "no such file or directory '{}'", filename);
+        } else {
+            remove_file(&file, &stat, options);
         }
     }
 }
 
-fn remove_dir(path: &Path, name: &str, interactive: InteractiveMode, verbose: bool) {
-    let response =
-        if interactive == InteractiveAlways {
-            prompt_file(path, name)
+// Removes a tree children first.  Whatever cannot be removed is reported and
+// skipped, along with the directories above it, which could not be removed
+// anyway.
+struct RemoveVisitor<'a> {
+    options: &'a Options,
+    // the device of the command line argument, for --one-file-system
+    device: u64,
+    // for each directory being removed, whether something in it was left
+    failed: Vec<bool>,
+}
+
+impl<'a> RemoveVisitor<'a> {
+    fn fail(&mut self) {
+        match self.failed.mut_last() {
+            Some(failed) => *failed = true,
+            None => {}
+        }
+    }
+}
+
+impl<'a> Visitor for RemoveVisitor<'a> {
+    fn enter_dir(&mut self, dir: &WalkEntry) -> bool {
+        if self.options.one_fs && dir.stat.st_dev as u64 != self.device {
+            show_error!(1, "skipping '{}', since it's on a different device", dir.path.display());
+            self.failed.push(true);
+            return false;
+        }
+        // an empty directory is simply removed, without descending
+        let empty = match fs::readdir(&dir.path) {
+            Ok(entries) => entries.is_empty(),
+            Err(_) => false
+        };
+        let protected = write_protected(&dir.path, &dir.stat, self.options);
+        let descend = empty || if self.options.interactive == InteractiveAlways || protected {
+            prompt_yes!("descend into {}directory '{}'?",
+                        if protected { "write-protected " } else { "" }, dir.path.display())
         } else {
             true
         };
-    if response {
+        self.failed.push(!descend);
+        descend
+    }
+
+    fn leave_dir(&mut self, dir: &WalkEntry) {
+        if self.failed.pop() == Some(true) {
+            self.fail();
+            return;
+        }
+        if !remove_dir(&dir.path, &dir.stat, self.options) {
+            self.fail();
+        }
+    }
+
+    fn visit_file(&mut self, file: &WalkEntry) {
+        if !remove_file(&file.path, &file.stat, self.options) {
+            self.fail();
+        }
+    }
+
+    fn error(&mut self, path: &Path, err: IoError) {
+        show_error!(1, "cannot remove '{}': {}", path.display(), util::strerror(&err));
+        self.fail();
+    }
+}
+
+fn remove_dir(path: &Path, stat: &fsext::c_stat, options: &Options) -> bool {
+    if confirm(path, stat, options) {
         match fs::rmdir(path) {
-            Ok(_) => if verbose { println!("Removed '{}'", name); },
+            Ok(_) => {
+                if options.verbose { println!("Removed '{}'", path.display()); }
+                return true;
+            }
             Err(f) => {
-                show_error!(1, "{}", f.to_str());
+                show_error!(1, "cannot remove '{}': {}", path.display(), util::strerror(&f));
             }
         }
     }
+    false
 }
 
-fn remove_file(path: &Path, name: &str, interactive: InteractiveMode, verbose: bool) {
-    let response =
-        if interactive == InteractiveAlways {
-            prompt_file(path, name)
-        } else {
-            true
-        };
-    if response {
+fn remove_file(path: &Path, stat: &fsext::c_stat, options: &Options) -> bool {
+    if confirm(path, stat, options) {
         match fs::unlink(path) {
-            Ok(_) => if verbose { println!("Removed '{}'", name); },
+            Ok(_) => {
+                if options.verbose { println!("Removed '{}'", path.display()); }
+                return true;
+            }
             Err(f) => {
-                show_error!(1, "{}", f.to_str());
+                show_error!(1, "cannot remove '{}': {}", path.display(), util::strerror(&f));
             }
         }
     }
+    false
 }
 
-fn prompt_file(path: &Path, name: &str) -> bool {
-    if path.is_dir() {
-        prompt(format!("Remove directory '{}'? ", name).as_slice())
+// Whether path should go: -i always asks, and otherwise so does removing a
+// write-protected file from a terminal without -f.
+fn confirm(path: &Path, stat: &fsext::c_stat, options: &Options) -> bool {
+    let protected = write_protected(path, stat, options);
+    if options.interactive == InteractiveAlways || protected {
+        prompt_yes!("remove {}{} '{}'?", if protected { "write-protected " } else { "" },
+                    describe(stat), path.display())
     } else {
-        prompt(format!("Remove file '{}'? ", name).as_slice())
+        true
     }
 }
 
-fn prompt(msg: &str) -> bool {
-    print(msg);
-    read_prompt()
+// Symbolic links have no permissions of their own to protect them.
+fn write_protected(path: &Path, stat: &fsext::c_stat, options: &Options) -> bool {
+    if options.force || !options.tty || fsext::is_lnk(stat.st_mode) {
+        return false;
+    }
+    path.with_c_str(|p| unsafe { access(p, libc::W_OK) }) != 0
 }
 
-fn read_prompt() -> bool {
-    stdio::flush();
-    match BufferedReader::new(stdin()).read_line() {
-        Ok(line) => {
-            match line.as_slice().char_at(0) {
-                'y' | 'Y' => true,
-                'n' | 'N' => false,
-                _ => {
-                    print!("Please enter either Y or N: ");
-                    read_prompt()
-                }
-            }
-        }
-        Err(_) => true
+fn describe(stat: &fsext::c_stat) -> &'static str {
+    let mode = stat.st_mode as libc::mode_t;
+    if fsext::is_dir(mode) {
+        "directory"
+    } else if fsext::is_lnk(mode) {
+        "symbolic link"
+    } else if fsext::is_reg(mode) {
+        if stat.st_size == 0 { "regular empty file" } else { "regular file" }
+    } else if fsext::is_fifo(mode) {
+        "fifo"
+    } else if fsext::is_chr(mode) {
+        "character special file"
+    } else if fsext::is_blk(mode) {
+        "block special file"
+    } else {
+        "file"
     }
 }
-
@@ -0,0 +1,44 @@
+use std::io;
+use std::io::process::{Command, ExitStatus};
+
+static PROG: &'static str = "build/rm";
+
+// Runs rm with `answer` for any prompt, giving back its exit status.
+fn run(args: &[&'static str], answer: &str) -> int {
+    let mut process = Command::new(PROG).args(args).spawn().unwrap();
+    process.stdin.take_unwrap().write_str(answer).unwrap();
+    match process.wait_with_output().unwrap().status {
+        ExitStatus(c) => c,
+        _ => -1
+    }
+}
+
+fn make_file(name: &str) {
+    io::File::create(&Path::new(name)).unwrap();
+}
+
+#[test]
+fn test_remove_file() {
+    let file = "tmp/rm_test_file";
+    make_file(file);
+    assert_eq!(run([file], ""), 0);
+    assert!(!Path::new(file).exists());
+}
+
+#[test]
+fn test_last_of_force_and_interactive_wins() {
+    let file = "tmp/rm_test_prompt";
+    make_file(file);
+    assert_eq!(run(["-f", "-i", file], "n\n"), 0);
+    assert!(Path::new(file).exists());
+    assert_eq!(run(["-i", "-f", file], "n\n"), 0);
+    assert!(!Path::new(file).exists());
+}
+
+#[test]
+fn test_missing_file() {
+    let file = "tmp/rm_test_missing";
+    assert_eq!(run(["-f", file], ""), 0);
+    assert_eq!(run(["-f", "-i", file], ""), 1);
+    assert_eq!(run([file], ""), 1);
+}
@@ -20,22 +20,33 @@ use std::io::{print, fs};
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "rmdir";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("", "ignore-fail-on-non-empty", "ignore each failure that is solely because a directory is non-empty"),
         getopts::optflag("p", "parents", "remove DIRECTORY and its ancestors; e.g., 'rmdir -p a/b/c' is similar to rmdir a/b/c a/b a"),
         getopts::optflag("v", "verbose", "output a diagnostic for every directory processed"),
         getopts::optflag("h", "help", "print this help and exit"),
         getopts::optflag("V", "version", "output version information and exit")
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
             show_error!(1, "{}", f.to_err_msg());
@@ -49,7 +60,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {0:s} [OPTION]... DIRECTORY...", program);
         println!("");
-        print(getopts::usage("Remove the DIRECTORY(ies), if they are empty.", opts).as_slice());
+        print(getopts::usage("Remove the DIRECTORY(ies), if they are empty.", opts.as_slice()).as_slice());
     } else if matches.opt_present("version") {
         println!("rmdir 1.0.0");
     } else if matches.free.is_empty() {
@@ -65,46 +76,40 @@ pub fn uumain(args: Vec<String>) {
 
 fn remove(dirs: Vec<String>, ignore: bool, parents: bool, verbose: bool) {
     for dir in dirs.iter() {
-        let path = Path::new(dir.as_slice());
-        if path.exists() {
-            if path.is_dir() {
-                remove_dir(&path, dir.as_slice(), ignore, parents, verbose);
-            } else {
-                show_error!(1, "failed to remove '{}' (file)", *dir);
+        let mut dir = dir.as_slice();
+        loop {
+            if !remove_dir(dir, ignore, verbose) || !parents {
+                break;
+            }
+            // -p works on the name as given, so a/b/c is followed by a/b and
+            // then a, whatever they turn out to be
+            let trimmed = dir.trim_right_chars('/');
+            dir = match trimmed.rfind('/') {
+                Some(slash) => trimmed.slice_to(slash).trim_right_chars('/'),
+                None => break
+            };
+            if dir.is_empty() {
+                break;
             }
-        } else {
-            show_error!(1, "no such file or directory '{}'", *dir);
         }
     }
 }
 
-fn remove_dir(path: &Path, dir: &str, ignore: bool, parents: bool, verbose: bool) {
-    let mut walk_dir = match fs::walk_dir(path) {
-        Ok(m) => m,
+// Returns whether dir has gone.  Directories that are not empty are quietly
+// left alone with --ignore-fail-on-non-empty.
+fn remove_dir(dir: &str, ignore: bool, verbose: bool) -> bool {
+    if verbose {
+        println!("{}: removing directory, '{}'", NAME, dir);
+    }
+    match fs::rmdir(&Path::new(dir)) {
+        Ok(_) => true,
         Err(f) => {
-            show_error!(1, "{}", f.to_str());
-            return;
-        }
-    };
-    if walk_dir.next() == None {
-        match fs::rmdir(path) {
-            Ok(_) => {
-                if verbose {
-                    println!("Removed directory '{}'", dir);
-                }
-                if parents {
-                    let dirname = path.dirname_str().unwrap();
-                    if dirname != "." {
-                        remove_dir(&Path::new(dirname), dirname, ignore, parents, verbose);
-                    }
-                }
-            }
-            Err(f) => {
-                show_error!(1, "{}", f.to_str());
+            let errno = os::errno() as libc::c_int;
+            let non_empty = errno == libc::ENOTEMPTY || errno == libc::EEXIST;
+            if !(ignore && non_empty) {
+                show_error!(1, "failed to remove '{}': {}", dir, util::strerror(&f));
             }
+            false
         }
-    } else if !ignore {
-        show_error!(1, "Failed to remove directory '{}' (non-empty)", dir);
     }
 }
-
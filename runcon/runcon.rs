@@ -0,0 +1,274 @@
+#![crate_id(name="runcon", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * This talks to the kernel through selinuxfs and /proc rather than through
+ * libselinux: the context for the command is written to
+ * /proc/self/attr/exec, which the kernel moves the process to at execve(2).
+ * It is only built with `make SELINUX=1`.
+ */
+
+extern crate libc;
+
+use std::os;
+use std::str;
+use std::io::{File, IoResult};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/exec.rs"]
+mod exec;
+
+#[path = "../common/xattr.rs"]
+mod xattr;
+
+static NAME: &'static str = "runcon";
+static VERSION: &'static str = "1.0.0";
+
+// Which parts of the context -u, -r, -t and -l replace.
+struct Components {
+    user: Option<String>,
+    role: Option<String>,
+    type_: Option<String>,
+    range: Option<String>,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    exec::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let prog = args.get(0).as_slice();
+    let mut compute = false;
+    let mut components = Components { user: None, role: None, type_: None, range: None };
+
+    // options stop at the context or the command
+    let mut i = 1;
+    while i < args.len() {
+        let opt = args.get(i).as_slice();
+        if opt == "--" {
+            i += 1;
+            break;
+        } else if !opt.starts_with("-") || opt == "-" {
+            break;
+        }
+        i += 1;
+
+        let (name, value) = if opt.starts_with("--") {
+            match opt.find('=') {
+                Some(eq) => (opt.slice_to(eq), Some(opt.slice_from(eq + 1).to_string())),
+                None => (opt, None)
+            }
+        } else if opt.len() > 2 {
+            (opt.slice_to(2), Some(opt.slice_from(2).to_string()))
+        } else {
+            (opt, None)
+        };
+        match name {
+            "--help" => { help(prog); return }
+            "--version" => { println!("{} {}", NAME, VERSION); return }
+            "-c" | "--compute" if value.is_none() => compute = true,
+            "-u" | "--user" | "-r" | "--role" | "-t" | "--type" | "-l" | "--range" => {
+                let value = match value {
+                    Some(value) => value,
+                    None if i < args.len() => {
+                        i += 1;
+                        args.get(i - 1).clone()
+                    }
+                    None => bad_usage(prog, format!("option '{}' requires an argument", name))
+                };
+                match name {
+                    "-u" | "--user" => components.user = Some(value),
+                    "-r" | "--role" => components.role = Some(value),
+                    "-t" | "--type" => components.type_ = Some(value),
+                    _ => components.range = Some(value)
+                }
+            }
+            _ => bad_usage(prog, format!("unrecognized option '{}'", opt))
+        }
+    }
+
+    let any_component = components.user.is_some() || components.role.is_some() ||
+                        components.type_.is_some() || components.range.is_some();
+    let operands = args.slice_from(i);
+    // without any of the options the context is given whole, before the
+    // command
+    let (context, command) = if !compute && !any_component {
+        match operands.len() {
+            0 => {
+                print_current();
+                return;
+            }
+            1 => bad_usage(prog, "missing operand".to_string()),
+            _ => (Some(operands[0].clone()), operands.tail())
+        }
+    } else {
+        (None, operands)
+    };
+    if command.is_empty() {
+        bad_usage(prog, "no command specified".to_string());
+    }
+
+    if !selinux_enabled() {
+        crash!(exec::EXIT_CANCELED, "{} may be used only on a SELinux kernel", NAME);
+    }
+
+    let context = match context {
+        Some(context) => context,
+        None => {
+            let base = if compute {
+                match transition_context(command[0].as_slice()) {
+                    Ok(context) => context,
+                    Err(e) => crash!(exec::EXIT_CANCELED, "failed to compute a new context: {}",
+                                     e)
+                }
+            } else {
+                match current_context() {
+                    Ok(context) => context,
+                    Err(e) => crash!(exec::EXIT_CANCELED, "failed to get current context: {}",
+                                     util::strerror(&e))
+                }
+            };
+            with_components(base.as_slice(), &components)
+        }
+    };
+
+    match set_exec_context(context.as_slice()) {
+        Ok(()) => {}
+        Err(e) => crash!(exec::EXIT_CANCELED, "unable to set security context '{}': {}",
+                         context, util::strerror(&e))
+    }
+    exec::exec_or_exit(command);
+}
+
+fn help(prog: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0} CONTEXT COMMAND [args]", prog);
+    println!("  {0} [ -c ] [-u USER] [-r ROLE] [-t TYPE] [-l RANGE] COMMAND [args]", prog);
+    println!("");
+    println!("Run a program in a different SELinux security context.");
+    println!("With neither CONTEXT nor COMMAND, print the current security context.");
+    println!("");
+    println!("  CONTEXT            Complete security context");
+    println!("  -c, --compute      compute process transition context before modifying");
+    println!("  -t, --type=TYPE    type (for same role as parent)");
+    println!("  -u, --user=USER    user identity");
+    println!("  -r, --role=ROLE    role");
+    println!("  -l, --range=RANGE  levelrange");
+    println!("      --help         display this help and exit");
+    println!("      --version      output version information and exit");
+}
+
+fn bad_usage(prog: &str, msg: String) -> ! {
+    show_error!(exec::EXIT_CANCELED, "{}", msg);
+    crash!(exec::EXIT_CANCELED, "Try '{} --help' for more information.", prog)
+}
+
+fn print_current() {
+    if !selinux_enabled() {
+        crash!(exec::EXIT_CANCELED, "{} may be used only on a SELinux kernel", NAME);
+    }
+    match current_context() {
+        Ok(context) => println!("{}", context),
+        Err(e) => crash!(exec::EXIT_CANCELED, "failed to get current context: {}",
+                         util::strerror(&e))
+    }
+}
+
+// Where selinuxfs is mounted, when it is.
+fn selinuxfs() -> Option<Path> {
+    ["/sys/fs/selinux", "/selinux"].iter().map(|dir| Path::new(*dir))
+                                   .find(|dir| dir.join("enforce").exists())
+}
+
+fn selinux_enabled() -> bool {
+    cfg!(target_os = "linux") && selinuxfs().is_some()
+}
+
+// The kernel hands contexts out with a NUL, and sometimes a newline, on the
+// end.
+fn read_context(path: &Path) -> IoResult<String> {
+    let bytes = try!(File::open(path).read_to_end());
+    let context = str::from_utf8_lossy(bytes.as_slice()).into_string();
+    Ok(context.as_slice().trim_right_chars(|c: char| c == '\0' || c == '\n').to_string())
+}
+
+fn current_context() -> IoResult<String> {
+    read_context(&Path::new("/proc/self/attr/current"))
+}
+
+fn set_exec_context(context: &str) -> IoResult<()> {
+    File::open_mode(&Path::new("/proc/self/attr/exec"), std::io::Open, std::io::Write)
+        .write_str(context)
+}
+
+// What the process would become by executing command with no context
+// asked for: the policy's transition from the current context on the
+// command's file context, for the process class.
+fn transition_context(command: &str) -> Result<String, String> {
+    let path = match find_command(command) {
+        Some(path) => path,
+        None => return Err(format!("cannot find '{}'", command))
+    };
+    let file_context = match xattr::get(&path, "security.selinux") {
+        Ok(context) => str::from_utf8_lossy(context.as_slice()).into_string(),
+        Err(e) => return Err(format!("{}: {}", path.display(), util::strerror(&e)))
+    };
+    let file_context = file_context.as_slice().trim_right_chars('\0').to_string();
+    let current = try!(current_context().map_err(|e| util::strerror(&e)));
+
+    let fs = selinuxfs().unwrap();
+    let class = try!(read_context(&fs.join_many(["class", "process", "index"]))
+                         .map_err(|e| util::strerror(&e)));
+    // selinuxfs answers on the same open file that was asked
+    let mut create = try!(File::open_mode(&fs.join("create"), std::io::Open,
+                                          std::io::ReadWrite).map_err(|e| util::strerror(&e)));
+    let request = format!("{} {} {}", current, file_context, class);
+    try!(create.write_str(request.as_slice()).map_err(|e| util::strerror(&e)));
+    let answer = try!(create.read_to_end().map_err(|e| util::strerror(&e)));
+    Ok(str::from_utf8_lossy(answer.as_slice()).into_string()
+                                                .as_slice()
+                                                .trim_right_chars('\0')
+                                                .to_string())
+}
+
+// The command as execvp would find it.
+fn find_command(command: &str) -> Option<Path> {
+    if command.contains_char('/') {
+        return Some(Path::new(command));
+    }
+    let path = os::getenv("PATH").unwrap_or("/bin:/usr/bin".to_string());
+    path.as_slice().split(':').map(|dir| Path::new(if dir.is_empty() { "." } else { dir })
+                                              .join(command))
+                              .find(|file| file.is_file())
+}
+
+// The context with the given components put in place of its own.  The
+// range is last and may itself have colons in it.
+fn with_components(context: &str, components: &Components) -> String {
+    let mut parts: Vec<String> = context.splitn(':', 3).map(|s| s.to_string()).collect();
+    for (i, part) in [&components.user, &components.role, &components.type_,
+                      &components.range].iter().enumerate() {
+        match **part {
+            Some(ref value) if i < parts.len() => *parts.get_mut(i) = value.clone(),
+            Some(ref value) => parts.push(value.clone()),
+            None => {}
+        }
+    }
+    parts.connect(":")
+}
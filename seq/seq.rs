@@ -13,6 +13,9 @@ use std::os;
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "seq";
 
 fn print_usage(opts: &[getopts::OptGroup]) {
@@ -34,26 +37,34 @@ fn escape_sequences(s: &str) -> String {
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-pub fn uumain(args: Vec<String>) {
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optopt("s", "separator", "Separator character (defaults to \\n)", ""),
         getopts::optopt("t", "terminator", "Terminator character (defaults to separator)", ""),
         getopts::optflag("w", "widths", "Equalize widths of all numbers by padding with zeros"),
         getopts::optflag("h", "help", "print this help text and exit"),
         getopts::optflag("V", "version", "print version and exit"),
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => { m }
         Err(f) => {
             show_error!(1, "{:s}", f.to_err_msg());
-            print_usage(opts);
+            print_usage(opts.as_slice());
             return;
         }
     };
     if matches.opt_present("help") {
-        print_usage(opts);
+        print_usage(opts.as_slice());
         return;
     }
     if matches.opt_present("version") {
@@ -61,8 +72,8 @@ pub fn uumain(args: Vec<String>) {
         return;
     }
     if matches.free.len() < 1 || matches.free.len() > 3 {
-        os::set_exit_status(1);
-        print_usage(opts);
+        util::set_status(1);
+        print_usage(opts.as_slice());
         return;
     }
     let first = if matches.free.len() > 1 {
@@ -0,0 +1,352 @@
+#![crate_id(name="shred", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::cmp;
+use std::os;
+use std::io::{fs, print, File, IoResult, Open, SeekEnd, SeekSet, Write};
+use std::rand::{task_rng, Rng};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/size.rs"]
+mod size;
+
+static NAME: &'static str = "shred";
+static VERSION: &'static str = "1.0.0";
+
+static BUF_SIZE: uint = 64 * 1024;
+
+// The fixed patterns gone through between the random passes, as bytes
+// repeated over the file.
+static PATTERNS: &'static [&'static [u8]] = &[
+    &[0x55], &[0xaa], &[0x92, 0x49, 0x24], &[0x49, 0x24, 0x92], &[0x24, 0x92, 0x49],
+    &[0x00], &[0x11], &[0x22], &[0x33], &[0x44], &[0x66], &[0x77], &[0x88], &[0x99],
+    &[0xbb], &[0xcc], &[0xdd], &[0xee], &[0xff], &[0x6d, 0xb6, 0xdb], &[0xb6, 0xdb, 0x6d],
+    &[0xdb, 0x6d, 0xb6],
+];
+
+#[deriving(PartialEq)]
+enum Remove {
+    NoRemove,
+    // just unlink
+    RemoveUnlink,
+    // rename to shorter and shorter names first
+    RemoveWipe,
+    // the same, syncing the directory after each rename
+    RemoveWipeSync,
+}
+
+enum Pass {
+    Random,
+    Pattern(&'static [u8]),
+    Zeros,
+}
+
+struct Options {
+    iterations: uint,
+    size: Option<u64>,
+    exact: bool,
+    zero: bool,
+    force: bool,
+    remove: Remove,
+    verbose: bool,
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("f", "force", "change permissions to allow writing if necessary"),
+        getopts::optopt("n", "iterations", "overwrite N times instead of the default (3)", "N"),
+        getopts::optopt("s", "size", "shred this many bytes (suffixes like K, M, G accepted)",
+                        "N"),
+        getopts::optflag("u", "", "deallocate and remove file after overwriting"),
+        getopts::optflagopt("", "remove", "like -u but give control on HOW to delete", "HOW"),
+        getopts::optflag("v", "verbose", "show progress"),
+        getopts::optflag("x", "exact", "do not round file sizes up to the next full block"),
+        getopts::optflag("z", "zero", "add a final overwrite with zeros to hide shredding"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... FILE...", args.get(0));
+        println!("");
+        print(getopts::usage("Overwrite the specified FILE(s) repeatedly, in order to make it \
+                              harder\nfor even very expensive hardware probing to recover \
+                              the data.", opts.as_slice()).as_slice());
+        println!("");
+        println!("Delete FILE(s) if --remove (-u) is specified.  The default is not to remove");
+        println!("the files because it is common to operate on device files like /dev/hda,");
+        println!("and those files usually should not be removed.  HOW is one of unlink, wipe");
+        println!("or wipesync, the default.");
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    if matches.free.is_empty() {
+        crash!(1, "missing file operand");
+    }
+
+    let iterations = match matches.opt_str("iterations") {
+        Some(n) => match from_str(n.as_slice()) {
+            Some(n) => n,
+            None => crash!(1, "invalid number of passes: '{}'", n)
+        },
+        None => 3
+    };
+    let size = match matches.opt_str("size") {
+        Some(s) => match size::parse_size(s.as_slice()) {
+            Some((bytes, _)) => Some(bytes),
+            None => crash!(1, "invalid file size: '{}'", s)
+        },
+        None => None
+    };
+    let remove = match matches.opt_str("remove") {
+        Some(how) => match how.as_slice() {
+            "unlink" => RemoveUnlink,
+            "wipe" => RemoveWipe,
+            "wipesync" => RemoveWipeSync,
+            _ => crash!(1, "invalid argument '{}' for '--remove'", how)
+        },
+        None if matches.opt_present("remove") || matches.opt_present("u") => RemoveWipeSync,
+        None => NoRemove
+    };
+    let options = Options {
+        iterations: iterations,
+        size: size,
+        exact: matches.opt_present("exact"),
+        zero: matches.opt_present("zero"),
+        force: matches.opt_present("force"),
+        remove: remove,
+        verbose: matches.opt_present("verbose"),
+    };
+
+    for name in matches.free.iter() {
+        let path = Path::new(name.as_slice());
+        if shred(&path, name.as_slice(), &options) && options.remove != NoRemove {
+            remove_file(&path, name.as_slice(), &options);
+        }
+    }
+}
+
+// Random passes come first, last and in the middle, with patterns in
+// between; a few passes are all random.
+fn passes(options: &Options) -> Vec<Pass> {
+    let n = options.iterations;
+    let mut passes = vec!();
+    let mut next_pattern = 0;
+    for i in range(0, n) {
+        if n <= 3 || i == 0 || i == n - 1 || i == n / 2 {
+            passes.push(Random);
+        } else {
+            passes.push(Pattern(PATTERNS[next_pattern % PATTERNS.len()]));
+            next_pattern += 1;
+        }
+    }
+    if options.zero {
+        passes.push(Zeros);
+    }
+    passes
+}
+
+fn shred(path: &Path, name: &str, options: &Options) -> bool {
+    let stat = match fsext::stat_path(path) {
+        Ok(stat) => stat,
+        Err(e) => {
+            show_error!(1, "{}: failed to open for writing: {}", name, util::strerror(&e));
+            return false;
+        }
+    };
+    if options.force && stat.st_mode as libc::mode_t & 0o200 == 0 {
+        match fsext::set_mode(path, stat.st_mode as libc::mode_t & 0o7777 | 0o200) {
+            Ok(()) => {}
+            Err(e) => {
+                show_error!(1, "{}: cannot change permissions: {}", name, util::strerror(&e));
+                return false;
+            }
+        }
+    }
+    let mut file = match File::open_mode(path, Open, Write) {
+        Ok(file) => file,
+        Err(e) => {
+            show_error!(1, "{}: failed to open for writing: {}", name, util::strerror(&e));
+            return false;
+        }
+    };
+
+    let size = match options.size {
+        Some(size) => size,
+        None if fsext::is_reg(stat.st_mode) => {
+            let size = stat.st_size as u64;
+            let block = cmp::max(stat.st_blksize as u64, 1);
+            // the rest of the last block may still hold old data
+            if options.exact || size % block == 0 { size } else { size + block - size % block }
+        }
+        // devices know how big they are only by seeking
+        None => match file.seek(0, SeekEnd).and_then(|_| file.tell()) {
+            Ok(size) => size,
+            Err(e) => {
+                show_error!(1, "{}: cannot get size: {}", name, util::strerror(&e));
+                return false;
+            }
+        }
+    };
+
+    let passes = passes(options);
+    for (i, pass) in passes.iter().enumerate() {
+        if options.verbose {
+            let what = match *pass {
+                Random => "random".to_string(),
+                Pattern(pattern) => pattern.iter().map(|b| format!("{:02x}", *b))
+                                           .collect::<Vec<String>>().as_slice().concat(),
+                Zeros => "000000".to_string()
+            };
+            show_info(format!("{}: pass {}/{} ({})...", name, i + 1, passes.len(), what));
+        }
+        match overwrite(&mut file, size, pass) {
+            Ok(()) => {}
+            Err(e) => {
+                show_error!(1, "{}: error writing: {}", name, util::strerror(&e));
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Writes one pass over the first size bytes, and waits for it to reach the
+// disk before the next one replaces it in the cache.
+fn overwrite(file: &mut File, size: u64, pass: &Pass) -> IoResult<()> {
+    let mut buf = Vec::from_elem(BUF_SIZE, 0u8);
+    match *pass {
+        Pattern(pattern) => {
+            // a whole number of repeats, so each block carries on from the
+            // last
+            for (i, b) in buf.mut_iter().enumerate() {
+                *b = pattern[i % pattern.len()];
+            }
+        }
+        Random | Zeros => {}
+    }
+    let usable = BUF_SIZE - BUF_SIZE % 3;
+    let mut rng = task_rng();
+
+    try!(file.seek(0, SeekSet));
+    let mut left = size;
+    while left > 0 {
+        let len = cmp::min(left, usable as u64) as uint;
+        match *pass {
+            Random => rng.fill_bytes(buf.mut_slice_to(len)),
+            _ => {}
+        }
+        try!(file.write(buf.slice_to(len)));
+        left -= len as u64;
+    }
+    try!(file.flush());
+    file.fsync()
+}
+
+// Renames the file to ever shorter names of zeros before unlinking it, so
+// that what it was called goes too.
+fn remove_file(path: &Path, name: &str, options: &Options) {
+    if options.verbose {
+        show_info(format!("{}: removing", name));
+    }
+    let mut current = path.clone();
+    if options.remove != RemoveUnlink {
+        let mut len = match path.filename() {
+            Some(filename) => filename.len(),
+            None => 0
+        };
+        while len > 0 {
+            let mut next = None;
+            // the first unused name of that many zeros, ones, ...
+            for c in "0123456789abcdefghijklmnopqrstuvwxyz".chars() {
+                let candidate = current.with_filename(String::from_char(len, c).as_slice());
+                if !candidate.exists() {
+                    next = Some(candidate);
+                    break;
+                }
+            }
+            match next {
+                Some(next) => match fs::rename(&current, &next) {
+                    Ok(()) => {
+                        if options.remove == RemoveWipeSync {
+                            sync_dir(&next.dir_path());
+                        }
+                        if options.verbose {
+                            show_info(format!("{}: renamed to {}", current.display(),
+                                              next.display()));
+                        }
+                        current = next;
+                    }
+                    Err(_) => {}
+                },
+                None => {}
+            }
+            len -= 1;
+        }
+    }
+    match fs::unlink(&current) {
+        Ok(()) => {
+            if options.remove == RemoveWipeSync {
+                sync_dir(&current.dir_path());
+            }
+            if options.verbose {
+                show_info(format!("{}: removed", name));
+            }
+        }
+        Err(e) => show_error!(1, "{}: failed to remove: {}", name, util::strerror(&e))
+    }
+}
+
+fn sync_dir(dir: &Path) {
+    match File::open(dir) {
+        Ok(mut dir) => { let _ = dir.fsync(); }
+        Err(_) => {}
+    }
+}
+
+// Progress goes to stderr, as GNU shred's does.
+fn show_info(msg: String) {
+    safe_writeln!(&mut ::std::io::stderr(), "{}: {}", NAME, msg);
+}
@@ -14,26 +14,43 @@
 extern crate getopts;
 extern crate libc;
 
-use std::num;
 use std::os;
-use std::io::{print, timer};
+use std::io::print;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/duration.rs"]
+mod duration;
+
 static NAME: &'static str = "sleep";
 
+extern {
+    fn nanosleep(req: *libc::timespec, rem: *mut libc::timespec) -> libc::c_int;
+}
+
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit")
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
             show_error!(1, "{}", f.to_err_msg());
@@ -53,7 +70,7 @@ pub fn uumain(args: Vec<String>) {
 'm' for minutes, 'h' for hours or 'd' for days.  Unlike most implementations
 that require NUMBER be an integer, here NUMBER may be an arbitrary floating
 point number.  Given two or more arguments, pause for the amount of time
-specified by the sum of their values.", opts).as_slice());
+specified by the sum of their values.", opts.as_slice()).as_slice());
     } else if matches.opt_present("version") {
         println!("sleep 1.0.0");
     } else if matches.free.is_empty() {
@@ -65,42 +82,39 @@ specified by the sum of their values.", opts).as_slice());
 }
 
 fn sleep(args: Vec<String>) {
-    let sleep_time = args.iter().fold(0.0, |result, arg| {
-        let (arg, suffix_time) = match match_suffix(arg.as_slice()) {
-            Ok(m) => m,
-            Err(f) => {
-                crash!(1, "{}", f.to_string())
-            }
-        };
-        let num =
-            if suffix_time == 0 {
-                0.0
-            } else {
-                match num::from_str_radix::<f64>((arg.as_slice()), 10) {
-                    Some(m) => m,
-                    None => {
-                        crash!(1, "Invalid time interval '{}'", arg.to_string())
-                    }
-                }
-            };
-        result + num * suffix_time as f64
-    });
-    timer::sleep((sleep_time * 1000.0) as u64);
+    let mut total = 0.0;
+    for arg in args.iter() {
+        match duration::parse(arg.as_slice()) {
+            Ok(secs) => total += secs,
+            Err(e) => show_error!(1, "{}", e)
+        }
+    }
+    if util::status() != 0 {
+        return;
+    }
+
+    // a total too big for a timespec is as good as forever, so it is slept
+    // off a year at a time
+    static YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+    while total > YEAR {
+        pause(YEAR);
+        total -= YEAR;
+    }
+    pause(total);
 }
 
-fn match_suffix(arg: &str) -> Result<(String, int), String> {
-    let result = match (arg).char_at_reverse(0) {
-        's' | 'S' => 1,
-        'm' | 'M' => 60,
-        'h' | 'H' => 60 * 60,
-        'd' | 'D' => 60 * 60 * 24,
-        val => {
-            if !val.is_alphabetic() {
-                return Ok((arg.to_string(), 1))
-            } else {
-                return Err(format!("Invalid time interval '{}'", arg))
-            }
-        }
+// Sleeps to the nanosecond, going back to sleep for whatever is left when a
+// signal cuts it short.
+fn pause(secs: f64) {
+    let mut req = libc::timespec {
+        tv_sec: secs.floor() as libc::time_t,
+        tv_nsec: ((secs - secs.floor()) * 1e9) as libc::c_long
     };
-    Ok(((arg).slice_to((arg).len() - 1).to_string(), result))
+    let mut rem = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    while unsafe { nanosleep(&req, &mut rem) } == -1 {
+        if os::errno() != libc::EINTR as int {
+            crash!(1, "cannot read realtime clock: {}", os::last_os_error());
+        }
+        req = libc::timespec { tv_sec: rem.tv_sec, tv_nsec: rem.tv_nsec };
+    }
 }
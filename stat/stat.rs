@@ -0,0 +1,376 @@
+#![crate_id(name="stat", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+extern crate time;
+
+use std::os;
+use std::io::{print, stdout};
+use libc::mode_t;
+use time::Timespec;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/c_types.rs"]
+mod c_types;
+
+#[path = "../common/escape.rs"]
+mod escape;
+
+#[path = "../common/json.rs"]
+mod json;
+
+static NAME: &'static str = "stat";
+static VERSION: &'static str = "1.0.0";
+
+static DEFAULT_FORMAT: &'static str =
+    "  File: %N\n  Size: %-10s\tBlocks: %-10b IO Block: %-6o %F\n\
+     Device: %Dh/%dd\tInode: %-11i Links: %h\n\
+     Access: (%04a/%A)  Uid: (%5u/%8U)   Gid: (%5g/%8G)\n\
+     Access: %x\nModify: %y\nChange: %z\n Birth: %w\n";
+
+// Devices show their numbers after the link count.
+static DEVICE_FORMAT: &'static str =
+    "  File: %N\n  Size: %-10s\tBlocks: %-10b IO Block: %-6o %F\n\
+     Device: %Dh/%dd\tInode: %-11i Links: %-5h Device type: %t,%T\n\
+     Access: (%04a/%A)  Uid: (%5u/%8U)   Gid: (%5g/%8G)\n\
+     Access: %x\nModify: %y\nChange: %z\n Birth: %w\n";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("L", "dereference", "follow links"),
+        getopts::optopt("c", "format", "use the specified FORMAT instead of the default; \
+                                        output a newline after each use of FORMAT", "FORMAT"),
+        getopts::optopt("", "printf", "like --format, but interpret backslash escapes, and do \
+                                       not output a mandatory trailing newline", "FORMAT"),
+        getopts::optflag("", "json", "print everything about each FILE as a JSON object, \
+                                      and an array of them for more than one FILE"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... FILE...", args.get(0));
+        println!("");
+        print(getopts::usage("Display file status.", opts.as_slice()).as_slice());
+        println!("");
+        println!("The valid format sequences for files are:");
+        println!("");
+        println!("  %a   access rights in octal");
+        println!("  %A   access rights in human readable form");
+        println!("  %b   number of blocks allocated (see %B)");
+        println!("  %B   the size in bytes of each block reported by %b");
+        println!("  %d   device number in decimal");
+        println!("  %D   device number in hex");
+        println!("  %f   raw mode in hex");
+        println!("  %F   file type");
+        println!("  %g   group ID of owner");
+        println!("  %G   group name of owner");
+        println!("  %h   number of hard links");
+        println!("  %i   inode number");
+        println!("  %n   file name");
+        println!("  %N   quoted file name with dereference if symbolic link");
+        println!("  %o   optimal I/O transfer size hint");
+        println!("  %s   total size, in bytes");
+        println!("  %t   major device type in hex, for character/block device special files");
+        println!("  %T   minor device type in hex, for character/block device special files");
+        println!("  %u   user ID of owner");
+        println!("  %U   user name of owner");
+        println!("  %w   time of file birth, human-readable; - if unknown");
+        println!("  %W   time of file birth, seconds since Epoch; 0 if unknown");
+        println!("  %x   time of last access, human-readable");
+        println!("  %X   time of last access, seconds since Epoch");
+        println!("  %y   time of last modification, human-readable");
+        println!("  %Y   time of last modification, seconds since Epoch");
+        println!("  %z   time of last change, human-readable");
+        println!("  %Z   time of last change, seconds since Epoch");
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    if matches.free.is_empty() {
+        crash!(1, "missing operand");
+    }
+    let as_json = matches.opt_present("json");
+    if as_json && (matches.opt_present("format") || matches.opt_present("printf")) {
+        crash!(1, "--json cannot be combined with --format or --printf");
+    }
+
+    // --printf takes escapes and leaves off the newline that --format adds
+    let format = match (matches.opt_str("printf"), matches.opt_str("format")) {
+        (Some(f), _) => {
+            let mut out = vec!();
+            escape::unescape(f.as_bytes(), escape::OctalBare, &mut out);
+            Some(out)
+        }
+        (None, Some(f)) => {
+            let mut out = Vec::from_slice(f.as_bytes());
+            out.push('\n' as u8);
+            Some(out)
+        }
+        (None, None) => None
+    };
+    let dereference = matches.opt_present("dereference");
+    let mut objects = vec!();
+
+    for name in matches.free.iter() {
+        let path = Path::new(name.as_slice());
+        let result = if dereference { fsext::stat_path(&path) } else { fsext::lstat_path(&path) };
+        let stat = match result {
+            Ok(stat) => stat,
+            Err(e) => {
+                show_error!(1, "cannot stat '{}': {}", name, util::strerror(&e));
+                continue;
+            }
+        };
+        if as_json {
+            objects.push(json_object(name.as_slice(), &path, &stat, dereference));
+            continue;
+        }
+        let mode = stat.st_mode as mode_t;
+        let format = match format {
+            Some(ref format) => format.as_slice(),
+            None if fsext::is_chr(mode) || fsext::is_blk(mode) => DEVICE_FORMAT.as_bytes(),
+            None => DEFAULT_FORMAT.as_bytes()
+        };
+        let out = expand(format, name.as_slice(), &path, &stat, dereference);
+        match stdout().write(out.as_slice()) {
+            Ok(()) => {}
+            Err(e) => crash!(1, "write error: {}", util::strerror(&e))
+        }
+    }
+
+    if as_json && !objects.is_empty() {
+        // a lone operand gets its object, not an array of one
+        let out = if matches.free.len() == 1 {
+            objects.get(0).clone()
+        } else {
+            json::array(objects.as_slice())
+        };
+        println!("{}", out);
+    }
+}
+
+// Everything the default format shows, with numbers as numbers and times in
+// seconds and nanoseconds since the Epoch.
+fn json_object(name: &str, path: &Path, stat: &fsext::c_stat, dereference: bool) -> String {
+    let mode = stat.st_mode as mode_t;
+    let mut object = json::Object::new();
+    object.string("name", name);
+    if !dereference && fsext::is_lnk(mode) {
+        match fsext::read_link(path) {
+            Ok(target) => object.bytes("target", target.as_slice()),
+            Err(_) => object.null("target")
+        }
+    }
+    object.string("type", file_type(stat).as_slice());
+    object.number("size", stat.st_size);
+    object.number("blocks", stat.st_blocks);
+    object.number("block_size", 512u);
+    object.number("io_block", stat.st_blksize);
+    object.number("device", stat.st_dev as u64);
+    object.number("inode", stat.st_ino);
+    object.number("links", stat.st_nlink);
+    if fsext::is_chr(mode) || fsext::is_blk(mode) {
+        object.number("major", fsext::major(stat.st_rdev as u64));
+        object.number("minor", fsext::minor(stat.st_rdev as u64));
+    }
+    object.string("access", format!("{:04o}", mode & 0o7777).as_slice());
+    object.string("permissions", fsext::mode_string(mode).as_slice());
+    object.number("uid", stat.st_uid);
+    match c_types::uid2usr(stat.st_uid) {
+        Some(user) => object.string("user", user.as_slice()),
+        None => object.null("user")
+    }
+    object.number("gid", stat.st_gid);
+    match c_types::gid2grp(stat.st_gid) {
+        Some(group) => object.string("group", group.as_slice()),
+        None => object.null("group")
+    }
+    for &(key, (secs, nsecs)) in [("atime", fsext::atime(stat)),
+                                  ("mtime", fsext::mtime(stat)),
+                                  ("ctime", fsext::ctime(stat))].iter() {
+        let mut time = json::Object::new();
+        time.number("sec", secs);
+        time.number("nsec", nsecs);
+        object.raw(key, time.encode().as_slice());
+    }
+    // as with %w, nothing here knows when a file was made
+    object.null("birth");
+    object.encode()
+}
+
+// Replaces each %-sequence in the format with what it stands for, padded to
+// any width given with it as printf(3) would, e.g. %-10s or %04a.
+fn expand(format: &[u8], name: &str, path: &Path, stat: &fsext::c_stat,
+          dereference: bool) -> Vec<u8> {
+    let mut out = vec!();
+    let mut i = 0;
+    while i < format.len() {
+        if format[i] != '%' as u8 {
+            out.push(format[i]);
+            i += 1;
+            continue;
+        }
+        let start = i;
+        i += 1;
+        let mut left = false;
+        let mut zero = false;
+        while i < format.len() && (format[i] == '-' as u8 || format[i] == '0' as u8) {
+            if format[i] == '-' as u8 { left = true } else { zero = true }
+            i += 1;
+        }
+        let mut width = 0u;
+        while i < format.len() && (format[i] as char).is_digit() {
+            width = width * 10 + (format[i] - '0' as u8) as uint;
+            i += 1;
+        }
+        if i == format.len() {
+            // a lone % at the end is left as it is
+            out.push_all(format.slice_from(start));
+            break;
+        }
+        let directive = format[i] as char;
+        i += 1;
+        let value = match directive {
+            '%' => {
+                out.push('%' as u8);
+                continue;
+            }
+            _ => match field(directive, name, path, stat, dereference) {
+                Some(value) => value,
+                None => {
+                    // unknown sequences come out as they are
+                    out.push_all(format.slice(start, i));
+                    continue;
+                }
+            }
+        };
+        let pad = if width > value.len() { width - value.len() } else { 0 };
+        if left {
+            out.push_all(value.as_slice());
+            out.grow(pad, &(' ' as u8));
+        } else {
+            out.grow(pad, &(if zero { '0' as u8 } else { ' ' as u8 }));
+            out.push_all(value.as_slice());
+        }
+    }
+    out
+}
+
+fn field(directive: char, name: &str, path: &Path, stat: &fsext::c_stat,
+         dereference: bool) -> Option<Vec<u8>> {
+    let mode = stat.st_mode as mode_t;
+    let text = match directive {
+        'a' => format!("{:o}", mode & 0o7777),
+        'A' => fsext::mode_string(mode),
+        'b' => stat.st_blocks.to_str(),
+        'B' => "512".to_string(),
+        'd' => (stat.st_dev as u64).to_str(),
+        'D' => format!("{:x}", stat.st_dev as u64),
+        'f' => format!("{:x}", mode),
+        'F' => file_type(stat),
+        'g' => stat.st_gid.to_str(),
+        'G' => c_types::gid2grp(stat.st_gid).unwrap_or("UNKNOWN".to_string()),
+        'h' => stat.st_nlink.to_str(),
+        'i' => stat.st_ino.to_str(),
+        'n' => return Some(Vec::from_slice(name.as_bytes())),
+        'N' => return Some(quoted_name(name, path, stat, dereference)),
+        'o' => stat.st_blksize.to_str(),
+        's' => stat.st_size.to_str(),
+        't' => format!("{:x}", fsext::major(stat.st_rdev as u64)),
+        'T' => format!("{:x}", fsext::minor(stat.st_rdev as u64)),
+        'u' => stat.st_uid.to_str(),
+        'U' => c_types::uid2usr(stat.st_uid).unwrap_or("UNKNOWN".to_string()),
+        // nothing here knows when a file was made
+        'w' => "-".to_string(),
+        'W' => "0".to_string(),
+        'x' => human_time(fsext::atime(stat)),
+        'X' => epoch(fsext::atime(stat)),
+        'y' => human_time(fsext::mtime(stat)),
+        'Y' => epoch(fsext::mtime(stat)),
+        'z' => human_time(fsext::ctime(stat)),
+        'Z' => epoch(fsext::ctime(stat)),
+        _ => return None
+    };
+    Some(text.into_bytes())
+}
+
+fn file_type(stat: &fsext::c_stat) -> String {
+    let mode = stat.st_mode as mode_t;
+    match fsext::file_type(mode) {
+        fsext::S_IFREG if stat.st_size == 0 => "regular empty file",
+        fsext::S_IFREG => "regular file",
+        fsext::S_IFDIR => "directory",
+        fsext::S_IFLNK => "symbolic link",
+        fsext::S_IFCHR => "character special file",
+        fsext::S_IFBLK => "block special file",
+        fsext::S_IFIFO => "fifo",
+        fsext::S_IFSOCK => "socket",
+        _ => "weird file"
+    }.to_string()
+}
+
+// 'name', or 'name' -> 'target' for a link that was not followed.
+fn quoted_name(name: &str, path: &Path, stat: &fsext::c_stat, dereference: bool) -> Vec<u8> {
+    let mut out = format!("'{}'", name).into_bytes();
+    if !dereference && fsext::is_lnk(stat.st_mode as mode_t) {
+        match fsext::read_link(path) {
+            Ok(target) => {
+                out.push_all(" -> '".as_bytes());
+                out.push_all(target.as_slice());
+                out.push('\'' as u8);
+            }
+            Err(_) => {}
+        }
+    }
+    out
+}
+
+// e.g. 2014-06-01 12:34:56.123456789 +0200
+fn human_time((secs, nsecs): (i64, i64)) -> String {
+    let tm = time::at(Timespec::new(secs, nsecs as i32));
+    format!("{}.{:09} {}", tm.strftime("%Y-%m-%d %H:%M:%S"), nsecs, tm.strftime("%z"))
+}
+
+fn epoch((secs, _): (i64, i64)) -> String {
+    secs.to_str()
+}
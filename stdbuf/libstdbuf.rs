@@ -0,0 +1,118 @@
+#![crate_id(name="libstdbuf", vers="1.0.0", author="agent")]
+#![crate_type = "dylib"]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * The library stdbuf preloads into the command.  Its constructor runs
+ * before the command's main and calls setvbuf(3) on the streams stdbuf was
+ * told about, through _STDBUF_I, _STDBUF_O and _STDBUF_E: "0" for
+ * unbuffered, "L" for line buffered, or a buffer size in bytes.  Nothing
+ * here may allocate through Rust or otherwise lean on its runtime, which
+ * is never started in the command; buffers come from malloc(3).
+ */
+
+extern crate libc;
+
+use libc::{c_char, c_int, size_t, FILE};
+use std::ptr;
+
+static _IOFBF: c_int = 0;
+static _IOLBF: c_int = 1;
+static _IONBF: c_int = 2;
+
+extern {
+    fn getenv(name: *c_char) -> *c_char;
+    fn setvbuf(stream: *mut FILE, buf: *mut c_char, mode: c_int, size: size_t) -> c_int;
+
+    #[cfg(target_os = "linux")]
+    static stdin: *mut FILE;
+    #[cfg(target_os = "linux")]
+    static stdout: *mut FILE;
+    #[cfg(target_os = "linux")]
+    static stderr: *mut FILE;
+
+    #[cfg(target_os = "macos")]
+    #[link_name = "__stdinp"]
+    static stdin: *mut FILE;
+    #[cfg(target_os = "macos")]
+    #[link_name = "__stdoutp"]
+    static stdout: *mut FILE;
+    #[cfg(target_os = "macos")]
+    #[link_name = "__stderrp"]
+    static stderr: *mut FILE;
+}
+
+#[cfg(target_os = "linux")]
+#[link_section = ".init_array"]
+#[no_mangle]
+pub static STDBUF_INIT: extern "C" fn() = init;
+
+#[cfg(target_os = "macos")]
+#[link_section = "__DATA,__mod_init_func"]
+#[no_mangle]
+pub static STDBUF_INIT: extern "C" fn() = init;
+
+extern "C" fn init() {
+    unsafe {
+        set_buffering(stdin, b"_STDBUF_I\0");
+        set_buffering(stdout, b"_STDBUF_O\0");
+        set_buffering(stderr, b"_STDBUF_E\0");
+    }
+}
+
+// var is NUL-terminated, to be handed to getenv as it is.
+unsafe fn set_buffering(stream: *mut FILE, var: &[u8]) {
+    let value = getenv(var.as_ptr() as *c_char);
+    if value.is_null() {
+        return;
+    }
+    let (mode, size) = match *value as u8 {
+        b'0' if *value.offset(1) == 0 => (_IONBF, 0),
+        b'L' => (_IOLBF, 0),
+        _ => match parse_size(value) {
+            Some(size) => (_IOFBF, size),
+            None => return
+        }
+    };
+    // glibc ignores the size when it is left to allocate the buffer itself,
+    // so the buffer is allocated here.  It lasts as long as the stream.
+    let buf = if size > 0 {
+        let buf = libc::malloc(size) as *mut c_char;
+        if buf.is_null() {
+            let msg = b"stdbuf: failed to allocate a stdio buffer\n";
+            libc::write(libc::STDERR_FILENO, msg.as_ptr() as *libc::c_void, msg.len() as size_t);
+            return;
+        }
+        buf
+    } else {
+        ptr::mut_null()
+    };
+    if setvbuf(stream, buf, mode, size) != 0 && !buf.is_null() {
+        libc::free(buf as *mut libc::c_void);
+    }
+}
+
+unsafe fn parse_size(mut digits: *c_char) -> Option<size_t> {
+    let mut size: size_t = 0;
+    while *digits != 0 {
+        let digit = *digits as u8;
+        if digit < b'0' || digit > b'9' {
+            return None;
+        }
+        let digit = (digit - b'0') as size_t;
+        size = match size.checked_mul(&10).and_then(|n| n.checked_add(&digit)) {
+            Some(n) => n,
+            None => return None
+        };
+        digits = digits.offset(1);
+    }
+    if size == 0 { None } else { Some(size) }
+}
@@ -0,0 +1,198 @@
+#![crate_id(name="stdbuf", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate libc;
+
+use std::os;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/exec.rs"]
+mod exec;
+
+#[path = "../common/size.rs"]
+mod size;
+
+static NAME: &'static str = "stdbuf";
+static VERSION: &'static str = "1.0.0";
+
+// built beside stdbuf by stdbuf/Makefile, from libstdbuf.rs
+#[cfg(target_os = "linux")]
+static LIBSTDBUF: &'static str = "libstdbuf.so";
+#[cfg(target_os = "macos")]
+static LIBSTDBUF: &'static str = "libstdbuf.dylib";
+
+#[cfg(target_os = "linux")]
+static PRELOAD: &'static str = "LD_PRELOAD";
+#[cfg(target_os = "macos")]
+static PRELOAD: &'static str = "DYLD_INSERT_LIBRARIES";
+
+// The variables libstdbuf reads, for stdin, stdout and stderr.
+static STREAMS: [(char, &'static str, &'static str), ..3] = [
+    ('i', "input", "_STDBUF_I"),
+    ('o', "output", "_STDBUF_O"),
+    ('e', "error", "_STDBUF_E"),
+];
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    exec::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let prog = args.get(0).as_slice();
+    let mut modes: Vec<(&'static str, String)> = vec!();
+
+    // options stop at the command, whose own options are left to it
+    let mut i = 1;
+    while i < args.len() {
+        let opt = args.get(i).as_slice();
+        if opt == "--" {
+            i += 1;
+            break;
+        } else if !opt.starts_with("-") || opt == "-" {
+            break;
+        }
+        i += 1;
+
+        if opt == "--help" {
+            help(prog);
+            return;
+        } else if opt == "--version" {
+            println!("{} {}", NAME, VERSION);
+            return;
+        }
+        let stream = STREAMS.iter().find(|&&(short, long, _)| {
+            opt.slice_from(1).starts_with(short.to_str().as_slice()) ||
+                (opt.starts_with("--") && opt.slice_from(2).split('=').next() == Some(long))
+        });
+        let &(short, long, var) = match stream {
+            Some(stream) => stream,
+            None => bad_usage(prog, format!("unrecognized option '{}'", opt))
+        };
+        let value = if opt.starts_with("--") {
+            match opt.find('=') {
+                Some(eq) => Some(opt.slice_from(eq + 1).to_string()),
+                None => None
+            }
+        } else if opt.len() > 2 {
+            Some(opt.slice_from(2).to_string())
+        } else {
+            None
+        };
+        let value = match value {
+            Some(value) => value,
+            None if i < args.len() => {
+                i += 1;
+                args.get(i - 1).clone()
+            }
+            None => bad_usage(prog, format!("option '--{}' requires an argument", long))
+        };
+        modes.push((var, parse_mode(short, value.as_slice())));
+    }
+
+    let command = args.slice_from(i);
+    if modes.is_empty() {
+        bad_usage(prog, "you must specify a buffering mode option".to_string());
+    }
+    if command.is_empty() {
+        bad_usage(prog, "missing operand".to_string());
+    }
+
+    preload(&find_library());
+    for &(var, ref mode) in modes.iter() {
+        os::setenv(var, mode.as_slice());
+    }
+    exec::exec_or_exit(command);
+}
+
+fn help(prog: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0} OPTION... COMMAND", prog);
+    println!("");
+    println!("Run COMMAND, with modified buffering operations for its standard streams.");
+    println!("");
+    println!("  -i, --input=MODE   adjust standard input stream buffering");
+    println!("  -o, --output=MODE  adjust standard output stream buffering");
+    println!("  -e, --error=MODE   adjust standard error stream buffering");
+    println!("      --help         display this help and exit");
+    println!("      --version      output version information and exit");
+    println!("");
+    println!("If MODE is 'L' the corresponding stream will be line buffered.");
+    println!("This option is invalid with standard input.");
+    println!("");
+    println!("If MODE is '0' the corresponding stream will be unbuffered.");
+    println!("");
+    println!("Otherwise MODE is a number which may be followed by one of the following:");
+    println!("KB 1000, K 1024, MB 1000*1000, M 1024*1024, and so on for G, T, P, E, Z, Y.");
+    println!("In this case the corresponding stream will be fully buffered with the buffer");
+    println!("size set to MODE bytes.");
+    println!("");
+    println!("NOTE: If COMMAND adjusts the buffering of its standard streams ('tee' does");
+    println!("for example) then that will override corresponding changes by '{}'.", NAME);
+    println!("Also some filters (like 'dd' and 'cat' etc.) don't use streams for I/O,");
+    println!("and are thus unaffected by '{}' settings.", NAME);
+}
+
+fn bad_usage(prog: &str, msg: String) -> ! {
+    show_error!(exec::EXIT_CANCELED, "{}", msg);
+    crash!(exec::EXIT_CANCELED, "Try '{} --help' for more information.", prog)
+}
+
+// What libstdbuf is to be told for the stream: 0, L or a size in bytes.
+fn parse_mode(stream: char, mode: &str) -> String {
+    match mode {
+        "L" if stream == 'i' => {
+            crash!(exec::EXIT_CANCELED, "line buffering stdin is meaningless")
+        }
+        "0" | "L" => mode.to_string(),
+        _ => match size::parse_size(mode) {
+            Some((size, _)) if size > 0 => size.to_str(),
+            _ => crash!(exec::EXIT_CANCELED, "invalid mode '{}'", mode)
+        }
+    }
+}
+
+fn find_library() -> Path {
+    let lib = match os::self_exe_path() {
+        Some(dir) => dir.join(LIBSTDBUF),
+        None => crash!(exec::EXIT_CANCELED, "failed to find the directory {} is in", NAME)
+    };
+    if !lib.exists() {
+        crash!(exec::EXIT_CANCELED, "failed to find '{}'", lib.display());
+    }
+    lib
+}
+
+// Has the dynamic linker load lib into the command ahead of anything else,
+// keeping whatever was to be preloaded already.
+fn preload(lib: &Path) {
+    let lib = match lib.as_str() {
+        Some(lib) => lib,
+        None => crash!(exec::EXIT_CANCELED, "unusable path '{}'", lib.display())
+    };
+    let value = match os::getenv(PRELOAD) {
+        Some(ref old) if !old.is_empty() => format!("{}:{}", lib, old),
+        _ => lib.to_string()
+    };
+    os::setenv(PRELOAD, value.as_slice());
+    // without this the library's setvbuf calls would not reach the streams
+    // the command uses
+    if cfg!(target_os = "macos") {
+        os::setenv("DYLD_FORCE_FLAT_NAMESPACE", "1");
+    }
+}
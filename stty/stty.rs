@@ -0,0 +1,760 @@
+#![crate_id(name="stty", vers="1.0.0", author="agent")]
+#![feature(macro_rules, globs)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::print;
+use fdio::Fd;
+use termios::*;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/fdio.rs"]
+mod fdio;
+
+mod termios;
+
+static NAME: &'static str = "stty";
+static VERSION: &'static str = "1.0.0";
+
+#[cfg(target_os = "linux")]
+static O_NONBLOCK: libc::c_int = 0o4000;
+#[cfg(not(target_os = "linux"))]
+static O_NONBLOCK: libc::c_int = 0x4;
+
+enum Field {
+    Control,
+    Input,
+    Output,
+    Local,
+}
+
+struct Flag {
+    name: &'static str,
+    field: Field,
+    bits: tcflag_t,
+    mask: tcflag_t,
+    // whether it can be turned off with a -, rather than being one of
+    // several values of the bits under its mask, as cs8 is
+    negatable: bool,
+    // what sane sets it to, if anything
+    sane: Option<bool>,
+}
+
+macro_rules! flag(
+    ($name:expr, $field:ident, $bits:expr) => (
+        Flag { name: $name, field: $field, bits: $bits, mask: $bits, negatable: true,
+               sane: None }
+    );
+    ($name:expr, $field:ident, $bits:expr, $sane:expr) => (
+        Flag { name: $name, field: $field, bits: $bits, mask: $bits, negatable: true,
+               sane: Some($sane) }
+    );
+)
+
+macro_rules! choice(
+    ($name:expr, $field:ident, $bits:expr, $mask:expr) => (
+        Flag { name: $name, field: $field, bits: $bits, mask: $mask, negatable: false,
+               sane: None }
+    );
+    ($name:expr, $field:ident, $bits:expr, $mask:expr, $sane:expr) => (
+        Flag { name: $name, field: $field, bits: $bits, mask: $mask, negatable: false,
+               sane: Some($sane) }
+    );
+)
+
+// In the order -a lists them, which is GNU's.
+static FLAGS: &'static [Flag] = &[
+    flag!("parenb", Control, PARENB),
+    flag!("parodd", Control, PARODD),
+    flag!("cmspar", Control, CMSPAR),
+    choice!("cs5", Control, CS5, CSIZE),
+    choice!("cs6", Control, CS6, CSIZE),
+    choice!("cs7", Control, CS7, CSIZE),
+    choice!("cs8", Control, CS8, CSIZE),
+    flag!("hupcl", Control, HUPCL),
+    flag!("hup", Control, HUPCL),
+    flag!("cstopb", Control, CSTOPB),
+    flag!("cread", Control, CREAD, true),
+    flag!("clocal", Control, CLOCAL),
+    flag!("crtscts", Control, CRTSCTS),
+
+    flag!("ignbrk", Input, IGNBRK, false),
+    flag!("brkint", Input, BRKINT, true),
+    flag!("ignpar", Input, IGNPAR),
+    flag!("parmrk", Input, PARMRK),
+    flag!("inpck", Input, INPCK),
+    flag!("istrip", Input, ISTRIP),
+    flag!("inlcr", Input, INLCR, false),
+    flag!("igncr", Input, IGNCR, false),
+    flag!("icrnl", Input, ICRNL, true),
+    flag!("ixon", Input, IXON),
+    flag!("ixoff", Input, IXOFF, false),
+    flag!("tandem", Input, IXOFF),
+    flag!("iuclc", Input, IUCLC, false),
+    flag!("ixany", Input, IXANY, false),
+    flag!("imaxbel", Input, IMAXBEL, true),
+    flag!("iutf8", Input, IUTF8, false),
+
+    flag!("opost", Output, OPOST, true),
+    flag!("olcuc", Output, OLCUC, false),
+    flag!("ocrnl", Output, OCRNL, false),
+    flag!("onlcr", Output, ONLCR, true),
+    flag!("onocr", Output, ONOCR, false),
+    flag!("onlret", Output, ONLRET, false),
+    flag!("ofill", Output, OFILL, false),
+    flag!("ofdel", Output, OFDEL, false),
+    choice!("nl0", Output, NL0, NLDLY, true),
+    choice!("nl1", Output, NL1, NLDLY, false),
+    choice!("cr0", Output, CR0, CRDLY, true),
+    choice!("cr1", Output, CR1, CRDLY, false),
+    choice!("cr2", Output, CR2, CRDLY, false),
+    choice!("cr3", Output, CR3, CRDLY, false),
+    choice!("tab0", Output, TAB0, TABDLY, true),
+    choice!("tab1", Output, TAB1, TABDLY, false),
+    choice!("tab2", Output, TAB2, TABDLY, false),
+    choice!("tab3", Output, TAB3, TABDLY, false),
+    choice!("bs0", Output, BS0, BSDLY, true),
+    choice!("bs1", Output, BS1, BSDLY, false),
+    choice!("vt0", Output, VT0, VTDLY, true),
+    choice!("vt1", Output, VT1, VTDLY, false),
+    choice!("ff0", Output, FF0, FFDLY, true),
+    choice!("ff1", Output, FF1, FFDLY, false),
+
+    flag!("isig", Local, ISIG, true),
+    flag!("icanon", Local, ICANON, true),
+    flag!("iexten", Local, IEXTEN, true),
+    flag!("echo", Local, ECHO, true),
+    flag!("echoe", Local, ECHOE, true),
+    flag!("crterase", Local, ECHOE),
+    flag!("echok", Local, ECHOK, true),
+    flag!("echonl", Local, ECHONL, false),
+    flag!("noflsh", Local, NOFLSH, false),
+    flag!("xcase", Local, XCASE, false),
+    flag!("tostop", Local, TOSTOP, false),
+    flag!("echoprt", Local, ECHOPRT, false),
+    flag!("prterase", Local, ECHOPRT),
+    flag!("echoctl", Local, ECHOCTL, true),
+    flag!("ctlecho", Local, ECHOCTL),
+    flag!("echoke", Local, ECHOKE, true),
+    flag!("crtkill", Local, ECHOKE),
+    flag!("flusho", Local, FLUSHO, false),
+    flag!("extproc", Local, EXTPROC, false),
+];
+
+// Names that are only other names for a flag above, left out of listings.
+static ALIASES: &'static [&'static str] = &[
+    "hup", "tandem", "crterase", "prterase", "ctlecho", "crtkill",
+];
+
+struct ControlChar {
+    name: &'static str,
+    index: uint,
+    // what sane sets it to, None being no character
+    default: Option<cc_t>,
+}
+
+static CONTROL_CHARS: &'static [ControlChar] = &[
+    ControlChar { name: "intr", index: VINTR, default: Some(3) },
+    ControlChar { name: "quit", index: VQUIT, default: Some(28) },
+    ControlChar { name: "erase", index: VERASE, default: Some(127) },
+    ControlChar { name: "kill", index: VKILL, default: Some(21) },
+    ControlChar { name: "eof", index: VEOF, default: Some(4) },
+    ControlChar { name: "eol", index: VEOL, default: None },
+    ControlChar { name: "eol2", index: VEOL2, default: None },
+    ControlChar { name: "swtch", index: VSWTC, default: None },
+    ControlChar { name: "start", index: VSTART, default: Some(17) },
+    ControlChar { name: "stop", index: VSTOP, default: Some(19) },
+    ControlChar { name: "susp", index: VSUSP, default: Some(26) },
+    ControlChar { name: "dsusp", index: VDSUSP, default: Some(25) },
+    ControlChar { name: "rprnt", index: VREPRINT, default: Some(18) },
+    ControlChar { name: "werase", index: VWERASE, default: Some(23) },
+    ControlChar { name: "lnext", index: VLNEXT, default: Some(22) },
+    ControlChar { name: "discard", index: VDISCARD, default: Some(15) },
+    ControlChar { name: "status", index: VSTATUS, default: Some(20) },
+];
+
+// Settings that stand for several others at once; sane, raw and cooked,
+// which touch more than flags can say, are done by hand.
+static COMBINATIONS: &'static [(&'static str, &'static str)] = &[
+    ("evenp", "parenb -parodd cs7"),
+    ("-evenp", "-parenb cs8"),
+    ("parity", "parenb -parodd cs7"),
+    ("-parity", "-parenb cs8"),
+    ("oddp", "parenb parodd cs7"),
+    ("-oddp", "-parenb cs8"),
+    ("nl", "-icrnl -onlcr"),
+    ("-nl", "icrnl -inlcr -igncr onlcr -ocrnl -onlret"),
+    ("pass8", "-parenb -istrip cs8"),
+    ("-pass8", "parenb istrip cs7"),
+    ("litout", "-parenb -istrip -opost cs8"),
+    ("-litout", "parenb istrip opost cs7"),
+    ("cbreak", "-icanon"),
+    ("-cbreak", "icanon"),
+    ("crt", "echoe echoctl echoke"),
+    ("dec", "echoe echoctl echoke -ixany intr ^c erase 0177 kill ^u"),
+    ("tabs", "tab0"),
+    ("-tabs", "tab3"),
+];
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("a", "all", "print all current settings in human-readable form"),
+        getopts::optflag("g", "save", "print all current settings in a stty-readable form"),
+        getopts::optopt("F", "file", "open and use the specified DEVICE instead of stdin",
+                        "DEVICE"),
+        getopts::optflag("", "help", "display this help and exit"),
+        getopts::optflag("", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    // getopts would take -echo and the like for options, so only what stty
+    // has for options is picked out, and the rest left as settings
+    let mut all = false;
+    let mut save = false;
+    let mut device = None;
+    let mut settings = vec!();
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args.get(i).as_slice();
+        match arg {
+            "-a" | "--all" => all = true,
+            "-g" | "--save" => save = true,
+            "-F" | "--file" => {
+                i += 1;
+                if i == args.len() {
+                    crash!(1, "option '{}' requires an argument", arg);
+                }
+                device = Some(args.get(i).clone());
+            }
+            "--help" => {
+                println!("{} {}", NAME, VERSION);
+                println!("");
+                println!("Usage:");
+                println!("  {0} [-F DEVICE | --file=DEVICE] [SETTING]...", args.get(0));
+                println!("  {0} [-F DEVICE | --file=DEVICE] [-a|--all]", args.get(0));
+                println!("  {0} [-F DEVICE | --file=DEVICE] [-g|--save]", args.get(0));
+                println!("");
+                print(getopts::usage("Print or change terminal characteristics.",
+                                     opts.as_slice()).as_slice());
+                println!("");
+                println!("Settings are flags such as echo or -echo, special characters such as \
+                          intr ^C,");
+                println!("min N, time N, rows N, cols N, size, speed, ispeed N, ospeed N, N, \
+                          line N,");
+                println!("and the combinations sane, raw, cooked, cbreak, evenp, oddp, nl, ek, \
+                          pass8 and litout.");
+                return;
+            }
+            "--version" => {
+                println!("{} {}", NAME, VERSION);
+                return;
+            }
+            _ if arg.starts_with("--file=") => device = Some(arg.slice_from(7).to_string()),
+            _ if arg.starts_with("-F") => device = Some(arg.slice_from(2).to_string()),
+            _ => settings.push(arg.to_string())
+        }
+        i += 1;
+    }
+
+    if all && save {
+        crash!(1, "the options for verbose and stty-readable output styles are mutually \
+                   exclusive");
+    }
+    if (all || save) && !settings.is_empty() {
+        crash!(1, "when specifying an output style, modes may not be set");
+    }
+
+    // O_NONBLOCK keeps the open from waiting for carrier on a serial line;
+    // it is cleared again before anything else is done
+    let file = match device {
+        Some(ref name) => {
+            let path = Path::new(name.as_slice());
+            let mut fd = match Fd::open(&path, libc::O_RDONLY | O_NONBLOCK, 0) {
+                Ok(fd) => fd,
+                Err(e) => crash!(1, "{}: {}", name, util::strerror(&e))
+            };
+            match fd.clear_flags(O_NONBLOCK) {
+                Ok(()) => {}
+                Err(e) => crash!(1, "{}: couldn't reset non-blocking mode: {}", name,
+                                 util::strerror(&e))
+            }
+            Some(fd)
+        }
+        None => None
+    };
+    let fd = match file {
+        Some(ref fd) => fd.raw(),
+        None => libc::STDIN_FILENO
+    };
+    let device_name = match device {
+        Some(ref name) => name.clone(),
+        None => "standard input".to_string()
+    };
+
+    let mut t = match termios::get(fd) {
+        Ok(t) => t,
+        Err(e) => crash!(1, "{}: {}", device_name, util::strerror(&e))
+    };
+
+    if settings.is_empty() {
+        if save {
+            println!("{}", saved(&t));
+        } else {
+            print!("{}", display(fd, &t, all));
+        }
+        return;
+    }
+
+    let mut size = None;
+    let mut i = 0;
+    while i < settings.len() {
+        let name = settings.get(i).as_slice();
+        let value = settings.as_slice().get(i + 1).map(|v| v.as_slice());
+        let used = match apply(name, value, &mut t, &mut size, fd) {
+            Ok(used) => used,
+            Err(e) => crash!(1, "{}", e)
+        };
+        i += 1 + used;
+    }
+
+    match termios::set(fd, &t) {
+        Ok(()) => {}
+        Err(e) => crash!(1, "{}: {}", device_name, util::strerror(&e))
+    }
+    // tcsetattr succeeds when any of the changes could be made
+    match termios::get(fd) {
+        Ok(now) => {
+            if !same(&now, &t) {
+                crash!(1, "{}: unable to perform all requested operations", device_name);
+            }
+        }
+        Err(e) => crash!(1, "{}: {}", device_name, util::strerror(&e))
+    }
+
+    match size {
+        Some((rows, cols)) => {
+            let mut ws = match termios::window_size(fd) {
+                Ok(ws) => ws,
+                Err(e) => crash!(1, "{}: {}", device_name, util::strerror(&e))
+            };
+            match rows {
+                Some(rows) => ws.ws_row = rows,
+                None => {}
+            }
+            match cols {
+                Some(cols) => ws.ws_col = cols,
+                None => {}
+            }
+            match termios::set_window_size(fd, &ws) {
+                Ok(()) => {}
+                Err(e) => crash!(1, "{}: {}", device_name, util::strerror(&e))
+            }
+        }
+        None => {}
+    }
+}
+
+// Makes one change, returning how many of the following arguments it took
+// as its value.
+fn apply(name: &str, value: Option<&str>, t: &mut termios,
+         size: &mut Option<(Option<u16>, Option<u16>)>, fd: libc::c_int) -> Result<uint, String> {
+    let arg = || match value {
+        Some(value) => Ok(value),
+        None => Err(format!("missing argument to '{}'", name))
+    };
+
+    match name {
+        "speed" => {
+            println!("{}", baud(ospeed(t)).unwrap_or(0));
+            return Ok(0);
+        }
+        "size" => {
+            match termios::window_size(fd) {
+                Ok(ws) => println!("{} {}", ws.ws_row, ws.ws_col),
+                Err(e) => return Err(util::strerror(&e))
+            }
+            return Ok(0);
+        }
+        "rows" | "cols" | "columns" => {
+            let n = try!(integer(try!(arg()), 0xffff)) as u16;
+            let (rows, cols) = size.unwrap_or((None, None));
+            *size = Some(if name == "rows" { (Some(n), cols) } else { (rows, Some(n)) });
+            return Ok(1);
+        }
+        "ispeed" | "ospeed" => {
+            let value = try!(arg());
+            let code = try!(speed_code(value));
+            if name == "ispeed" { set_ispeed(t, code) } else { set_ospeed(t, code) }
+            return Ok(1);
+        }
+        "min" | "time" => {
+            let n = try!(integer(try!(arg()), 0xff)) as cc_t;
+            t.c_cc[if name == "min" { VMIN } else { VTIME }] = n;
+            return Ok(1);
+        }
+        "line" => {
+            let n = try!(integer(try!(arg()), 0xff));
+            set_line(t, n as cc_t);
+            return Ok(1);
+        }
+        "sane" => {
+            sane(t);
+            return Ok(0);
+        }
+        "raw" | "-cooked" => {
+            raw(t);
+            return Ok(0);
+        }
+        "-raw" | "cooked" => {
+            cooked(t);
+            return Ok(0);
+        }
+        "ek" => {
+            t.c_cc[VERASE] = 127;
+            t.c_cc[VKILL] = 21;
+            return Ok(0);
+        }
+        _ => {}
+    }
+
+    if name.len() > 0 && name.chars().all(|c| c.is_digit()) {
+        let code = try!(speed_code(name));
+        set_ispeed(t, code);
+        set_ospeed(t, code);
+        return Ok(0);
+    }
+    if name.contains_char(':') {
+        return restore(name, t).map(|_| 0);
+    }
+
+    match COMBINATIONS.iter().find(|&&(n, _)| n == name) {
+        Some(&(_, settings)) => {
+            let words: Vec<&str> = settings.words().collect();
+            let mut i = 0;
+            while i < words.len() {
+                let value = words.as_slice().get(i + 1).map(|v| *v);
+                i += 1 + try!(apply(*words.get(i), value, t, size, fd));
+            }
+            return Ok(0);
+        }
+        None => {}
+    }
+
+    match CONTROL_CHARS.iter().find(|c| c.name == name && c.index != VNONE) {
+        Some(c) => {
+            let value = try!(arg());
+            match control_char(value) {
+                Some(ch) => t.c_cc[c.index] = ch,
+                None => return Err(format!("invalid integer argument: '{}'", value))
+            }
+            return Ok(1);
+        }
+        None => {}
+    }
+
+    let (on, flag_name) = if name.starts_with("-") { (false, name.slice_from(1)) } else {
+        (true, name)
+    };
+    match FLAGS.iter().find(|f| f.name == flag_name && f.mask != 0) {
+        Some(flag) if on || flag.negatable => {
+            set_flag(t, flag, on);
+            Ok(0)
+        }
+        _ => Err(format!("invalid argument '{}'", name))
+    }
+}
+
+fn field<'a>(t: &'a mut termios, field: Field) -> &'a mut tcflag_t {
+    match field {
+        Control => &mut t.c_cflag,
+        Input => &mut t.c_iflag,
+        Output => &mut t.c_oflag,
+        Local => &mut t.c_lflag,
+    }
+}
+
+fn field_value(t: &termios, field: Field) -> tcflag_t {
+    match field {
+        Control => t.c_cflag,
+        Input => t.c_iflag,
+        Output => t.c_oflag,
+        Local => t.c_lflag,
+    }
+}
+
+fn set_flag(t: &mut termios, flag: &Flag, on: bool) {
+    let bits = field(t, flag.field);
+    if on {
+        *bits = *bits & !flag.mask | flag.bits;
+    } else {
+        *bits &= !flag.bits;
+    }
+}
+
+fn is_set(t: &termios, flag: &Flag) -> bool {
+    field_value(t, flag.field) & flag.mask == flag.bits
+}
+
+fn sane(t: &mut termios) {
+    for flag in FLAGS.iter().filter(|f| f.mask != 0) {
+        match flag.sane {
+            Some(on) if flag.negatable || on => set_flag(t, flag, on),
+            _ => {}
+        }
+    }
+    for c in CONTROL_CHARS.iter().filter(|c| c.index != VNONE) {
+        t.c_cc[c.index] = c.default.unwrap_or(VDISABLE);
+    }
+    t.c_cc[VMIN] = 1;
+    t.c_cc[VTIME] = 0;
+}
+
+// Every byte as it comes, with no processing on the way in or out.
+fn raw(t: &mut termios) {
+    t.c_iflag = 0;
+    t.c_oflag &= !OPOST;
+    t.c_lflag &= !(ISIG | ICANON | XCASE);
+    t.c_cc[VMIN] = 1;
+    t.c_cc[VTIME] = 0;
+}
+
+fn cooked(t: &mut termios) {
+    t.c_iflag |= BRKINT | IGNPAR | ISTRIP | ICRNL | IXON;
+    t.c_oflag |= OPOST;
+    t.c_lflag |= ISIG | ICANON;
+    t.c_cc[VEOF] = 4;
+    t.c_cc[VEOL] = VDISABLE;
+}
+
+#[cfg(target_os = "linux")]
+fn set_line(t: &mut termios, line: cc_t) {
+    t.c_line = line;
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_line(_: &mut termios, _: cc_t) {}
+
+#[cfg(target_os = "linux")]
+fn line(t: &termios) -> Option<cc_t> {
+    Some(t.c_line)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn line(_: &termios) -> Option<cc_t> {
+    None
+}
+
+fn same(a: &termios, b: &termios) -> bool {
+    a.c_iflag == b.c_iflag && a.c_oflag == b.c_oflag && a.c_cflag == b.c_cflag
+    && a.c_lflag == b.c_lflag && a.c_cc.as_slice() == b.c_cc.as_slice()
+    && ospeed(a) == ospeed(b) && ispeed(a) == ispeed(b)
+}
+
+fn integer(s: &str, max: u64) -> Result<u64, String> {
+    let n = if s.starts_with("0x") || s.starts_with("0X") {
+        std::num::from_str_radix::<u64>(s.slice_from(2), 16)
+    } else if s.len() > 1 && s.starts_with("0") {
+        std::num::from_str_radix::<u64>(s.slice_from(1), 8)
+    } else {
+        from_str::<u64>(s)
+    };
+    match n {
+        Some(n) if n <= max => Ok(n),
+        _ => Err(format!("invalid integer argument: '{}'", s))
+    }
+}
+
+fn speed_code(s: &str) -> Result<speed_t, String> {
+    match from_str::<uint>(s).and_then(|b| speed(b)) {
+        Some(code) => Ok(code),
+        None => Err(format!("invalid argument '{}'", s))
+    }
+}
+
+// ^X, ^? and ^- or undef for none, a character as it is, or its number.
+fn control_char(s: &str) -> Option<cc_t> {
+    let bytes = s.as_bytes();
+    if s == "^-" || s == "undef" {
+        Some(VDISABLE)
+    } else if bytes.len() == 2 && bytes[0] == '^' as u8 {
+        if bytes[1] == '?' as u8 { Some(127) } else { Some(bytes[1] & 0x1f) }
+    } else if bytes.len() == 1 {
+        Some(bytes[0])
+    } else {
+        integer(s, 0xff).ok().map(|n| n as cc_t)
+    }
+}
+
+fn visible(c: cc_t) -> String {
+    if c == VDISABLE {
+        return "<undef>".to_string();
+    }
+    let mut out = String::new();
+    let mut c = c;
+    if c >= 128 {
+        out.push_str("M-");
+        c -= 128;
+    }
+    if c < 32 {
+        out.push_char('^');
+        out.push_char((c + 64) as char);
+    } else if c == 127 {
+        out.push_str("^?");
+    } else {
+        out.push_char(c as char);
+    }
+    out
+}
+
+// The -g form, which stty takes back as a setting.
+fn saved(t: &termios) -> String {
+    let mut out = format!("{:x}:{:x}:{:x}:{:x}", t.c_iflag, t.c_oflag, t.c_cflag, t.c_lflag);
+    for c in t.c_cc.iter() {
+        out.push_str(format!(":{:x}", *c).as_slice());
+    }
+    out
+}
+
+fn restore(s: &str, t: &mut termios) -> Result<(), String> {
+    let fields: Vec<&str> = s.split(':').collect();
+    let values: Vec<u64> = fields.iter()
+                                 .filter_map(|f| std::num::from_str_radix::<u64>(*f, 16))
+                                 .collect();
+    if fields.len() != 4 + NCCS || values.len() != fields.len() {
+        return Err(format!("invalid argument '{}'", s));
+    }
+    t.c_iflag = *values.get(0) as tcflag_t;
+    t.c_oflag = *values.get(1) as tcflag_t;
+    t.c_cflag = *values.get(2) as tcflag_t;
+    t.c_lflag = *values.get(3) as tcflag_t;
+    for (i, v) in values.slice_from(4).iter().enumerate() {
+        t.c_cc[i] = *v as cc_t;
+    }
+    Ok(())
+}
+
+// Lays words out across lines no wider than the terminal, as GNU stty does.
+struct Wrapper {
+    out: String,
+    col: uint,
+    max: uint,
+}
+
+impl Wrapper {
+    fn add(&mut self, word: &str) {
+        if self.col > 0 {
+            if self.col + 1 + word.len() >= self.max {
+                self.out.push_char('\n');
+                self.col = 0;
+            } else {
+                self.out.push_char(' ');
+                self.col += 1;
+            }
+        }
+        self.out.push_str(word);
+        self.col += word.len();
+    }
+
+    fn end_line(&mut self) {
+        if self.col > 0 {
+            self.out.push_char('\n');
+            self.col = 0;
+        }
+    }
+}
+
+// Everything with -a, or else only what sane would change.
+fn display(fd: libc::c_int, t: &termios, all: bool) -> String {
+    let ws = termios::window_size(fd).ok();
+    let max = match ws {
+        Some(ref ws) if ws.ws_col > 0 => ws.ws_col as uint,
+        _ => match os::getenv("COLUMNS").and_then(|c| from_str::<uint>(c.as_slice())) {
+            Some(c) if c > 0 => c,
+            _ => 80
+        }
+    };
+    let mut w = Wrapper { out: String::new(), col: 0, max: max };
+
+    let (ispeed, ospeed) = (baud(ispeed(t)).unwrap_or(0), baud(ospeed(t)).unwrap_or(0));
+    if ispeed == ospeed || ispeed == 0 {
+        w.add(format!("speed {} baud;", ospeed).as_slice());
+    } else {
+        w.add(format!("ispeed {} baud; ospeed {} baud;", ispeed, ospeed).as_slice());
+    }
+    if all {
+        match ws {
+            Some(ref ws) => {
+                w.add(format!("rows {}; columns {};", ws.ws_row, ws.ws_col).as_slice())
+            }
+            None => {}
+        }
+    }
+    match line(t) {
+        Some(line) => w.add(format!("line = {};", line).as_slice()),
+        None => {}
+    }
+    w.end_line();
+
+    for c in CONTROL_CHARS.iter().filter(|c| c.index != VNONE) {
+        let value = t.c_cc[c.index];
+        if all || value != c.default.unwrap_or(VDISABLE) {
+            w.add(format!("{} = {};", c.name, visible(value)).as_slice());
+        }
+    }
+    if all || t.c_lflag & ICANON == 0 {
+        w.add(format!("min = {}; time = {};", t.c_cc[VMIN], t.c_cc[VTIME]).as_slice());
+    }
+    w.end_line();
+
+    for &group in [Control, Input, Output, Local].iter() {
+        for flag in FLAGS.iter() {
+            if flag.mask == 0 || ALIASES.contains(&flag.name) || !same_field(flag.field, group) {
+                continue;
+            }
+            let set = is_set(t, flag);
+            let word = if set {
+                if all || flag.sane == Some(false) { Some(flag.name.to_string()) } else { None }
+            } else if flag.negatable && (all || flag.sane == Some(true)) {
+                Some(format!("-{}", flag.name))
+            } else {
+                None
+            };
+            match word {
+                Some(word) => w.add(word.as_slice()),
+                None => {}
+            }
+        }
+        if all {
+            w.end_line();
+        }
+    }
+    w.end_line();
+    w.out
+}
+
+fn same_field(a: Field, b: Field) -> bool {
+    a as uint == b as uint
+}
@@ -0,0 +1,363 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * termios(3) and the terminal size ioctls.  A flag that one system lacks is
+ * 0 there, so that stty can leave it out.
+ */
+
+#![allow(dead_code, non_camel_case_types)]
+
+use std::io::{IoError, IoResult};
+use std::mem;
+use libc::{c_int, c_ulong, c_ushort};
+
+pub use self::os::*;
+
+#[cfg(target_os = "linux")]
+mod os {
+    pub type tcflag_t = u32;
+    pub type cc_t = u8;
+    pub type speed_t = u32;
+
+    pub static NCCS: uint = 32;
+
+    pub struct termios {
+        pub c_iflag: tcflag_t,
+        pub c_oflag: tcflag_t,
+        pub c_cflag: tcflag_t,
+        pub c_lflag: tcflag_t,
+        pub c_line: cc_t,
+        pub c_cc: [cc_t, ..NCCS],
+        pub c_ispeed: speed_t,
+        pub c_ospeed: speed_t,
+    }
+
+    pub static IGNBRK: tcflag_t = 0o1;
+    pub static BRKINT: tcflag_t = 0o2;
+    pub static IGNPAR: tcflag_t = 0o4;
+    pub static PARMRK: tcflag_t = 0o10;
+    pub static INPCK: tcflag_t = 0o20;
+    pub static ISTRIP: tcflag_t = 0o40;
+    pub static INLCR: tcflag_t = 0o100;
+    pub static IGNCR: tcflag_t = 0o200;
+    pub static ICRNL: tcflag_t = 0o400;
+    pub static IUCLC: tcflag_t = 0o1000;
+    pub static IXON: tcflag_t = 0o2000;
+    pub static IXANY: tcflag_t = 0o4000;
+    pub static IXOFF: tcflag_t = 0o10000;
+    pub static IMAXBEL: tcflag_t = 0o20000;
+    pub static IUTF8: tcflag_t = 0o40000;
+
+    pub static OPOST: tcflag_t = 0o1;
+    pub static OLCUC: tcflag_t = 0o2;
+    pub static ONLCR: tcflag_t = 0o4;
+    pub static OCRNL: tcflag_t = 0o10;
+    pub static ONOCR: tcflag_t = 0o20;
+    pub static ONLRET: tcflag_t = 0o40;
+    pub static OFILL: tcflag_t = 0o100;
+    pub static OFDEL: tcflag_t = 0o200;
+    pub static NLDLY: tcflag_t = 0o400;
+    pub static NL0: tcflag_t = 0;
+    pub static NL1: tcflag_t = 0o400;
+    pub static CRDLY: tcflag_t = 0o3000;
+    pub static CR0: tcflag_t = 0;
+    pub static CR1: tcflag_t = 0o1000;
+    pub static CR2: tcflag_t = 0o2000;
+    pub static CR3: tcflag_t = 0o3000;
+    pub static TABDLY: tcflag_t = 0o14000;
+    pub static TAB0: tcflag_t = 0;
+    pub static TAB1: tcflag_t = 0o4000;
+    pub static TAB2: tcflag_t = 0o10000;
+    pub static TAB3: tcflag_t = 0o14000;
+    pub static BSDLY: tcflag_t = 0o20000;
+    pub static BS0: tcflag_t = 0;
+    pub static BS1: tcflag_t = 0o20000;
+    pub static VTDLY: tcflag_t = 0o40000;
+    pub static VT0: tcflag_t = 0;
+    pub static VT1: tcflag_t = 0o40000;
+    pub static FFDLY: tcflag_t = 0o100000;
+    pub static FF0: tcflag_t = 0;
+    pub static FF1: tcflag_t = 0o100000;
+
+    pub static CSIZE: tcflag_t = 0o60;
+    pub static CS5: tcflag_t = 0;
+    pub static CS6: tcflag_t = 0o20;
+    pub static CS7: tcflag_t = 0o40;
+    pub static CS8: tcflag_t = 0o60;
+    pub static CSTOPB: tcflag_t = 0o100;
+    pub static CREAD: tcflag_t = 0o200;
+    pub static PARENB: tcflag_t = 0o400;
+    pub static PARODD: tcflag_t = 0o1000;
+    pub static HUPCL: tcflag_t = 0o2000;
+    pub static CLOCAL: tcflag_t = 0o4000;
+    pub static CMSPAR: tcflag_t = 0o10000000000;
+    pub static CRTSCTS: tcflag_t = 0o20000000000;
+
+    pub static ISIG: tcflag_t = 0o1;
+    pub static ICANON: tcflag_t = 0o2;
+    pub static XCASE: tcflag_t = 0o4;
+    pub static ECHO: tcflag_t = 0o10;
+    pub static ECHOE: tcflag_t = 0o20;
+    pub static ECHOK: tcflag_t = 0o40;
+    pub static ECHONL: tcflag_t = 0o100;
+    pub static NOFLSH: tcflag_t = 0o200;
+    pub static TOSTOP: tcflag_t = 0o400;
+    pub static ECHOCTL: tcflag_t = 0o1000;
+    pub static ECHOPRT: tcflag_t = 0o2000;
+    pub static ECHOKE: tcflag_t = 0o4000;
+    pub static FLUSHO: tcflag_t = 0o10000;
+    pub static PENDIN: tcflag_t = 0o40000;
+    pub static IEXTEN: tcflag_t = 0o100000;
+    pub static EXTPROC: tcflag_t = 0o200000;
+
+    pub static VINTR: uint = 0;
+    pub static VQUIT: uint = 1;
+    pub static VERASE: uint = 2;
+    pub static VKILL: uint = 3;
+    pub static VEOF: uint = 4;
+    pub static VTIME: uint = 5;
+    pub static VMIN: uint = 6;
+    pub static VSWTC: uint = 7;
+    pub static VSTART: uint = 8;
+    pub static VSTOP: uint = 9;
+    pub static VSUSP: uint = 10;
+    pub static VEOL: uint = 11;
+    pub static VREPRINT: uint = 12;
+    pub static VDISCARD: uint = 13;
+    pub static VWERASE: uint = 14;
+    pub static VLNEXT: uint = 15;
+    pub static VEOL2: uint = 16;
+    // no such character
+    pub static VNONE: uint = ::std::uint::MAX;
+    pub static VDSUSP: uint = VNONE;
+    pub static VSTATUS: uint = VNONE;
+
+    // what stands for no character at all
+    pub static VDISABLE: cc_t = 0;
+
+    // the speeds from 57600 up are in the CBAUDEX bit's own range
+    pub static SPEEDS: &'static [(uint, speed_t)] = &[
+        (0, 0), (50, 1), (75, 2), (110, 3), (134, 4), (150, 5), (200, 6), (300, 7),
+        (600, 8), (1200, 9), (1800, 10), (2400, 11), (4800, 12), (9600, 13), (19200, 14),
+        (38400, 15), (57600, 0o10001), (115200, 0o10002), (230400, 0o10003),
+        (460800, 0o10004), (500000, 0o10005), (576000, 0o10006), (921600, 0o10007),
+        (1000000, 0o10010), (1152000, 0o10011), (1500000, 0o10012), (2000000, 0o10013),
+        (2500000, 0o10014), (3000000, 0o10015), (3500000, 0o10016), (4000000, 0o10017),
+    ];
+
+    pub static TIOCGWINSZ: ::libc::c_ulong = 0x5413;
+    pub static TIOCSWINSZ: ::libc::c_ulong = 0x5414;
+}
+
+#[cfg(target_os = "macos")]
+mod os {
+    pub type tcflag_t = u64;
+    pub type cc_t = u8;
+    pub type speed_t = u64;
+
+    pub static NCCS: uint = 20;
+
+    pub struct termios {
+        pub c_iflag: tcflag_t,
+        pub c_oflag: tcflag_t,
+        pub c_cflag: tcflag_t,
+        pub c_lflag: tcflag_t,
+        pub c_cc: [cc_t, ..NCCS],
+        pub c_ispeed: speed_t,
+        pub c_ospeed: speed_t,
+    }
+
+    pub static IGNBRK: tcflag_t = 0x1;
+    pub static BRKINT: tcflag_t = 0x2;
+    pub static IGNPAR: tcflag_t = 0x4;
+    pub static PARMRK: tcflag_t = 0x8;
+    pub static INPCK: tcflag_t = 0x10;
+    pub static ISTRIP: tcflag_t = 0x20;
+    pub static INLCR: tcflag_t = 0x40;
+    pub static IGNCR: tcflag_t = 0x80;
+    pub static ICRNL: tcflag_t = 0x100;
+    pub static IUCLC: tcflag_t = 0;
+    pub static IXON: tcflag_t = 0x200;
+    pub static IXANY: tcflag_t = 0x800;
+    pub static IXOFF: tcflag_t = 0x400;
+    pub static IMAXBEL: tcflag_t = 0x2000;
+    pub static IUTF8: tcflag_t = 0x4000;
+
+    pub static OPOST: tcflag_t = 0x1;
+    pub static OLCUC: tcflag_t = 0;
+    pub static ONLCR: tcflag_t = 0x2;
+    pub static OCRNL: tcflag_t = 0x10;
+    pub static ONOCR: tcflag_t = 0x20;
+    pub static ONLRET: tcflag_t = 0x40;
+    pub static OFILL: tcflag_t = 0x80;
+    pub static OFDEL: tcflag_t = 0x20000;
+    pub static NLDLY: tcflag_t = 0x300;
+    pub static NL0: tcflag_t = 0;
+    pub static NL1: tcflag_t = 0x100;
+    pub static CRDLY: tcflag_t = 0x3000;
+    pub static CR0: tcflag_t = 0;
+    pub static CR1: tcflag_t = 0x1000;
+    pub static CR2: tcflag_t = 0x2000;
+    pub static CR3: tcflag_t = 0x3000;
+    pub static TABDLY: tcflag_t = 0xc04;
+    pub static TAB0: tcflag_t = 0;
+    pub static TAB1: tcflag_t = 0x400;
+    pub static TAB2: tcflag_t = 0x800;
+    pub static TAB3: tcflag_t = 0x4;
+    pub static BSDLY: tcflag_t = 0x8000;
+    pub static BS0: tcflag_t = 0;
+    pub static BS1: tcflag_t = 0x8000;
+    pub static VTDLY: tcflag_t = 0x10000;
+    pub static VT0: tcflag_t = 0;
+    pub static VT1: tcflag_t = 0x10000;
+    pub static FFDLY: tcflag_t = 0x4000;
+    pub static FF0: tcflag_t = 0;
+    pub static FF1: tcflag_t = 0x4000;
+
+    pub static CSIZE: tcflag_t = 0x300;
+    pub static CS5: tcflag_t = 0;
+    pub static CS6: tcflag_t = 0x100;
+    pub static CS7: tcflag_t = 0x200;
+    pub static CS8: tcflag_t = 0x300;
+    pub static CSTOPB: tcflag_t = 0x400;
+    pub static CREAD: tcflag_t = 0x800;
+    pub static PARENB: tcflag_t = 0x1000;
+    pub static PARODD: tcflag_t = 0x2000;
+    pub static HUPCL: tcflag_t = 0x4000;
+    pub static CLOCAL: tcflag_t = 0x8000;
+    pub static CMSPAR: tcflag_t = 0;
+    pub static CRTSCTS: tcflag_t = 0x30000;
+
+    pub static ISIG: tcflag_t = 0x80;
+    pub static ICANON: tcflag_t = 0x100;
+    pub static XCASE: tcflag_t = 0;
+    pub static ECHO: tcflag_t = 0x8;
+    pub static ECHOE: tcflag_t = 0x2;
+    pub static ECHOK: tcflag_t = 0x4;
+    pub static ECHONL: tcflag_t = 0x10;
+    pub static NOFLSH: tcflag_t = 0x80000000;
+    pub static TOSTOP: tcflag_t = 0x400000;
+    pub static ECHOCTL: tcflag_t = 0x40;
+    pub static ECHOPRT: tcflag_t = 0x20;
+    pub static ECHOKE: tcflag_t = 0x1;
+    pub static FLUSHO: tcflag_t = 0x800000;
+    pub static PENDIN: tcflag_t = 0x20000000;
+    pub static IEXTEN: tcflag_t = 0x400;
+    pub static EXTPROC: tcflag_t = 0x800;
+
+    pub static VEOF: uint = 0;
+    pub static VEOL: uint = 1;
+    pub static VEOL2: uint = 2;
+    pub static VERASE: uint = 3;
+    pub static VWERASE: uint = 4;
+    pub static VKILL: uint = 5;
+    pub static VREPRINT: uint = 6;
+    pub static VINTR: uint = 8;
+    pub static VQUIT: uint = 9;
+    pub static VSUSP: uint = 10;
+    pub static VDSUSP: uint = 11;
+    pub static VSTART: uint = 12;
+    pub static VSTOP: uint = 13;
+    pub static VLNEXT: uint = 14;
+    pub static VDISCARD: uint = 15;
+    pub static VMIN: uint = 16;
+    pub static VTIME: uint = 17;
+    pub static VSTATUS: uint = 18;
+    // no such character
+    pub static VNONE: uint = ::std::uint::MAX;
+    pub static VSWTC: uint = VNONE;
+
+    pub static VDISABLE: cc_t = 0xff;
+
+    // the speed is the number of baud itself
+    pub static SPEEDS: &'static [(uint, speed_t)] = &[
+        (0, 0), (50, 50), (75, 75), (110, 110), (134, 134), (150, 150), (200, 200),
+        (300, 300), (600, 600), (1200, 1200), (1800, 1800), (2400, 2400), (4800, 4800),
+        (9600, 9600), (19200, 19200), (38400, 38400), (57600, 57600), (115200, 115200),
+        (230400, 230400),
+    ];
+
+    pub static TIOCGWINSZ: ::libc::c_ulong = 0x40087468;
+    pub static TIOCSWINSZ: ::libc::c_ulong = 0x80087467;
+}
+
+pub static TCSADRAIN: c_int = 1;
+
+pub struct winsize {
+    pub ws_row: c_ushort,
+    pub ws_col: c_ushort,
+    pub ws_xpixel: c_ushort,
+    pub ws_ypixel: c_ushort,
+}
+
+extern {
+    fn tcgetattr(fd: c_int, termios: *mut termios) -> c_int;
+    fn tcsetattr(fd: c_int, optional_actions: c_int, termios: *termios) -> c_int;
+    fn cfgetospeed(termios: *termios) -> speed_t;
+    fn cfgetispeed(termios: *termios) -> speed_t;
+    fn cfsetospeed(termios: *mut termios, speed: speed_t) -> c_int;
+    fn cfsetispeed(termios: *mut termios, speed: speed_t) -> c_int;
+    fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+}
+
+pub fn get(fd: c_int) -> IoResult<termios> {
+    let mut t: termios = unsafe { mem::zeroed() };
+    if unsafe { tcgetattr(fd, &mut t) } != 0 {
+        return Err(IoError::last_error());
+    }
+    Ok(t)
+}
+
+pub fn set(fd: c_int, t: &termios) -> IoResult<()> {
+    if unsafe { tcsetattr(fd, TCSADRAIN, t) } != 0 {
+        return Err(IoError::last_error());
+    }
+    Ok(())
+}
+
+pub fn ospeed(t: &termios) -> speed_t {
+    unsafe { cfgetospeed(t) }
+}
+
+pub fn ispeed(t: &termios) -> speed_t {
+    unsafe { cfgetispeed(t) }
+}
+
+pub fn set_ospeed(t: &mut termios, speed: speed_t) {
+    unsafe { cfsetospeed(t, speed); }
+}
+
+pub fn set_ispeed(t: &mut termios, speed: speed_t) {
+    unsafe { cfsetispeed(t, speed); }
+}
+
+// The number of baud a speed code stands for, and the other way around.
+pub fn baud(speed: speed_t) -> Option<uint> {
+    SPEEDS.iter().find(|&&(_, s)| s == speed).map(|&(b, _)| b)
+}
+
+pub fn speed(baud: uint) -> Option<speed_t> {
+    SPEEDS.iter().find(|&&(b, _)| b == baud).map(|&(_, s)| s)
+}
+
+pub fn window_size(fd: c_int) -> IoResult<winsize> {
+    let mut size = winsize { ws_row: 0, ws_col: 0, ws_xpixel: 0, ws_ypixel: 0 };
+    if unsafe { ioctl(fd, TIOCGWINSZ, &mut size as *mut winsize) } != 0 {
+        return Err(IoError::last_error());
+    }
+    Ok(size)
+}
+
+pub fn set_window_size(fd: c_int, size: &winsize) -> IoResult<()> {
+    if unsafe { ioctl(fd, TIOCSWINSZ, size as *winsize) } != 0 {
+        return Err(IoError::last_error());
+    }
+    Ok(())
+}
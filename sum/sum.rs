@@ -20,6 +20,9 @@ use std::io::stdio::{stdin_raw};
 #[path="../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static VERSION: &'static str = "1.0.0";
 static NAME: &'static str = "sum";
 
@@ -77,18 +80,26 @@ fn open(name: &str) -> IoResult<Box<Reader>> {
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).as_slice();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("r", "", "use the BSD compatible algorithm (default)"),
         getopts::optflag("s", "sysv", "use System V compatible algorithm"),
         getopts::optflag("h", "help", "show this help message"),
         getopts::optflag("v", "version", "print the version and exit"),
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).as_slice();
+    let opts = opts();
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => crash!(1, "Invalid options\n{}", f.to_err_msg())
     };
@@ -99,7 +110,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {0:s} [OPTION]... [FILE]...", program);
         println!("");
-        print(getopts::usage("checksum and count the blocks in a file", opts).as_slice());
+        print(getopts::usage("checksum and count the blocks in a file", opts.as_slice()).as_slice());
         println!("");
         println!("With no FILE, or when  FILE is -, read standard input.");
         return;
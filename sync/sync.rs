@@ -0,0 +1,126 @@
+#![crate_id(name="sync", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+
+use std::os;
+use std::io::{print, IoError, IoResult};
+use libc::c_int;
+use fdio::Fd;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fdio.rs"]
+mod fdio;
+
+static NAME: &'static str = "sync";
+static VERSION: &'static str = "1.0.0";
+
+#[cfg(target_os = "linux")]
+static O_NONBLOCK: c_int = 0o4000;
+#[cfg(not(target_os = "linux"))]
+static O_NONBLOCK: c_int = 0x4;
+
+extern {
+    fn sync();
+    #[cfg(target_os = "linux")]
+    fn syncfs(fd: c_int) -> c_int;
+}
+
+// Without syncfs(2) the only way to sync a file system is to sync them all.
+#[cfg(not(target_os = "linux"))]
+unsafe fn syncfs(_: c_int) -> c_int {
+    sync();
+    0
+}
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("d", "data", "sync only file data, no unneeded metadata"),
+        getopts::optflag("f", "file-system", "sync the file systems that contain the files"),
+        getopts::optflag("h", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION] [FILE]...", args.get(0));
+        println!("");
+        print(getopts::usage("Synchronize cached writes to persistent storage.\n\nIf one or \
+                              more files are specified, sync only them,\nor their containing \
+                              file systems.", opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    let data = matches.opt_present("data");
+    let file_system = matches.opt_present("file-system");
+    if data && file_system {
+        crash!(1, "cannot specify both --data and --file-system");
+    }
+    if matches.free.is_empty() {
+        if data {
+            crash!(1, "--data needs at least one argument");
+        }
+        unsafe { sync(); }
+        return;
+    }
+
+    for name in matches.free.iter() {
+        match sync_file(&Path::new(name.as_slice()), data, file_system) {
+            Ok(()) => {}
+            Err(e) => show_error!(1, "error syncing '{}': {}", name, util::strerror(&e))
+        }
+    }
+}
+
+fn sync_file(path: &Path, data: bool, file_system: bool) -> IoResult<()> {
+    // a write-only file can still be synced through a descriptor opened for
+    // writing, and O_NONBLOCK keeps a FIFO from waiting for the other end
+    let mut fd = match Fd::open(path, libc::O_RDONLY | O_NONBLOCK, 0) {
+        Ok(fd) => fd,
+        Err(e) => match Fd::open(path, libc::O_WRONLY | O_NONBLOCK, 0) {
+            Ok(fd) => fd,
+            Err(_) => return Err(e)
+        }
+    };
+    if file_system {
+        if unsafe { syncfs(fd.raw()) } == 0 { Ok(()) } else { Err(IoError::last_error()) }
+    } else {
+        fd.sync(data)
+    }
+}
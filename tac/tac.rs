@@ -13,92 +13,184 @@
 
 extern crate getopts;
 extern crate libc;
+extern crate regex;
 
 use std::io;
+use std::io::stdio::stdout_raw;
 use std::os;
+use regex::Regex;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "tac";
 static VERSION: &'static str = "1.0.0";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    // the records are written a piece at a time, so stdout is buffered here
+    let mut out = io::BufferedWriter::new(stdout_raw());
+    uumain_with(args, &mut out, &mut io::stderr())
+}
 
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("b", "before", "attach the separator before instead of after"),
-        getopts::optflag("r", "regex", "interpret the sequence as a regular expression (NOT IMPLEMENTED)"),
+        getopts::optflag("r", "regex", "interpret the sequence as a regular expression"),
         getopts::optopt("s", "separator", "use STRING as the separator instead of newline", "STRING"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit")
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+// uumain with the output and the diagnostics going to the given writers.
+pub fn uumain_with(args: Vec<String>, out: &mut Writer, err: &mut Writer) -> util::Outcome {
+    let program = args.get(0).clone();
+
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
-        Err(f) => crash!(1, "{}", f.to_err_msg())
+        Err(f) => {
+            write_error!(err, "{}", f.to_err_msg());
+            return util::outcome(1);
+        }
     };
     if matches.opt_present("help") {
-        println!("tac {}", VERSION);
-        println!("");
-        println!("Usage:");
-        println!("  {0:s} [OPTION]... [FILE]...", program);
-        println!("");
-        print!("{}", getopts::usage("Write each file to standard output, last line first.", opts));
+        try_write!(err, writeln!(out, "tac {}", VERSION));
+        try_write!(err, writeln!(out, ""));
+        try_write!(err, writeln!(out, "Usage:"));
+        try_write!(err, writeln!(out, "  {0:s} [OPTION]... [FILE]...", program));
+        try_write!(err, writeln!(out, ""));
+        let brief = "Write each file to standard output, last line first.";
+        try_write!(err, write!(out, "{}", getopts::usage(brief, opts.as_slice())));
+        Ok(())
     } else if matches.opt_present("version") {
-        println!("tac {}", VERSION);
+        try_write!(err, writeln!(out, "tac {}", VERSION));
+        Ok(())
     } else {
-        let before = matches.opt_present("b");
-        let regex = matches.opt_present("r");
-        let separator = match matches.opt_str("s") {
-            Some(m) => {
-                if m.len() == 0 {
-                    crash!(1, "separator cannot be empty")
-                } else {
-                    m
-                }
-            }
-            None => "\n".to_string()
-        };
-        let files = if matches.free.is_empty() {
-            vec!("-".to_string())
-        } else {
-            matches.free
-        };
-        tac(files, before, regex, separator.as_slice());
+        let mut config = Config::new().before(matches.opt_present("b"))
+                                      .regex(matches.opt_present("r"))
+                                      .files(matches.free.clone());
+        match matches.opt_str("s") {
+            Some(m) => config = config.separator(m.as_slice()),
+            None => {}
+        }
+        run(&config, out, err)
+    }
+}
+
+// How to reverse the files, or standard input when there are none.
+pub struct Config {
+    before: bool,
+    regex: bool,
+    separator: String,
+    files: Vec<String>,
+}
+
+impl Config {
+    // Records ending in a newline, as tac does without options.
+    pub fn new() -> Config {
+        Config { before: false, regex: false, separator: "\n".to_string(), files: vec!() }
+    }
+
+    pub fn before(self, before: bool) -> Config {
+        Config { before: before, ..self }
+    }
+
+    pub fn regex(self, regex: bool) -> Config {
+        Config { regex: regex, ..self }
+    }
+
+    pub fn separator(self, separator: &str) -> Config {
+        Config { separator: separator.to_string(), ..self }
+    }
+
+    pub fn files(self, files: Vec<String>) -> Config {
+        Config { files: files, ..self }
     }
 }
 
-fn tac(filenames: Vec<String>, before: bool, _: bool, separator: &str) {
-    for filename in filenames.move_iter() {
+// Writes the records of each file last first, returning the exit status.
+pub fn run(config: &Config, out: &mut Writer, err: &mut Writer) -> util::Outcome {
+    let before = config.before;
+    let separator = config.separator.as_slice();
+    if separator.len() == 0 {
+        write_error!(err, "separator cannot be empty");
+        return util::outcome(1);
+    }
+    let pattern = if config.regex {
+        match Regex::new(separator) {
+            Ok(re) => Some(re),
+            Err(e) => {
+                write_error!(err, "invalid regular expression: {}", e);
+                return util::outcome(1);
+            }
+        }
+    } else {
+        None
+    };
+    let mut status = 0;
+
+    let stdin = vec!("-".to_string());
+    let filenames = if config.files.is_empty() { &stdin } else { &config.files };
+    for filename in filenames.iter() {
         let mut file = io::BufferedReader::new(
             if filename.as_slice() == "-" {
                 box io::stdio::stdin_raw() as Box<Reader>
             } else {
-                box crash_if_err!(1, io::File::open(&Path::new(filename))) as Box<Reader>
+                match io::File::open(&Path::new(filename.as_slice())) {
+                    Ok(f) => box f as Box<Reader>,
+                    Err(e) => {
+                        write_error!(err, "failed to open '{}': {}", filename, util::strerror(&e));
+                        status = 1;
+                        continue;
+                    }
+                }
             }
         );
-        let mut data = crash_if_err!(1, file.read_to_str());
-        if data.as_slice().ends_with("\n") {
-            // removes blank line that is inserted otherwise
-            let mut buf = data.into_string();
-            let len = buf.len();
-            buf.truncate(len - 1);
-            data = buf.into_string();
-        }
-        let split_vec: Vec<&str> = data.as_slice().split_str(separator).collect();
-        let rev: String = split_vec.iter().rev().fold(String::new(), |mut a, &b| {
-            if before {
-               a.push_str(separator);
-               a.push_str(b);
-            } else {
-                a.push_str(b);
-                a.push_str(separator);
+        let data = match file.read_to_str() {
+            Ok(d) => d,
+            Err(e) => {
+                write_error!(err, "failed to read '{}': {}", filename, util::strerror(&e));
+                status = 1;
+                continue;
             }
-            a
-        });
-        print!("{}", rev);
+        };
+        let data = data.as_slice();
+
+        // (start, end) of every separator in the input; empty regex matches
+        // cannot delimit anything, so they are dropped
+        let matches: Vec<(uint, uint)> = match pattern {
+            Some(ref re) => re.find_iter(data).filter(|&(s, e)| s != e).collect(),
+            None => data.match_indices(separator).collect()
+        };
+
+        // Each record owns its separator: the one that ends it by default, or
+        // the one that starts it with --before.  Text before the first
+        // separator (--before) or after the last one (default) forms a record
+        // of its own without one.
+        let mut records = Vec::with_capacity(matches.len() + 1);
+        let mut start = 0;
+        for &(sep_start, sep_end) in matches.iter() {
+            let end = if before { sep_start } else { sep_end };
+            if end > start {
+                records.push(data.slice(start, end));
+            }
+            start = end;
+        }
+        if start < data.len() {
+            records.push(data.slice_from(start));
+        }
+
+        for record in records.iter().rev() {
+            try_write!(err, out.write_str(*record));
+        }
     }
+    try_write!(err, out.flush());
+    util::outcome(status)
 }
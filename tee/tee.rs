@@ -1,6 +1,5 @@
 #![crate_id(name="tee", vers="1.0.0", author="Aleksander Bielawski")]
 #![license="MIT"]
-#![feature(phase)]
 #![feature(macro_rules)]
 
 /*
@@ -13,54 +12,120 @@
  */
 
 extern crate getopts;
-#[phase(syntax, link)] extern crate log;
+extern crate libc;
 
-use std::io::{println, stdin, stdout, Append, File, Truncate, Write};
-use std::io::{IoResult};
-use std::io::util::{copy, NullWriter, MultiWriter};
+use std::io::{println, stdin, stdout, Append, BrokenPipe, EndOfFile, File, IoError, Truncate,
+              Write};
 use std::os::{args, set_exit_status};
-use getopts::{getopts, optflag, usage};
+use getopts::{optflag, optflagopt, usage};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/signal.rs"]
+mod signal;
 
 static NAME: &'static str = "tee";
 static VERSION: &'static str = "1.0.0";
 
 #[allow(dead_code)]
-fn main() { uumain(args()); }
+fn main() { set_exit_status(util::exit_status(uumain(args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    match options(args.as_slice()).and_then(exec) {
-        Ok(_) => set_exit_status(0),
-        Err(_) => set_exit_status(1)
-    }
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() {
+        match options(args.as_slice()).and_then(exec) {
+            Ok(_) => util::set_status(0),
+            Err(_) => util::set_status(1)
+        }
+    })
+}
+
+// What happens when writing to an output fails.
+#[deriving(PartialEq)]
+enum OutputError {
+    // SIGPIPE is let kill tee, and other errors are reported
+    Sigpipe,
+    Warn,
+    WarnNoPipe,
+    Exit,
+    ExitNoPipe,
 }
 
 struct Options {
     program: String,
     append: bool,
     ignore_interrupts: bool,
+    output_error: OutputError,
     print_and_exit: Option<String>,
     files: Box<Vec<Path>>
 }
 
-fn options(args: &[String]) -> Result<Options, ()> {
-    let opts = [
+// Somewhere the input goes, until writing to it fails.
+struct Output {
+    path: Path,
+    writer: Option<Box<Writer>>
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         optflag("a", "append", "append to the given FILEs, do not overwrite"),
         optflag("i", "ignore-interrupts", "ignore interrupt signals"),
+        optflag("p", "", "diagnose errors writing to non pipes"),
+        optflagopt("", "output-error", "set behavior on write error: 'warn' diagnoses errors \
+                                        writing to any output, 'warn-nopipe' those writing to \
+                                        any output but a pipe, 'exit' exits on error writing to \
+                                        any output, and 'exit-nopipe' on error writing to any \
+                                        output but a pipe.  The default MODE for -p and \
+                                        --output-error is 'warn-nopipe'", "MODE"),
         optflag("h", "help", "display this help and exit"),
         optflag("V", "version", "output version information and exit"),
-    ];
+    )
+}
 
-    let args: Vec<String> = args.iter().map(|x| x.to_string()).collect();
+fn options(args: &[String]) -> Result<Options, ()> {
+    let opts = opts();
+
+    // getopts would take the word after a bare --output-error as its MODE,
+    // but tee only takes one given as --output-error=MODE, so the default is
+    // filled in here before getopts sees it
+    let mut options_done = false;
+    let args: Vec<String> = args.iter().map(|arg| {
+        if !options_done && arg.as_slice() == "--output-error" {
+            "--output-error=warn-nopipe".to_string()
+        } else {
+            options_done = options_done || arg.as_slice() == "--";
+            arg.to_string()
+        }
+    }).collect();
 
-    getopts(args.tail(), opts).map_err(|e| e.to_err_msg()).and_then(|m| {
+    posix::getopts(args.tail(), opts.as_slice()).map_err(|e| e.to_err_msg()).and_then(|m| {
         let version = format!("{} {}", NAME, VERSION);
         let program = args.get(0).as_slice();
         let arguments = "[OPTION]... [FILE]...";
         let brief = "Copy standard input to each FILE, and also to standard output.";
         let comment = "If a FILE is -, copy again to standard output.";
         let help = format!("{}\n\nUsage:\n  {} {}\n\n{}\n{}",
-                           version, program, arguments, usage(brief, opts),
+                           version, program, arguments, usage(brief, opts.as_slice()),
                            comment);
+        let output_error = if m.opt_present("output-error") {
+            match m.opt_str("output-error") {
+                None => WarnNoPipe,
+                Some(mode) => match mode.as_slice() {
+                    "warn" => Warn,
+                    "warn-nopipe" => WarnNoPipe,
+                    "exit" => Exit,
+                    "exit-nopipe" => ExitNoPipe,
+                    _ => return Err(format!("invalid argument '{}' for '--output-error'", mode))
+                }
+            }
+        } else if m.opt_present("p") {
+            WarnNoPipe
+        } else {
+            Sigpipe
+        };
         let names = m.free.clone().move_iter().collect::<Vec<String>>().append_one("-".to_string());
         let to_print = if m.opt_present("help") { Some(help) }
                        else if m.opt_present("version") { Some(version) }
@@ -69,6 +134,7 @@ fn options(args: &[String]) -> Result<Options, ()> {
             program: program.to_string(),
             append: m.opt_present("append"),
             ignore_interrupts: m.opt_present("ignore-interrupts"),
+            output_error: output_error,
             print_and_exit: to_print,
             files: box names.iter().map(|name| Path::new(name.clone())).collect()
         })
@@ -82,76 +148,101 @@ fn exec(options: Options) -> Result<(), ()> {
     }
 }
 
+// Every output gets all of the input it can take: one failing is reported
+// and dropped, and the rest carry on.
 fn tee(options: Options) -> Result<(), ()> {
-    let writers = options.files.iter().map(|path| open(path, options.append)).collect();
-    let output = &mut MultiWriter::new(writers);
-    let input = &mut NamedReader { inner: box stdin() as Box<Reader> };
-    if copy(input, output).is_err() || output.flush().is_err() {
-        Err(())
-    } else {
-        Ok(())
+    if options.ignore_interrupts {
+        signal::ignore(signal::SIGINT);
     }
-}
-
-fn open(path: &Path, append: bool) -> Box<Writer> {
-    let inner = if *path == Path::new("-") {
-        box stdout() as Box<Writer>
+    // a reader going away is only an error like any other once the mode
+    // says how to deal with one
+    if options.output_error == Sigpipe {
+        signal::reset(signal::SIGPIPE);
     } else {
-        let mode = if append { Append } else { Truncate };
-        match File::open_mode(path, mode, Write) {
-            Ok(file) => box file as Box<Writer>,
-            Err(_) => box NullWriter as Box<Writer>
+        signal::ignore(signal::SIGPIPE);
+    }
+    let mut failed = false;
+    let mut outputs: Vec<Output> = options.files.iter().filter_map(|path| {
+        match open(path, options.append) {
+            Some(writer) => Some(Output { path: path.clone(), writer: Some(writer) }),
+            None => { failed = true; None }
         }
-    };
-    box NamedWriter { inner: inner, path: box path.clone() } as Box<Writer>
-}
-
-struct NamedWriter {
-    inner: Box<Writer>,
-    path: Box<Path>
-}
-
-impl Writer for NamedWriter {
-    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
-        with_path(self.path.clone(), || {
-            let val = self.inner.write(buf);
-            if val.is_err() {
-                self.inner = box NullWriter as Box<Writer>;
+    }).collect();
+
+    let mut input = stdin();
+    let mut buf = [0u8, ..8192];
+    loop {
+        let n = match input.read(buf) {
+            Ok(n) => n,
+            Err(ref e) if e.kind == EndOfFile => break,
+            Err(e) => {
+                warn(format!("stdin: {}", util::strerror(&e)).as_slice());
+                failed = true;
+                break;
             }
-            val
-        })
+        };
+        for output in outputs.mut_iter() {
+            failed |= !write(output, buf.slice_to(n), &options.output_error);
+        }
+        if outputs.iter().all(|output| output.writer.is_none()) {
+            break;
+        }
     }
-
-    fn flush(&mut self) -> IoResult<()> {
-        with_path(self.path.clone(), || {
-            let val = self.inner.flush();
-            if val.is_err() {
-                self.inner = box NullWriter as Box<Writer>;
-            }
-            val
-        })
+    for output in outputs.mut_iter() {
+        let result = match output.writer {
+            Some(ref mut writer) => writer.flush(),
+            None => Ok(())
+        };
+        match result {
+            Ok(()) => {}
+            Err(e) => failed |= !write_failed(&output.path, e, &options.output_error)
+        }
     }
+    if failed { Err(()) } else { Ok(()) }
 }
 
-struct NamedReader {
-    inner: Box<Reader>
+fn write(output: &mut Output, buf: &[u8], mode: &OutputError) -> bool {
+    let result = match output.writer {
+        Some(ref mut writer) => writer.write(buf),
+        None => return true
+    };
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            output.writer = None;
+            write_failed(&output.path, e, mode)
+        }
+    }
 }
 
-impl Reader for NamedReader {
-    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
-        with_path(&Path::new("stdin"), || {
-            self.inner.read(buf)
-        })
+// Whether the write error leaves tee still succeeding, which it does when
+// it was only a pipe closing and the mode does not care about those.
+fn write_failed(path: &Path, e: IoError, mode: &OutputError) -> bool {
+    let nopipe = *mode == WarnNoPipe || *mode == ExitNoPipe;
+    if e.kind == BrokenPipe && nopipe {
+        return true;
+    }
+    if *mode == Exit || *mode == ExitNoPipe {
+        crash!(1, "{}: {}", path.display(), util::strerror(&e));
     }
+    warn(format!("{}: {}", path.display(), util::strerror(&e)).as_slice());
+    false
 }
 
-fn with_path<T>(path: &Path, cb: || -> IoResult<T>) -> IoResult<T> {
-    match cb() {
-        Err(f) => { warn(format!("{}: {}", path.display(), f.to_str()).as_slice()); Err(f) }
-        okay => okay
+fn open(path: &Path, append: bool) -> Option<Box<Writer>> {
+    if *path == Path::new("-") {
+        return Some(box stdout() as Box<Writer>);
+    }
+    let mode = if append { Append } else { Truncate };
+    match File::open_mode(path, mode, Write) {
+        Ok(file) => Some(box file as Box<Writer>),
+        Err(e) => {
+            warn(format!("{}: {}", path.display(), util::strerror(&e)).as_slice());
+            None
+        }
     }
 }
 
 fn warn(message: &str) {
-    error!("{}: {}", args().get(0), message);
+    show_error!(1, "{}", message);
 }
@@ -0,0 +1,363 @@
+#![crate_id(name="uutest", vers="1.0.0", author="agent")]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * The crate is called uutest so that it does not clash with the standard
+ * test crate when linked into the multicall binary.
+ */
+
+#![feature(macro_rules)]
+
+extern crate libc;
+
+use std::os;
+use std::io::fs;
+use libc::{c_int, R_OK, W_OK, X_OK};
+use libc::funcs::posix88::unistd::{access, getegid, geteuid, isatty};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+static NAME: &'static str = "test";
+static VERSION: &'static str = "1.0.0";
+
+// Exit statuses: 0 when true, 1 when false, 2 on error.
+static EXIT_TRUE: int = 0;
+static EXIT_FALSE: int = 1;
+static EXIT_ERROR: int = 2;
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let program = Path::new(args.get(0).as_slice());
+    let bracket = program.filename_str() == Some("[");
+
+    let mut operands: Vec<&str> = args.tail().iter().map(|s| s.as_slice()).collect();
+
+    if bracket {
+        if operands.len() == 1 {
+            match *operands.get(0) {
+                "--help" => { help(); return }
+                "--version" => { println!("[ ({}) {}", NAME, VERSION); return }
+                _ => {}
+            }
+        }
+        if operands.last() != Some(&"]") {
+            crash!(EXIT_ERROR, "missing ']'");
+        }
+        operands.pop();
+    }
+
+    let result = match eval(operands.as_slice()) {
+        Ok(b) => b,
+        Err(msg) => crash!(EXIT_ERROR, "{}", msg)
+    };
+    util::set_status(if result { EXIT_TRUE } else { EXIT_FALSE });
+}
+
+fn help() {
+    println!("Usage: test EXPRESSION");
+    println!("  or:  test");
+    println!("  or:  [ EXPRESSION ]");
+    println!("  or:  [ ]");
+    println!("  or:  [ OPTION");
+    println!("Exit with the status determined by EXPRESSION.
+
+An omitted EXPRESSION defaults to false.  Otherwise,
+EXPRESSION is true or false and sets exit status.  It is one of:
+
+  ( EXPRESSION )               EXPRESSION is true
+  ! EXPRESSION                 EXPRESSION is false
+  EXPRESSION1 -a EXPRESSION2   both EXPRESSION1 and EXPRESSION2 are true
+  EXPRESSION1 -o EXPRESSION2   either EXPRESSION1 or EXPRESSION2 is true
+
+  -n STRING            the length of STRING is nonzero
+  STRING               equivalent to -n STRING
+  -z STRING            the length of STRING is zero
+  STRING1 = STRING2    the strings are equal
+  STRING1 != STRING2   the strings are not equal
+
+  INTEGER1 -eq INTEGER2   INTEGER1 is equal to INTEGER2
+  INTEGER1 -ge INTEGER2   INTEGER1 is greater than or equal to INTEGER2
+  INTEGER1 -gt INTEGER2   INTEGER1 is greater than INTEGER2
+  INTEGER1 -le INTEGER2   INTEGER1 is less than or equal to INTEGER2
+  INTEGER1 -lt INTEGER2   INTEGER1 is less than INTEGER2
+  INTEGER1 -ne INTEGER2   INTEGER1 is not equal to INTEGER2
+
+  FILE1 -ef FILE2   FILE1 and FILE2 have the same device and inode numbers
+  FILE1 -nt FILE2   FILE1 is newer (modification date) than FILE2
+  FILE1 -ot FILE2   FILE1 is older than FILE2
+
+  -b FILE     FILE exists and is block special
+  -c FILE     FILE exists and is character special
+  -d FILE     FILE exists and is a directory
+  -e FILE     FILE exists
+  -f FILE     FILE exists and is a regular file
+  -g FILE     FILE exists and is set-group-ID
+  -G FILE     FILE exists and is owned by the effective group ID
+  -h FILE     FILE exists and is a symbolic link (same as -L)
+  -k FILE     FILE exists and has its sticky bit set
+  -L FILE     FILE exists and is a symbolic link (same as -h)
+  -O FILE     FILE exists and is owned by the effective user ID
+  -p FILE     FILE exists and is a named pipe
+  -r FILE     FILE exists and read permission is granted
+  -s FILE     FILE exists and has a size greater than zero
+  -S FILE     FILE exists and is a socket
+  -t FD       file descriptor FD is opened on a terminal
+  -u FILE     FILE exists and its set-user-ID bit is set
+  -w FILE     FILE exists and write permission is granted
+  -x FILE     FILE exists and execute (or search) permission is granted
+
+Except for -h and -L, all FILE-related tests dereference symbolic links.");
+}
+
+fn is_unary_op(s: &str) -> bool {
+    match s {
+        "-b" | "-c" | "-d" | "-e" | "-f" | "-g" | "-G" | "-h" | "-k" | "-L" |
+        "-n" | "-O" | "-p" | "-r" | "-s" | "-S" | "-t" | "-u" | "-w" | "-x" |
+        "-z" => true,
+        _ => false
+    }
+}
+
+fn is_binary_op(s: &str) -> bool {
+    match s {
+        "=" | "==" | "!=" | "<" | ">" | "-eq" | "-ne" | "-lt" | "-le" |
+        "-gt" | "-ge" | "-nt" | "-ot" | "-ef" => true,
+        _ => false
+    }
+}
+
+/*
+ * POSIX fixes the meaning of expressions with up to four arguments by their
+ * count alone, which resolves ambiguities such as `test ! = x`.  Longer
+ * expressions are handled by the general parser.
+ */
+fn eval(args: &[&str]) -> Result<bool, String> {
+    match args.len() {
+        0 => Ok(false),
+        1 => Ok(!args[0].is_empty()),
+        2 => {
+            if args[0] == "!" {
+                Ok(args[1].is_empty())
+            } else if is_unary_op(args[0]) {
+                unary(args[0], args[1])
+            } else {
+                Err(format!("{}: unary operator expected", args[0]))
+            }
+        }
+        3 => {
+            if is_binary_op(args[1]) {
+                binary(args[0], args[1], args[2])
+            } else if args[1] == "-a" || args[1] == "-o" {
+                Parser::new(args).parse()
+            } else if args[0] == "!" {
+                eval(args.slice_from(1)).map(|b| !b)
+            } else if args[0] == "(" && args[2] == ")" {
+                Ok(!args[1].is_empty())
+            } else {
+                Parser::new(args).parse()
+            }
+        }
+        4 => {
+            if args[0] == "!" {
+                eval(args.slice_from(1)).map(|b| !b)
+            } else if args[0] == "(" && args[3] == ")" {
+                eval(args.slice(1, 3))
+            } else {
+                Parser::new(args).parse()
+            }
+        }
+        _ => Parser::new(args).parse()
+    }
+}
+
+struct Parser<'a> {
+    args: &'a [&'a str],
+    pos: uint,
+}
+
+impl<'a> Parser<'a> {
+    fn new(args: &'a [&'a str]) -> Parser<'a> {
+        Parser { args: args, pos: 0 }
+    }
+
+    fn peek(&self, offset: uint) -> Option<&'a str> {
+        if self.pos + offset < self.args.len() {
+            Some(self.args[self.pos + offset])
+        } else {
+            None
+        }
+    }
+
+    fn next(&mut self) -> Result<&'a str, String> {
+        match self.peek(0) {
+            Some(s) => { self.pos += 1; Ok(s) }
+            None => Err(format!("argument expected"))
+        }
+    }
+
+    fn parse(&mut self) -> Result<bool, String> {
+        let result = try!(self.or());
+        match self.peek(0) {
+            Some(s) => Err(format!("extra argument '{}'", s)),
+            None => Ok(result)
+        }
+    }
+
+    // -o binds more loosely than -a
+    fn or(&mut self) -> Result<bool, String> {
+        let mut result = try!(self.and());
+        while self.peek(0) == Some("-o") {
+            self.pos += 1;
+            let rhs = try!(self.and());
+            result = result || rhs;
+        }
+        Ok(result)
+    }
+
+    fn and(&mut self) -> Result<bool, String> {
+        let mut result = try!(self.not());
+        while self.peek(0) == Some("-a") {
+            self.pos += 1;
+            let rhs = try!(self.not());
+            result = result && rhs;
+        }
+        Ok(result)
+    }
+
+    fn not(&mut self) -> Result<bool, String> {
+        if self.peek(0) == Some("!") && self.peek(1).is_some() &&
+           !self.peek(1).map_or(false, is_binary_op) {
+            self.pos += 1;
+            return self.not().map(|b| !b);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<bool, String> {
+        let first = try!(self.next());
+
+        // a binary operator takes precedence, so that `-f = -f` compares
+        // strings rather than testing a file named "="
+        match (self.peek(0), self.peek(1)) {
+            (Some(op), Some(rhs)) if is_binary_op(op) => {
+                self.pos += 2;
+                return binary(first, op, rhs);
+            }
+            _ => {}
+        }
+
+        if first == "(" {
+            let result = try!(self.or());
+            return match self.next() {
+                Ok(")") => Ok(result),
+                _ => Err(format!("')' expected"))
+            };
+        }
+
+        if is_unary_op(first) {
+            match self.peek(0) {
+                Some(operand) => {
+                    self.pos += 1;
+                    return unary(first, operand);
+                }
+                // a lone operator is just a non-empty string
+                None => return Ok(true)
+            }
+        }
+
+        Ok(!first.is_empty())
+    }
+}
+
+fn parse_int(s: &str) -> Result<i64, String> {
+    match from_str::<i64>(s.trim()) {
+        Some(n) => Ok(n),
+        None => Err(format!("invalid integer '{}'", s))
+    }
+}
+
+fn binary(lhs: &str, op: &str, rhs: &str) -> Result<bool, String> {
+    Ok(match op {
+        "=" | "==" => lhs == rhs,
+        "!=" => lhs != rhs,
+        "<" => lhs < rhs,
+        ">" => lhs > rhs,
+        "-eq" => try!(parse_int(lhs)) == try!(parse_int(rhs)),
+        "-ne" => try!(parse_int(lhs)) != try!(parse_int(rhs)),
+        "-lt" => try!(parse_int(lhs)) < try!(parse_int(rhs)),
+        "-le" => try!(parse_int(lhs)) <= try!(parse_int(rhs)),
+        "-gt" => try!(parse_int(lhs)) > try!(parse_int(rhs)),
+        "-ge" => try!(parse_int(lhs)) >= try!(parse_int(rhs)),
+        "-nt" | "-ot" => {
+            // a file that does not exist is older than any that does
+            let l = fs::stat(&Path::new(lhs)).ok().map(|s| s.modified);
+            let r = fs::stat(&Path::new(rhs)).ok().map(|s| s.modified);
+            let (newer, older) = if op == "-nt" { (l, r) } else { (r, l) };
+            match (newer, older) {
+                (Some(a), Some(b)) => a > b,
+                (Some(_), None) => true,
+                _ => false
+            }
+        }
+        "-ef" => {
+            match (fsext::stat_path(&Path::new(lhs)), fsext::stat_path(&Path::new(rhs))) {
+                (Ok(a), Ok(b)) => a.st_dev == b.st_dev && a.st_ino == b.st_ino,
+                _ => false
+            }
+        }
+        _ => return Err(format!("{}: binary operator expected", op))
+    })
+}
+
+fn has_access(path: &str, mode: c_int) -> bool {
+    path.with_c_str(|p| unsafe { access(p, mode) == 0 })
+}
+
+fn unary(op: &str, operand: &str) -> Result<bool, String> {
+    let path = Path::new(operand);
+    let stat = || fsext::stat_path(&path).ok();
+    Ok(match op {
+        "-n" => !operand.is_empty(),
+        "-z" => operand.is_empty(),
+        "-t" => {
+            let fd = try!(parse_int(operand));
+            unsafe { isatty(fd as c_int) == 1 }
+        }
+        "-h" | "-L" => fsext::lstat_path(&path).ok().map_or(false, |s| fsext::is_lnk(s.st_mode)),
+        "-e" => stat().is_some(),
+        "-f" => stat().map_or(false, |s| fsext::is_reg(s.st_mode)),
+        "-d" => stat().map_or(false, |s| fsext::is_dir(s.st_mode)),
+        "-b" => stat().map_or(false, |s| fsext::is_blk(s.st_mode)),
+        "-c" => stat().map_or(false, |s| fsext::is_chr(s.st_mode)),
+        "-p" => stat().map_or(false, |s| fsext::is_fifo(s.st_mode)),
+        "-S" => stat().map_or(false, |s| fsext::is_sock(s.st_mode)),
+        "-s" => stat().map_or(false, |s| s.st_size > 0),
+        "-g" => stat().map_or(false, |s| s.st_mode & fsext::S_ISGID != 0),
+        "-u" => stat().map_or(false, |s| s.st_mode & fsext::S_ISUID != 0),
+        "-k" => stat().map_or(false, |s| s.st_mode & fsext::S_ISVTX != 0),
+        "-O" => stat().map_or(false, |s| s.st_uid == unsafe { geteuid() }),
+        "-G" => stat().map_or(false, |s| s.st_gid == unsafe { getegid() }),
+        "-r" => has_access(operand, R_OK),
+        "-w" => has_access(operand, W_OK),
+        "-x" => has_access(operand, X_OK),
+        _ => return Err(format!("{}: unary operator expected", op))
+    })
+}
@@ -0,0 +1,224 @@
+#![crate_id(name="timeout", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate libc;
+
+use std::os;
+use std::io::TimedOut;
+use std::io::process::{Command, ExitSignal, ExitStatus, InheritFd, Process, ProcessExit};
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/exec.rs"]
+mod exec;
+
+#[path = "../common/duration.rs"]
+mod duration;
+
+#[path = "../common/signal.rs"]
+mod signal;
+
+static NAME: &'static str = "timeout";
+static VERSION: &'static str = "1.0.0";
+
+// The command ran out of time.
+static EXIT_TIMEDOUT: int = 124;
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    exec::catch_exit(proc() run(args))
+}
+
+fn run(args: Vec<String>) {
+    let prog = args.get(0).as_slice();
+    let mut sig = signal::SIGTERM;
+    let mut kill_after = None;
+    let mut preserve_status = false;
+
+    // options stop at the duration, so the command's are left alone
+    let mut i = 1;
+    while i < args.len() {
+        let opt = args.get(i).as_slice();
+        if opt == "--" {
+            i += 1;
+            break;
+        } else if !opt.starts_with("-") || opt == "-" {
+            break;
+        }
+        i += 1;
+
+        if opt == "--help" {
+            help(prog);
+            return;
+        } else if opt == "--version" {
+            println!("{} {}", NAME, VERSION);
+            return;
+        } else if opt == "--preserve-status" {
+            preserve_status = true;
+        } else if opt == "-s" || opt == "--signal" || opt == "-k" || opt == "--kill-after" {
+            if i == args.len() {
+                bad_usage(prog, format!("option requires an argument -- '{}'", opt));
+            }
+            i += 1;
+            let value = args.get(i - 1).as_slice();
+            if opt == "-s" || opt == "--signal" {
+                sig = parse_signal(value);
+            } else {
+                kill_after = Some(parse_duration(value));
+            }
+        } else if opt.starts_with("--signal=") {
+            sig = parse_signal(opt.slice_from("--signal=".len()));
+        } else if opt.starts_with("--kill-after=") {
+            kill_after = Some(parse_duration(opt.slice_from("--kill-after=".len())));
+        } else if opt.starts_with("-s") {
+            sig = parse_signal(opt.slice_from(2));
+        } else if opt.starts_with("-k") {
+            kill_after = Some(parse_duration(opt.slice_from(2)));
+        } else {
+            bad_usage(prog, format!("unrecognized option '{}'", opt));
+        }
+    }
+
+    if args.len() - i < 2 {
+        bad_usage(prog, "missing operand".to_string());
+    }
+    let limit = parse_duration(args.get(i).as_slice());
+    let command = args.slice_from(i + 1);
+
+    let mut child = match Command::new(command[0].as_slice())
+                                  .args(command.tail())
+                                  .stdin(InheritFd(0))
+                                  .stdout(InheritFd(1))
+                                  .stderr(InheritFd(2))
+                                  .spawn() {
+        Ok(child) => child,
+        Err(e) => crash!(exec::exec_status(&e), "failed to run command '{}': {}", command[0],
+                         util::strerror(&e))
+    };
+
+    // a duration of 0 means no time limit at all
+    if limit > 0.0 {
+        child.set_timeout(duration::to_millis(limit));
+    }
+    let status = match child.wait() {
+        Ok(status) => status,
+        Err(ref e) if e.kind == TimedOut => {
+            send(&child, sig);
+            // and if that was not enough, KILL, which cannot be caught
+            let mut killed = sig == signal::SIGKILL;
+            let status = match kill_after {
+                Some(secs) if secs > 0.0 && !killed => {
+                    child.set_timeout(duration::to_millis(secs));
+                    match child.wait() {
+                        Ok(status) => status,
+                        Err(ref e) if e.kind == TimedOut => {
+                            send(&child, signal::SIGKILL);
+                            killed = true;
+                            wait(&mut child)
+                        }
+                        Err(e) => crash!(exec::EXIT_CANCELED, "error waiting for command: {}",
+                                         util::strerror(&e))
+                    }
+                }
+                _ => wait(&mut child)
+            };
+            let code = if preserve_status {
+                exit_code(status)
+            } else if killed {
+                // the status when the command was killed is that of the KILL
+                128 + signal::SIGKILL as int
+            } else {
+                EXIT_TIMEDOUT
+            };
+            exit!(code as libc::c_int)
+        }
+        Err(e) => crash!(exec::EXIT_CANCELED, "error waiting for command: {}", util::strerror(&e))
+    };
+    exit!(exit_code(status) as libc::c_int)
+}
+
+fn help(prog: &str) {
+    println!("{} {}", NAME, VERSION);
+    println!("");
+    println!("Usage:");
+    println!("  {0} [OPTION] DURATION COMMAND [ARG]...", prog);
+    println!("  {0} [OPTION]", prog);
+    println!("");
+    println!("Start COMMAND, and kill it if still running after DURATION.");
+    println!("");
+    println!("      --preserve-status");
+    println!("                   exit with the same status as COMMAND, even when the");
+    println!("                     command times out");
+    println!("  -k, --kill-after=DURATION");
+    println!("                   also send a KILL signal if COMMAND is still running");
+    println!("                     this long after the initial signal was sent");
+    println!("  -s, --signal=SIGNAL");
+    println!("                   specify the signal to be sent on timeout;");
+    println!("                     SIGNAL may be a name like 'HUP' or a number;");
+    println!("                     see 'kill -l' for a list of signals");
+    println!("      --help     display this help and exit");
+    println!("      --version  output version information and exit");
+    println!("");
+    println!("DURATION is a floating point number with an optional suffix:");
+    println!("'s' for seconds (the default), 'm' for minutes, 'h' for hours or 'd' for days.");
+    println!("A duration of 0 disables the associated timeout.");
+    println!("");
+    println!("If the command times out, and --preserve-status is not set, then exit with");
+    println!("status 124.  Otherwise, exit with the status of COMMAND.  If no signal is");
+    println!("specified, send the TERM signal upon timeout.  If the KILL signal is sent,");
+    println!("exit with status 128+9.");
+}
+
+fn bad_usage(prog: &str, msg: String) -> ! {
+    show_error!(exec::EXIT_CANCELED, "{}", msg);
+    crash!(exec::EXIT_CANCELED, "Try '{} --help' for more information.", prog)
+}
+
+fn parse_signal(s: &str) -> libc::c_int {
+    match signal::parse(s) {
+        Some(n) if n > 0 => n,
+        _ => crash!(exec::EXIT_CANCELED, "{}: invalid signal", s)
+    }
+}
+
+fn parse_duration(s: &str) -> f64 {
+    match duration::parse(s) {
+        Ok(secs) => secs,
+        Err(e) => crash!(exec::EXIT_CANCELED, "{}", e)
+    }
+}
+
+fn send(child: &Process, sig: libc::c_int) {
+    if !signal::send(child.id(), sig) {
+        crash!(exec::EXIT_CANCELED, "failed to send signal: {}", os::last_os_error());
+    }
+}
+
+fn wait(child: &mut Process) -> ProcessExit {
+    child.set_timeout(None);
+    match child.wait() {
+        Ok(status) => status,
+        Err(e) => crash!(exec::EXIT_CANCELED, "error waiting for command: {}", util::strerror(&e))
+    }
+}
+
+// The status a shell would give for the command: its own, or 128 more than
+// the signal that killed it.
+fn exit_code(status: ProcessExit) -> int {
+    match status {
+        ExitStatus(code) => code,
+        ExitSignal(sig) => 128 + sig
+    }
+}
@@ -11,22 +11,37 @@
  */
 
 extern crate getopts;
+extern crate libc;
 extern crate time;
 
 use std::io::File;
 use std::os;
+use time::Timespec;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/datetime.rs"]
+mod datetime;
+
 static NAME: &'static str = "touch";
 static VERSION: &'static str = "1.0.0";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("a", "",               "change only the access time"),
         getopts::optflag("c", "no-create",      "do not create any files"),
         getopts::optopt( "d", "date",           "parse argument and use it instead of current time", "STRING"),
@@ -38,13 +53,17 @@ pub fn uumain(args: Vec<String>) {
         getopts::optopt( "",  "time",           "change only the specified time: \"access\", \"atime\", or \
                                                  \"use\" are equivalent to -a; \"modify\" or \"mtime\" are \
                                                  equivalent to -m", "WORD"),
-        getopts::optflag("h", "help",           "display this help and exit"),
+        getopts::optflag("",  "help",           "display this help and exit"),
         getopts::optflag("V", "version",        "output version information and exit"),
-    ];
+    )
+}
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m)  => m,
-        Err(e) => fail!("Invalid options\n{}", e.to_err_msg())
+        Err(e) => crash!(1, "{}", e.to_err_msg())
     };
 
     if matches.opt_present("version") {
@@ -52,126 +71,111 @@ pub fn uumain(args: Vec<String>) {
         return;
     }
 
-    if matches.opt_present("help") || matches.free.is_empty() {
+    if matches.opt_present("help") {
         println!("{:s} {:s}", NAME, VERSION);
         println!("");
         println!("Usage: {:s} [OPTION]... FILE...", NAME);
         println!("");
         println!("{:s}", getopts::usage("Update the access and modification times of \
-                                         each FILE to the current time.", opts));
+                                         each FILE to the current time.", opts.as_slice()));
         return;
     }
 
-    if matches.opt_present("date") && matches.opts_present(["reference".to_string(), "t".to_string()]) ||
-       matches.opt_present("reference") && matches.opts_present(["date".to_string(), "t".to_string()]) ||
-       matches.opt_present("t") && matches.opts_present(["date".to_string(), "reference".to_string()]) {
-        fail!("Invalid options: cannot specify reference time from more than one source");
+    if matches.free.is_empty() {
+        crash!(1, "missing file operand");
+    }
+
+    // -d may be given with -r, and is then relative to the reference file
+    if matches.opt_present("t")
+            && matches.opts_present(["date".to_string(), "reference".to_string()]) {
+        crash!(1, "cannot specify times from more than one source");
     }
 
-    let (mut atime, mut mtime) =
-        if matches.opt_present("reference") {
-            let path = Path::new(matches.opt_str("reference").unwrap().to_string());
-            let stat = stat(&path, !matches.opt_present("no-dereference"));
-            (stat.accessed, stat.modified)
-        } else if matches.opts_present(["date".to_string(), "t".to_string()]) {
-            let timestamp = if matches.opt_present("date") {
-                parse_date(matches.opt_str("date").unwrap().as_slice())
-            } else {
-                parse_timestamp(matches.opt_str("t").unwrap().as_slice())
+    let follow = !matches.opt_present("no-dereference");
+    let now = time::get_time();
+    let (mut atime, mut mtime) = match matches.opt_str("reference") {
+        Some(reference) => {
+            let path = Path::new(reference.as_slice());
+            match if follow { fsext::stat_path(&path) } else { fsext::lstat_path(&path) } {
+                Ok(stat) => (fsext::atime(&stat), fsext::mtime(&stat)),
+                Err(e) => crash!(1, "failed to get attributes of '{}': {}", reference,
+                                 util::strerror(&e))
+            }
+        }
+        // the kernel's idea of now works even on files we do not own
+        None => ((0, fsext::UTIME_NOW), (0, fsext::UTIME_NOW))
+    };
+
+    match matches.opt_str("date") {
+        Some(date) => {
+            let base = |(sec, nsec): (i64, i64)| {
+                if nsec == fsext::UTIME_NOW { now } else { Timespec::new(sec, nsec as i32) }
+            };
+            let parse = |base: Timespec| {
+                match datetime::parse_datetime(date.as_slice(), base, false) {
+                    Some(t) => (t.sec, t.nsec as i64),
+                    None => crash!(1, "invalid date format '{}'", date)
+                }
             };
-            (timestamp, timestamp)
-        } else {
-            // FIXME: Should use Timespec. https://github.com/mozilla/rust/issues/10301
-            let now = (time::get_time().sec * 1000) as u64;
-            (now, now)
-        };
+            atime = parse(base(atime));
+            mtime = parse(base(mtime));
+        }
+        None => {}
+    }
+    match matches.opt_str("t") {
+        Some(stamp) => match datetime::parse_stamp(stamp.as_slice(), now) {
+            Some(t) => {
+                atime = (t.sec, t.nsec as i64);
+                mtime = atime;
+            }
+            None => crash!(1, "invalid date format '{}'", stamp)
+        },
+        None => {}
+    }
+
+    // -a and -m together are the same as neither
+    let mut change_atime = matches.opt_present("a");
+    let mut change_mtime = matches.opt_present("m");
+    match matches.opt_str("time") {
+        Some(word) => match word.as_slice() {
+            "access" | "atime" | "use" => change_atime = true,
+            "modify" | "mtime" => change_mtime = true,
+            _ => crash!(1, "invalid argument '{}' for '--time'", word)
+        },
+        None => {}
+    }
+    if !change_atime && !change_mtime {
+        change_atime = true;
+        change_mtime = true;
+    }
+    if !change_atime {
+        atime = (0, fsext::UTIME_OMIT);
+    }
+    if !change_mtime {
+        mtime = (0, fsext::UTIME_OMIT);
+    }
 
     for filename in matches.free.iter() {
-        let path = Path::new(filename.to_string());
+        let path = Path::new(filename.as_slice());
 
-        if !path.exists() {
+        if fsext::lstat_path(&path).is_err() {
             // no-dereference included here for compatibility
             if matches.opts_present(["no-create".to_string(), "no-dereference".to_string()]) {
                 continue;
             }
 
             match File::create(&path) {
-                Ok(fd) => fd,
-                Err(e) => fail!("Unable to create file: {}\n{}", filename, e.desc)
-            };
-
-            // Minor optimization: if no reference time was specified, we're done.
-            if !matches.opts_present(["date".to_string(), "reference".to_string(), "t".to_string()]) {
-                continue;
+                Ok(_) => {}
+                Err(e) => {
+                    show_error!(1, "cannot touch '{}': {}", filename, util::strerror(&e));
+                    continue;
+                }
             }
         }
 
-        // If changing "only" atime or mtime, grab the existing value of the other.
-        // Note that "-a" and "-m" may be passed together; this is not an xor.
-        if matches.opts_present(["a".to_string(), "m".to_string(), "time".to_string()]) {
-            let stat = stat(&path, !matches.opt_present("no-dereference"));
-            let time = matches.opt_strs("time");
-
-            if !(matches.opt_present("a") ||
-                 time.contains(&"access".to_string()) ||
-                 time.contains(&"atime".to_string()) ||
-                 time.contains(&"use".to_string())) {
-                atime = stat.accessed;
-            }
-
-            if !(matches.opt_present("m") ||
-                 time.contains(&"modify".to_string()) ||
-                 time.contains(&"mtime".to_string())) {
-                mtime = stat.modified;
-            }
-        }
-
-        match std::io::fs::change_file_times(&path, atime, mtime) {
-            Ok(t) => t,
-            Err(e) => fail!("Unable to modify times\n{}", e.desc)
-        }
-    }
-}
-
-fn stat(path: &Path, follow: bool) -> std::io::FileStat {
-    if follow {
-        match std::io::fs::stat(path) {
-            Ok(stat) => stat,
-            Err(e)   => fail!("Unable to open file\n{}", e.desc)
-        }
-    } else {
-        match std::io::fs::lstat(path) {
-            Ok(stat) => stat,
-            Err(e)   => fail!("Unable to open file\n{}", e.desc)
+        match fsext::set_times(&path, atime, mtime, follow) {
+            Ok(()) => {}
+            Err(e) => show_error!(1, "setting times of '{}': {}", filename, util::strerror(&e))
         }
     }
 }
-
-fn parse_date(str: &str) -> u64 {
-    // This isn't actually compatible with GNU touch, but there doesn't seem to
-    // be any simple specification for what format this parameter allows and I'm
-    // not about to implement GNU parse_datetime.
-    // http://git.savannah.gnu.org/gitweb/?p=gnulib.git;a=blob_plain;f=lib/parse-datetime.y
-    match time::strptime(str, "%c") {
-        Ok(tm) => (tm.to_timespec().sec * 1000) as u64,
-        Err(e) => fail!("Unable to parse date\n{}", e)
-    }
-}
-
-fn parse_timestamp(str: &str) -> u64 {
-    let format = match str.char_len() {
-        15 => "%Y%m%d%H%M.%S",
-        12 => "%Y%m%d%H%M",
-        13 => "%y%m%d%H%M.%S",
-        10 => "%y%m%d%H%M",
-        11 => "%m%d%H%M.%S",
-         8 => "%m%d%H%M",
-         _ => fail!("Unknown timestamp format")
-    };
-
-    match time::strptime(str, format) {
-        Ok(tm) => (tm.to_timespec().sec * 1000) as u64,
-        Err(e) => fail!("Unable to parse timestamp\n{}", e)
-    }
-}
-
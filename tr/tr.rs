@@ -26,6 +26,9 @@ use std::vec::Vec;
 #[path="../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME : &'static str = "tr";
 static VERSION : &'static str = "1.0.0";
 
@@ -147,18 +150,26 @@ fn usage(opts: &[OptGroup]) {
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-pub fn uumain(args: Vec<String>) {
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("c", "complement", "use the complement of SET1"),
         getopts::optflag("C", "", "same as -c"),
         getopts::optflag("d", "delete", "delete characters in SET1"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(err) => {
             show_error!(1, "{}", err.to_err_msg());
@@ -167,7 +178,7 @@ pub fn uumain(args: Vec<String>) {
     };
 
     if matches.opt_present("help") {
-        usage(opts);
+        usage(opts.as_slice());
         return;
     }
 
@@ -177,8 +188,8 @@ pub fn uumain(args: Vec<String>) {
     }
 
     if matches.free.len() == 0 {
-        usage(opts);
-        os::set_exit_status(1);
+        usage(opts.as_slice());
+        util::set_status(1);
         return;
     }
 
@@ -9,5 +9,14 @@
  * file that was distributed with this source code.
  */
 
-fn main() {
-}
+#![feature(macro_rules)]
+
+use std::os;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(_: Vec<String>) -> util::Outcome { Ok(()) }
@@ -4,6 +4,7 @@ use std::io::process::Command;
 static PROG: &'static str = "build/truncate";
 static TFILE1: &'static str = "truncate_test_1";
 static TFILE2: &'static str = "truncate_test_2";
+static TFILE3: &'static str = "truncate_test_3";
 
 fn make_file(name: &str) -> io::File {
     match io::File::create(&Path::new(name)) {
@@ -39,3 +40,17 @@ fn test_decrease_file_size() {
     }
     io::fs::unlink(&Path::new(TFILE2)).unwrap();
 }
+
+#[test]
+fn test_round_up_file_size() {
+    let mut file = make_file(TFILE3);
+    file.write(bytes!("1234567890")).unwrap();
+    if !Command::new(PROG).args(["--size=%4K", TFILE3]).status().unwrap().success() {
+        fail!();
+    }
+    file.seek(0, io::SeekEnd).unwrap();
+    if file.tell().unwrap() != 4 * 1024 {
+        fail!();
+    }
+    io::fs::unlink(&Path::new(TFILE3)).unwrap();
+}
@@ -14,24 +14,20 @@
 extern crate getopts;
 extern crate libc;
 
-use std::io::{File, Open, ReadWrite, fs};
+use std::io::{File, Open, Write};
 use std::os;
-use std::u64;
 
 #[path = "../common/util.rs"]
 mod util;
 
-macro_rules! get_file_size(
-    ($file:ident, $action:expr) => ({
-        match fs::stat($file.path()) {
-            Ok(stat) => stat.size,
-            Err(f) => {
-                show_error!(1, "{}", f.to_str());
-                $action
-            }
-        }
-    })
-)
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/size.rs"]
+mod size;
 
 #[deriving(Eq, PartialEq)]
 enum TruncateMode {
@@ -47,20 +43,28 @@ enum TruncateMode {
 static NAME: &'static str = "truncate";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-    let opts = [
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("c", "no-create", "do not create files that do not exist"),
-        getopts::optflag("o", "io-blocks", "treat SIZE as the number of I/O blocks of the file rather than bytes (NOT IMPLEMENTED)"),
+        getopts::optflag("o", "io-blocks", "treat SIZE as the number of I/O blocks of the file rather than bytes"),
         getopts::optopt("r", "reference", "base the size of each file on the size of RFILE", "RFILE"),
         getopts::optopt("s", "size", "set or adjust the size of each file according to SIZE, which is in bytes unless --io-blocks is specified", "SIZE"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit")
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
             crash!(1, "{}", f.to_err_msg())
@@ -73,7 +77,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {0:s} [OPTION]... FILE...", program);
         println!("");
-        print!("{}", getopts::usage("Shrink or extend the size of each file to the specified size.", opts));
+        print!("{}", getopts::usage("Shrink or extend the size of each file to the specified size.", opts.as_slice()));
         print!("
 SIZE is an integer with an optional prefix and optional unit.
 The available units (K, M, G, T, P, E, Z, and Y) use the following format:
@@ -110,110 +114,87 @@ file based on its current size:
     }
 }
 
-fn truncate(no_create: bool, _: bool, reference: Option<String>, size: Option<String>, filenames: Vec<String>) {
-    let (refsize, mode) = match reference {
-        Some(rfilename) => {
-            let rfile = match File::open(&Path::new(rfilename.clone())) {
-                Ok(m) => m,
-                Err(f) => {
-                    crash!(1, "{}", f.to_str())
-                }
-            };
-            (get_file_size!(rfile, return), Reference)
-        }
-        None => parse_size(size.unwrap().as_slice())
+fn truncate(no_create: bool, io_blocks: bool, reference: Option<String>, size: Option<String>, filenames: Vec<String>) {
+    let has_size = size.is_some();
+    let (number, mode) = match size {
+        Some(size) => parse_size(size.as_slice()),
+        None => (0, Reference)
+    };
+    // with --reference, a relative --size adjusts the reference's size
+    let refsize = match reference {
+        Some(rfilename) => match fsext::stat_path(&Path::new(rfilename.as_slice())) {
+            Ok(stat) => Some(stat.st_size as u64),
+            Err(f) => crash!(1, "cannot stat '{}': {}", rfilename, util::strerror(&f))
+        },
+        None => None
     };
+    if mode == Reference && has_size && refsize.is_some() {
+        crash!(1, "you must specify a relative '--size' with '--reference'");
+    }
+    if (mode == RoundDown || mode == RoundUp) && number == 0 {
+        crash!(1, "division by zero");
+    }
     for filename in filenames.iter() {
         let filename = filename.as_slice();
         let path = Path::new(filename);
-        if path.exists() || !no_create {
-            match File::open_mode(&path, Open, ReadWrite) {
-                Ok(mut file) => {
-                    let fsize = get_file_size!(file, continue);
-                    let tsize = match mode {
-                        Reference => refsize,
-                        Extend => fsize + refsize,
-                        Reduce => fsize - refsize,
-                        AtMost => if fsize > refsize { refsize } else { fsize },
-                        AtLeast => if fsize < refsize { refsize } else { fsize },
-                        RoundDown => fsize - fsize % refsize,
-                        RoundUp => fsize + fsize % refsize
-                    };
-                    match file.truncate(tsize as i64) {
-                        Ok(_) => {}
-                        Err(f) => {
-                            show_error!(1, "{}", f.to_str());
-                        }
-                    }
-                }
-                Err(f) => {
-                    show_error!(1, "{}", f.to_str());
-                }
+        if !path.exists() && no_create {
+            continue;
+        }
+        // ftruncate(2) underneath, so growing a file leaves a hole
+        let mut file = match File::open_mode(&path, Open, Write) {
+            Ok(file) => file,
+            Err(f) => {
+                show_error!(1, "cannot open '{}' for writing: {}", filename, util::strerror(&f));
+                continue;
+            }
+        };
+        let stat = match fsext::stat_path(&path) {
+            Ok(stat) => stat,
+            Err(f) => {
+                show_error!(1, "cannot stat '{}': {}", filename, util::strerror(&f));
+                continue;
+            }
+        };
+        let number = if io_blocks { number * stat.st_blksize as u64 } else { number };
+        let fsize = match refsize {
+            Some(refsize) => refsize,
+            None => stat.st_size as u64
+        };
+        let tsize = match mode {
+            Reference => if has_size { number } else { fsize },
+            Extend => fsize + number,
+            Reduce => if fsize > number { fsize - number } else { 0 },
+            AtMost => if fsize > number { number } else { fsize },
+            AtLeast => if fsize < number { number } else { fsize },
+            RoundDown => fsize - fsize % number,
+            RoundUp => if fsize % number == 0 { fsize } else { fsize + number - fsize % number }
+        };
+        match file.truncate(tsize as i64) {
+            Ok(_) => {}
+            Err(f) => {
+                show_error!(1, "failed to truncate '{}' at {} bytes: {}", filename, tsize,
+                            util::strerror(&f));
             }
         }
     }
 }
 
 fn parse_size(size: &str) -> (u64, TruncateMode) {
+    if size.is_empty() {
+        crash!(1, "invalid number: '{}'", size);
+    }
     let mode = match size.char_at(0) {
         '+' => Extend,
         '-' => Reduce,
         '<' => AtMost,
         '>' => AtLeast,
         '/' => RoundDown,
-        '*' => RoundUp,
+        '%' => RoundUp,
         _ => Reference /* assume that the size is just a number */
     };
-    let bytes = {
-        let mut slice =
-            if mode == Reference {
-                let size: &str = size;
-                size
-            } else {
-                size.slice_from(1)
-            };
-        if slice.char_at(slice.len() - 1).is_alphabetic() {
-            slice = slice.slice_to(slice.len() - 1);
-            if slice.len() > 0 && slice.char_at(slice.len() - 1).is_alphabetic() {
-                slice = slice.slice_to(slice.len() - 1);
-            }
-        }
-        slice
-    }.to_string().into_bytes();
-    let mut number = match u64::parse_bytes(bytes.as_slice(), 10) {
-        Some(num) => num,
-        None => {
-            crash!(1, "'{}' is not a valid number.", size)
-        }
-    };
-    if size.char_at(size.len() - 1).is_alphabetic() {
-        number *= match size.char_at(size.len() - 1).to_ascii().to_uppercase().to_char() {
-            'B' => match size.char_at(size.len() - 2).to_ascii().to_uppercase().to_char() {
-                'K' => 1000,
-                'M' => 1000 * 1000,
-                'G' => 1000 * 1000 * 1000,
-                'T' => 1000 * 1000 * 1000 * 1000,
-                'P' => 1000 * 1000 * 1000 * 1000 * 1000,
-                'E' => 1000 * 1000 * 1000 * 1000 * 1000 * 1000,
-                'Z' => 1000 * 1000 * 1000 * 1000 * 1000 * 1000 * 1000,
-                'Y' => 1000 * 1000 * 1000 * 1000 * 1000 * 1000 * 1000 * 1000,
-                letter => {
-                    crash!(1, "'{}B' is not a valid suffix.", letter)
-                }
-            },
-            'K' => 1024,
-            'M' => 1024 * 1024,
-            'G' => 1024 * 1024 * 1024,
-            'T' => 1024 * 1024 * 1024 * 1024,
-            'P' => 1024 * 1024 * 1024 * 1024 * 1024,
-            'E' => 1024 * 1024 * 1024 * 1024 * 1024 * 1024,
-            'Z' => 1024 * 1024 * 1024 * 1024 * 1024 * 1024 * 1024,
-            'Y' => 1024 * 1024 * 1024 * 1024 * 1024 * 1024 * 1024 * 1024,
-            letter => {
-                crash!(1, "'{}' is not a valid suffix.", letter)
-            }
-        };
+    let number = if mode == Reference { size } else { size.slice_from(1) };
+    match size::parse_size(number) {
+        Some((bytes, _)) => (bytes, mode),
+        None => crash!(1, "invalid number: '{}'", size)
     }
-    (number, mode)
 }
-
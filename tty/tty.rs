@@ -20,61 +20,105 @@ extern crate getopts;
 extern crate libc;
 
 use std::{str,os};
-use std::io::println;
+use std::io::{print, stdout};
 use std::io::stdio::stderr;
-use getopts::{optflag,getopts};
+use getopts::{optflag,usage};
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 extern {
     fn ttyname(filedesc: libc::c_int) -> *libc::c_char;
     fn isatty(filedesc: libc::c_int) -> libc::c_int;
 }
 
 static NAME: &'static str = "tty";
+static VERSION: &'static str = "1.0.0";
+
+// What scripts look at instead of the output: whether stdin is a terminal,
+// or why there is no telling.
+static EXIT_TTY: int = 0;
+static EXIT_NOT_TTY: int = 1;
+static EXIT_USAGE: int = 2;
+static EXIT_WRITE_ERROR: int = 3;
 
 #[allow(dead_code)]
-fn main () { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
 
-pub fn uumain(args: Vec<String>) {
-    let options = [
-        optflag("s", "silent", "print nothing, only return an exit status")
-    ];
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        optflag("s", "silent", "print nothing, only return an exit status"),
+        optflag("", "quiet", "same as -s"),
+        optflag("h", "help", "display this help and exit"),
+        optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let options = opts();
 
-    let silent = match getopts(args.tail(), options) {
-        Ok(m) => {
-            m.opt_present("s")
-        },
+    let matches = match posix::getopts(args.tail(), options.as_slice()) {
+        Ok(m) => m,
         Err(f) => {
-            println(f.to_err_msg().as_slice());
-            usage();
+            safe_writeln!(&mut stderr() as &mut Writer, "{}: {}", NAME, f.to_err_msg());
+            safe_writeln!(&mut stderr() as &mut Writer, "usage: tty [-s]");
+            util::set_status(EXIT_USAGE);
             return
         }
     };
 
-    let tty = unsafe { str::raw::from_c_str(ttyname(libc::STDIN_FILENO)) };
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]...", args.get(0));
+        println!("");
+        print(usage("Print the file name of the terminal connected to standard input.",
+                    options.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+    if !matches.free.is_empty() {
+        safe_writeln!(&mut stderr() as &mut Writer, "{}: extra operand '{}'", NAME,
+                      matches.free.get(0));
+        util::set_status(EXIT_USAGE);
+        return;
+    }
+
+    let silent = matches.opt_present("silent") || matches.opt_present("quiet");
+    let is_tty = unsafe { isatty(libc::STDIN_FILENO) } == 1;
+    let exit_code = if is_tty { EXIT_TTY } else { EXIT_NOT_TTY };
 
     if !silent {
-        if !tty.as_slice().is_whitespace() {
-            println(tty.as_slice());
+        let name = unsafe { ttyname(libc::STDIN_FILENO) };
+        let line = if name.is_not_null() {
+            format!("{}\n", unsafe { str::raw::from_c_str(name) })
         } else {
-            println!("not a tty");
+            "not a tty\n".to_string()
+        };
+        let mut out = stdout();
+        match out.write(line.as_bytes()).and_then(|_| out.flush()) {
+            Ok(()) => {}
+            Err(e) => {
+                safe_writeln!(&mut stderr() as &mut Writer, "{}: write error: {}", NAME, e);
+                util::set_status(EXIT_WRITE_ERROR);
+                return;
+            }
         }
-    }
-
-    let exit_code = unsafe {
-        if isatty(libc::STDIN_FILENO) == 1 {
-            libc::EXIT_SUCCESS
-        } else {
-            libc::EXIT_FAILURE
+        if name.is_null() {
+            util::set_status(EXIT_NOT_TTY);
+            return;
         }
-    };
-
-    os::set_exit_status(exit_code as int);
-}
+    }
 
-fn usage () {
-    safe_writeln!(&mut stderr() as &mut Writer, "usage: tty [-s]");
-    os::set_exit_status(2);
+    util::set_status(exit_code);
 }
@@ -11,97 +11,102 @@
 
 /* last synced with: uname (GNU coreutils) 8.21 */
 
-#![allow(non_camel_case_types)]
 #![feature(macro_rules)]
 
 extern crate getopts;
 extern crate libc;
 
 use std::os;
-use std::mem::uninitialized;
 use std::io::print;
-use std::str::raw::from_c_str;
-use c_types::utsname;
 
 #[path = "../common/util.rs"] mod util;
-#[path = "../common/c_types.rs"] mod c_types;
+#[path = "../common/posix.rs"] mod posix;
+#[path = "../common/utsname.rs"] mod utsname;
 
-struct utsrust {
-    sysname: String,
-    nodename: String,
-    release: String,
-    version: String,
-    machine: String 
-}
+static NAME: &'static str = "uname";
+static VERSION: &'static str = "1.0.0";
 
-extern {
-    fn uname(uts: *mut utsname);
-}
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-unsafe fn getuname() -> utsrust {
-    let mut uts: utsname = uninitialized();
-    uname(&mut uts);
-    utsrust {
-        sysname: from_c_str(uts.sysname.as_ptr()), nodename: from_c_str(uts.nodename.as_ptr()),
-        release: from_c_str(uts.release.as_ptr()), version: from_c_str(uts.version.as_ptr()),
-        machine: from_c_str(uts.machine.as_ptr())
-    }
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
 }
 
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("a", "all", "print all information, in the following order, except \
+                                      omit -p and -i if unknown:"),
+        getopts::optflag("s", "kernel-name", "print the kernel name"),
+        getopts::optflag("n", "nodename", "print the network node hostname"),
+        getopts::optflag("r", "kernel-release", "print the kernel release"),
+        getopts::optflag("v", "kernel-version", "print the kernel version"),
+        getopts::optflag("m", "machine", "print the machine hardware name"),
+        getopts::optflag("p", "processor", "print the processor type or \"unknown\""),
+        getopts::optflag("i", "hardware-platform", "print the hardware platform or \"unknown\""),
+        getopts::optflag("o", "operating-system", "print the operating system"),
+        getopts::optflag("", "help", "display this help and exit"),
+        getopts::optflag("", "version", "output version information and exit"),
+    )
+}
 
-static NAME: &'static str = "uname";
-
-#[allow(dead_code)]
-fn main() { uumain(os::args()); }
-
-pub fn uumain(args: Vec<String>) {
+fn run(args: Vec<String>) {
     let program = args.get(0).as_slice();
-    let opts = [
-        getopts::optflag("h", "help", "display this help and exit"),
-        getopts::optflag("a", "all", "Behave as though all of the options -mnrsv were specified."),
-        getopts::optflag("m", "machine", "print the machine hardware name."),
-        getopts::optflag("n", "nodename", "print the nodename (the nodename may be a name that the system is known by to a communications network)."),
-        getopts::optflag("p", "processor", "print the machine processor architecture name."),
-        getopts::optflag("r", "release", "print the operating system release."),
-        getopts::optflag("s", "sysname", "print the operating system name."),
-        getopts::optflag("v", "version", "print the operating system version."),
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => crash!(1, "{}", f.to_err_msg()),
     };
     if matches.opt_present("help") {
-        println!("uname 1.0.0");
+        println!("{} {}", NAME, VERSION);
         println!("");
         println!("Usage:");
-        println!("  {:s}", program);
+        println!("  {:s} [OPTION]...", program);
         println!("");
-        print(getopts::usage("The uname utility writes symbols representing one or more system characteristics to the standard output.", opts).as_slice());
+        print(getopts::usage("Print certain system information.  With no OPTION, same as -s.",
+                             opts.as_slice()).as_slice());
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
         return;
     }
-    let uname = unsafe { getuname() };
-    let mut output = String::new();
-    if matches.opt_present("sysname") || matches.opt_present("all")
-        || !matches.opts_present(["nodename".to_string(), "release".to_string(), "version".to_string(), "machine".to_string()]) {
-            output.push_str(uname.sysname.as_slice());
-            output.push_str(" ");
+    if !matches.free.is_empty() {
+        crash!(1, "extra operand '{}'", matches.free.get(0));
     }
 
-    if matches.opt_present("nodename") || matches.opt_present("all") {
-        output.push_str(uname.nodename.as_slice());
-        output.push_str(" ");
+    let uname = utsname::get();
+    let all = matches.opt_present("all");
+    let flag = |name: &str| all || matches.opt_present(name);
+    let none = !["s", "n", "r", "v", "m", "p", "i", "o"].iter().any(|f| matches.opt_present(*f));
+
+    // the kernel gives no answer for either, so -a leaves them out
+    let processor = "unknown";
+    let platform = "unknown";
+
+    let mut fields = vec!();
+    if flag("s") || none {
+        fields.push(uname.sysname.as_slice());
+    }
+    if flag("n") {
+        fields.push(uname.nodename.as_slice());
+    }
+    if flag("r") {
+        fields.push(uname.release.as_slice());
+    }
+    if flag("v") {
+        fields.push(uname.version.as_slice());
+    }
+    if flag("m") {
+        fields.push(uname.machine.as_slice());
     }
-    if matches.opt_present("release") || matches.opt_present("all") {
-        output.push_str(uname.release.as_slice());
-        output.push_str(" ");
+    if matches.opt_present("p") {
+        fields.push(processor);
     }
-    if matches.opt_present("version") || matches.opt_present("all") {
-        output.push_str(uname.version.as_slice());
-        output.push_str(" ");
+    if matches.opt_present("i") {
+        fields.push(platform);
     }
-    if matches.opt_present("machine") || matches.opt_present("all") {
-        output.push_str(uname.machine.as_slice());
-        output.push_str(" ");
+    if flag("o") {
+        fields.push(utsname::OPERATING_SYSTEM);
     }
-    println!("{}", output.as_slice().trim_left())
+    println!("{}", fields.connect(" "));
 }
@@ -17,26 +17,36 @@ extern crate getopts;
 extern crate libc;
 
 use std::os;
-use std::io;
 use std::io::fs;
 use std::io::print;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "unlink";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    )
+}
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
             crash!(1, "invalid options\n{}", f.to_err_msg())
@@ -49,7 +59,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {0:s} [FILE]... [OPTION]...", program);
         println!("");
-        print(getopts::usage("Unlink the file at [FILE].", opts).as_slice());
+        print(getopts::usage("Unlink the file at [FILE].", opts.as_slice()).as_slice());
         return;
     }
 
@@ -66,24 +76,12 @@ pub fn uumain(args: Vec<String>) {
 
     let path = Path::new(matches.free.get(0).clone());
 
-    let result = path.lstat().and_then(|info| {
-        match info.kind {
-            io::TypeFile => Ok(()),
-            io::TypeSymlink => Ok(()),
-            _ => Err(io::IoError {
-                kind: io::OtherIoError,
-                desc: "is not a file or symlink",
-                detail: None
-            })
-        }
-    }).and_then(|_| {
-        fs::unlink(&path)
-    });
-
-    match result {
+    // straight to unlink(2): whatever it refuses, such as a directory, is
+    // reported as it says
+    match fs::unlink(&path) {
         Ok(_) => (),
         Err(e) => {
-            crash!(1, "cannot unlink '{0}': {1}", path.display(), e.desc);
+            crash!(1, "cannot unlink '{0}': {1}", path.display(), util::strerror(&e));
         }
     }
 }
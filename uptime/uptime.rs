@@ -12,51 +12,46 @@
 /* last synced with: cat (GNU coreutils) 8.13 */
 
 #![allow(non_camel_case_types)]
-#![feature(macro_rules, globs)]
+#![feature(macro_rules)]
 
 extern crate getopts;
 extern crate libc;
+extern crate time;
 
 use std::os;
-use std::mem::transmute;
 use std::io::{print, File};
-use std::ptr::null;
 use std::from_str::from_str;
-use libc::{time_t, c_double, c_int, c_char};
-use c_types::c_tm;
-use utmpx::*;
+use libc::{c_double, c_int};
 
 #[path = "../common/util.rs"] mod util;
-
-#[path = "../common/c_types.rs"] mod c_types;
+#[path = "../common/posix.rs"] mod posix;
 
 #[path = "../common/utmpx.rs"] mod utmpx;
 
 static NAME: &'static str = "uptime";
 
 extern {
-    fn time(timep: *time_t) -> time_t;
-    fn localtime(timep: *time_t) -> *c_tm;
-
-    fn getloadavg(loadavg: *c_double, nelem: c_int) -> c_int;
-
-    fn getutxent() -> *c_utmp;
-    fn setutxent();
-    fn endutxent();
-
-    fn utmpxname(file: *c_char) -> c_int;
+    fn getloadavg(loadavg: *mut c_double, nelem: c_int) -> c_int;
 }
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("v", "version", "output version information and exit"),
         getopts::optflag("h", "help", "display this help and exit"),
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => crash!(1, "Invalid options\n{}", f.to_err_msg())
     };
@@ -64,131 +59,169 @@ pub fn uumain(args: Vec<String>) {
         println!("uptime 1.0.0");
         return;
     }
-    if matches.opt_present("help") || matches.free.len() > 0 {
+    if matches.opt_present("help") {
         println!("Usage:");
-        println!("  {0:s} [OPTION]", program);
+        println!("  {0:s} [OPTION]... [FILE]", program);
         println!("");
         print(getopts::usage("Print the current time, the length of time the system has been up,\n\
                               the number of users on the system, and the average number of jobs\n\
-                              in the run queue over the last 1, 5 and 15 minutes.", opts).as_slice());
+                              in the run queue over the last 1, 5 and 15 minutes.\n\
+                              If FILE is not specified, use /var/run/utmp.", opts.as_slice()).as_slice());
         return;
     }
+    let file = match matches.free.len() {
+        0 => utmpx::DEFAULT_FILE.to_string(),
+        1 => matches.free.get(0).clone(),
+        _ => crash!(1, "extra operand '{}'", matches.free.get(1))
+    };
 
-    print_time();
-    let (boot_time, user_count) = process_utmpx();
-    let upsecs = get_uptime(boot_time) / 100;
-    print_uptime(upsecs);
-    print_nusers(user_count);
-    print_loadavg();
-}
-
-fn print_loadavg() {
-    let avg: [c_double, ..3] = [0.0, ..3];
-    let loads: i32 = unsafe { transmute(getloadavg(avg.as_ptr(), 3)) };
-
-    if loads == -1 {
-        print!("\n");
-    }
-    else {
-        print!("load average: ")
-        for n in range(0, loads) {
-            print!("{:.2f}{}", avg[n as uint], if n == loads - 1 { "\n" }
-                                   else { ", " } );
+    let records = match utmpx::read_utmpx(file.as_slice()) {
+        Ok(records) => records,
+        Err(e) => {
+            // the rest can still be told without it
+            if !matches.free.is_empty() {
+                show_error!(1, "{}: {}", file, util::strerror(&e));
+            }
+            vec!()
+        }
+    };
+    let boot_time = records.iter()
+                           .filter(|r| r.ut_type == utmpx::BOOT_TIME && r.time > 0)
+                           .map(|r| r.time)
+                           .last();
+    let nusers = records.iter().filter(|r| r.is_user_process()).count();
+
+    let mut line = time::now().strftime(" %H:%M:%S ");
+    line.push_str(uptime_string(get_uptime(boot_time)).as_slice());
+    line.push_str(if nusers == 1 {
+        "1 user".to_string()
+    } else {
+        format!("{} users", nusers)
+    }.as_slice());
+    match get_loadavg() {
+        Some(loads) => {
+            line.push_str(format!(",  load average: {:.2f}, {:.2f}, {:.2f}",
+                                  loads[0], loads[1], loads[2]).as_slice());
         }
+        None => {}
     }
+    println!("{}", line);
 }
 
-fn process_utmpx() -> (Option<time_t>, uint) {
-    DEFAULT_FILE.with_c_str(|filename| {
-        unsafe {
-            utmpxname(filename);
-        }
-    });
-
-    let mut nusers = 0;
-    let mut boot_time = None;
-
-    unsafe {
-        setutxent();
-
-        loop {
-            let line = getutxent();
-
-            if line == null() {
-                break;
+// Seconds since boot: /proc/uptime, or failing that the boot record in utmp
+// or what the kernel says it booted at.
+fn get_uptime(boot_time: Option<i64>) -> Option<i64> {
+    match File::open(&Path::new("/proc/uptime")).read_to_str() {
+        Ok(text) => match text.as_slice().words().next() {
+            Some(secs) => {
+                let whole = secs.split('.').next().unwrap_or(secs);
+                match from_str::<i64>(whole) {
+                    Some(n) => return Some(n),
+                    None => {}
+                }
             }
+            None => {}
+        },
+        Err(_) => {}
+    }
+    let boot_time = match boot_time {
+        Some(t) => Some(t),
+        None => kernel_boot_time()
+    };
+    boot_time.map(|t| time::get_time().sec - t)
+}
+
+fn uptime_string(upsecs: Option<i64>) -> String {
+    let upsecs = match upsecs {
+        Some(secs) if secs >= 0 => secs,
+        _ => return "up ???? days ??:??,  ".to_string()
+    };
+    let updays = upsecs / 86400;
+    let uphours = upsecs % 86400 / 3600;
+    let upmins = upsecs % 3600 / 60;
+    if updays == 1 {
+        format!("up {} day {:2d}:{:02d},  ", updays, uphours, upmins)
+    } else if updays > 1 {
+        format!("up {} days {:2d}:{:02d},  ", updays, uphours, upmins)
+    } else {
+        format!("up  {:2d}:{:02d},  ", uphours, upmins)
+    }
+}
 
-            match (*line).ut_type {
-                USER_PROCESS => nusers += 1,
-                BOOT_TIME => {
-                    let t = (*line).ut_tv;
-                    if t.tv_sec > 0 {
-                        boot_time = Some(t.tv_sec);
-                    }
-                },
-                _ => continue
+// The 1, 5 and 15 minute load averages.
+#[cfg(target_os = "linux")]
+fn get_loadavg() -> Option<[f64, ..3]> {
+    match File::open(&Path::new("/proc/loadavg")).read_to_str() {
+        Ok(text) => {
+            let loads: Vec<f64> = text.as_slice().words().take(3)
+                                      .filter_map(|w| from_str::<f64>(w))
+                                      .collect();
+            if loads.len() == 3 {
+                return Some([*loads.get(0), *loads.get(1), *loads.get(2)]);
             }
         }
-
-        endutxent();
+        Err(_) => {}
     }
-
-    (boot_time, nusers)
+    libc_loadavg()
 }
 
-fn print_nusers(nusers: uint) {
-    if nusers == 1 {
-        print!("1 user, ");
-    } else if nusers > 1 {
-        print!("{} users, ", nusers);
+#[cfg(not(target_os = "linux"))]
+fn get_loadavg() -> Option<[f64, ..3]> {
+    let mut load = bsd::loadavg { ldavg: [0, ..3], fscale: 0 };
+    if bsd::sysctl_value("vm.loadavg", &mut load) && load.fscale > 0 {
+        let scale = load.fscale as f64;
+        Some([load.ldavg[0] as f64 / scale, load.ldavg[1] as f64 / scale,
+              load.ldavg[2] as f64 / scale])
+    } else {
+        libc_loadavg()
     }
 }
 
-fn print_time() {
-    let local_time = unsafe { *localtime(&time(null())) };
-
-    if local_time.tm_hour >= 0 && local_time.tm_min >= 0 &&
-       local_time.tm_sec >= 0 {
-        print!(" {:02d}:{:02d}:{:02d} ", local_time.tm_hour,
-               local_time.tm_min, local_time.tm_sec);
+fn libc_loadavg() -> Option<[f64, ..3]> {
+    let mut avg: [c_double, ..3] = [0.0, ..3];
+    if unsafe { getloadavg(avg.as_mut_ptr(), 3) } == 3 {
+        Some([avg[0] as f64, avg[1] as f64, avg[2] as f64])
+    } else {
+        None
     }
 }
 
-fn get_uptime(boot_time: Option<time_t>) -> i64 {
-    let proc_uptime = File::open(&Path::new("/proc/uptime"))
-                            .read_to_str();
-
-    let uptime_text = match proc_uptime {
-        Ok(s) => s,
-        _ => return match boot_time {
-                Some(t) => {
-                    let now = unsafe { time(null()) };
-                    (now - t) * 100 // Return in ms
-                },
-                _ => -1
-             }
-    };
+#[cfg(target_os = "linux")]
+fn kernel_boot_time() -> Option<i64> {
+    None
+}
 
-    match uptime_text.as_slice().words().next() {
-        Some(s) => match from_str(s.replace(".","").as_slice()) {
-                    Some(n) => n,
-                    None => -1
-                   },
-        None => -1
+#[cfg(not(target_os = "linux"))]
+fn kernel_boot_time() -> Option<i64> {
+    let mut boot = libc::timeval { tv_sec: 0, tv_usec: 0 };
+    if bsd::sysctl_value("kern.boottime", &mut boot) && boot.tv_sec > 0 {
+        Some(boot.tv_sec as i64)
+    } else {
+        None
     }
 }
 
-fn print_uptime(upsecs: i64) {
-    let updays = upsecs / 86400;
-    let uphours = (upsecs - (updays * 86400)) / 3600;
-    let upmins = (upsecs - (updays * 86400) - (uphours * 3600)) / 60;
-    if updays == 1 { 
-        print!("up {:1d} day, {:2d}:{:02d},  ", updays, uphours, upmins);
+#[cfg(not(target_os = "linux"))]
+mod bsd {
+    use std::mem;
+    use libc::{c_char, c_int, c_long, size_t, uint32_t};
+
+    pub struct loadavg {
+        pub ldavg: [uint32_t, ..3],
+        pub fscale: c_long,
     }
-    else if updays > 1 {
-        print!("up {:1d} days, {:2d}:{:02d},  ", updays, uphours, upmins);
+
+    extern {
+        fn sysctlbyname(name: *c_char, oldp: *mut u8, oldlenp: *mut size_t, newp: *u8,
+                        newlen: size_t) -> c_int;
     }
-    else {
-        print!("up  {:2d}:{:02d},  ", uphours, upmins);
+
+    // Fills in the value of a sysctl(3) variable the size of T.
+    pub fn sysctl_value<T>(name: &str, value: &mut T) -> bool {
+        let mut size = mem::size_of::<T>() as size_t;
+        let ret = name.with_c_str(|name| unsafe {
+            sysctlbyname(name, value as *mut T as *mut u8, &mut size, ::std::ptr::null(), 0)
+        });
+        ret == 0 && size as uint == mem::size_of::<T>()
     }
 }
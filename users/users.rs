@@ -14,52 +14,46 @@
 // Allow dead code here in order to keep all fields, constants here, for consistency.
 #![allow(dead_code, non_camel_case_types)]
 
-#![feature(macro_rules, globs)]
+#![feature(macro_rules)]
 
 extern crate getopts;
 extern crate libc;
 
 use std::io::print;
-use std::mem;
 use std::os;
-use std::ptr;
-use std::str;
-use utmpx::*;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 #[path = "../common/utmpx.rs"]
 mod utmpx;
 
-extern {
-    fn getutxent() -> *c_utmp;
-    fn getutxid(ut: *c_utmp) -> *c_utmp;
-    fn getutxline(ut: *c_utmp) -> *c_utmp;
-
-    fn pututxline(ut: *c_utmp) -> *c_utmp;
-
-    fn setutxent();
-    fn endutxent();
-
-    fn utmpxname(file: *libc::c_char) -> libc::c_int;
-}
-
 static NAME: &'static str = "users";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).as_slice();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    )
+}
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+fn run(args: Vec<String>) {
+    let program = args.get(0).as_slice();
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
-        Err(f) => fail!(f.to_err_msg()),
+        Err(f) => crash!(1, "{}", f.to_err_msg()),
     };
 
     if matches.opt_present("help") {
@@ -68,7 +62,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {:s} [OPTION]... [FILE]", program);
         println!("");
-        print(getopts::usage("Output who is currently logged in according to FILE.", opts).as_slice());
+        print(getopts::usage("Output who is currently logged in according to FILE.", opts.as_slice()).as_slice());
         return;
     }
 
@@ -77,7 +71,7 @@ pub fn uumain(args: Vec<String>) {
         return;
     }
 
-    let mut filename = DEFAULT_FILE;
+    let mut filename = utmpx::DEFAULT_FILE;
     if matches.free.len() > 0 {
         filename = matches.free.get(0).as_slice();
     }
@@ -86,32 +80,15 @@ pub fn uumain(args: Vec<String>) {
 }
 
 fn exec(filename: &str) {
-    filename.with_c_str(|filename| {
-        unsafe {
-            utmpxname(filename);
-        }
-    });
-
-    let mut users = vec!();
-
-    unsafe {
-        setutxent();
-
-        loop {
-            let line = getutxent();
-
-            if line == ptr::null() {
-                break;
-            }
-
-            if (*line).ut_type == USER_PROCESS {
-                let user = str::raw::from_c_str(mem::transmute(&(*line).ut_user));
-                users.push(user);
-            }
-        }
+    let records = match utmpx::read_utmpx(filename) {
+        Ok(records) => records,
+        Err(e) => crash!(1, "{}: {}", filename, util::strerror(&e))
+    };
 
-        endutxent();
-    }
+    let mut users: Vec<String> = records.move_iter()
+                                        .filter(|r| r.is_user_process())
+                                        .map(|r| r.user)
+                                        .collect();
 
     if users.len() > 0 {
         users.sort();
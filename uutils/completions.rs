@@ -0,0 +1,142 @@
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+/*
+ * Shell completion scripts for the utilities, written from the same option
+ * tables they parse their arguments with, so the two cannot disagree.
+ * Arguments that are not options are completed as file names.
+ */
+
+use getopts::{No, OptGroup};
+
+pub type Table<'a> = &'a [(&'a str, Vec<OptGroup>)];
+
+pub fn script(shell: &str, utils: Table) -> Option<String> {
+    match shell {
+        "bash" => Some(bash(utils)),
+        "zsh" => Some(zsh(utils)),
+        "fish" => Some(fish(utils)),
+        _ => None
+    }
+}
+
+// One function for every utility, which offers the options once a word
+// starts with '-' and otherwise leaves bash to complete file names.
+fn bash(utils: Table) -> String {
+    let mut out = String::new();
+    out.push_str("_uutils() {\n");
+    out.push_str("    local cur=${COMP_WORDS[COMP_CWORD]} words\n");
+    out.push_str("    case $1 in\n");
+    for &(name, ref opts) in utils.iter() {
+        let mut words = vec!();
+        for opt in opts.iter() {
+            if !opt.short_name.is_empty() {
+                words.push(format!("-{}", opt.short_name));
+            }
+            if !opt.long_name.is_empty() {
+                words.push(format!("--{}", opt.long_name));
+            }
+        }
+        out.push_str(format!("        {}) words=\"{}\";;\n", shell_word(name),
+                             words.connect(" ")).as_slice());
+    }
+    out.push_str("    esac\n");
+    out.push_str("    if [[ $cur == -* ]]; then\n");
+    out.push_str("        COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))\n");
+    out.push_str("    fi\n");
+    out.push_str("}\n");
+    let names: Vec<String> = utils.iter().map(|&(name, _)| shell_word(name)).collect();
+    out.push_str(format!("complete -o default -F _uutils {}\n", names.connect(" ")).as_slice());
+    out
+}
+
+fn zsh(utils: Table) -> String {
+    let mut out = String::new();
+    for &(name, ref opts) in utils.iter() {
+        let mut specs = vec!();
+        for opt in opts.iter() {
+            let desc = zsh_description(opt.desc.as_slice());
+            // what follows the option's name when it takes a value
+            let (short_arg, long_arg, value) = match opt.hasarg {
+                No => ("", "", "".to_string()),
+                _ => ("+", "=", format!(":{}:", opt.hint.as_slice().replace(":", "")))
+            };
+            if !opt.short_name.is_empty() {
+                specs.push(format!("-{}{}[{}]{}", opt.short_name, short_arg, desc, value));
+            }
+            if !opt.long_name.is_empty() {
+                specs.push(format!("--{}{}[{}]{}", opt.long_name, long_arg, desc, value));
+            }
+        }
+        specs.push("*:file:_files".to_string());
+        let function = format!("_uutils_{}", function_name(name));
+        out.push_str(function.as_slice());
+        out.push_str("() {\n    _arguments -s");
+        for spec in specs.iter() {
+            out.push_str(format!(" \\\n        {}", quote(spec.as_slice())).as_slice());
+        }
+        out.push_str("\n}\n");
+        out.push_str(format!("compdef {} {}\n", function, shell_word(name)).as_slice());
+    }
+    out
+}
+
+fn fish(utils: Table) -> String {
+    let mut out = String::new();
+    for &(name, ref opts) in utils.iter() {
+        for opt in opts.iter() {
+            let mut line = format!("complete -c {}", shell_word(name));
+            if !opt.short_name.is_empty() {
+                line.push_str(format!(" -s {}", opt.short_name).as_slice());
+            }
+            if !opt.long_name.is_empty() {
+                line.push_str(format!(" -l {}", opt.long_name).as_slice());
+            }
+            match opt.hasarg {
+                No => {}
+                _ => line.push_str(" -r")
+            }
+            if !opt.desc.is_empty() {
+                line.push_str(format!(" -d {}", quote(one_line(opt.desc.as_slice()).as_slice()))
+                              .as_slice());
+            }
+            line.push_char('\n');
+            out.push_str(line.as_slice());
+        }
+    }
+    out
+}
+
+// The text in single quotes, which every one of the shells reads literally.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace("'", "'\\''"))
+}
+
+// The name as a word for the shell: "[" has to be quoted.
+fn shell_word(name: &str) -> String {
+    if name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        name.to_string()
+    } else {
+        quote(name)
+    }
+}
+
+fn function_name(name: &str) -> String {
+    if name == "[" { "bracket".to_string() } else { name.replace("-", "_") }
+}
+
+// Help text runs over several lines in places.
+fn one_line(desc: &str) -> String {
+    desc.words().collect::<Vec<&str>>().connect(" ")
+}
+
+// Brackets end the description in an _arguments spec, so they are escaped.
+fn zsh_description(desc: &str) -> String {
+    one_line(desc).replace("[", "\\[").replace("]", "\\]")
+}
@@ -9,111 +9,324 @@
  * file that was distributed with this source code.
  */
 
+#![feature(macro_rules)]
+
 extern crate collections;
 extern crate getopts;
 
+#[cfg(unix)] extern crate arch;
 extern crate base64;
 extern crate basename;
 extern crate cat;
+#[cfg(unix)] extern crate chgrp;
+#[cfg(unix)] extern crate chmod;
+#[cfg(unix)] extern crate chown;
+#[cfg(unix)] extern crate chroot;
 extern crate cksum;
 extern crate comm;
-extern crate cp;
+#[cfg(unix)] extern crate cp;
+#[cfg(unix)] extern crate date;
+#[cfg(unix)] extern crate dd;
+#[cfg(unix)] extern crate df;
+#[cfg(unix)] extern crate dir;
+extern crate dircolors;
 extern crate dirname;
-extern crate du;
+#[cfg(unix)] extern crate du;
 extern crate echo;
 extern crate env;
+extern crate expr;
+extern crate factor;
 extern crate fold;
-extern crate groups;
+#[cfg(unix)] extern crate groups;
 extern crate head;
-extern crate hostid;
-extern crate hostname;
-extern crate id;
-extern crate kill;
-extern crate logname;
-extern crate mkdir;
+#[cfg(unix)] extern crate hostid;
+#[cfg(unix)] extern crate hostname;
+#[cfg(unix)] extern crate id;
+#[cfg(unix)] extern crate install;
+#[cfg(unix)] extern crate kill;
+extern crate link;
+#[cfg(unix)] extern crate ln;
+#[cfg(unix)] extern crate logname;
+#[cfg(unix)] extern crate ls;
+#[cfg(unix)] extern crate mkdir;
+#[cfg(unix)] extern crate mkfifo;
+#[cfg(unix)] extern crate mknod;
+#[cfg(unix)] extern crate mktemp;
+#[cfg(unix)] extern crate mv;
+#[cfg(unix)] extern crate nice;
+#[cfg(unix)] extern crate nohup;
+#[cfg(unix)] extern crate nproc;
 extern crate paste;
+#[cfg(unix)] extern crate pathchk;
+#[cfg(unix)] extern crate pinky;
 extern crate printenv;
-extern crate pwd;
-extern crate rm;
-extern crate rmdir;
+extern crate printf;
+#[cfg(unix)] extern crate pwd;
+#[cfg(unix)] extern crate readlink;
+#[cfg(unix)] extern crate realpath;
+#[cfg(unix)] extern crate rm;
+#[cfg(unix)] extern crate rmdir;
 extern crate seq;
-extern crate sleep;
+#[cfg(unix)] extern crate shred;
+#[cfg(unix)] extern crate sleep;
+#[cfg(unix)] extern crate stat;
+#[cfg(unix)] extern crate stdbuf;
+#[cfg(unix)] extern crate stty;
 extern crate sum;
+#[cfg(unix)] extern crate sync;
 extern crate tac;
-extern crate tee;
-extern crate touch;
+#[cfg(unix)] extern crate tee;
+#[cfg(unix)] extern crate timeout;
+#[cfg(unix)] extern crate touch;
 extern crate tr;
-extern crate truncate;
-extern crate tty;
-extern crate uname;
+#[cfg(unix)] extern crate truncate;
+#[cfg(unix)] extern crate uutest;
+#[cfg(unix)] extern crate tty;
+#[cfg(unix)] extern crate uname;
 extern crate unlink;
-extern crate uptime;
-extern crate users;
+#[cfg(unix)] extern crate uptime;
+#[cfg(unix)] extern crate users;
+#[cfg(unix)] extern crate vdir;
 extern crate wc;
-extern crate whoami;
+#[cfg(unix)] extern crate who;
+#[cfg(unix)] extern crate whoami;
 extern crate yes;
 
+use std::io;
 use std::os;
 use collections::hashmap::HashMap;
+use getopts::OptGroup;
+
+mod completions;
 
 static NAME: &'static str = "uutils";
 static VERSION: &'static str = "1.0.0";
 
-fn util_map() -> HashMap<&str, fn(Vec<String>)> {
-    fn uutrue(_: Vec<String>) { os::set_exit_status(0); }
-    fn uufalse(_: Vec<String>) { os::set_exit_status(1); }
+// Every utility has its own copy of common/util.rs and so its own Outcome
+// type, which the map cannot hold; it holds wrappers that reduce each outcome
+// to an exit status instead.
+macro_rules! util(
+    ($krate:ident) => ({
+        fn uumain(args: Vec<String>) -> int {
+            match $krate::uumain(args) {
+                Ok(()) => 0,
+                Err(failure) => failure.status
+            }
+        }
+        uumain
+    })
+)
+
+fn util_map() -> HashMap<&str, fn(Vec<String>) -> int> {
+    fn uutrue(_: Vec<String>) -> int { 0 }
+    fn uufalse(_: Vec<String>) -> int { 1 }
 
     let mut map = HashMap::new();
-    map.insert("base64", base64::uumain);
-    map.insert("basename", basename::uumain);
-    map.insert("cat", cat::uumain);
-    map.insert("cksum", cksum::uumain);
-    map.insert("comm", comm::uumain);
-    map.insert("cp", cp::uumain);
-    map.insert("dirname", dirname::uumain);
-    map.insert("du", du::uumain);
-    map.insert("echo", echo::uumain);
-    map.insert("env", env::uumain);
+    map.insert("base64", util!(base64));
+    map.insert("basename", util!(basename));
+    map.insert("cat", util!(cat));
+    map.insert("cksum", util!(cksum));
+    map.insert("comm", util!(comm));
+    map.insert("dircolors", util!(dircolors));
+    map.insert("dirname", util!(dirname));
+    map.insert("echo", util!(echo));
+    map.insert("env", util!(env));
+    map.insert("expr", util!(expr));
+    map.insert("factor", util!(factor));
     map.insert("false", uufalse);
-    map.insert("fold", fold::uumain);
-    map.insert("groups", groups::uumain);
-    map.insert("head", head::uumain);
-    map.insert("hostid", hostid::uumain);
-    map.insert("hostname", hostname::uumain);
-    map.insert("id", id::uumain);
-    map.insert("kill", kill::uumain);
-    map.insert("logname", logname::uumain);
-    map.insert("mkdir", mkdir::uumain);
-    map.insert("paste", paste::uumain);
-    map.insert("printenv", printenv::uumain);
-    map.insert("pwd", pwd::uumain);
-    map.insert("rm", rm::uumain);
-    map.insert("rmdir", rmdir::uumain);
-    map.insert("seq", seq::uumain);
-    map.insert("sleep", sleep::uumain);
-    map.insert("sum", sum::uumain);
-    map.insert("tac", tac::uumain);
-    map.insert("tee", tee::uumain);
-    map.insert("touch", touch::uumain);
-    map.insert("tr", tr::uumain);
+    map.insert("fold", util!(fold));
+    map.insert("head", util!(head));
+    map.insert("link", util!(link));
+    map.insert("paste", util!(paste));
+    map.insert("printenv", util!(printenv));
+    map.insert("printf", util!(printf));
+    map.insert("seq", util!(seq));
+    map.insert("sum", util!(sum));
+    map.insert("tac", util!(tac));
+    map.insert("tr", util!(tr));
     map.insert("true", uutrue);
-    map.insert("truncate", truncate::uumain);
-    map.insert("tty", tty::uumain);
-    map.insert("uname", uname::uumain);
-    map.insert("unlink", unlink::uumain);
-    map.insert("uptime", uptime::uumain);
-    map.insert("users", users::uumain);
-    map.insert("wc", wc::uumain);
-    map.insert("whoami", whoami::uumain);
-    map.insert("yes", yes::uumain);
+    map.insert("unlink", util!(unlink));
+    map.insert("wc", util!(wc));
+    map.insert("yes", util!(yes));
+    add_unix_utils(&mut map);
+    map
+}
+
+#[cfg(unix)]
+fn add_unix_utils(map: &mut HashMap<&str, fn(Vec<String>) -> int>) {
+    map.insert("[", util!(uutest));
+    map.insert("arch", util!(arch));
+    map.insert("chgrp", util!(chgrp));
+    map.insert("chmod", util!(chmod));
+    map.insert("chown", util!(chown));
+    map.insert("chroot", util!(chroot));
+    map.insert("cp", util!(cp));
+    map.insert("date", util!(date));
+    map.insert("dd", util!(dd));
+    map.insert("df", util!(df));
+    map.insert("dir", util!(dir));
+    map.insert("du", util!(du));
+    map.insert("groups", util!(groups));
+    map.insert("hostid", util!(hostid));
+    map.insert("hostname", util!(hostname));
+    map.insert("id", util!(id));
+    map.insert("install", util!(install));
+    map.insert("kill", util!(kill));
+    map.insert("ln", util!(ln));
+    map.insert("logname", util!(logname));
+    map.insert("ls", util!(ls));
+    map.insert("mkdir", util!(mkdir));
+    map.insert("mkfifo", util!(mkfifo));
+    map.insert("mknod", util!(mknod));
+    map.insert("mktemp", util!(mktemp));
+    map.insert("mv", util!(mv));
+    map.insert("nice", util!(nice));
+    map.insert("nohup", util!(nohup));
+    map.insert("nproc", util!(nproc));
+    map.insert("pathchk", util!(pathchk));
+    map.insert("pinky", util!(pinky));
+    map.insert("pwd", util!(pwd));
+    map.insert("readlink", util!(readlink));
+    map.insert("realpath", util!(realpath));
+    map.insert("rm", util!(rm));
+    map.insert("rmdir", util!(rmdir));
+    map.insert("shred", util!(shred));
+    map.insert("sleep", util!(sleep));
+    map.insert("stat", util!(stat));
+    map.insert("stdbuf", util!(stdbuf));
+    map.insert("stty", util!(stty));
+    map.insert("sync", util!(sync));
+    map.insert("tee", util!(tee));
+    map.insert("test", util!(uutest));
+    map.insert("timeout", util!(timeout));
+    map.insert("touch", util!(touch));
+    map.insert("truncate", util!(truncate));
+    map.insert("tty", util!(tty));
+    map.insert("uname", util!(uname));
+    map.insert("uptime", util!(uptime));
+    map.insert("users", util!(users));
+    map.insert("vdir", util!(vdir));
+    map.insert("who", util!(who));
+    map.insert("whoami", util!(whoami));
+}
+
+#[cfg(not(unix))]
+fn add_unix_utils(_: &mut HashMap<&str, fn(Vec<String>) -> int>) {
+}
+
+// The option tables of the utilities that have one, for completions.  Those
+// left out read their own arguments on purpose: chroot, env, kill, nice,
+// nohup, stdbuf and timeout stop at the command they run, dd takes operands
+// such as if=FILE, expr and test take expressions, printf a format, and true
+// and false take nothing at all.
+fn opts_map() -> HashMap<&str, fn() -> Vec<OptGroup>> {
+    let mut map = HashMap::new();
+    map.insert("base64", base64::opts);
+    map.insert("basename", basename::opts);
+    map.insert("cat", cat::opts);
+    map.insert("cksum", cksum::opts);
+    map.insert("comm", comm::opts);
+    map.insert("dircolors", dircolors::opts);
+    map.insert("dirname", dirname::opts);
+    map.insert("echo", echo::opts);
+    map.insert("factor", factor::opts);
+    map.insert("fold", fold::opts);
+    map.insert("head", head::opts);
+    map.insert("link", link::opts);
+    map.insert("paste", paste::opts);
+    map.insert("printenv", printenv::opts);
+    map.insert("seq", seq::opts);
+    map.insert("sum", sum::opts);
+    map.insert("tac", tac::opts);
+    map.insert("tr", tr::opts);
+    map.insert("unlink", unlink::opts);
+    map.insert("wc", wc::opts);
+    map.insert("yes", yes::opts);
+    add_unix_opts(&mut map);
     map
 }
 
-fn usage(cmap: &HashMap<&str, fn(Vec<String>)>) {
+#[cfg(unix)]
+fn add_unix_opts(map: &mut HashMap<&str, fn() -> Vec<OptGroup>>) {
+    map.insert("arch", arch::opts);
+    map.insert("chgrp", chgrp::opts);
+    map.insert("chmod", chmod::opts);
+    map.insert("chown", chown::opts);
+    map.insert("cp", cp::opts);
+    map.insert("date", date::opts);
+    map.insert("df", df::opts);
+    map.insert("dir", dir::opts);
+    map.insert("du", du::opts);
+    map.insert("groups", groups::opts);
+    map.insert("hostid", hostid::opts);
+    map.insert("hostname", hostname::opts);
+    map.insert("id", id::opts);
+    map.insert("install", install::opts);
+    map.insert("ln", ln::opts);
+    map.insert("logname", logname::opts);
+    map.insert("ls", ls::opts);
+    map.insert("mkdir", mkdir::opts);
+    map.insert("mkfifo", mkfifo::opts);
+    map.insert("mknod", mknod::opts);
+    map.insert("mktemp", mktemp::opts);
+    map.insert("mv", mv::opts);
+    map.insert("nproc", nproc::opts);
+    map.insert("pathchk", pathchk::opts);
+    map.insert("pinky", pinky::opts);
+    map.insert("pwd", pwd::opts);
+    map.insert("readlink", readlink::opts);
+    map.insert("realpath", realpath::opts);
+    map.insert("rm", rm::opts);
+    map.insert("rmdir", rmdir::opts);
+    map.insert("shred", shred::opts);
+    map.insert("sleep", sleep::opts);
+    map.insert("stat", stat::opts);
+    map.insert("stty", stty::opts);
+    map.insert("sync", sync::opts);
+    map.insert("tee", tee::opts);
+    map.insert("touch", touch::opts);
+    map.insert("truncate", truncate::opts);
+    map.insert("tty", tty::opts);
+    map.insert("uname", uname::opts);
+    map.insert("uptime", uptime::opts);
+    map.insert("users", users::opts);
+    map.insert("vdir", vdir::opts);
+    map.insert("who", who::opts);
+    map.insert("whoami", whoami::opts);
+}
+
+#[cfg(not(unix))]
+fn add_unix_opts(_: &mut HashMap<&str, fn() -> Vec<OptGroup>>) {
+}
+
+// Prints the completion script for the shell.  Every utility is in it, but
+// those without an option table only have their file names completed.
+fn print_completions(umap: &HashMap<&str, fn(Vec<String>) -> int>, shell: &str) {
+    let omap = opts_map();
+    let mut names: Vec<&str> = umap.keys().map(|&s| s).collect();
+    names.sort();
+    let table: Vec<(&str, Vec<OptGroup>)> = names.iter().map(|&name| {
+        match omap.find(&name) {
+            Some(&opts) => (name, opts()),
+            None => (name, vec!())
+        }
+    }).collect();
+    match completions::script(shell, table.as_slice()) {
+        Some(script) => print!("{}", script),
+        None => {
+            let _ = writeln!(&mut io::stderr(),
+                             "{}: unsupported shell '{}' (try bash, zsh or fish)", NAME, shell);
+            os::set_exit_status(1);
+        }
+    }
+}
+
+fn usage(cmap: &HashMap<&str, fn(Vec<String>) -> int>) {
         println!("{} {}", NAME, VERSION);
         println!("");
         println!("Usage:");
         println!("  {} [util [arguments...]", NAME);
+        println!("  {} completions bash|zsh|fish", NAME);
         println!("Currently defined functions:");
         let mut utils: Vec<&str> = cmap.keys().map(|&s| s).collect();
         utils.sort();
@@ -129,10 +342,13 @@ fn main() {
 
     // try binary name as util name.
     let binary = Path::new(args.get(0).as_slice());
-    let binary_as_util = binary.filename_str().unwrap();
+    let mut binary_as_util = binary.filename_str().unwrap();
+    if cfg!(windows) && binary_as_util.ends_with(".exe") {
+        binary_as_util = binary_as_util.slice_to(binary_as_util.len() - 4);
+    }
     if umap.contains_key(&binary_as_util) {
         let &uumain = umap.get(&binary_as_util);
-        uumain(args);
+        os::set_exit_status(uumain(args));
         return
     } else if binary_as_util.starts_with("uutils")
         || binary_as_util.starts_with("busybox") {
@@ -149,9 +365,19 @@ fn main() {
     if args.len() >= 2 {
         args.shift();
         let util = args.get(0).as_slice();
-        if umap.contains_key(&util) {
+        if util == "completions" {
+            match args.as_slice().get(1) {
+                Some(shell) => print_completions(&umap, shell.as_slice()),
+                None => {
+                    let _ = writeln!(&mut io::stderr(), "Usage: {} completions bash|zsh|fish",
+                                     NAME);
+                    os::set_exit_status(1);
+                }
+            }
+            return
+        } else if umap.contains_key(&util) {
             let &uumain = umap.get(&util);
-            uumain(args.clone());
+            os::set_exit_status(uumain(args.clone()));
             return
         } else if args.get(0).as_slice() == "--help" {
             // see if they want help on a specific util
@@ -159,7 +385,7 @@ fn main() {
                 let util = args.get(1).as_slice();
                 if umap.contains_key(&util) {
                     let &uumain = umap.get(&util);
-                    uumain(vec!["--help".to_string()]);
+                    os::set_exit_status(uumain(vec!["--help".to_string()]));
                     return
                 } else {
                     println!("{}: applet not found", util);
@@ -0,0 +1,62 @@
+#![crate_id(name="vdir", vers="1.0.0", author="agent")]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+#![feature(macro_rules)]
+
+extern crate getopts;
+extern crate libc;
+extern crate time;
+
+use std::os;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/fsext.rs"]
+mod fsext;
+
+#[path = "../common/c_types.rs"]
+mod c_types;
+
+#[path = "../common/walk.rs"]
+mod walk;
+
+#[path = "../common/colors.rs"]
+mod colors;
+
+#[path = "../common/size.rs"]
+mod size;
+
+#[path = "../common/platform.rs"]
+mod platform;
+
+#[path = "../common/collate.rs"]
+mod collate;
+
+#[path = "../common/listing.rs"]
+mod listing;
+
+static NAME: &'static str = "vdir";
+static VERSION: &'static str = "1.0.0";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() listing::run(args, Some(listing::Long)))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    listing::opts()
+}
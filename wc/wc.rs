@@ -14,6 +14,7 @@
 extern crate getopts;
 extern crate libc;
 
+use std::cmp;
 use std::os;
 use std::str::from_utf8;
 use std::io::{print, stdin, File, BufferedReader};
@@ -22,6 +23,12 @@ use getopts::Matches;
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/mbchar.rs"]
+mod mbchar;
+
 struct Result {
     filename: String,
     bytes: uint,
@@ -34,11 +41,14 @@ struct Result {
 static NAME: &'static str = "wc";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("c", "bytes", "print the byte counts"),
         getopts::optflag("m", "chars", "print the character counts"),
         getopts::optflag("l", "lines", "print the newline counts"),
@@ -46,9 +56,14 @@ pub fn uumain(args: Vec<String>) {
         getopts::optflag("w", "words", "print the word counts"),
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
+    )
+}
 
-    let matches = match getopts::getopts(args.tail(), opts) {
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
             crash!(1, "Invalid options\n{}", f.to_err_msg())
@@ -59,7 +74,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {0:s} [OPTION]... [FILE]...", program);
         println!("");
-        print(getopts::usage("Print newline, word and byte counts for each FILE", opts).as_slice());
+        print(getopts::usage("Print newline, word and byte counts for each FILE", opts.as_slice()).as_slice());
         println!("");
         println!("With no FILE, or when FILE is -, read standard input.");
         return;
@@ -109,7 +124,6 @@ pub fn wc(files: Vec<String>, matches: &Matches) {
         let mut word_count: uint = 0;
         let mut byte_count: uint = 0;
         let mut char_count: uint = 0;
-        let mut current_char_count: uint = 0;
         let mut longest_line_length: uint = 0;
 
         loop {
@@ -126,30 +140,31 @@ pub fn wc(files: Vec<String>, matches: &Matches) {
 
                     // try and convert the bytes to UTF-8 first
                     match from_utf8(raw_line.as_slice()) {
-                        Some(line) => {
-                            word_count += line.words().len();
-                            current_char_count = line.chars().len();
-                            char_count += current_char_count;
-                        },
+                        Some(line) => word_count += line.words().len(),
                         None => {
                             word_count += raw_line.as_slice().split(|&x| is_word_seperator(x)).len();
-                            for byte in raw_line.iter() {
-                                match byte.is_ascii() {
-                                    true => {
-                                        current_char_count += 1;
-                                    }
-                                    false => { }
-                                }
-                            }
-                            char_count += current_char_count;
                         }
                     }
 
-                    if current_char_count > longest_line_length {
-                        // we subtract one here because `line.iter().len()` includes the LF
-                        // matches GNU 'wc' behaviour
-                        longest_line_length = current_char_count - 1;
+                    // bytes that are not UTF-8 are not characters, and take
+                    // no columns for -L; tabs take up to the next stop
+                    let mut width = 0;
+                    for c in mbchar::chars(raw_line.as_slice()) {
+                        let ch = match c.ch {
+                            Some(ch) => ch,
+                            None => continue
+                        };
+                        char_count += 1;
+                        match ch {
+                            '\t' => width += 8 - width % 8,
+                            '\n' | '\r' | '\x0c' => {
+                                longest_line_length = cmp::max(longest_line_length, width);
+                                width = 0;
+                            }
+                            _ => width += mbchar::width(ch)
+                        }
                     }
+                    longest_line_length = cmp::max(longest_line_length, width);
                 },
                 _ => break
             }
@@ -236,7 +251,7 @@ fn open(path: String) -> Option<BufferedReader<Box<Reader>>> {
             return Some(BufferedReader::new(reader));
         },
         Err(e) => {
-            show_error!(1, "wc: {0:s}: {1:s}", path, e.desc.to_str());
+            show_error!(1, "{}: {}", path, util::strerror(&e));
         }
     }
 
@@ -0,0 +1,148 @@
+#![crate_id(name="who", vers="1.0.0", author="agent")]
+#![feature(macro_rules)]
+
+/*
+ * This file is part of the uutils coreutils package.
+ *
+ * (c) agent <agent@local>
+ *
+ * For the full copyright and license information, please view the LICENSE
+ * file that was distributed with this source code.
+ */
+
+extern crate getopts;
+extern crate libc;
+extern crate time;
+
+use std::os;
+use std::io::print;
+use time::Timespec;
+use utmpx::Utmpx;
+
+#[path = "../common/util.rs"]
+mod util;
+
+#[path = "../common/posix.rs"]
+mod posix;
+
+#[path = "../common/utmpx.rs"]
+mod utmpx;
+
+static NAME: &'static str = "who";
+static VERSION: &'static str = "1.0.0";
+
+#[allow(dead_code)]
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
+
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
+        getopts::optflag("b", "boot", "time of last system boot"),
+        getopts::optflag("H", "heading", "print line of column headings"),
+        getopts::optflag("m", "", "only hostname and user associated with stdin"),
+        getopts::optflag("q", "count", "all login names and number of users logged on"),
+        getopts::optflag("s", "short", "print only name, line, and time (default)"),
+        getopts::optflag("", "help", "display this help and exit"),
+        getopts::optflag("V", "version", "output version information and exit"),
+    )
+}
+
+fn run(args: Vec<String>) {
+    let opts = opts();
+
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
+        Ok(m) => m,
+        Err(f) => crash!(1, "{}", f.to_err_msg())
+    };
+
+    if matches.opt_present("help") {
+        println!("{} {}", NAME, VERSION);
+        println!("");
+        println!("Usage:");
+        println!("  {0} [OPTION]... [ FILE | ARG1 ARG2 ]", args.get(0));
+        println!("");
+        print(getopts::usage("Print information about users who are currently logged in.",
+                             opts.as_slice()).as_slice());
+        println!("");
+        println!("If FILE is not specified, use {}.  If ARG1 ARG2 given, -m presumed: \
+                  'am i' or 'mom likes' are usual.", utmpx::DEFAULT_FILE);
+        return;
+    } else if matches.opt_present("version") {
+        println!("{} {}", NAME, VERSION);
+        return;
+    }
+
+    // who am i, or any other two words, means -m
+    let (file, only_stdin) = match matches.free.len() {
+        0 => (utmpx::DEFAULT_FILE.to_string(), matches.opt_present("m")),
+        1 => (matches.free.get(0).clone(), matches.opt_present("m")),
+        2 => (utmpx::DEFAULT_FILE.to_string(), true),
+        _ => crash!(1, "extra operand '{}'", matches.free.get(2))
+    };
+
+    let records = match utmpx::read_utmpx(file.as_slice()) {
+        Ok(records) => records,
+        Err(e) => crash!(1, "{}: {}", file, util::strerror(&e))
+    };
+
+    if matches.opt_present("count") {
+        let users: Vec<&str> = records.iter()
+                                      .filter(|r| r.is_user_process())
+                                      .map(|r| r.user.as_slice())
+                                      .collect();
+        println!("{}", users.connect(" "));
+        println!("# users={}", users.len());
+        return;
+    }
+
+    if matches.opt_present("heading") {
+        print_line("NAME", "LINE", "TIME", "COMMENT");
+    }
+
+    if matches.opt_present("boot") {
+        for record in records.iter().filter(|r| r.ut_type == utmpx::BOOT_TIME) {
+            print_line("", "system boot", format_time(record.time).as_slice(), "");
+        }
+        return;
+    }
+
+    let line = if only_stdin {
+        match utmpx::stdin_line() {
+            Some(line) => Some(line),
+            // not on a terminal, so nobody is associated with it
+            None => return
+        }
+    } else {
+        None
+    };
+    for record in records.iter().filter(|r| r.is_user_process()) {
+        match line {
+            Some(ref line) if record.line != *line => continue,
+            _ => {}
+        }
+        print_user(record);
+    }
+}
+
+fn print_user(record: &Utmpx) {
+    let comment = if record.host.is_empty() {
+        String::new()
+    } else {
+        format!("({})", record.host)
+    };
+    print_line(record.user.as_slice(), record.line.as_slice(),
+               format_time(record.time).as_slice(), comment.as_slice());
+}
+
+// The columns as GNU who lays them out, with nothing trailing an empty one.
+fn print_line(user: &str, line: &str, time: &str, comment: &str) {
+    let text = format!("{:<8} {:<12} {:<16} {}", user, line, time, comment);
+    println!("{}", text.as_slice().trim_right());
+}
+
+fn format_time(secs: i64) -> String {
+    time::at(Timespec::new(secs, 0)).strftime("%Y-%m-%d %H:%M")
+}
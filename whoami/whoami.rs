@@ -20,37 +20,32 @@ extern crate libc;
 
 use std::io::print;
 use std::os;
-use std::str;
-use c_types::{c_passwd, getpwuid};
+use libc::funcs::posix88::unistd::geteuid;
 
 #[path = "../common/util.rs"] mod util;
+#[path = "../common/posix.rs"] mod posix;
 #[path = "../common/c_types.rs"] mod c_types;
 
-extern {
-    pub fn geteuid() -> libc::c_int;
-}
-
-unsafe fn getusername() -> String {
-    let passwd: *c_passwd = getpwuid(geteuid());
-
-    let pw_name: *libc::c_char = (*passwd).pw_name;
-    let name = str::raw::from_c_str(pw_name);
-
-    name
-}
-
 static NAME: &'static str = "whoami";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).as_slice();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).as_slice();
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => crash!(1, "{}", f.to_err_msg()),
     };
@@ -60,7 +55,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {:s}", program);
         println!("");
-        print(getopts::usage("print effective userid", opts).as_slice());
+        print(getopts::usage("print effective userid", opts.as_slice()).as_slice());
         return;
     }
     if matches.opt_present("version") {
@@ -72,8 +67,9 @@ pub fn uumain(args: Vec<String>) {
 }
 
 pub fn exec() {
-    unsafe {
-        let username = getusername();
-        println!("{:s}", username);
+    let uid = unsafe { geteuid() };
+    match c_types::uid2usr(uid) {
+        Some(username) => println!("{:s}", username),
+        None => crash!(1, "cannot find name for user ID {}", uid)
     }
 }
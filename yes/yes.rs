@@ -17,23 +17,36 @@ extern crate getopts;
 extern crate libc;
 
 use std::os;
-use std::io::{print, println};
+use std::io;
+use std::io::print;
+use std::io::stdio::stdout_raw;
 
 #[path = "../common/util.rs"]
 mod util;
 
+#[path = "../common/posix.rs"]
+mod posix;
+
 static NAME: &'static str = "yes";
 
 #[allow(dead_code)]
-fn main() { uumain(os::args()); }
+fn main() { os::set_exit_status(util::exit_status(uumain(os::args()))); }
 
-pub fn uumain(args: Vec<String>) {
-    let program = args.get(0).clone();
-    let opts = [
+pub fn uumain(args: Vec<String>) -> util::Outcome {
+    util::catch_exit(proc() run(args))
+}
+
+pub fn opts() -> Vec<getopts::OptGroup> {
+    vec!(
         getopts::optflag("h", "help", "display this help and exit"),
         getopts::optflag("V", "version", "output version information and exit"),
-    ];
-    let matches = match getopts::getopts(args.tail(), opts) {
+    )
+}
+
+fn run(args: Vec<String>) {
+    let program = args.get(0).clone();
+    let opts = opts();
+    let matches = match posix::getopts(args.tail(), opts.as_slice()) {
         Ok(m) => m,
         Err(f) => {
             crash!(1, "invalid options\n{}", f.to_err_msg())
@@ -45,7 +58,7 @@ pub fn uumain(args: Vec<String>) {
         println!("Usage:");
         println!("  {0:s} [STRING]... [OPTION]...", program);
         println!("");
-        print(getopts::usage("Repeatedly output a line with all specified STRING(s), or 'y'.", opts).as_slice());
+        print(getopts::usage("Repeatedly output a line with all specified STRING(s), or 'y'.", opts.as_slice()).as_slice());
         return;
     }
     if matches.opt_present("version") {
@@ -60,8 +73,31 @@ pub fn uumain(args: Vec<String>) {
     exec(string.as_slice());
 }
 
+// Size of the pre-formatted output buffer.  Writing several kilobytes per
+// syscall is what makes yes fast enough to be useful as a data source.
+static BUF_SIZE: uint = 8 * 1024;
+
 pub fn exec(string: &str) {
+    let mut line = string.to_string();
+    line.push_char('\n');
+    let line = line.into_bytes();
+
+    // Fill the buffer with as many whole lines as fit (at least one), so
+    // every write ends at a line boundary.
+    let copies = if line.len() >= BUF_SIZE { 1 } else { BUF_SIZE / line.len() };
+    let mut buf = Vec::with_capacity(copies * line.len());
+    for _ in range(0, copies) {
+        buf.push_all(line.as_slice());
+    }
+
+    let mut out = stdout_raw();
     loop {
-        println(string);
+        match out.write(buf.as_slice()) {
+            Ok(_) => {}
+            // The reader went away (e.g. `yes | head`); that is the normal
+            // way for yes to finish.
+            Err(ref e) if e.kind == io::BrokenPipe => return,
+            Err(e) => crash!(1, "standard output: {}", util::strerror(&e))
+        }
     }
 }